@@ -1,61 +1,1728 @@
+use std::collections::BTreeMap;
 use std::sync::Arc;
 
-use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
 
 use crate::{
     components::{
-        element::Element,
-        list::List,
+        block::Block,
+        element::{DialogState, Element},
+        list::{List, ListAction},
         input::Input,
-        shortcuts::ShortcutsView, editor::Editor,
+        method::MethodView,
+        picker::Picker,
+        prompt::Prompt,
+        response::ResponseView,
+        shortcuts::{
+            ShortcutsView, AUTH_HINTS, CAPTURE_HINTS, CODE_HINTS, DEFAULT_HINTS, DIFF_HINTS, FILTER_HINTS,
+            LIST_HINTS, PATH_HINTS, QUERY_HINTS, REQUEST_HINTS, SCRIPT_HINTS, TESTS_HINTS,
+        },
+        editor::Editor,
+        diff::{DiffAction, DiffView},
+        history::{HistoryAction, HistoryBrowser},
+        run::RunView,
+        sync::SyncView,
+        har::HarView,
+        code::{CodeAction, CodeView},
+        table::{Column, Table, TableDialog, TableRow},
     },
+    assertions::{self, Assertion, AssertionKind},
+    auth::{self, Auth},
+    capture::{self, Capture, CaptureSource},
     console::Console,
-    layout::Layout,
-    model::{Model, Request},
+    contract,
+    diff,
+    har,
+    history::{self, HistoryEntry, ReceivedResponse},
+    http::{self, BodyUsage, HttpResponse},
+    import,
+    layout::{Layout, Pane},
+    model::{Header, Model, PathParam, QueryParam, Request, Sending},
+    paths::Paths,
+    resync,
+    runner::CollectionRun,
+    script,
+    secrets::Secret,
+    url, vars,
 };
 
+const DIALOG_WIDTH: u16 = 50;
+const SECRETS_WIDTH: u16 = 90;
+const HISTORY_WIDTH: u16 = 80;
+const RUN_WIDTH: u16 = 90;
+const SYNC_WIDTH: u16 = 90;
+const HAR_WIDTH: u16 = 100;
+const CUSTOM_METHOD: &str = "Custom…";
+
+/// Option of the environment picker leaving only the collection variables.
+const NO_ENVIRONMENT: &str = "No environment";
+
+/// Tab shown in the request pane.
+#[derive(Clone, Copy, PartialEq)]
+enum RequestTab {
+    Body,
+    Query,
+    Headers,
+    Path,
+    Auth,
+    Tests,
+    Capture,
+    PreScript,
+    PostScript,
+}
+
+const REQUEST_TABS: [(RequestTab, &str); 9] = [
+    (RequestTab::Body, "Body"),
+    (RequestTab::Query, "Query"),
+    (RequestTab::Headers, "Headers"),
+    (RequestTab::Path, "Path"),
+    (RequestTab::Auth, "Auth"),
+    (RequestTab::Tests, "Tests"),
+    (RequestTab::Capture, "Capture"),
+    (RequestTab::PreScript, "Pre"),
+    (RequestTab::PostScript, "Post"),
+];
+
+/// Whose auth the auth tab edits.
+#[derive(Clone, Copy, PartialEq)]
+enum AuthScope {
+    Request,
+    Folder,
+}
+
+/// What to do once the secrets are unlocked.
+#[derive(Clone, Copy)]
+enum AfterUnlock {
+    Execute,
+    OpenSecrets,
+    ShowCode,
+    CompareEnvironments,
+}
+
+/// Request the chosen method is applied to.
+#[derive(Clone, Copy)]
+enum MethodTarget {
+    New,
+    Existing(usize),
+}
+
+/// Edited request sent in two environments, diffed once both responses are there.
+struct Comparison {
+    sides: [ComparedSide; 2],
+}
+
+struct ComparedSide {
+    environment: String,
+    sending: Sending,
+    result: Option<Result<HttpResponse, String>>,
+}
+
+impl ComparedSide {
+    fn title(&self) -> String {
+        format!("{} {} · {}", self.sending.request.method, self.sending.request.title, self.environment)
+    }
+}
+
+/// Responses shown in the diff view, kept to re-diff them with other ignored fields.
+struct DiffSource {
+    left: ReceivedResponse,
+    right: ReceivedResponse,
+    left_title: String,
+    right_title: String,
+}
+
+enum Dialog {
+    NewRequestTitle(String, Prompt),
+    Rename(usize, Prompt),
+    ChangeMethod(MethodTarget, Picker),
+    CustomMethod(MethodTarget, Prompt),
+    ConfirmDelete(usize, Picker),
+    MoveToFolder(usize, Prompt),
+    AuthType(AuthScope, Picker),
+    Passphrase(AfterUnlock, Prompt),
+    NewPassphrase(Prompt),
+    Secrets(TableDialog),
+    History(HistoryBrowser),
+    Run(RunView),
+    Sync(SyncView),
+    Diff(DiffSource, DiffView),
+    DiffIgnore(DiffSource, Prompt),
+    Import(Prompt),
+    Har(HarView),
+    Code(CodeView),
+    SaveCode(String, Prompt),
+    Environment(Picker),
+    CompareWith(Picker),
+}
+
+impl Dialog {
+    fn title(&self) -> &str {
+        match self {
+            Dialog::ChangeMethod(MethodTarget::New, _)
+            | Dialog::CustomMethod(MethodTarget::New, _)
+            | Dialog::NewRequestTitle(_, _) => "New request",
+            Dialog::Rename(_, _) => "Rename request",
+            Dialog::ChangeMethod(_, _) | Dialog::CustomMethod(_, _) => "Change method",
+            Dialog::ConfirmDelete(_, _) => "Delete request",
+            Dialog::MoveToFolder(_, _) => "Move to folder",
+            Dialog::AuthType(AuthScope::Request, _) => "Request auth",
+            Dialog::AuthType(AuthScope::Folder, _) => "Folder auth",
+            Dialog::Passphrase(_, _) | Dialog::NewPassphrase(_) => "Secrets passphrase",
+            Dialog::Secrets(_) => "Secrets · use as {{name}}, sources: stored, env, cmd",
+            Dialog::History(_) => "Request history",
+            Dialog::Run(_) => "Run collection",
+            Dialog::Sync(_) => "Sync with the OpenAPI spec",
+            Dialog::Diff(_, _) => "Response diff",
+            Dialog::DiffIgnore(_, _) => "Ignored fields",
+            Dialog::Import(_) => "Import OpenAPI, Postman, Insomnia or HAR",
+            Dialog::Har(_) => "Import HAR entries",
+            Dialog::Code(_) => "Code",
+            Dialog::SaveCode(_, _) => "Save code",
+            Dialog::Environment(_) => "Environment",
+            Dialog::CompareWith(_) => "Compare environments",
+        }
+    }
+
+    fn width(&self) -> u16 {
+        match self {
+            Dialog::Secrets(_) => SECRETS_WIDTH,
+            Dialog::History(_) => HISTORY_WIDTH,
+            Dialog::Run(_) => RUN_WIDTH,
+            Dialog::Sync(_) => SYNC_WIDTH,
+            Dialog::Har(_) => HAR_WIDTH,
+            _ => DIALOG_WIDTH,
+        }
+    }
+
+    fn height(&self) -> u16 {
+        match self {
+            Dialog::ChangeMethod(_, p)
+            | Dialog::ConfirmDelete(_, p)
+            | Dialog::AuthType(_, p)
+            | Dialog::Environment(p)
+            | Dialog::CompareWith(p) => p.height(),
+            Dialog::NewRequestTitle(_, _)
+            | Dialog::Rename(_, _)
+            | Dialog::CustomMethod(_, _)
+            | Dialog::MoveToFolder(_, _)
+            | Dialog::Passphrase(_, _)
+            | Dialog::NewPassphrase(_)
+            | Dialog::DiffIgnore(_, _)
+            | Dialog::SaveCode(_, _)
+            | Dialog::Import(_) => 5,
+            Dialog::Secrets(t) => t.height(),
+            Dialog::History(h) => h.height(),
+            Dialog::Run(r) => r.height(),
+            Dialog::Sync(s) => s.height(),
+            Dialog::Har(h) => h.height(),
+            // Shown in an overlay, see `show_diff` and `show_code`
+            Dialog::Diff(_, _) | Dialog::Code(_) => 0,
+        }
+    }
+
+    fn element(&mut self) -> &mut dyn Element {
+        match self {
+            Dialog::ChangeMethod(_, p)
+            | Dialog::ConfirmDelete(_, p)
+            | Dialog::AuthType(_, p)
+            | Dialog::Environment(p)
+            | Dialog::CompareWith(p) => p,
+            Dialog::NewRequestTitle(_, p)
+            | Dialog::Rename(_, p)
+            | Dialog::CustomMethod(_, p)
+            | Dialog::MoveToFolder(_, p)
+            | Dialog::Passphrase(_, p)
+            | Dialog::NewPassphrase(p)
+            | Dialog::DiffIgnore(_, p)
+            | Dialog::SaveCode(_, p)
+            | Dialog::Import(p) => p,
+            Dialog::Secrets(t) => t,
+            Dialog::History(h) => h,
+            Dialog::Run(r) => r,
+            Dialog::Sync(s) => s,
+            Dialog::Har(h) => h,
+            Dialog::Diff(_, d) => d,
+            Dialog::Code(c) => c,
+        }
+    }
+
+    fn state(&self) -> &DialogState {
+        match self {
+            Dialog::ChangeMethod(_, p)
+            | Dialog::ConfirmDelete(_, p)
+            | Dialog::AuthType(_, p)
+            | Dialog::Environment(p)
+            | Dialog::CompareWith(p) => &p.state,
+            Dialog::NewRequestTitle(_, p)
+            | Dialog::Rename(_, p)
+            | Dialog::CustomMethod(_, p)
+            | Dialog::MoveToFolder(_, p)
+            | Dialog::Passphrase(_, p)
+            | Dialog::NewPassphrase(p)
+            | Dialog::DiffIgnore(_, p)
+            | Dialog::SaveCode(_, p)
+            | Dialog::Import(p) => &p.state,
+            Dialog::Secrets(t) => &t.state,
+            Dialog::History(h) => &h.state,
+            Dialog::Run(r) => &r.state,
+            Dialog::Sync(s) => &s.state,
+            Dialog::Har(h) => &h.state,
+            Dialog::Diff(_, d) => &d.state,
+            Dialog::Code(c) => &c.state,
+        }
+    }
+}
+
+fn method_picker(label: &str, current: &str) -> Picker {
+    let mut options = http::METHODS.to_vec();
+    options.push(CUSTOM_METHOD);
+
+    let current = current.to_uppercase();
+    let selected = if current.is_empty() || http::METHODS.contains(&current.as_str()) {
+        current.as_str()
+    } else {
+        CUSTOM_METHOD
+    };
+
+    Picker::new(label, &options).select(selected)
+}
+
+fn auth_table() -> Table {
+    Table::new(vec![
+        Column { title: "Field", editable: false },
+        Column { title: "Value", editable: true },
+    ])
+}
+
+fn rows_from_auth(auth: &Auth) -> Vec<TableRow> {
+    auth.fields()
+        .into_iter()
+        .map(|(field, value)| TableRow {
+            cells: vec![field.to_string(), value],
+            enabled: true,
+            masked: Auth::is_secret_field(field),
+        })
+        .collect()
+}
+
+fn tests_table() -> Table {
+    Table::new(vec![
+        Column { title: "Check", editable: true },
+        Column { title: "Target", editable: true },
+        Column { title: "Expected", editable: true },
+    ])
+    .toggleable()
+    .growable()
+}
+
+fn rows_from_assertions(assertions: &[Assertion]) -> Vec<TableRow> {
+    assertions
+        .iter()
+        .map(|a| TableRow {
+            cells: vec![a.kind.name().to_string(), a.target.clone(), a.expected.clone()],
+            enabled: a.enabled,
+            masked: false,
+        })
+        .collect()
+}
+
+/// Rows with an unknown check are left out, the tests tab points them out.
+fn assertions_from_rows(rows: &[TableRow]) -> Vec<Assertion> {
+    rows
+        .iter()
+        .filter_map(|r| Some(Assertion {
+            kind: AssertionKind::from_name(&r.cells[0])?,
+            target: r.cells[1].clone(),
+            expected: r.cells[2].clone(),
+            enabled: r.enabled,
+        }))
+        .collect()
+}
+
+fn captures_table() -> Table {
+    Table::new(vec![
+        Column { title: "Variable", editable: true },
+        Column { title: "From", editable: true },
+        Column { title: "Expression", editable: true },
+    ])
+    .toggleable()
+    .growable()
+}
+
+fn rows_from_captures(captures: &[Capture]) -> Vec<TableRow> {
+    captures
+        .iter()
+        .map(|c| TableRow {
+            cells: vec![c.variable.clone(), c.source.name().to_string(), c.expression.clone()],
+            enabled: c.enabled,
+            masked: false,
+        })
+        .collect()
+}
+
+/// Rows with an unknown source are left out, the capture tab points them out.
+fn captures_from_rows(rows: &[TableRow]) -> Vec<Capture> {
+    rows
+        .iter()
+        .filter_map(|r| Some(Capture {
+            variable: r.cells[0].trim().to_string(),
+            source: CaptureSource::from_name(&r.cells[1])?,
+            expression: r.cells[2].clone(),
+            enabled: r.enabled,
+        }))
+        .collect()
+}
+
+fn secrets_table(secrets: &BTreeMap<String, Secret>) -> Table {
+    let mut table = Table::new(vec![
+        Column { title: "Name", editable: true },
+        Column { title: "Source", editable: true },
+        Column { title: "Value, variable or command", editable: true },
+    ])
+    .growable();
+
+    table.set_rows(
+        secrets
+            .iter()
+            .map(|(name, secret)| TableRow {
+                cells: vec![name.clone(), secret.source().to_string(), secret.value().to_string()],
+                enabled: true,
+                masked: matches!(secret, Secret::Stored { .. }),
+            })
+            .collect(),
+    );
+
+    table
+}
+
+fn secrets_from_rows(rows: &[TableRow]) -> BTreeMap<String, Secret> {
+    rows
+        .iter()
+        .filter(|r| !r.cells[0].trim().is_empty())
+        .map(|r| (r.cells[0].trim().to_string(), Secret::from_source(&r.cells[1], &r.cells[2])))
+        .collect()
+}
+
+fn query_table() -> Table {
+    Table::new(vec![
+        Column { title: "Key", editable: true },
+        Column { title: "Value", editable: true },
+    ])
+    .toggleable()
+    .growable()
+}
+
+fn header_table() -> Table {
+    Table::new(vec![
+        Column { title: "Name", editable: true },
+        Column { title: "Value", editable: true },
+    ])
+    .toggleable()
+    .growable()
+}
+
+fn path_table() -> Table {
+    Table::new(vec![
+        Column { title: "Name", editable: false },
+        Column { title: "Type", editable: true },
+        Column { title: "Value", editable: true },
+    ])
+}
+
+fn rows_from_path_params(params: &[PathParam]) -> Vec<TableRow> {
+    params
+        .iter()
+        .map(|p| TableRow {
+            cells: vec![
+                if p.required { format!("{}*", p.name) } else { p.name.clone() },
+                p.param_type.clone(),
+                p.value.clone(),
+            ],
+            enabled: true,
+            masked: false,
+        })
+        .collect()
+}
+
+fn rows_from_params(params: &[QueryParam]) -> Vec<TableRow> {
+    params
+        .iter()
+        .map(|p| TableRow {
+            cells: vec![p.key.clone(), p.value.clone()],
+            enabled: p.enabled,
+            masked: false,
+        })
+        .collect()
+}
+
+fn params_from_rows(rows: &[TableRow]) -> Vec<QueryParam> {
+    rows
+        .iter()
+        .map(|r| QueryParam {
+            key: r.cells[0].clone(),
+            value: r.cells[1].clone(),
+            enabled: r.enabled,
+        })
+        .collect()
+}
+
+fn rows_from_headers(headers: &[Header]) -> Vec<TableRow> {
+    headers
+        .iter()
+        .map(|h| TableRow {
+            cells: vec![h.name.clone(), h.value.clone()],
+            enabled: h.enabled,
+            masked: false,
+        })
+        .collect()
+}
+
+fn headers_from_rows(rows: &[TableRow]) -> Vec<Header> {
+    rows
+        .iter()
+        .map(|r| Header {
+            name: r.cells[0].clone(),
+            value: r.cells[1].clone(),
+            enabled: r.enabled,
+        })
+        .collect()
+}
+
 pub struct Application {
     model: Model,
     layout: Layout,
     input: Input,
+    method: MethodView,
     options: List,
     editor: Editor,
+    pre_script: Editor,
+    post_script: Editor,
+    query: Table,
+    headers: Table,
+    path: Table,
+    /// Path params of the opened request, the path table shows them
+    path_params: Vec<PathParam>,
+    auth_table: Table,
+    tests: Table,
+    captures: Table,
+    /// Auth of the opened request, folder auth is edited in the model directly
+    auth: Auth,
+    auth_scope: AuthScope,
+    request_tab: RequestTab,
+    response: ResponseView,
     shortcuts: ShortcutsView,
+    dialog: Option<(Dialog, Block)>,
     current_request: Option<Request>,
     current_request_id: Option<usize>,
+    /// Executed request on its way, its response is shown once it's there
+    sending: Option<Sending>,
+    comparison: Option<Comparison>,
 }
 
 impl Application {
-    pub fn new() -> Self {
+    pub fn new(model: Model) -> Self {
         let layout = Layout::new();
-        let model = Model::load_from_disk_or_default();
 
-        Self {
+        let mut app = Self {
             current_request: None,
             current_request_id: None,
             input: Input::new(),
+            method: MethodView::new(),
             options: List::new(
                 Arc::clone(&model.requests),
                 layout.list_cont.width.into(),
             ),
             editor: Editor::new(),
+            pre_script: Editor::new().with_syntax("Rust"),
+            post_script: Editor::new().with_syntax("Rust"),
+            query: query_table(),
+            headers: header_table(),
+            path: path_table(),
+            path_params: vec![],
+            auth_table: auth_table(),
+            tests: tests_table(),
+            captures: captures_table(),
+            auth: Auth::Inherit,
+            auth_scope: AuthScope::Request,
+            request_tab: RequestTab::Body,
+            response: ResponseView::new(),
             shortcuts: ShortcutsView::new(),
+            dialog: None,
+            sending: None,
+            comparison: None,
             model,
             layout,
+        };
+
+        app.refresh_masks();
+
+        let load_errors: Vec<&str> = [&app.model.history.load_error, &app.model.secrets.load_error]
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .collect();
+
+        if !load_errors.is_empty() {
+            app.response.set_error(&load_errors.join("\n"));
         }
+
+        app
     }
 
     pub fn check_option_selected(&mut self) {
         if self.options.option_selected {
-            let request_ptr = self.model.requests.read().unwrap()[self.options.selected].clone();
-            let request = Request::from(request_ptr.as_ref());
             self.options.option_selected = false;
-            self.input.set_val(&request.url);
-            self.editor.set_val(&request.body);
-            self.current_request = Some(request);
-            self.current_request_id = Some(self.options.selected);
+
+            let Some(selected) = self.options.get_selected() else {
+                return;
+            };
+
+            if let Some(request) = self.model.get_request(selected) {
+                self.current_request = Some(request.clone());
+                self.current_request_id = Some(selected);
+                self.show_request(&request);
+                self.layout.enter_select_mode();
+            }
+        }
+    }
+
+    /// Loads a version of the opened request into the input, the editor and the tables.
+    fn show_request(&mut self, request: &Request) {
+        self.input.set_val(&request.url);
+        self.editor.set_val(&request.body);
+        self.pre_script.set_val(&request.pre_script);
+        self.post_script.set_val(&request.post_script);
+        self.query.set_rows(rows_from_params(
+            &url::merge_query(&request.url, &request.query_params),
+        ));
+        self.headers.set_rows(rows_from_headers(&request.headers));
+        self.path_params = url::merge_path_params(&request.url, &request.path_params);
+        self.path.set_rows(rows_from_path_params(&self.path_params));
+        self.tests.set_rows(rows_from_assertions(&request.assertions));
+        self.captures.set_rows(rows_from_captures(&request.captures));
+        self.auth = request.auth.clone();
+        self.auth_scope = AuthScope::Request;
+        self.refresh_auth_table();
+        self.show_method(&request.method);
+    }
+
+    fn open_dialog(&mut self, dialog: Dialog) {
+        let block = self.layout.dialog(dialog.title(), dialog.width(), dialog.height());
+        self.dialog = Some((dialog, block));
+    }
+
+    pub fn check_list_action(&mut self) {
+        let selected = self.options.selected;
+        let selected_request = self.options
+            .get_selected()
+            .and_then(|i| self.model.get_request(i));
+
+        match (self.options.action.take(), selected_request) {
+            (Some(ListAction::New), _) => {
+                self.open_dialog(Dialog::ChangeMethod(
+                    MethodTarget::New,
+                    method_picker("Method of the new request:", "GET"),
+                ));
+            },
+            (Some(ListAction::Duplicate), Some(_)) => {
+                if let Some(i) = self.model.duplicate_request(selected) {
+                    self.options.select(i);
+                }
+            },
+            (Some(ListAction::Rename), Some(r)) => {
+                self.open_dialog(Dialog::Rename(
+                    selected,
+                    Prompt::new("Title:", &r.title),
+                ));
+            },
+            (Some(ListAction::ChangeMethod), Some(r)) => {
+                self.open_dialog(Dialog::ChangeMethod(
+                    MethodTarget::Existing(selected),
+                    method_picker("Method:", &r.method),
+                ));
+            },
+            (Some(ListAction::MoveToFolder), Some(r)) => {
+                self.open_dialog(Dialog::MoveToFolder(
+                    selected,
+                    Prompt::new("Folder, empty for none:", r.folder.as_deref().unwrap_or_default()),
+                ));
+            },
+            (Some(ListAction::Delete), Some(r)) => {
+                self.open_dialog(Dialog::ConfirmDelete(
+                    selected,
+                    Picker::confirm(&format!("Delete \"{}\"?", r.title)),
+                ));
+            },
+            (Some(ListAction::MoveUp), Some(_)) => self.move_request(selected, true),
+            (Some(ListAction::MoveDown), Some(_)) => self.move_request(selected, false),
+            _ => {}
+        }
+    }
+
+    fn move_request(&mut self, i: usize, up: bool) {
+        let target = self.model.move_request(i, up);
+
+        if self.current_request_id == Some(i) {
+            self.current_request_id = Some(target);
+        } else if self.current_request_id == Some(target) {
+            self.current_request_id = Some(i);
+        }
+
+        self.options.select(target);
+    }
+
+    /// Keeps the opened request in sync with list changes made through dialogs.
+    fn refresh_current_request(&mut self, i: usize) {
+        if self.current_request_id == Some(i) {
+            if let (Some(current), Some(updated)) = (
+                self.current_request.as_mut(),
+                self.model.get_request(i),
+            ) {
+                current.title = updated.title;
+                current.method = updated.method.clone();
+                current.folder = updated.folder.clone();
+
+                if updated.folder.is_none() {
+                    self.auth_scope = AuthScope::Request;
+                }
+
+                self.refresh_auth_table();
+                self.show_method(&updated.method);
+            }
+        }
+    }
+
+    /// Shows the method and warns in the body editor when the method doesn't take a body.
+    fn show_method(&mut self, method: &str) {
+        self.method.set_val(method);
+
+        let read_only = matches!(http::body_usage(method), BodyUsage::Forbidden);
+
+        if read_only && self.request_tab == RequestTab::Body && self.layout.req_cont.is_active() {
             self.layout.enter_select_mode();
         }
+
+        self.editor.set_read_only(read_only);
+        self.update_request_title();
+    }
+
+    fn update_request_title(&mut self) {
+        let method = self.method.get_value();
+
+        let tabs = REQUEST_TABS
+            .iter()
+            .map(|(tab, name)| if *tab == self.request_tab {
+                format!("[{}]", name)
+            } else {
+                name.to_string()
+            })
+            .collect::<Vec<String>>()
+            .join(" ");
+
+        let path_error = self.path_params
+            .iter()
+            .find_map(|p| p.validate().err());
+
+        let note = match (self.request_tab, http::body_usage(method)) {
+            (RequestTab::Path, _) if path_error.is_some() => {
+                format!(" · {}", path_error.unwrap_or_default())
+            },
+            (RequestTab::Path, _) if self.path_params.is_empty() => {
+                String::from(" · add {name} placeholders to the URL path")
+            },
+            (RequestTab::Headers, _) => String::from(" · a Content-Type set here replaces the JSON default"),
+            (RequestTab::Auth, _) => self.auth_note(),
+            (RequestTab::Tests, _) => self.tests_note(),
+            (RequestTab::Capture, _) => self.captures_note(),
+            (RequestTab::PreScript, _) => String::from(" · Rhai, runs before sending, can change request and vars"),
+            (RequestTab::PostScript, _) => String::from(" · Rhai, runs after a response, sees response and vars"),
+            (RequestTab::Body, BodyUsage::Discouraged) => {
+                format!(" · {} requests usually have no body", method)
+            },
+            (RequestTab::Body, BodyUsage::Forbidden) => {
+                format!(" · body is not sent with {} requests", method)
+            },
+            _ => String::new(),
+        };
+
+        self.layout.req_cont.set_title(Some(&format!("{}{}", tabs, note)));
+        self.editor.invalidate();
+        self.query.invalidate();
+        self.headers.invalidate();
+        self.path.invalidate();
+        self.auth_table.invalidate();
+        self.tests.invalidate();
+        self.captures.invalidate();
+        self.pre_script.invalidate();
+        self.post_script.invalidate();
+    }
+
+    fn tests_note(&self) -> String {
+        let unknown = self.tests
+            .get_rows()
+            .iter()
+            .find(|r| AssertionKind::from_name(&r.cells[0]).is_none());
+
+        match unknown {
+            Some(row) => format!(" · unknown check \"{}\", use {}", row.cells[0], assertions::KINDS.join("/")),
+            None if self.tests.get_rows().is_empty() => String::from(" · checked after every execution"),
+            None => String::new(),
+        }
+    }
+
+    fn captures_note(&self) -> String {
+        let unknown = self.captures
+            .get_rows()
+            .iter()
+            .find(|r| CaptureSource::from_name(&r.cells[1]).is_none());
+
+        match unknown {
+            Some(row) => format!(" · unknown source \"{}\", use {}", row.cells[1], capture::SOURCES.join("/")),
+            None if self.captures.get_rows().is_empty() => {
+                String::from(" · stores values of 2xx responses into {{variables}}")
+            },
+            None => String::new(),
+        }
+    }
+
+    fn current_folder(&self) -> Option<String> {
+        self.current_request.as_ref().and_then(|r| r.folder.clone())
+    }
+
+    /// Auth shown in the auth tab.
+    fn shown_auth(&self) -> Auth {
+        match (self.auth_scope, self.current_folder()) {
+            (AuthScope::Folder, Some(folder)) => self.model
+                .get_folder(&folder)
+                .map(|f| f.auth.clone())
+                .unwrap_or(Auth::None),
+            _ => self.auth.clone(),
+        }
+    }
+
+    fn auth_note(&self) -> String {
+        let folder = self.current_folder();
+
+        match (self.auth_scope, &self.auth, folder) {
+            (AuthScope::Folder, _, Some(folder)) => {
+                format!(" · folder {}: {}", folder, self.shown_auth().kind())
+            },
+            (_, Auth::Inherit, Some(folder)) => {
+                let inherited = self.model
+                    .get_folder(&folder)
+                    .map_or(Auth::None.kind(), |f| f.auth.kind());
+
+                format!(" · inherited from {}: {}", folder, inherited)
+            },
+            (_, Auth::Inherit, None) => String::from(" · not in a folder, nothing to inherit"),
+            (_, auth, _) => format!(" · {}", auth.kind()),
+        }
+    }
+
+    fn refresh_auth_table(&mut self) {
+        self.auth_table.set_rows(rows_from_auth(&self.shown_auth()));
+    }
+
+    /// Takes values typed into the auth table.
+    fn sync_auth(&mut self) {
+        if !self.auth_table.changed {
+            return;
+        }
+
+        self.auth_table.changed = false;
+
+        let mut auth = self.shown_auth();
+
+        for row in self.auth_table.get_rows() {
+            auth.set_field(&row.cells[0], &row.cells[1]);
+        }
+
+        self.set_shown_auth(auth);
+    }
+
+    fn set_shown_auth(&mut self, auth: Auth) {
+        match (self.auth_scope, self.current_folder()) {
+            (AuthScope::Folder, Some(folder)) => self.model.set_folder_auth(&folder, &auth),
+            _ => self.auth = auth,
+        }
+    }
+
+    fn on_auth_event(&mut self, event: &Event, code: &KeyCode) -> std::io::Result<()> {
+        if self.auth_table.is_editing() {
+            self.auth_table.on_event(event)?;
+            self.sync_auth();
+            return Ok(());
+        }
+
+        match code {
+            KeyCode::Char('t') if self.current_request.is_some() => {
+                let kinds: Vec<&str> = match self.auth_scope {
+                    // Folders have nothing to inherit from
+                    AuthScope::Folder => auth::KINDS[1..].to_vec(),
+                    AuthScope::Request => auth::KINDS.to_vec(),
+                };
+
+                self.open_dialog(Dialog::AuthType(
+                    self.auth_scope,
+                    Picker::new("Auth type:", &kinds).select(self.shown_auth().kind()),
+                ));
+            },
+            KeyCode::Char('f') => {
+                self.auth_scope = match (self.auth_scope, self.current_folder()) {
+                    (AuthScope::Request, Some(_)) => AuthScope::Folder,
+                    _ => AuthScope::Request,
+                };
+
+                self.refresh_auth_table();
+                self.update_request_title();
+            },
+            _ => {
+                self.auth_table.on_event(event)?;
+                self.sync_auth();
+            },
+        }
+
+        Ok(())
+    }
+
+    fn switch_request_tab(&mut self) {
+        self.request_tab = match self.request_tab {
+            RequestTab::Body => RequestTab::Query,
+            RequestTab::Query => RequestTab::Headers,
+            RequestTab::Headers => RequestTab::Path,
+            RequestTab::Path => RequestTab::Auth,
+            RequestTab::Auth => RequestTab::Tests,
+            RequestTab::Tests => RequestTab::Capture,
+            RequestTab::Capture => RequestTab::PreScript,
+            RequestTab::PreScript => RequestTab::PostScript,
+            RequestTab::PostScript => RequestTab::Body,
+        };
+
+        self.update_request_title();
+    }
+
+    /// Re-parses the URL into the query table after the URL was edited.
+    fn sync_query_from_url(&mut self) {
+        let params = params_from_rows(self.query.get_rows());
+        let merged = url::merge_query(&self.input.get_value(), &params);
+        self.query.set_rows(rows_from_params(&merged));
+
+        let path_params = url::merge_path_params(&self.input.get_value(), &self.path_params);
+
+        if path_params.len() != self.path_params.len()
+            || path_params.iter().zip(self.path_params.iter()).any(|(a, b)| a.name != b.name) {
+            self.path_params = path_params;
+            self.path.set_rows(rows_from_path_params(&self.path_params));
+            self.update_request_title();
+        }
+    }
+
+    /// Takes types and values typed into the path table.
+    fn sync_path_params(&mut self) {
+        if self.path.changed {
+            self.path.changed = false;
+
+            for (param, row) in self.path_params.iter_mut().zip(self.path.get_rows()) {
+                param.param_type = row.cells[1].trim().to_lowercase();
+                param.value = row.cells[2].clone();
+            }
+
+            self.update_request_title();
+        }
+    }
+
+    fn is_editing_table(&self) -> bool {
+        match self.request_tab {
+            RequestTab::Body | RequestTab::PreScript | RequestTab::PostScript => false,
+            RequestTab::Query => self.query.is_editing(),
+            RequestTab::Headers => self.headers.is_editing(),
+            RequestTab::Path => self.path.is_editing(),
+            RequestTab::Auth => self.auth_table.is_editing(),
+            RequestTab::Tests => self.tests.is_editing(),
+            RequestTab::Capture => self.captures.is_editing(),
+        }
+    }
+
+    /// Rebuilds the URL after the query table was edited.
+    fn sync_url_from_query(&mut self) {
+        if self.query.changed {
+            self.query.changed = false;
+
+            let params = params_from_rows(self.query.get_rows());
+            self.input.set_val(&url::with_query(&self.input.get_value(), &params));
+        }
+    }
+
+    fn apply_method(&mut self, target: MethodTarget, method: &str) {
+        match target {
+            MethodTarget::New => {
+                self.open_dialog(Dialog::NewRequestTitle(
+                    method.to_string(),
+                    Prompt::new("Title:", "New request"),
+                ));
+            },
+            MethodTarget::Existing(i) => {
+                self.model.set_request_method(i, method);
+                self.refresh_current_request(i);
+            },
+        }
+    }
+
+    /// Request as currently edited in the input and the body editor.
+    fn edited_request(&self) -> Option<Request> {
+        self.current_request
+            .as_ref()
+            .map(|r| Request {
+                url: self.input.get_value(),
+                body: self.editor.get_body(),
+                pre_script: self.pre_script.get_body(),
+                post_script: self.post_script.get_body(),
+                query_params: params_from_rows(self.query.get_rows()),
+                headers: headers_from_rows(self.headers.get_rows()),
+                path_params: self.path_params.clone(),
+                auth: self.auth.clone(),
+                assertions: assertions_from_rows(self.tests.get_rows()),
+                captures: captures_from_rows(self.captures.get_rows()),
+                ..r.clone()
+            })
+    }
+
+    fn execute(&mut self) {
+        let Some(request) = self.edited_request() else {
+            self.response.set_message("Open a request from the list first");
+            return;
+        };
+
+        if self.sending.is_some() {
+            self.response.set_message("Still sending the last request");
+            return;
+        }
+
+        if self.model.secrets.is_locked() && self.uses_variables(&request) {
+            self.open_dialog(Dialog::Passphrase(
+                AfterUnlock::Execute,
+                Prompt::new("Passphrase:", "").masked(),
+            ));
+            return;
+        }
+
+        self.response.set_message("Sending…");
+        self.sending = Some(self.model.send_request(&request));
+    }
+
+    /// Shows the response of the executed request once it's there.
+    fn check_response(&mut self) {
+        let Some(sending) = &self.sending else {
+            return;
+        };
+
+        let Some(result) = self.model.receive_response(sending, false) else {
+            return;
+        };
+
+        let request = sending.request.clone();
+        self.sending = None;
+
+        match result {
+            Ok(response) => {
+                // The post-response script sees the captured values
+                let captured = self.model.capture(&request, &response);
+                let mut results = self.model.check_assertions(&request, &response);
+                results.extend(self.model.run_post_script(&request, &response));
+
+                self.response.set_response(&response);
+                self.response.set_log(&self.model.script_log);
+                self.response.set_captures(&captured);
+                self.response.set_assertions(&results);
+
+                if let Some(operation) = &request.operation {
+                    self.response.set_contract(operation, &contract::check(&mut self.model.specs, operation, &response));
+                }
+            },
+            Err(e) => {
+                self.response.set_error(&e);
+                self.response.set_log(&self.model.script_log);
+            },
+        }
+
+        self.model.script_log.clear();
+
+        // Values of command secrets are known once they've run
+        self.refresh_masks();
+    }
+
+    fn uses_variables(&self, request: &Request) -> bool {
+        let auth = self.model.effective_auth(request);
+
+        let placeholders: Vec<String> = [serde_json::to_string(request), serde_json::to_string(&auth)]
+            .iter()
+            .flatten()
+            .flat_map(|json| vars::placeholders(json).into_iter().map(|(_, name)| name.to_string()))
+            .collect();
+
+        let script_secrets = [&request.pre_script, &request.post_script]
+            .into_iter()
+            .flat_map(|s| script::secret_names(s));
+
+        placeholders
+            .into_iter()
+            .chain(script_secrets)
+            .any(|name| !self.model.variables.contains_key(&name) && !self.model.collection_variables.contains_key(&name))
+    }
+
+    fn refresh_masks(&mut self) {
+        let values = self.model.secrets.known_values();
+
+        self.input.set_masked(values.clone());
+        self.editor.set_masked(values.clone());
+        self.response.set_masked(values);
+    }
+
+    fn open_secrets(&mut self) {
+        if self.model.secrets.is_locked() {
+            self.open_dialog(Dialog::Passphrase(
+                AfterUnlock::OpenSecrets,
+                Prompt::new("Passphrase:", "").masked(),
+            ));
+        } else {
+            self.open_dialog(Dialog::Secrets(TableDialog::new(
+                secrets_table(self.model.secrets.get_all()),
+            )));
+        }
+    }
+
+    /// Client code sending the edited request, placeholders filled like for
+    /// sending it, from the active environment first. Nothing is fetched, an
+    /// OAuth2 token is only in the code once a send got one.
+    fn show_code(&mut self) {
+        let Some(request) = self.edited_request() else {
+            self.response.set_message("Open a request from the list first");
+            return;
+        };
+
+        if self.model.secrets.is_locked() && self.uses_variables(&request) {
+            self.open_dialog(Dialog::Passphrase(
+                AfterUnlock::ShowCode,
+                Prompt::new("Passphrase:", "").masked(),
+            ));
+            return;
+        }
+
+        match self.model.resolve_request(&request) {
+            Ok(resolved) => {
+                let environment = self.model.active_environment().map(|e| e.name.as_str());
+                let view = CodeView::new(resolved, environment, self.model.secrets.known_values());
+                let block = self.layout.overlay("Code");
+                self.dialog = Some((Dialog::Code(view), block));
+            },
+            Err(e) => self.response.set_error(&e),
+        }
+    }
+
+    fn save_code(&mut self, code: &str, path: &str) {
+        if path.trim().is_empty() {
+            return;
+        }
+
+        let path = Paths::project_root().join(path.trim());
+
+        match std::fs::write(&path, code) {
+            Ok(()) => self.response.set_message(&format!("Saved the code to {}", path.display())),
+            Err(e) => self.response.set_error(&format!("Couldn't write {}: {}", path.display(), e)),
+        }
+    }
+
+    fn open_environments(&mut self) {
+        if self.model.environments.is_empty() {
+            self.response.set_message("No environments, import a Postman or Insomnia one with i");
+            return;
+        }
+
+        let mut options: Vec<&str> = self.model.environments.iter().map(|e| e.name.as_str()).collect();
+        options.push(NO_ENVIRONMENT);
+
+        let current = self.model.active_environment().map(|e| e.name.as_str()).unwrap_or(NO_ENVIRONMENT);
+        let picker = Picker::new("Variables are looked up in:", &options).select(current);
+
+        self.open_dialog(Dialog::Environment(picker));
+    }
+
+    /// Asks which environment the edited request is compared in, next to the active one.
+    fn open_comparison(&mut self) {
+        let Some(request) = self.edited_request() else {
+            self.response.set_message("Open a request from the list first");
+            return;
+        };
+
+        if self.model.secrets.is_locked() && self.uses_variables(&request) {
+            self.open_dialog(Dialog::Passphrase(
+                AfterUnlock::CompareEnvironments,
+                Prompt::new("Passphrase:", "").masked(),
+            ));
+            return;
+        }
+
+        let active = self.model.active_environment().map(|e| e.name.as_str());
+        let mut options: Vec<&str> = self.model.environments
+            .iter()
+            .map(|e| e.name.as_str())
+            .filter(|name| Some(*name) != active)
+            .collect();
+
+        if active.is_some() {
+            options.push(NO_ENVIRONMENT);
+        }
+
+        if options.is_empty() {
+            self.response.set_message("No other environment to compare with, import one with i");
+            return;
+        }
+
+        let label = format!("Send in {} and in:", active.unwrap_or(NO_ENVIRONMENT));
+        self.open_dialog(Dialog::CompareWith(Picker::new(&label, &options)));
+    }
+
+    /// Sends the edited request in the active environment and the other one.
+    fn compare_environments(&mut self, other: Option<&str>) {
+        let Some(request) = self.edited_request() else {
+            return;
+        };
+
+        if self.comparison.is_some() {
+            self.response.set_message("Still comparing the last request");
+            return;
+        }
+
+        let active = self.model.environment.clone();
+        let mut send = |environment: Option<&str>| ComparedSide {
+            environment: environment.unwrap_or(NO_ENVIRONMENT).to_string(),
+            sending: self.model.send_request_in(&request, environment),
+            result: None,
+        };
+        let sides = [send(active.as_deref()), send(other)];
+
+        self.response.set_message(&format!(
+            "Sending in {} and {}…",
+            sides[0].environment, sides[1].environment
+        ));
+        self.comparison = Some(Comparison { sides });
+    }
+
+    /// Shows the diff of the compared responses once both are there.
+    fn check_comparison(&mut self) {
+        let Some(comparison) = &mut self.comparison else {
+            return;
+        };
+
+        for side in comparison.sides.iter_mut() {
+            if side.result.is_none() {
+                side.result = self.model.receive_response(&side.sending, false);
+            }
+        }
+
+        if comparison.sides.iter().any(|side| side.result.is_none()) {
+            return;
+        }
+
+        let Some(Comparison { sides: [left, right] }) = self.comparison.take() else {
+            return;
+        };
+
+        self.model.script_log.clear();
+        let (left_title, right_title) = (left.title(), right.title());
+
+        match (left.result, right.result) {
+            (Some(Ok(left_response)), Some(Ok(right_response))) => {
+                self.response.set_message("");
+
+                self.show_diff(DiffSource {
+                    left: ReceivedResponse::redacted(&left_response, &self.model.secrets),
+                    right: ReceivedResponse::redacted(&right_response, &self.model.secrets),
+                    left_title,
+                    right_title,
+                });
+            },
+            (Some(Err(e)), _) => self.response.set_error(&format!("Couldn't send in {}: {}", left.environment, e)),
+            (_, Some(Err(e))) => self.response.set_error(&format!("Couldn't send in {}: {}", right.environment, e)),
+            _ => {},
+        }
+    }
+
+    fn toggle_tls_verification(&mut self) {
+        let insecure = !self.model.tls_insecure();
+
+        if let Err(e) = self.model.set_tls_insecure(insecure) {
+            self.response.set_error(&e);
+            return;
+        }
+
+        let environment = self.model.environment.clone().unwrap_or_default();
+
+        self.response.set_message(&match insecure {
+            true => format!("TLS verification is off in {}, any server certificate is accepted", environment),
+            false => format!("TLS verification is on in {}", environment),
+        });
+    }
+
+    fn save_secrets(&mut self) {
+        if let Err(e) = self.model.secrets.save() {
+            self.response.set_error(&e);
+        }
+
+        self.refresh_masks();
+    }
+
+    fn open_history(&mut self) {
+        let Some(request) = self.current_request.as_ref() else {
+            self.response.set_message("Open a request from the list first");
+            return;
+        };
+
+        let entries = self.model.history
+            .for_request(&request.id)
+            .iter()
+            .map(|e| e.summary())
+            .collect();
+
+        self.open_dialog(Dialog::History(HistoryBrowser::new(entries)));
+    }
+
+    fn open_run(&mut self) {
+        let folder = self.current_folder();
+        let requests = self.model.requests.read().unwrap().len();
+
+        self.open_dialog(Dialog::Run(RunView::new(folder.as_deref(), requests)));
+    }
+
+    /// Compares the collection with the spec its requests were imported from,
+    /// the open request's spec when it has one.
+    fn open_sync(&mut self) {
+        let current = self.current_request.as_ref().and_then(|r| r.operation.as_ref()).map(|o| o.spec.clone());
+
+        let spec = current
+            .or_else(|| self.model.requests
+                .read().unwrap()
+                .iter()
+                .find_map(|r| r.operation.as_ref().map(|o| o.spec.clone())))
+            .map(|spec| Paths::project_root().join(spec))
+            .unwrap_or(Paths::openapi_path());
+
+        let (incoming, folder) = match import::openapi(&spec) {
+            Ok(imported) => imported,
+            Err(e) => {
+                self.response.set_error(&e);
+                return;
+            },
+        };
+
+        let existing: Vec<Request> = self.model.requests
+            .read().unwrap()
+            .iter()
+            .map(|r| Request::from(r.as_ref()))
+            .collect();
+
+        let spec = contract::spec_name(&spec);
+        let changes = resync::plan(&existing, incoming, &spec);
+
+        self.open_dialog(Dialog::Sync(SyncView::new(&spec, folder, changes)));
+    }
+
+    /// Adds the requests of a spec or collection file, relative paths are taken from the project root.
+    fn import_file(&mut self, path: &str) {
+        if path.trim().is_empty() {
+            return;
+        }
+
+        let path = Paths::project_root().join(path.trim());
+
+        // Recordings hold every request of a page, so the entries are picked first
+        if har::is_har_file(&path) {
+            match har::read(&path) {
+                Ok(entries) => self.open_dialog(Dialog::Har(HarView::new(&har::folder_name(&path), entries))),
+                Err(e) => self.response.set_error(&e),
+            }
+
+            return;
+        }
+
+        match import::import_file(&mut self.model, &path) {
+            Ok(summary) => {
+                self.response.set_message(&summary);
+                self.options.invalidate();
+            },
+            Err(e) => self.response.set_error(&e),
+        }
+    }
+
+    fn apply_sync(&mut self, view: SyncView) {
+        let current = self.current_request.as_ref().map(|r| r.id.clone());
+
+        let reload = view.changes
+            .iter()
+            .any(|c| c.accepted && c.request_id.is_some() && c.request_id == current);
+
+        self.model.apply_sync(view.changes, view.folder);
+
+        let index = current.and_then(|id| self.model.requests.read().unwrap().iter().position(|r| r.id == id));
+
+        match index {
+            Some(i) if reload => {
+                self.current_request = self.model.get_request(i);
+                self.current_request_id = Some(i);
+
+                if let Some(request) = self.current_request.clone() {
+                    self.show_request(&request);
+                }
+            },
+            Some(i) => self.current_request_id = Some(i),
+            None if self.current_request.is_some() => self.clear_current_request(),
+            None => {},
+        }
+
+        self.options.invalidate();
+    }
+
+    /// Starts the run once its settings are confirmed in the run dialog.
+    fn start_run(&mut self) {
+        let settings = match &mut self.dialog {
+            Some((Dialog::Run(view), _)) if view.start => {
+                view.start = false;
+                view.settings()
+            },
+            _ => return,
+        };
+
+        let run = settings
+            .and_then(|settings| CollectionRun::new(&self.model, &settings))
+            .and_then(|run| {
+                if self.model.secrets.is_locked() && run.requests().iter().any(|r| self.uses_variables(r)) {
+                    Err(String::from("Secrets are locked, unlock them with [v] first"))
+                } else {
+                    Ok(run)
+                }
+            });
+
+        if let Some((Dialog::Run(view), _)) = &mut self.dialog {
+            match run {
+                Ok(run) => view.begin(run),
+                Err(e) => view.set_error(&e),
+            }
+        }
+    }
+
+    fn apply_history_action(&mut self, action: HistoryAction, selected: usize, marked: Option<usize>) {
+        let Some(id) = self.current_request.as_ref().map(|r| r.id.clone()) else {
+            return;
+        };
+
+        let entries: Vec<HistoryEntry> = self.model.history
+            .for_request(&id)
+            .into_iter()
+            .cloned()
+            .collect();
+
+        let Some(entry) = entries.get(selected) else {
+            return;
+        };
+
+        let time = history::format_time(entry.timestamp);
+
+        match (action, &entry.response) {
+            (HistoryAction::Restore, _) => {
+                if let Some(current) = self.current_request.as_mut() {
+                    current.method = entry.request.method.clone();
+                }
+
+                self.show_request(&entry.request);
+                self.response.set_message(&format!("Restored the version from {}, [s] saves it", time));
+            },
+            (HistoryAction::Open, Some(response)) => {
+                self.response.set_past_response(&HttpResponse::from(response), &format!("from {}", time));
+            },
+            (HistoryAction::Compare, Some(_)) => {
+                // The latest response, or the one before it when the latest is selected
+                let other = marked
+                    .filter(|&m| m != selected)
+                    .or_else(|| entries
+                        .iter()
+                        .enumerate()
+                        .position(|(i, e)| i != selected && e.response.is_some()))
+                    .and_then(|i| entries.get(i))
+                    .filter(|e| e.response.is_some());
+
+                match other {
+                    // Entries are newest first, the older one goes on the left
+                    Some(other) if other.timestamp <= entry.timestamp => self.open_diff(other, entry),
+                    Some(other) => self.open_diff(entry, other),
+                    None => self.response.set_message(
+                        "Nothing to compare with, mark another response with [Space] first",
+                    ),
+                }
+            },
+            (_, None) => {
+                self.response.set_error(&format!(
+                    "No response at {}: {}",
+                    time,
+                    entry.error.as_deref().unwrap_or_default(),
+                ));
+            },
+        }
+    }
+
+    /// Opens the diff of the responses of two history entries.
+    fn open_diff(&mut self, left: &HistoryEntry, right: &HistoryEntry) {
+        let (Some(left_response), Some(right_response)) = (&left.response, &right.response) else {
+            return;
+        };
+
+        let title = |entry: &HistoryEntry, response: &ReceivedResponse| format!(
+            "{} · {} {}",
+            history::format_time(entry.timestamp),
+            response.status,
+            response.reason,
+        );
+
+        self.show_diff(DiffSource {
+            left_title: title(left, left_response),
+            right_title: title(right, right_response),
+            left: left_response.clone(),
+            right: right_response.clone(),
+        });
+    }
+
+    fn show_diff(&mut self, source: DiffSource) {
+        let (rows, summary) = diff::response_diff(&source.left, &source.right, &self.model.diff_ignore);
+        let view = DiffView::new(
+            &source.left_title,
+            &format!("{} · {}", source.right_title, summary),
+            rows,
+        );
+
+        let block = self.layout.overlay("Response diff");
+        self.dialog = Some((Dialog::Diff(source, view), block));
+    }
+
+    fn remove_request(&mut self, i: usize) {
+        self.model.remove_request(i);
+
+        match self.current_request_id {
+            Some(id) if id == i => self.clear_current_request(),
+            Some(id) if id > i => self.current_request_id = Some(id - 1),
+            _ => {}
+        }
+
+        self.options.select(i);
+    }
+
+    fn clear_current_request(&mut self) {
+        self.current_request = None;
+        self.current_request_id = None;
+        self.input.set_val("");
+        self.editor.set_val("");
+        self.pre_script.set_val("");
+        self.post_script.set_val("");
+        self.query.set_rows(vec![]);
+        self.headers.set_rows(vec![]);
+        self.path_params = vec![];
+        self.path.set_rows(vec![]);
+        self.auth = Auth::Inherit;
+        self.auth_table.set_rows(vec![]);
+        self.tests.set_rows(vec![]);
+        self.captures.set_rows(vec![]);
+    }
+
+    fn check_dialog_closed(&mut self, console: &mut Console) {
+        if let Some((dialog, _)) = &self.dialog {
+            if let DialogState::Open = dialog.state() {
+                return;
+            }
+        } else {
+            return;
+        }
+
+        let (dialog, _) = self.dialog.take().unwrap();
+        let submitted = matches!(dialog.state(), DialogState::Submitted);
+
+        self.redraw_all(console);
+
+        if !submitted {
+            return;
+        }
+
+        match dialog {
+            Dialog::ChangeMethod(target, picker) => {
+                if picker.get_value() == CUSTOM_METHOD {
+                    self.open_dialog(Dialog::CustomMethod(
+                        target,
+                        Prompt::new("Method:", ""),
+                    ));
+                } else {
+                    self.apply_method(target, picker.get_value());
+                }
+            },
+            Dialog::CustomMethod(target, prompt) => {
+                let method = prompt.get_value();
+
+                if http::is_valid_method(&method) {
+                    self.apply_method(target, &method);
+                } else {
+                    self.open_dialog(Dialog::CustomMethod(
+                        target,
+                        Prompt::new("Not a valid method token, try again:", &method),
+                    ));
+                }
+            },
+            Dialog::NewRequestTitle(method, prompt) => {
+                let i = self.model.add_request(
+                    Request::new(&method, &prompt.get_value(), "", ""),
+                );
+                self.options.select(i);
+            },
+            Dialog::Rename(i, prompt) => {
+                self.model.rename_request(i, &prompt.get_value());
+                self.refresh_current_request(i);
+            },
+            Dialog::ConfirmDelete(i, picker) => {
+                if picker.get_value() == "Yes" {
+                    self.remove_request(i);
+                }
+            },
+            Dialog::MoveToFolder(i, prompt) => {
+                let folder = prompt.get_value();
+                self.model.set_request_folder(i, Some(folder.as_str()).filter(|f| !f.is_empty()));
+                self.refresh_current_request(i);
+            },
+            Dialog::Passphrase(after, prompt) => {
+                match self.model.secrets.unlock(&prompt.get_value()) {
+                    Ok(()) => {
+                        self.refresh_masks();
+
+                        match after {
+                            AfterUnlock::Execute => self.execute(),
+                            AfterUnlock::OpenSecrets => self.open_secrets(),
+                            AfterUnlock::ShowCode => self.show_code(),
+                            AfterUnlock::CompareEnvironments => self.open_comparison(),
+                        }
+                    },
+                    Err(e) => self.open_dialog(Dialog::Passphrase(
+                        after,
+                        Prompt::new(&format!("{}, try again:", e), "").masked(),
+                    )),
+                }
+            },
+            Dialog::NewPassphrase(prompt) => {
+                let passphrase = prompt.get_value();
+
+                if passphrase.is_empty() {
+                    self.open_dialog(Dialog::NewPassphrase(
+                        Prompt::new("Passphrase can't be empty:", "").masked(),
+                    ));
+                } else if let Err(e) = self.model.secrets.unlock(&passphrase) {
+                    self.response.set_error(&e);
+                } else {
+                    self.save_secrets();
+                }
+            },
+            Dialog::Secrets(dialog) => {
+                self.model.secrets.set_all(secrets_from_rows(dialog.table.get_rows()));
+
+                if self.model.secrets.has_passphrase() {
+                    self.save_secrets();
+                } else if !self.model.secrets.get_all().is_empty() {
+                    self.open_dialog(Dialog::NewPassphrase(
+                        Prompt::new("New passphrase for the secrets file:", "").masked(),
+                    ));
+                }
+            },
+            Dialog::Diff(source, view) => {
+                if let Some(DiffAction::EditIgnored) = view.action {
+                    self.open_dialog(Dialog::DiffIgnore(
+                        source,
+                        Prompt::new("Fields to ignore, comma separated:", &self.model.diff_ignore.join(", ")),
+                    ));
+                }
+            },
+            Dialog::Code(view) => {
+                if let Some(CodeAction::Save) = view.action {
+                    let title = self.current_request.as_ref().map_or("request", |r| r.title.as_str());
+                    let file = format!("{}.{}", title.replace(['/', ' '], "_"), view.language().extension());
+
+                    self.open_dialog(Dialog::SaveCode(view.code(), Prompt::new("Save to file:", &file)));
+                }
+            },
+            Dialog::SaveCode(code, prompt) => self.save_code(&code, &prompt.get_value()),
+            Dialog::DiffIgnore(source, prompt) => {
+                let fields = prompt
+                    .get_value()
+                    .split(',')
+                    .map(|f| f.trim().to_string())
+                    .filter(|f| !f.is_empty())
+                    .collect();
+
+                self.model.set_diff_ignore(fields);
+                self.show_diff(source);
+            },
+            Dialog::History(browser) => {
+                if let Some(action) = browser.action {
+                    self.apply_history_action(action, browser.selected, browser.marked);
+                }
+            },
+            Dialog::AuthType(scope, picker) => {
+                if scope == self.auth_scope {
+                    self.set_shown_auth(Auth::of_kind(picker.get_value()));
+                    self.refresh_auth_table();
+                    self.update_request_title();
+                }
+            },
+            // Only ever closed, the run happens while it's open
+            Dialog::Run(_) => {},
+            Dialog::Sync(view) => self.apply_sync(view),
+            Dialog::Import(prompt) => self.import_file(&prompt.get_value()),
+            Dialog::Environment(picker) => {
+                let name = Some(picker.get_value()).filter(|n| *n != NO_ENVIRONMENT);
+                self.model.set_environment(name);
+
+                self.response.set_message(&match name {
+                    Some(name) => format!("Variables are looked up in environment {} first", name),
+                    None => String::from("No environment is active"),
+                });
+            },
+            Dialog::CompareWith(picker) => {
+                let other = Some(picker.get_value()).filter(|n| *n != NO_ENVIRONMENT);
+                self.compare_environments(other);
+            },
+            Dialog::Har(view) => {
+                let folder = view.folder.clone();
+
+                match import::import_har_entries(&mut self.model, view.take_picked(), &folder) {
+                    Ok(summary) => {
+                        self.response.set_message(&summary);
+                        self.options.invalidate();
+                    },
+                    Err(e) => self.response.set_error(&e),
+                }
+            },
+        }
+    }
+
+    fn redraw_all(&mut self, console: &mut Console) {
+        console.clear();
+        self.layout.render(console);
+        self.options.invalidate();
+        self.method.invalidate();
+        self.editor.invalidate();
+        self.pre_script.invalidate();
+        self.post_script.invalidate();
+        self.query.invalidate();
+        self.headers.invalidate();
+        self.path.invalidate();
+        self.auth_table.invalidate();
+        self.tests.invalidate();
+        self.captures.invalidate();
+        self.input.invalidate();
+        self.response.invalidate();
+        self.shortcuts.invalidate();
+        console.hide_cursor();
     }
 
     pub fn run(&mut self) -> std::io::Result<()> {
@@ -68,6 +1735,41 @@ impl Application {
         self.layout.render(&mut console);
 
         loop {
+            if let Some((Dialog::Diff(_, _), _)) = &self.dialog {
+                self.shortcuts.set_hints(DIFF_HINTS);
+            } else if let Some((Dialog::Code(_), _)) = &self.dialog {
+                self.shortcuts.set_hints(CODE_HINTS);
+            } else if self.layout.list_cont.is_active() && self.options.is_filtering() {
+                self.shortcuts.set_hints(FILTER_HINTS);
+            } else if self.layout.list_cont.is_active() {
+                self.shortcuts.set_hints(LIST_HINTS);
+            } else if self.layout.req_cont.is_active()
+                && matches!(self.request_tab, RequestTab::Query | RequestTab::Headers) {
+                self.shortcuts.set_hints(QUERY_HINTS);
+            } else if self.layout.req_cont.is_active() && self.request_tab == RequestTab::Path {
+                self.shortcuts.set_hints(PATH_HINTS);
+            } else if self.layout.req_cont.is_active() && self.request_tab == RequestTab::Auth {
+                self.shortcuts.set_hints(AUTH_HINTS);
+            } else if self.layout.req_cont.is_active() && self.request_tab == RequestTab::Tests {
+                self.shortcuts.set_hints(TESTS_HINTS);
+            } else if self.layout.req_cont.is_active() && self.request_tab == RequestTab::Capture {
+                self.shortcuts.set_hints(CAPTURE_HINTS);
+            } else if self.layout.req_cont.is_active()
+                && matches!(self.request_tab, RequestTab::PreScript | RequestTab::PostScript) {
+                self.shortcuts.set_hints(SCRIPT_HINTS);
+            } else if self.layout.req_cont.is_selected() {
+                self.shortcuts.set_hints(REQUEST_HINTS);
+            } else {
+                self.shortcuts.set_hints(DEFAULT_HINTS);
+            }
+
+            if let Some(e) = self.model.save_error.take() {
+                self.response.set_error(&e);
+            }
+
+            self.shortcuts.set_warning(self.model.tls_insecure().then_some("TLS VERIFICATION OFF"));
+            self.shortcuts.set_environment(self.model.active_environment().map(|e| e.name.as_str()));
+
             if self.shortcuts.needs_re_render() {
                 self.shortcuts.output(&mut console, &mut self.layout.hint_cont);
             }
@@ -76,23 +1778,77 @@ impl Application {
                 self.options.output(&mut console, &mut self.layout.list_cont);
             }
 
-            if self.editor.needs_re_render() {
-                self.editor.output(&mut console, &mut self.layout.req_cont);
+            if self.method.needs_re_render() {
+                self.method.output(&mut console, &mut self.layout.method_cont);
+            }
+
+            match self.request_tab {
+                RequestTab::Body if self.editor.needs_re_render() => {
+                    self.editor.output(&mut console, &mut self.layout.req_cont);
+                },
+                RequestTab::Query if self.query.needs_re_render() => {
+                    self.query.output(&mut console, &mut self.layout.req_cont);
+                },
+                RequestTab::Headers if self.headers.needs_re_render() => {
+                    self.headers.output(&mut console, &mut self.layout.req_cont);
+                },
+                RequestTab::Path if self.path.needs_re_render() => {
+                    self.path.output(&mut console, &mut self.layout.req_cont);
+                },
+                RequestTab::Auth if self.auth_table.needs_re_render() => {
+                    self.auth_table.output(&mut console, &mut self.layout.req_cont);
+                },
+                RequestTab::Tests if self.tests.needs_re_render() => {
+                    self.tests.output(&mut console, &mut self.layout.req_cont);
+                },
+                RequestTab::Capture if self.captures.needs_re_render() => {
+                    self.captures.output(&mut console, &mut self.layout.req_cont);
+                },
+                RequestTab::PreScript if self.pre_script.needs_re_render() => {
+                    self.pre_script.output(&mut console, &mut self.layout.req_cont);
+                },
+                RequestTab::PostScript if self.post_script.needs_re_render() => {
+                    self.post_script.output(&mut console, &mut self.layout.req_cont);
+                },
+                _ => {},
+            }
+
+            if self.response.needs_re_render() {
+                self.response.output(&mut console, &mut self.layout.resp_cont);
             }
 
             if self.input.needs_re_render() {
                 self.input.output(&mut console, &mut self.layout.input_cont);
             }
 
+            self.check_response();
+            self.check_comparison();
+
+            if let Some((Dialog::Run(view), _)) = &mut self.dialog {
+                view.tick(&mut self.model);
+            }
+
+            if let Some((dialog, block)) = &mut self.dialog {
+                let element = dialog.element();
+
+                if element.needs_re_render() {
+                    element.output(&mut console, block);
+                }
+            }
+
             if event::poll(std::time::Duration::from_millis(10))? {
                 let event = event::read()?;
 
-                if let Event::Key(KeyEvent { 
+                if let Event::Key(KeyEvent {
                     code,
                     kind: KeyEventKind::Press,
                     ..
                 }) = event {
-                    if self.layout.select_mode {
+                    if let Some((dialog, _)) = &mut self.dialog {
+                        dialog.element().on_event(&event)?;
+                        self.start_run();
+                        self.check_dialog_closed(&mut console);
+                    } else if self.layout.select_mode {
                         match code {
                             KeyCode::Esc => {
                                 break;
@@ -100,16 +1856,7 @@ impl Application {
                             KeyCode::Char(c) => {
 
                                 if c == 's' {
-                                    self.current_request
-                                        .as_ref()
-                                        .map(|r| Request {
-                                            title: r.title.to_string(),
-                                            method: r.method.to_string(),
-                                            url: self.input.get_value(),
-                                            body: self.editor.get_body(),
-                                            query_params: vec![],
-                                            path_params: vec![],
-                                        })
+                                    self.edited_request()
                                         .and_then(|r| {
                                             console.move_to(0, 50);
                                             console.write("saved on disk");
@@ -125,30 +1872,128 @@ impl Application {
                                 }
 
                                 if c == 'e' {
-                                    if let Some(req) = &self.current_request {
-                                        self.model.make_request(req);
-                                    }
+                                    self.execute();
                                 }
+
+                                if c == 'v' {
+                                    self.open_secrets();
+                                }
+
+                                if c == 'h' {
+                                    self.open_history();
+                                }
+
+                                if c == 'c' {
+                                    self.open_run();
+                                }
+
+                                if c == 'o' {
+                                    self.open_sync();
+                                }
+
+                                if c == 'i' {
+                                    self.open_dialog(Dialog::Import(Prompt::new("Spec, collection, environment or HAR file:", "")));
+                                }
+
+                                if c == 'g' {
+                                    self.show_code();
+                                }
+
+                                if c == 'n' {
+                                    self.open_environments();
+                                }
+
+                                if c == 'k' {
+                                    self.toggle_tls_verification();
+                                }
+
+                                if c == 'd' {
+                                    self.open_comparison();
+                                }
+                            },
+                            KeyCode::Enter if self.layout.method_cont.is_selected() => {
+                                if let (Some(i), Some(r)) = (
+                                    self.current_request_id,
+                                    self.current_request.as_ref(),
+                                ) {
+                                    self.open_dialog(Dialog::ChangeMethod(
+                                        MethodTarget::Existing(i),
+                                        method_picker("Method:", &r.method),
+                                    ));
+                                }
+                            },
+                            KeyCode::Tab if self.layout.req_cont.is_selected() => {
+                                self.switch_request_tab();
                             },
+                            KeyCode::Enter if self.layout.req_cont.is_selected()
+                                && self.request_tab == RequestTab::Body
+                                && matches!(
+                                    self.current_request.as_ref().map(|r| http::body_usage(&r.method)),
+                                    Some(BodyUsage::Forbidden)
+                                ) => {},
                             _ => {
                                 self.layout.navigate(&event, &mut console);
                                 console.hide_cursor()
                             }
                         }
                     } else {
+                        // Esc first leaves the filter or the edited cell
+                        let esc_captured = (self.layout.list_cont.is_active() && self.options.is_filtering())
+                            || (self.layout.req_cont.is_active() && self.is_editing_table());
+
                         match code {
-                            KeyCode::Esc => {
+                            KeyCode::Esc if !esc_captured => {
                                 self.layout.enter_select_mode();
                                 console.hide_cursor();
                             },
                             _ => {
-                                if self.layout.list_cont.is_active() {
-                                    self.options.on_event(&event)?;
-                                    self.check_option_selected();
-                                } else if self.layout.req_cont.is_active() {  
-                                    self.editor.on_event(&event)?;
-                                } else if self.layout.input_cont.is_active() {
-                                    self.input.on_event(&event)?;
+                                match self.layout.active_pane() {
+                                    Some(Pane::List) => {
+                                        self.options.on_event(&event)?;
+                                        self.check_option_selected();
+                                        self.check_list_action();
+                                    },
+                                    Some(Pane::Request) => match self.request_tab {
+                                        RequestTab::Body => self.editor.on_event(&event)?,
+                                        RequestTab::PreScript => self.pre_script.on_event(&event)?,
+                                        RequestTab::PostScript => self.post_script.on_event(&event)?,
+                                        RequestTab::Query => {
+                                            self.query.on_event(&event)?;
+                                            self.sync_url_from_query();
+                                        },
+                                        RequestTab::Headers => self.headers.on_event(&event)?,
+                                        RequestTab::Path => {
+                                            self.path.on_event(&event)?;
+                                            self.sync_path_params();
+                                        },
+                                        RequestTab::Auth => self.on_auth_event(&event, &code)?,
+                                        RequestTab::Tests => {
+                                            self.tests.on_event(&event)?;
+
+                                            if self.tests.changed {
+                                                self.tests.changed = false;
+                                                self.update_request_title();
+                                            }
+                                        },
+                                        RequestTab::Capture => {
+                                            self.captures.on_event(&event)?;
+
+                                            if self.captures.changed {
+                                                self.captures.changed = false;
+                                                self.update_request_title();
+                                            }
+                                        },
+                                    },
+                                    Some(Pane::Input) => {
+                                        let before = self.input.get_value();
+                                        self.input.on_event(&event)?;
+
+                                        if self.input.get_value() != before {
+                                            self.sync_query_from_url();
+                                        }
+                                    },
+                                    Some(Pane::Response) => self.response.on_event(&event)?,
+                                    _ => {},
                                 }
                             }
                         };
@@ -0,0 +1,245 @@
+use std::fs;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::http::HttpResponse;
+use crate::paths::Paths;
+use crate::{jsonpath, schema};
+
+pub const KINDS: [&str; 5] = ["status", "header", "jsonpath", "time", "schema"];
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AssertionKind {
+    /// Expected is `200`, `2xx`, `200-299` or a comma separated list of those
+    Status,
+    /// Target is the header name
+    Header,
+    /// Target is the JSONPath of the value
+    #[serde(rename = "jsonpath")]
+    JsonPath,
+    /// Expected is the limit in milliseconds
+    Time,
+    /// Expected is an inline schema or `@file` relative to the project root
+    Schema,
+}
+
+impl AssertionKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AssertionKind::Status => KINDS[0],
+            AssertionKind::Header => KINDS[1],
+            AssertionKind::JsonPath => KINDS[2],
+            AssertionKind::Time => KINDS[3],
+            AssertionKind::Schema => KINDS[4],
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<AssertionKind> {
+        match name.trim().to_lowercase().as_str() {
+            "status" => Some(AssertionKind::Status),
+            "header" => Some(AssertionKind::Header),
+            "jsonpath" => Some(AssertionKind::JsonPath),
+            "time" => Some(AssertionKind::Time),
+            "schema" => Some(AssertionKind::Schema),
+            _ => None,
+        }
+    }
+}
+
+/// Check run against the response after the request is executed.
+///
+/// Header and JSONPath checks take an empty expected value or `exists`
+/// to check presence, `~ regex` to match and `= value` or a bare value
+/// to compare.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Assertion {
+    pub kind: AssertionKind,
+    #[serde(default)]
+    pub target: String,
+    #[serde(default)]
+    pub expected: String,
+    #[serde(default = "crate::model::enabled_by_default")]
+    pub enabled: bool,
+}
+
+pub struct AssertionResult {
+    pub description: String,
+    pub passed: bool,
+    /// Why it failed, empty if it passed
+    pub message: String,
+}
+
+enum Expectation {
+    Exists,
+    Equals(String),
+    Matches(Regex),
+}
+
+fn expectation(expected: &str) -> Result<Expectation, String> {
+    let expected = expected.trim();
+
+    if expected.is_empty() || expected == "exists" {
+        Ok(Expectation::Exists)
+    } else if let Some(pattern) = expected.strip_prefix('~') {
+        Regex::new(pattern.trim())
+            .map(Expectation::Matches)
+            .map_err(|e| format!("invalid regex: {}", e))
+    } else {
+        Ok(Expectation::Equals(expected.strip_prefix('=').unwrap_or(expected).trim().to_string()))
+    }
+}
+
+fn status_matches(status: u16, expected: &str) -> Result<bool, String> {
+    for part in expected.split(',').map(|p| p.trim()) {
+        let (low, high) = if let Some(class) = part.strip_suffix("xx") {
+            let class: u16 = class.parse().map_err(|_| format!("invalid status {}", part))?;
+            (class * 100, class * 100 + 99)
+        } else if let Some((low, high)) = part.split_once('-') {
+            (
+                low.trim().parse().map_err(|_| format!("invalid status {}", part))?,
+                high.trim().parse().map_err(|_| format!("invalid status {}", part))?,
+            )
+        } else {
+            let exact = part.parse().map_err(|_| format!("invalid status {}", part))?;
+            (exact, exact)
+        };
+
+        if (low..=high).contains(&status) {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+/// Strings are compared without quotes, other values as JSON.
+fn text_of(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn check_value(found: Option<String>, expected: &Expectation, json: Option<&Value>) -> Result<(), String> {
+    let Some(found) = found else {
+        return Err(String::from("not found"));
+    };
+
+    match expected {
+        Expectation::Exists => Ok(()),
+        Expectation::Matches(re) if re.is_match(&found) => Ok(()),
+        Expectation::Matches(re) => Err(format!("\"{}\" doesn't match {}", found, re)),
+        Expectation::Equals(value) => {
+            // Expected JSON, like 5 or true, is compared as JSON
+            let equal = match (json, serde_json::from_str::<Value>(value)) {
+                (Some(json), Ok(expected)) => *json == expected,
+                _ => found == *value,
+            };
+
+            if equal {
+                Ok(())
+            } else {
+                Err(format!("got {}", found))
+            }
+        },
+    }
+}
+
+fn load_schema(expected: &str) -> Result<Value, String> {
+    let expected = expected.trim();
+
+    let text = match expected.strip_prefix('@') {
+        Some(file) => fs::read_to_string(Paths::project_root().join(file.trim()))
+            .map_err(|e| format!("couldn't read {}: {}", file.trim(), e))?,
+        None => expected.to_string(),
+    };
+
+    serde_json::from_str(&text).map_err(|e| format!("schema is not JSON: {}", e))
+}
+
+impl Assertion {
+    pub fn describe(&self) -> String {
+        let target = self.target.trim();
+        let expected = self.expected.trim();
+
+        match self.kind {
+            AssertionKind::Status => format!("status is {}", expected),
+            AssertionKind::Time => format!("time is below {} ms", expected.trim_start_matches('<').trim()),
+            AssertionKind::Schema if expected.starts_with('@') => format!("body matches schema {}", expected),
+            AssertionKind::Schema => String::from("body matches the schema"),
+            _ if expected.is_empty() || expected == "exists" => format!("{} exists", target),
+            _ => match expected.strip_prefix('~') {
+                Some(pattern) => format!("{} matches {}", target, pattern.trim()),
+                None => format!("{} is {}", target, expected.trim_start_matches('=').trim()),
+            },
+        }
+    }
+
+    fn check(&self, response: &HttpResponse) -> Result<(), String> {
+        let body = || serde_json::from_str::<Value>(&response.body)
+            .map_err(|e| format!("body is not JSON: {}", e));
+
+        match self.kind {
+            AssertionKind::Status => match status_matches(response.status, &self.expected)? {
+                true => Ok(()),
+                false => Err(format!("got {}", response.status)),
+            },
+            AssertionKind::Header => check_value(
+                response.header(self.target.trim()).map(String::from),
+                &expectation(&self.expected)?,
+                None,
+            ),
+            AssertionKind::JsonPath => {
+                let body = body()?;
+                let found = jsonpath::select(&body, &self.target)?.first().map(|v| (*v).clone());
+
+                check_value(found.as_ref().map(text_of), &expectation(&self.expected)?, found.as_ref())
+            },
+            AssertionKind::Time => {
+                let limit: u128 = self.expected
+                    .trim()
+                    .trim_start_matches('<')
+                    .trim()
+                    .parse()
+                    .map_err(|_| format!("invalid time limit {}", self.expected.trim()))?;
+
+                let took = response.timings.total.as_millis();
+
+                if took < limit {
+                    Ok(())
+                } else {
+                    Err(format!("took {} ms", took))
+                }
+            },
+            AssertionKind::Schema => {
+                let errors = schema::validate(&load_schema(&self.expected)?, &body()?);
+
+                match errors.len() {
+                    0 => Ok(()),
+                    1 => Err(errors[0].clone()),
+                    n => Err(format!("{} and {} more", errors[0], n - 1)),
+                }
+            },
+        }
+    }
+}
+
+/// Results of the enabled assertions.
+pub fn check(assertions: &[Assertion], response: &HttpResponse) -> Vec<AssertionResult> {
+    assertions
+        .iter()
+        .filter(|a| a.enabled)
+        .map(|a| {
+            let result = a.check(response);
+
+            AssertionResult {
+                description: a.describe(),
+                passed: result.is_ok(),
+                message: result.err().unwrap_or_default(),
+            }
+        })
+        .collect()
+}
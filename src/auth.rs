@@ -0,0 +1,499 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde::{Deserialize, Serialize};
+
+use crate::http::{self, HttpRequest};
+use crate::tls::TlsOptions;
+use crate::url;
+
+/// Tokens are refreshed a bit before they expire, so they don't expire in flight.
+const EXPIRY_MARGIN: Duration = Duration::from_secs(30);
+const DEFAULT_EXPIRY: Duration = Duration::from_secs(3600);
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiKeyLocation {
+    Header,
+    Query,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum OAuth2Grant {
+    ClientCredentials,
+    Password,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Auth {
+    /// Auth of the folder the request is in
+    #[default]
+    Inherit,
+    None,
+    Basic {
+        username: String,
+        password: String,
+    },
+    Bearer {
+        token: String,
+    },
+    ApiKey {
+        name: String,
+        value: String,
+        location: ApiKeyLocation,
+    },
+    #[serde(rename = "oauth2")]
+    OAuth2 {
+        grant: OAuth2Grant,
+        token_url: String,
+        client_id: String,
+        #[serde(default)]
+        client_secret: String,
+        #[serde(default)]
+        scope: String,
+        #[serde(default)]
+        username: String,
+        #[serde(default)]
+        password: String,
+    },
+}
+
+pub const KINDS: [&str; 8] = [
+    "Inherit",
+    "None",
+    "Basic",
+    "Bearer",
+    "API key (header)",
+    "API key (query)",
+    "OAuth2 client credentials",
+    "OAuth2 password",
+];
+
+impl Auth {
+    /// Empty auth of one of `KINDS`.
+    pub fn of_kind(kind: &str) -> Auth {
+        let oauth2 = |grant| Auth::OAuth2 {
+            grant,
+            token_url: String::new(),
+            client_id: String::new(),
+            client_secret: String::new(),
+            scope: String::new(),
+            username: String::new(),
+            password: String::new(),
+        };
+
+        match kind {
+            "None" => Auth::None,
+            "Basic" => Auth::Basic { username: String::new(), password: String::new() },
+            "Bearer" => Auth::Bearer { token: String::new() },
+            "API key (header)" => Auth::ApiKey {
+                name: String::new(),
+                value: String::new(),
+                location: ApiKeyLocation::Header,
+            },
+            "API key (query)" => Auth::ApiKey {
+                name: String::new(),
+                value: String::new(),
+                location: ApiKeyLocation::Query,
+            },
+            "OAuth2 client credentials" => oauth2(OAuth2Grant::ClientCredentials),
+            "OAuth2 password" => oauth2(OAuth2Grant::Password),
+            _ => Auth::Inherit,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Auth::Inherit => KINDS[0],
+            Auth::None => KINDS[1],
+            Auth::Basic { .. } => KINDS[2],
+            Auth::Bearer { .. } => KINDS[3],
+            Auth::ApiKey { location: ApiKeyLocation::Header, .. } => KINDS[4],
+            Auth::ApiKey { location: ApiKeyLocation::Query, .. } => KINDS[5],
+            Auth::OAuth2 { grant: OAuth2Grant::ClientCredentials, .. } => KINDS[6],
+            Auth::OAuth2 { grant: OAuth2Grant::Password, .. } => KINDS[7],
+        }
+    }
+
+    /// Editable fields of the auth with their values.
+    pub fn fields(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Auth::Inherit | Auth::None => vec![],
+            Auth::Basic { username, password } => vec![
+                ("Username", username.clone()),
+                ("Password", password.clone()),
+            ],
+            Auth::Bearer { token } => vec![("Token", token.clone())],
+            Auth::ApiKey { name, value, .. } => vec![
+                ("Name", name.clone()),
+                ("Value", value.clone()),
+            ],
+            Auth::OAuth2 { grant, token_url, client_id, client_secret, scope, username, password } => {
+                let mut fields = vec![
+                    ("Token URL", token_url.clone()),
+                    ("Client ID", client_id.clone()),
+                    ("Client secret", client_secret.clone()),
+                    ("Scope", scope.clone()),
+                ];
+
+                if *grant == OAuth2Grant::Password {
+                    fields.push(("Username", username.clone()));
+                    fields.push(("Password", password.clone()));
+                }
+
+                fields
+            },
+        }
+    }
+
+    /// Fields holding credentials, they are masked in the UI.
+    pub fn is_secret_field(field: &str) -> bool {
+        matches!(field, "Password" | "Token" | "Value" | "Client secret")
+    }
+
+    pub fn set_field(&mut self, field: &str, new_value: &str) {
+        let target = match (self, field) {
+            (Auth::Basic { username, .. }, "Username") => username,
+            (Auth::Basic { password, .. }, "Password") => password,
+            (Auth::Bearer { token }, "Token") => token,
+            (Auth::ApiKey { name, .. }, "Name") => name,
+            (Auth::ApiKey { value, .. }, "Value") => value,
+            (Auth::OAuth2 { token_url, .. }, "Token URL") => token_url,
+            (Auth::OAuth2 { client_id, .. }, "Client ID") => client_id,
+            (Auth::OAuth2 { client_secret, .. }, "Client secret") => client_secret,
+            (Auth::OAuth2 { scope, .. }, "Scope") => scope,
+            (Auth::OAuth2 { username, .. }, "Username") => username,
+            (Auth::OAuth2 { password, .. }, "Password") => password,
+            _ => return,
+        };
+
+        *target = new_value.to_string();
+    }
+}
+
+/// Authorization header of an OAuth2 request whose token isn't cached, `authorize`
+/// replaces it while sending, so nothing is fetched while the request is built.
+pub const PENDING_TOKEN: &str = "Bearer <OAuth2 token, fetched when sending>";
+
+/// OAuth2 access tokens by the token request they were fetched with.
+pub struct TokenCache {
+    tokens: Mutex<HashMap<String, (String, Instant)>>,
+}
+
+impl TokenCache {
+    pub fn new() -> Self {
+        Self { tokens: Mutex::new(HashMap::new()) }
+    }
+
+    /// Token that's still valid for a while.
+    fn get(&self, key: &str) -> Option<String> {
+        let tokens = self.tokens.lock().unwrap();
+
+        tokens
+            .get(key)
+            .filter(|(_, expires_at)| Instant::now() + EXPIRY_MARGIN < *expires_at)
+            .map(|(token, _)| token.clone())
+    }
+
+    fn insert(&self, key: String, token: String, expires_in: Duration) {
+        self.tokens.lock().unwrap().insert(key, (token, Instant::now() + expires_in));
+    }
+}
+
+fn basic_credentials(username: &str, password: &str) -> String {
+    format!("Basic {}", STANDARD.encode(format!("{}:{}", username, password)))
+}
+
+/// Requests a token from the token endpoint, client credentials are sent with Basic auth.
+fn fetch_token(auth: &Auth, tls: &TlsOptions) -> Result<(String, Duration), String> {
+    let Auth::OAuth2 { grant, token_url, client_id, client_secret, scope, username, password } = auth else {
+        return Err(String::from("Not an OAuth2 auth"));
+    };
+
+    let mut form = match grant {
+        OAuth2Grant::ClientCredentials => vec![("grant_type", "client_credentials")],
+        OAuth2Grant::Password => vec![
+            ("grant_type", "password"),
+            ("username", username.as_str()),
+            ("password", password.as_str()),
+        ],
+    };
+
+    if !scope.trim().is_empty() {
+        form.push(("scope", scope.trim()));
+    }
+
+    let body = form
+        .iter()
+        .map(|(k, v)| format!("{}={}", k, url::percent_encode(v)))
+        .collect::<Vec<String>>()
+        .join("&");
+
+    let response = http::send(&HttpRequest {
+        method: String::from("POST"),
+        url: token_url.trim().to_string(),
+        headers: vec![
+            (String::from("Authorization"), basic_credentials(client_id, client_secret)),
+            (String::from("Content-Type"), String::from("application/x-www-form-urlencoded")),
+            (String::from("Accept"), String::from("application/json")),
+        ],
+        body,
+    }, tls)?;
+
+    if !(200..300).contains(&response.status) {
+        return Err(format!(
+            "Token request failed with {} {}: {}",
+            response.status,
+            response.reason,
+            response.body.lines().next().unwrap_or_default(),
+        ));
+    }
+
+    let json: serde_json::Value = serde_json::from_str(&response.body)
+        .map_err(|e| format!("Token response is not JSON: {}", e))?;
+
+    let token = json["access_token"]
+        .as_str()
+        .ok_or_else(|| String::from("Token response has no access_token"))?;
+
+    let expires_in = json["expires_in"]
+        .as_u64()
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_EXPIRY);
+
+    Ok((token.to_string(), expires_in))
+}
+
+/// Token request the auth makes, none for other auths.
+fn token_key(auth: &Auth) -> Option<String> {
+    let Auth::OAuth2 { grant, token_url, client_id, scope, username, .. } = auth else {
+        return None;
+    };
+
+    Some(format!(
+        "{}|{}|{}|{}|{}",
+        token_url,
+        client_id,
+        if *grant == OAuth2Grant::Password { username.as_str() } else { "" },
+        scope,
+        if *grant == OAuth2Grant::Password { "password" } else { "client_credentials" },
+    ))
+}
+
+fn set_header(request: &mut HttpRequest, name: &str, value: String) {
+    request.headers.retain(|(n, _)| !n.eq_ignore_ascii_case(name));
+    request.headers.push((name.to_string(), value));
+}
+
+/// Adds credentials of the auth to the request. OAuth2 tokens are only taken
+/// from the cache, without one the header is `PENDING_TOKEN`.
+pub fn apply(auth: &Auth, request: &mut HttpRequest, cache: &TokenCache) -> Result<(), String> {
+    if let Auth::ApiKey { name, .. } = auth {
+        if name.trim().is_empty() {
+            return Err(String::from("API key name is not set"));
+        }
+    }
+
+    match auth {
+        Auth::Inherit | Auth::None => {},
+        Auth::Basic { username, password } => {
+            set_header(request, "Authorization", basic_credentials(username, password));
+        },
+        Auth::Bearer { token } => set_header(request, "Authorization", format!("Bearer {}", token.trim())),
+        Auth::ApiKey { name, value, location: ApiKeyLocation::Header } => {
+            set_header(request, name.trim(), value.clone());
+        },
+        Auth::ApiKey { name, value, location: ApiKeyLocation::Query } => {
+            request.url = url::append_query(&request.url, name.trim(), value);
+        },
+        Auth::OAuth2 { token_url, .. } => {
+            if token_url.trim().is_empty() {
+                return Err(String::from("OAuth2 token URL is not set"));
+            }
+
+            let token = token_key(auth).and_then(|key| cache.get(&key));
+            let value = token.map_or(String::from(PENDING_TOKEN), |token| format!("Bearer {}", token));
+            set_header(request, "Authorization", value);
+        },
+    }
+
+    Ok(())
+}
+
+/// Fetches the OAuth2 token `apply` left pending and puts it into the request.
+///
+/// Blocks until the token endpoint answers, so it's called where the request
+/// is sent. A header a pre-request script changed is left as it is.
+pub fn authorize(auth: &Auth, request: &mut HttpRequest, cache: &TokenCache, tls: &TlsOptions) -> Result<(), String> {
+    let Some(key) = token_key(auth) else {
+        return Ok(());
+    };
+
+    let pending = request.headers
+        .iter()
+        .any(|(name, value)| name.eq_ignore_ascii_case("Authorization") && value == PENDING_TOKEN);
+
+    if !pending {
+        return Ok(());
+    }
+
+    // The cache isn't locked while fetching, building other requests doesn't wait for it
+    let token = match cache.get(&key) {
+        Some(token) => token,
+        None => {
+            let (token, expires_in) = fetch_token(auth, tls)?;
+            cache.insert(key, token.clone(), expires_in);
+            token
+        },
+    };
+
+    set_header(request, "Authorization", format!("Bearer {}", token));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+
+    use super::*;
+    use crate::model::{Folder, Model, Request};
+
+    fn request() -> HttpRequest {
+        HttpRequest {
+            method: String::from("GET"),
+            url: String::from("http://api.test/items?page=1"),
+            headers: vec![(String::from("authorization"), String::from("old"))],
+            body: String::new(),
+        }
+    }
+
+    fn applied(auth: Auth) -> HttpRequest {
+        let mut request = request();
+        apply(&auth, &mut request, &TokenCache::new()).unwrap();
+        request
+    }
+
+    fn header<'a>(request: &'a HttpRequest, name: &str) -> Option<&'a str> {
+        request.headers.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)).map(|(_, v)| v.as_str())
+    }
+
+    #[test]
+    fn credentials_replace_the_header_or_extend_the_query() {
+        let basic = applied(Auth::Basic { username: String::from("ann"), password: String::from("pw") });
+        assert_eq!(basic.headers, [(String::from("Authorization"), String::from("Basic YW5uOnB3"))]);
+
+        let bearer = applied(Auth::Bearer { token: String::from(" t0k ") });
+        assert_eq!(header(&bearer, "Authorization"), Some("Bearer t0k"));
+
+        let key = |location| Auth::ApiKey { name: String::from(" X-Key "), value: String::from("a b"), location };
+
+        let in_header = applied(key(ApiKeyLocation::Header));
+        assert_eq!(header(&in_header, "X-Key"), Some("a b"));
+        assert_eq!(in_header.url, "http://api.test/items?page=1");
+
+        let in_query = applied(key(ApiKeyLocation::Query));
+        assert_eq!(in_query.url, "http://api.test/items?page=1&X-Key=a%20b");
+        assert_eq!(header(&in_query, "Authorization"), Some("old"));
+
+        let unnamed = Auth::ApiKey { name: String::new(), value: String::new(), location: ApiKeyLocation::Header };
+        assert!(apply(&unnamed, &mut request(), &TokenCache::new()).is_err());
+        assert_eq!(applied(Auth::None).headers, request().headers);
+    }
+
+    #[test]
+    fn requests_inherit_the_auth_of_their_folder() {
+        let folder = Folder { name: String::from("Admin"), auth: Auth::Bearer { token: String::from("f") } };
+        let model = Model::new(vec![], vec![folder]);
+
+        let mut inheriting = Request::new_default();
+        inheriting.folder = Some(String::from("Admin"));
+        assert!(matches!(model.effective_auth(&inheriting), Auth::Bearer { token } if token == "f"));
+
+        let mut own = inheriting.clone();
+        own.auth = Auth::None;
+        assert!(matches!(model.effective_auth(&own), Auth::None));
+
+        inheriting.folder = None;
+        assert!(matches!(model.effective_auth(&inheriting), Auth::None));
+    }
+
+    /// Token endpoint answering `token-1`, `token-2`… and counting the requests.
+    fn token_server(expires_in: u64) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/token", listener.local_addr().unwrap());
+        let fetched = Arc::new(AtomicUsize::new(0));
+        let counter = fetched.clone();
+
+        thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut request = [0; 4096];
+                let _ = stream.read(&mut request);
+
+                let n = counter.fetch_add(1, Ordering::SeqCst) + 1;
+                let body = format!(r#"{{"access_token": "token-{}", "expires_in": {}}}"#, n, expires_in);
+                let _ = write!(stream, "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}", body.len(), body);
+            }
+        });
+
+        (url, fetched)
+    }
+
+    fn oauth2(token_url: &str) -> Auth {
+        Auth::OAuth2 {
+            grant: OAuth2Grant::ClientCredentials,
+            token_url: token_url.to_string(),
+            client_id: String::from("client"),
+            client_secret: String::from("secret"),
+            scope: String::new(),
+            username: String::new(),
+            password: String::new(),
+        }
+    }
+
+    fn send(auth: &Auth, cache: &TokenCache) -> (String, String) {
+        let mut request = request();
+        apply(auth, &mut request, cache).unwrap();
+        let built = header(&request, "Authorization").unwrap().to_string();
+
+        authorize(auth, &mut request, cache, &TlsOptions::default()).unwrap();
+        (built, header(&request, "Authorization").unwrap().to_string())
+    }
+
+    #[test]
+    fn tokens_are_fetched_when_sending_and_cached() {
+        let (url, fetched) = token_server(3600);
+        let (auth, cache) = (oauth2(&url), TokenCache::new());
+
+        assert_eq!(send(&auth, &cache), (String::from(PENDING_TOKEN), String::from("Bearer token-1")));
+        assert_eq!(send(&auth, &cache), (String::from("Bearer token-1"), String::from("Bearer token-1")));
+        assert_eq!(fetched.load(Ordering::SeqCst), 1);
+
+        // A header set by a pre-request script stays
+        let mut scripted = request();
+        scripted.headers = vec![(String::from("Authorization"), String::from("Bearer mine"))];
+        authorize(&auth, &mut scripted, &cache, &TlsOptions::default()).unwrap();
+        assert_eq!(header(&scripted, "Authorization"), Some("Bearer mine"));
+    }
+
+    #[test]
+    fn tokens_about_to_expire_are_fetched_again() {
+        let (url, fetched) = token_server(EXPIRY_MARGIN.as_secs() - 1);
+        let (auth, cache) = (oauth2(&url), TokenCache::new());
+
+        assert_eq!(send(&auth, &cache).1, "Bearer token-1");
+        assert_eq!(send(&auth, &cache), (String::from(PENDING_TOKEN), String::from("Bearer token-2")));
+        assert_eq!(fetched.load(Ordering::SeqCst), 2);
+
+        let error = apply(&oauth2(" "), &mut request(), &cache).unwrap_err();
+        assert_eq!(error, "OAuth2 token URL is not set");
+    }
+}
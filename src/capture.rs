@@ -0,0 +1,171 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::http::HttpResponse;
+use crate::jsonpath;
+
+pub const SOURCES: [&str; 3] = ["jsonpath", "regex", "header"];
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptureSource {
+    /// Expression is the JSONPath of the value in the body
+    #[serde(rename = "jsonpath")]
+    JsonPath,
+    /// Expression is matched against the body, the first group is taken if there is one
+    Regex,
+    /// Expression is the header name
+    Header,
+}
+
+impl CaptureSource {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CaptureSource::JsonPath => SOURCES[0],
+            CaptureSource::Regex => SOURCES[1],
+            CaptureSource::Header => SOURCES[2],
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<CaptureSource> {
+        match name.trim().to_lowercase().as_str() {
+            "jsonpath" => Some(CaptureSource::JsonPath),
+            "regex" => Some(CaptureSource::Regex),
+            "header" => Some(CaptureSource::Header),
+            _ => None,
+        }
+    }
+}
+
+/// Rule storing a value of a successful response into a `{{variable}}`.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Capture {
+    pub variable: String,
+    pub source: CaptureSource,
+    #[serde(default)]
+    pub expression: String,
+    #[serde(default = "crate::model::enabled_by_default")]
+    pub enabled: bool,
+}
+
+pub struct CaptureResult {
+    pub variable: String,
+    /// Captured value or why nothing was captured
+    pub value: Result<String, String>,
+}
+
+impl Capture {
+    fn extract(&self, response: &HttpResponse) -> Result<String, String> {
+        let expression = self.expression.trim();
+
+        match self.source {
+            CaptureSource::JsonPath => {
+                let body: Value = serde_json::from_str(&response.body)
+                    .map_err(|e| format!("body is not JSON: {}", e))?;
+
+                match jsonpath::select(&body, expression)?.first() {
+                    // Strings are stored without quotes, other values as JSON
+                    Some(Value::String(s)) => Ok(s.clone()),
+                    Some(other) => Ok(other.to_string()),
+                    None => Err(format!("{} not found", expression)),
+                }
+            },
+            CaptureSource::Regex => {
+                let re = Regex::new(expression).map_err(|e| format!("invalid regex: {}", e))?;
+                let found = re.captures(&response.body).ok_or(format!("{} doesn't match", expression))?;
+
+                Ok(found.get(1).or(found.get(0)).map(|m| m.as_str().to_string()).unwrap_or_default())
+            },
+            CaptureSource::Header => response
+                .header(expression)
+                .map(String::from)
+                .ok_or(format!("no {} header", expression)),
+        }
+    }
+}
+
+/// Values of the enabled rules that name a variable.
+pub fn extract(captures: &[Capture], response: &HttpResponse) -> Vec<CaptureResult> {
+    captures
+        .iter()
+        .filter(|c| c.enabled && !c.variable.trim().is_empty())
+        .map(|c| CaptureResult {
+            variable: c.variable.trim().to_string(),
+            value: c.extract(response),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    use crate::http::Timings;
+
+    fn response(status: u16, body: &str) -> HttpResponse {
+        HttpResponse {
+            status,
+            reason: String::new(),
+            headers: vec![(String::from("Location"), String::from("/pets/7"))],
+            body: body.to_string(),
+            timings: Timings { connect: Duration::ZERO, first_byte: Duration::ZERO, total: Duration::ZERO },
+        }
+    }
+
+    fn rule(variable: &str, source: CaptureSource, expression: &str) -> Capture {
+        Capture { variable: variable.to_string(), source, expression: expression.to_string(), enabled: true }
+    }
+
+    fn values(captures: &[Capture], response: &HttpResponse) -> Vec<(String, Result<String, String>)> {
+        extract(captures, response).into_iter().map(|r| (r.variable, r.value)).collect()
+    }
+
+    #[test]
+    fn values_come_from_the_body_and_headers() {
+        let response = response(201, r#"{ "token": "abc", "pet": { "id": 7, "tags": ["x"] } }"#);
+        let disabled = Capture { enabled: false, ..rule("skipped", CaptureSource::Header, "Location") };
+
+        let captured = values(&[
+            rule(" token ", CaptureSource::JsonPath, "$.token"),
+            rule("id", CaptureSource::JsonPath, " $.pet.id "),
+            rule("tags", CaptureSource::JsonPath, "$.pet.tags"),
+            rule("quoted", CaptureSource::Regex, r#""token": "(\w+)""#),
+            rule("whole", CaptureSource::Regex, r"\d+"),
+            rule("location", CaptureSource::Header, "location"),
+            rule("", CaptureSource::Header, "Location"),
+            disabled,
+        ], &response);
+
+        assert_eq!(captured, [
+            (String::from("token"), Ok(String::from("abc"))),
+            (String::from("id"), Ok(String::from("7"))),
+            (String::from("tags"), Ok(String::from(r#"["x"]"#))),
+            (String::from("quoted"), Ok(String::from("abc"))),
+            (String::from("whole"), Ok(String::from("7"))),
+            (String::from("location"), Ok(String::from("/pets/7"))),
+        ]);
+    }
+
+    #[test]
+    fn missing_values_say_why() {
+        let json = response(200, r#"{ "token": "abc" }"#);
+        let text = response(200, "plain text");
+
+        let captured = values(&[
+            rule("a", CaptureSource::JsonPath, "$.missing"),
+            rule("b", CaptureSource::Regex, "id=(\\d+)"),
+            rule("c", CaptureSource::Regex, "("),
+            rule("d", CaptureSource::Header, "X-Token"),
+        ], &json);
+
+        assert_eq!(captured[0].1, Err(String::from("$.missing not found")));
+        assert_eq!(captured[1].1, Err(String::from("id=(\\d+) doesn't match")));
+        assert!(captured[2].1.as_ref().unwrap_err().starts_with("invalid regex"));
+        assert_eq!(captured[3].1, Err(String::from("no X-Token header")));
+
+        let not_json = values(&[rule("a", CaptureSource::JsonPath, "$.token")], &text);
+        assert!(not_json[0].1.as_ref().unwrap_err().starts_with("body is not JSON"));
+    }
+}
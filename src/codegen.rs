@@ -0,0 +1,277 @@
+use crate::http::HttpRequest;
+
+/// Clients code is generated for.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Language {
+    ReqwestBlocking,
+    ReqwestAsync,
+    PythonRequests,
+    JavaScriptFetch,
+    GoNetHttp,
+    Wget,
+}
+
+pub const LANGUAGES: [Language; 6] = [
+    Language::ReqwestBlocking,
+    Language::ReqwestAsync,
+    Language::PythonRequests,
+    Language::JavaScriptFetch,
+    Language::GoNetHttp,
+    Language::Wget,
+];
+
+impl Language {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Language::ReqwestBlocking => "reqwest blocking",
+            Language::ReqwestAsync => "reqwest async",
+            Language::PythonRequests => "Python requests",
+            Language::JavaScriptFetch => "JS fetch",
+            Language::GoNetHttp => "Go net/http",
+            Language::Wget => "wget",
+        }
+    }
+
+    /// Name of the syntect syntax the code is highlighted as.
+    pub fn syntax(&self) -> &'static str {
+        match self {
+            Language::ReqwestBlocking | Language::ReqwestAsync => "Rust",
+            Language::PythonRequests => "Python",
+            Language::JavaScriptFetch => "JavaScript",
+            Language::GoNetHttp => "Go",
+            Language::Wget => "Bourne Again Shell (bash)",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Language::ReqwestBlocking | Language::ReqwestAsync => "rs",
+            Language::PythonRequests => "py",
+            Language::JavaScriptFetch => "js",
+            Language::GoNetHttp => "go",
+            Language::Wget => "sh",
+        }
+    }
+}
+
+/// Double quoted literal, valid in Python, JavaScript and Go alike.
+fn quote(text: &str) -> String {
+    serde_json::to_string(text).unwrap_or_default()
+}
+
+/// Double quoted Rust literal, Rust wants `\u{1f}` where JSON writes `\u001f`.
+fn rust_quote(text: &str) -> String {
+    format!("{:?}", text)
+}
+
+/// Single quoted shell word.
+fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', r"'\''"))
+}
+
+fn reqwest(request: &HttpRequest, asynchronous: bool) -> String {
+    let (client, dependencies, main, wait) = match asynchronous {
+        true => (
+            "reqwest::Client",
+            "reqwest = \"0.12\"\n// tokio = { version = \"1\", features = [\"full\"] }",
+            "#[tokio::main]\nasync fn main()",
+            ".await",
+        ),
+        false => (
+            "reqwest::blocking::Client",
+            "reqwest = { version = \"0.12\", features = [\"blocking\"] }",
+            "fn main()",
+            "",
+        ),
+    };
+
+    let mut code = format!("// Cargo.toml:\n// {}\n\n{} -> Result<(), Box<dyn std::error::Error>> {{\n", dependencies, main);
+    code.push_str(&format!("    let client = {}::new();\n", client));
+    code.push_str("    let response = client\n");
+
+    match request.method.as_str() {
+        "GET" | "POST" | "PUT" | "PATCH" | "DELETE" | "HEAD" => {
+            code.push_str(&format!("        .{}({})\n", request.method.to_lowercase(), rust_quote(&request.url)));
+        },
+        method => code.push_str(&format!(
+            "        .request(reqwest::Method::from_bytes(b{})?, {})\n",
+            rust_quote(method),
+            rust_quote(&request.url),
+        )),
+    }
+
+    for (name, value) in &request.headers {
+        code.push_str(&format!("        .header({}, {})\n", rust_quote(name), rust_quote(value)));
+    }
+
+    if !request.body.is_empty() {
+        code.push_str(&format!("        .body({})\n", rust_quote(&request.body)));
+    }
+
+    code.push_str(&format!("        .send(){}?;\n\n", wait));
+    code.push_str("    println!(\"{}\", response.status());\n");
+    code.push_str(&format!("    println!(\"{{}}\", response.text(){}?);\n", wait));
+    code.push_str("    Ok(())\n}\n");
+    code
+}
+
+fn python(request: &HttpRequest) -> String {
+    let mut code = String::from("import requests\n\n");
+    code.push_str(&format!("url = {}\n", quote(&request.url)));
+
+    let mut arguments = vec![quote(&request.method), String::from("url")];
+
+    if !request.headers.is_empty() {
+        code.push_str("headers = {\n");
+
+        for (name, value) in &request.headers {
+            code.push_str(&format!("    {}: {},\n", quote(name), quote(value)));
+        }
+
+        code.push_str("}\n");
+        arguments.push(String::from("headers=headers"));
+    }
+
+    if !request.body.is_empty() {
+        code.push_str(&format!("body = {}\n", quote(&request.body)));
+        arguments.push(String::from("data=body"));
+    }
+
+    code.push_str(&format!("\nresponse = requests.request({})\n\n", arguments.join(", ")));
+    code.push_str("print(response.status_code)\nprint(response.text)\n");
+    code
+}
+
+fn fetch(request: &HttpRequest) -> String {
+    let mut code = format!("const response = await fetch({}, {{\n", quote(&request.url));
+    code.push_str(&format!("  method: {},\n", quote(&request.method)));
+
+    if !request.headers.is_empty() {
+        code.push_str("  headers: {\n");
+
+        for (name, value) in &request.headers {
+            code.push_str(&format!("    {}: {},\n", quote(name), quote(value)));
+        }
+
+        code.push_str("  },\n");
+    }
+
+    if !request.body.is_empty() {
+        code.push_str(&format!("  body: {},\n", quote(&request.body)));
+    }
+
+    code.push_str("});\n\nconsole.log(response.status);\nconsole.log(await response.text());\n");
+    code
+}
+
+fn go(request: &HttpRequest) -> String {
+    let has_body = !request.body.is_empty();
+
+    let mut code = String::from("package main\n\nimport (\n\t\"fmt\"\n\t\"io\"\n\t\"net/http\"\n");
+
+    if has_body {
+        code.push_str("\t\"strings\"\n");
+    }
+
+    code.push_str(")\n\nfunc main() {\n");
+
+    let body = match has_body {
+        true => {
+            code.push_str(&format!("\tbody := strings.NewReader({})\n", quote(&request.body)));
+            "body"
+        },
+        false => "nil",
+    };
+
+    code.push_str(&format!(
+        "\treq, err := http.NewRequest({}, {}, {})\n\tif err != nil {{\n\t\tpanic(err)\n\t}}\n",
+        quote(&request.method),
+        quote(&request.url),
+        body,
+    ));
+
+    for (name, value) in &request.headers {
+        code.push_str(&format!("\treq.Header.Add({}, {})\n", quote(name), quote(value)));
+    }
+
+    code.push_str(concat!(
+        "\n\tres, err := http.DefaultClient.Do(req)\n",
+        "\tif err != nil {\n\t\tpanic(err)\n\t}\n",
+        "\tdefer res.Body.Close()\n\n",
+        "\tdata, err := io.ReadAll(res.Body)\n",
+        "\tif err != nil {\n\t\tpanic(err)\n\t}\n\n",
+        "\tfmt.Println(res.Status)\n",
+        "\tfmt.Println(string(data))\n",
+        "}\n",
+    ));
+    code
+}
+
+fn wget(request: &HttpRequest) -> String {
+    // Error responses are printed too, like every other snippet does
+    let mut lines = vec![
+        String::from("wget --quiet --content-on-error --output-document=-"),
+        format!("--method={}", request.method),
+    ];
+
+    for (name, value) in &request.headers {
+        lines.push(format!("--header={}", shell_quote(&format!("{}: {}", name, value))));
+    }
+
+    if !request.body.is_empty() {
+        lines.push(format!("--body-data={}", shell_quote(&request.body)));
+    }
+
+    lines.push(shell_quote(&request.url));
+    format!("{}\n", lines.join(" \\\n  "))
+}
+
+/// Code sending the request as it would be sent, placeholders and auth resolved.
+pub fn generate(language: Language, request: &HttpRequest) -> String {
+    match language {
+        Language::ReqwestBlocking => reqwest(request, false),
+        Language::ReqwestAsync => reqwest(request, true),
+        Language::PythonRequests => python(request),
+        Language::JavaScriptFetch => fetch(request),
+        Language::GoNetHttp => go(request),
+        Language::Wget => wget(request),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request() -> HttpRequest {
+        HttpRequest {
+            method: String::from("POST"),
+            url: String::from("https://api.test/items?q=a b"),
+            headers: vec![(String::from("Content-Type"), String::from("text/plain"))],
+            body: String::from("say \"hi\"\n\u{1f}it's"),
+        }
+    }
+
+    #[test]
+    fn rust_literals_use_rust_escapes() {
+        let code = generate(Language::ReqwestBlocking, &request());
+
+        assert!(code.contains(r#"        .post("https://api.test/items?q=a b")"#));
+        assert!(code.contains(r#"        .header("Content-Type", "text/plain")"#));
+        assert!(code.contains(r#"        .body("say \"hi\"\n\u{1f}it's")"#));
+    }
+
+    #[test]
+    fn other_literals_use_json_escapes() {
+        let code = generate(Language::PythonRequests, &request());
+
+        assert!(code.contains(r#"body = "say \"hi\"\n\u001fit's""#));
+    }
+
+    #[test]
+    fn shell_words_escape_single_quotes() {
+        let code = generate(Language::Wget, &request());
+
+        assert!(code.contains("--body-data='say \"hi\"\n"));
+        assert!(code.contains(r#"it'\''s'"#));
+    }
+}
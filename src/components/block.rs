@@ -26,6 +26,7 @@ pub struct Block {
     cursor_y: u16,
     has_border: bool,
     state: BlockState,
+    title: Option<String>,
 }
 
 
@@ -38,7 +39,7 @@ impl Block {
         border: bool,
     ) -> Self {
         let (inner_x, inner_y, aw, ah) = if border {
-            (x + 1, y + 1, width - 1, height - 1)
+            (x + 1, y + 1, width.saturating_sub(1), height.saturating_sub(1))
         } else {
             (x, y, width, height)
         };
@@ -54,29 +55,43 @@ impl Block {
             cursor_y: y,
             has_border: border,
             state: BlockState::Inactive,
+            title: None,
         }
     }
 
+    /// Two bordered blocks side by side, covering the area of this one.
+    pub fn split_columns(&self) -> (Block, Block) {
+        let (width, height) = if self.has_border {
+            (self.width + 1, self.height + 1)
+        } else {
+            (self.width, self.height)
+        };
+
+        let left_width = width / 2;
+
+        (
+            Block::new(self.full_x, self.full_y, left_width, height, true),
+            Block::new(self.full_x + left_width + 1, self.full_y, width.saturating_sub(left_width + 1), height, true),
+        )
+    }
+
     pub fn set_state(&mut self, state: BlockState) {
         self.state = state;
     }
+
+    pub fn set_title(&mut self, title: Option<&str>) {
+        self.title = title.map(String::from);
+    }
     
     pub fn is_selected(&self) -> bool {
-        if let BlockState::Selected = self.state {
-            true
-        } else {
-            false
-        }
+        matches!(self.state, BlockState::Selected)
     }
 
     pub fn is_active(&self) -> bool {
-        if let BlockState::Active = self.state {
-            true
-        } else {
-            false
-        }
+        matches!(self.state, BlockState::Active)
     }
 
+    #[allow(clippy::wrong_self_convention)]
     pub fn to_line_start(&mut self, console: &mut Console) {
         self.cursor_x = self.x;
         console.move_to(self.cursor_x, self.cursor_y);
@@ -88,9 +103,13 @@ impl Block {
         console.move_to(self.cursor_x, self.cursor_y);
     }
 
-    pub fn next_col(&mut self, console: &mut Console) {
-        self.cursor_x += 1;
-        console.move_to(self.cursor_x, self.cursor_y);
+    /// Number of lines available for content, the bottom border line excluded.
+    pub fn content_height(&self) -> u16 {
+        if self.has_border {
+            self.height.saturating_sub(1)
+        } else {
+            self.height
+        }
     }
 
     pub fn reset(&mut self) {
@@ -100,18 +119,18 @@ impl Block {
 
     pub fn empty_line(&mut self, console: &mut Console, line: u16) {
         console.move_to(self.x, self.y + line);
-        console.write(" ".repeat((self.width - 1) as usize));
+        console.write(" ".repeat(self.width as usize));
         console.flush();
     }
 
     pub fn empty(&mut self, console: &mut Console) {
-        for line in 0..self.height {
+        for line in 0..self.content_height() {
             self.empty_line(console, line);
         }
     }
 
     pub fn empty_after(&mut self, console: &mut Console, line: u16) {
-        for cl in line..self.height {
+        for cl in line..self.content_height() {
             self.empty_line(console, cl);
         }
     }
@@ -125,9 +144,22 @@ impl Block {
 
         console.move_to(self.full_x, self.full_y);
 
-        // Top box part
+        // Top box part, with the title embedded into the line if there is one
         console.write(BOX_LIGHT_TL_CORNER);
-        console.write(BOX_LIGHT_HORIZONTAL.repeat(self.width.into()));
+
+        let mut line_len: usize = self.width.into();
+
+        if let Some(title) = &self.title {
+            let title: String = format!(" {} ", title)
+                .chars()
+                .take(line_len)
+                .collect();
+
+            line_len -= title.chars().count();
+            console.write(title);
+        }
+
+        console.write(BOX_LIGHT_HORIZONTAL.repeat(line_len));
         console.write(BOX_LIGHT_TR_CORNER);
 
         // Vertical lines
@@ -162,8 +194,10 @@ impl Block {
         let mut in_escape_seq = false;
         let mut escape_seq = Vec::<u8>::new();
 
-        for &b in buf {
-            if self.cursor_y >= self.y + self.height {
+        let is_continuation = |b: Option<&u8>| b.is_some_and(|b| b & 0xC0 == 0x80);
+
+        for (pos, &b) in buf.iter().enumerate() {
+            if self.cursor_y >= self.y + self.content_height() {
                 break; // Stop writing if height boundary is exceeded
             }
 
@@ -218,19 +252,23 @@ impl Block {
                     continue;
                 }
 
-                if (b >= 0x41 && b <= 0x5A) || (b >= 0x61 && b <= 0x7A) { // End of escape sequence
+                if (0x41..=0x5A).contains(&b) || (0x61..=0x7A).contains(&b) { // End of escape sequence
                     in_escape_seq = false;
                     console.write_raw(&escape_seq);
                     escape_seq.clear();
                     continue;
                 }
             } else {
-                // Regular character handling
-                console.move_to(self.cursor_x, self.cursor_y);
+                // Regular character handling, trailing bytes of a multi-byte
+                // UTF-8 character are written as is in the same cell
+                if !is_continuation(Some(&b)) {
+                    console.move_to(self.cursor_x, self.cursor_y);
+                    self.cursor_x += 1;
+                }
+
                 console.write_raw(&[b]);
-                self.cursor_x += 1;
 
-                if self.cursor_x >= self.x + self.width {
+                if self.cursor_x >= self.x + self.width && !is_continuation(buf.get(pos + 1)) {
                     self.next_line(console);
                 }
             }
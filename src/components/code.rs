@@ -0,0 +1,109 @@
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind};
+
+use crate::codegen::{self, Language, LANGUAGES};
+use crate::console::Console;
+use crate::http::HttpRequest;
+
+use super::block::Block;
+use super::editor::Editor;
+use super::element::{DialogState, Element};
+
+pub enum CodeAction {
+    Save,
+}
+
+/// Read-only code sending the request, one tab per client.
+pub struct CodeView {
+    request: HttpRequest,
+    /// Environment the placeholders were filled from
+    environment: Option<String>,
+    language: usize,
+    editor: Editor,
+    pub action: Option<CodeAction>,
+    pub state: DialogState,
+}
+
+impl CodeView {
+    /// The secret values are hidden on screen, but kept in the code.
+    pub fn new(request: HttpRequest, environment: Option<&str>, masked: Vec<String>) -> Self {
+        let mut editor = Editor::new();
+        editor.set_read_only(true);
+        editor.set_masked(masked);
+
+        let mut view = Self {
+            request,
+            environment: environment.map(String::from),
+            language: 0,
+            editor,
+            action: None,
+            state: DialogState::Open,
+        };
+
+        view.show(0);
+        view
+    }
+
+    pub fn language(&self) -> Language {
+        LANGUAGES[self.language]
+    }
+
+    pub fn code(&self) -> String {
+        codegen::generate(self.language(), &self.request)
+    }
+
+    fn show(&mut self, language: usize) {
+        self.language = language;
+        self.editor.set_syntax(self.language().syntax());
+        self.editor.set_val(self.code().trim_end());
+    }
+
+    fn title(&self) -> String {
+        let tabs: Vec<String> = LANGUAGES
+            .iter()
+            .map(|l| match *l == self.language() {
+                true => format!("[{}]", l.name()),
+                false => l.name().to_string(),
+            })
+            .collect();
+
+        match &self.environment {
+            Some(environment) => format!("Code · {} · {}", environment, tabs.join(" ")),
+            None => format!("Code · {}", tabs.join(" ")),
+        }
+    }
+}
+
+impl Element for CodeView {
+    fn output(&mut self, console: &mut Console, target: &mut Block) {
+        target.set_title(Some(&self.title()));
+        self.editor.output(console, target);
+        console.hide_cursor();
+    }
+
+    fn on_event(&mut self, event: &Event) -> std::io::Result<()> {
+        let Event::Key(KeyEvent { code, kind: KeyEventKind::Press, .. }) = event else {
+            return Ok(());
+        };
+
+        match code {
+            KeyCode::Tab => self.show((self.language + 1) % LANGUAGES.len()),
+            KeyCode::BackTab => self.show((self.language + LANGUAGES.len() - 1) % LANGUAGES.len()),
+            KeyCode::Char('w') => {
+                self.action = Some(CodeAction::Save);
+                self.state = DialogState::Submitted;
+            },
+            KeyCode::Esc => self.state = DialogState::Cancelled,
+            _ => self.editor.on_event(event)?,
+        }
+
+        Ok(())
+    }
+
+    fn needs_re_render(&self) -> bool {
+        self.editor.needs_re_render()
+    }
+
+    fn invalidate(&mut self) {
+        self.editor.invalidate();
+    }
+}
@@ -0,0 +1,147 @@
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind};
+use crossterm::style::Color;
+
+use crate::console::Console;
+use crate::diff::{DiffKind, DiffRow};
+
+use super::block::{Block, BlockState};
+use super::element::{DialogState, Element};
+
+pub enum DiffAction {
+    EditIgnored,
+}
+
+/// Two responses side by side, removed lines on the left, added on the right.
+pub struct DiffView {
+    left_title: String,
+    right_title: String,
+    rows: Vec<DiffRow>,
+    scroll: usize,
+    /// Row of the change jumped to last
+    change: Option<usize>,
+    pub action: Option<DiffAction>,
+    pub state: DialogState,
+    to_re_render: bool,
+}
+
+fn colors(kind: DiffKind) -> (Color, Color) {
+    match kind {
+        DiffKind::Same => (Color::Reset, Color::Reset),
+        DiffKind::Removed => (Color::Red, Color::Reset),
+        DiffKind::Added => (Color::Reset, Color::Green),
+        DiffKind::Changed => (Color::Red, Color::Green),
+    }
+}
+
+fn write_line(console: &mut Console, target: &mut Block, y: u16, line: Option<&str>, color: Color) {
+    let width = target.width.saturating_sub(1) as usize;
+
+    let line: String = line
+        .unwrap_or_default()
+        .replace('\t', "    ")
+        .chars()
+        .filter(|c| !c.is_control())
+        .take(width)
+        .collect();
+
+    target.move_to(console, 0, y);
+    console.set_fg_color(color);
+    target.write_str(console, &line);
+    console.reset_color();
+}
+
+impl DiffView {
+    pub fn new(left_title: &str, right_title: &str, rows: Vec<DiffRow>) -> Self {
+        let mut view = Self {
+            left_title: left_title.to_string(),
+            right_title: right_title.to_string(),
+            rows,
+            scroll: 0,
+            change: None,
+            action: None,
+            state: DialogState::Open,
+            to_re_render: true,
+        };
+
+        view.next_change();
+        view
+    }
+
+    /// Scrolls to the next changed block of lines, wrapping to the first one.
+    fn next_change(&mut self) {
+        let is_start = |i: usize| self.rows[i].kind != DiffKind::Same
+            && (i == 0 || self.rows[i - 1].kind == DiffKind::Same);
+
+        let from = self.change.map_or(0, |c| c + 1);
+        let next = (from..self.rows.len())
+            .chain(0..std::cmp::min(from, self.rows.len()))
+            .find(|&i| is_start(i));
+
+        if let Some(i) = next {
+            // A few lines of context above the change
+            self.scroll = i.saturating_sub(3);
+            self.change = Some(i);
+        }
+    }
+}
+
+impl Element for DiffView {
+    fn output(&mut self, console: &mut Console, target: &mut Block) {
+        let (mut left, mut right) = target.split_columns();
+
+        for (block, title) in [(&mut left, &self.left_title), (&mut right, &self.right_title)] {
+            block.set_state(BlockState::Active);
+            block.set_title(Some(title));
+            block.render(console);
+            block.empty(console);
+        }
+
+        let height = left.content_height() as usize;
+
+        for (i, row) in self.rows.iter().skip(self.scroll).take(height).enumerate() {
+            let (left_color, right_color) = colors(row.kind);
+
+            write_line(console, &mut left, i as u16, row.left.as_deref(), left_color);
+            write_line(console, &mut right, i as u16, row.right.as_deref(), right_color);
+        }
+
+        console.hide_cursor();
+        self.to_re_render = false;
+    }
+
+    fn on_event(&mut self, event: &Event) -> std::io::Result<()> {
+        if let Event::Key(KeyEvent {
+            code,
+            kind: KeyEventKind::Press,
+            ..
+        }) = event {
+            let max_scroll = self.rows.len().saturating_sub(1);
+
+            match code {
+                KeyCode::Up => self.scroll = self.scroll.saturating_sub(1),
+                KeyCode::Down => self.scroll = std::cmp::min(self.scroll + 1, max_scroll),
+                KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(10),
+                KeyCode::PageDown => self.scroll = std::cmp::min(self.scroll + 10, max_scroll),
+                KeyCode::Char('n') => self.next_change(),
+                KeyCode::Char('i') => {
+                    self.action = Some(DiffAction::EditIgnored);
+                    self.state = DialogState::Submitted;
+                },
+                KeyCode::Esc => self.state = DialogState::Cancelled,
+                _ => {},
+            }
+
+            self.to_re_render = true;
+        }
+
+        Ok(())
+    }
+
+    fn needs_re_render(&self) -> bool {
+        self.to_re_render
+    }
+
+    fn invalidate(&mut self) {
+        self.to_re_render = true;
+    }
+}
@@ -4,6 +4,8 @@ use syntect::parsing::SyntaxSet;
 use syntect::highlighting::{ThemeSet, Style, Color};
 use crossterm::style::Color as CTColor;
 
+use crate::secrets;
+
 use super::element::Element;
 
 
@@ -16,7 +18,11 @@ pub struct Editor {
     to_re_render: bool,
     only_cursor: bool,
     lines_changed: Vec<bool>,
-    refresh: bool,
+    read_only: bool,
+    /// Secret values hidden in the display
+    masked: Vec<String>,
+    /// Name of the syntect syntax used for highlighting
+    syntax: &'static str,
 }
 
 
@@ -34,14 +40,38 @@ impl Editor {
             cursor_c: 0,
             to_re_render: true,
             only_cursor: false,
+            read_only: false,
+            masked: vec![],
+            syntax: "JSON",
             ps: SyntaxSet::load_defaults_newlines(),
             ts: ThemeSet::load_defaults(),
-            refresh: true,
         }
     }
     
+    /// Highlights the text as another language, JSON by default.
+    pub fn with_syntax(mut self, syntax: &'static str) -> Self {
+        self.syntax = syntax;
+        self
+    }
+
+    pub fn set_syntax(&mut self, syntax: &'static str) {
+        self.syntax = syntax;
+        self.invalidate();
+    }
+
+    /// Text of the editor, lines joined back with the line breaks they were split on.
     pub fn get_body(&self) -> String {
-        self.body.join("")
+        self.body.join("\n")
+    }
+
+    /// Read-only editor still allows moving the cursor, but ignores edits.
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+    }
+
+    pub fn set_masked(&mut self, values: Vec<String>) {
+        self.masked = values;
+        self.invalidate();
     }
 
     pub fn set_val(&mut self, val: &str) {
@@ -217,13 +247,17 @@ impl Element for Editor {
             target.reset();
 
             if !self.only_cursor {
-                let syntax = self.ps.find_syntax_by_name("JSON").expect("No json extension");
+                target.render(console);
+
+                let syntax = self.ps.find_syntax_by_name(self.syntax).expect("No syntax for the editor");
                 let mut h = HighlightLines::new(syntax, &self.ts.themes["InspiredGitHub"]);
 
                 for (line, render) in self.lines_changed.iter_mut().enumerate() {
-                    if *render == true {
+                    if *render {
+                        // Masking keeps the char count, so the cursor stays in place
+                        let text = secrets::mask(&self.body[line], &self.masked);
                         let ranges: Vec<(Style, &str)> = h.highlight_line(
-                            &self.body[line],
+                            &text,
                             &self.ps,
                         ).unwrap();
 
@@ -270,6 +304,8 @@ impl Element for Editor {
             ..
         }) = event {
             match code {
+                KeyCode::Char(_) | KeyCode::Backspace | KeyCode::Delete | KeyCode::Enter
+                    if self.read_only => {},
                 KeyCode::Char(c) => self.on_char_insert(c),
                 KeyCode::Backspace | KeyCode::Delete => self.on_delete(),
                 KeyCode::Enter => self.on_enter(),
@@ -287,4 +323,33 @@ impl Element for Editor {
     fn needs_re_render(&self) -> bool { 
         self.to_re_render
     }
+
+    fn invalidate(&mut self) {
+        self.lines_changed = [true].repeat(self.body.len());
+        self.only_cursor = false;
+        self.to_re_render = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn body_keeps_its_line_breaks() {
+        let mut editor = Editor::new();
+        editor.set_val("{\n  \"name\": \"Rex\"\n}");
+
+        assert_eq!(editor.get_body(), "{\n  \"name\": \"Rex\"\n}");
+    }
+
+    #[test]
+    fn enter_splits_the_line() {
+        let mut editor = Editor::new();
+        editor.set_val("ab");
+        editor.safe_go_to(0, 1);
+        editor.on_enter();
+
+        assert_eq!(editor.get_body(), "a\nb");
+    }
 }
@@ -7,4 +7,12 @@ pub trait Element {
     fn output(&mut self, console: &mut Console, target: &mut Block);
     fn on_event(&mut self, event: &Event) -> std::io::Result<()>;
     fn needs_re_render(&self) -> bool;
+    /// Forces a full re-render on the next frame, e.g. after a dialog was drawn on top.
+    fn invalidate(&mut self);
+}
+
+pub enum DialogState {
+    Open,
+    Submitted,
+    Cancelled,
 }
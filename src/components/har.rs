@@ -0,0 +1,177 @@
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind};
+use crossterm::style::Color;
+
+use crate::console::Console;
+use crate::har::{HarEntry, HarFilter};
+
+use super::block::Block;
+use super::element::{DialogState, Element};
+
+/// Most entries shown at once, the rest is scrolled to.
+const MAX_VISIBLE: usize = 15;
+
+/// Dialog picking the entries of a HAR file to import, narrowed by a typed filter.
+pub struct HarView {
+    entries: Vec<HarEntry>,
+    checked: Vec<bool>,
+    /// Folder the entries are imported into
+    pub folder: String,
+    /// Words matched against methods and host names
+    filter: String,
+    /// Index among the entries that match the filter
+    selected: usize,
+    scroll: usize,
+    pub state: DialogState,
+    to_re_render: bool,
+}
+
+impl HarView {
+    pub fn new(folder: &str, entries: Vec<HarEntry>) -> Self {
+        Self {
+            checked: vec![true; entries.len()],
+            entries,
+            folder: folder.to_string(),
+            filter: String::new(),
+            selected: 0,
+            scroll: 0,
+            state: DialogState::Open,
+            to_re_render: true,
+        }
+    }
+
+    /// Height of the block needed for the filter, the visible entries and the hints.
+    pub fn height(&self) -> u16 {
+        (self.entries.len().clamp(1, MAX_VISIBLE) + 7) as u16
+    }
+
+    /// Indexes of the entries matching the filter.
+    fn visible(&self) -> Vec<usize> {
+        let filter = HarFilter::parse(&self.filter);
+
+        (0..self.entries.len()).filter(|&i| filter.matches(&self.entries[i])).collect()
+    }
+
+    /// Checked entries that match the filter, in the order they were recorded.
+    pub fn take_picked(self) -> Vec<HarEntry> {
+        let visible = self.visible();
+
+        self.entries
+            .into_iter()
+            .zip(self.checked)
+            .enumerate()
+            .filter(|(i, (_, checked))| *checked && visible.contains(i))
+            .map(|(_, (entry, _))| entry)
+            .collect()
+    }
+}
+
+impl Element for HarView {
+    fn output(&mut self, console: &mut Console, target: &mut Block) {
+        console.reset_color();
+        target.empty(console);
+        target.render(console);
+        target.reset();
+
+        let width = target.width.saturating_sub(1) as usize;
+        let clip = |text: String| -> String { text.chars().take(width).collect() };
+
+        let visible = self.visible();
+        let picked = visible.iter().filter(|&&i| self.checked[i]).count();
+
+        target.write_str(console, &clip(format!(
+            "Into folder {} · {} of {} entries match, {} checked",
+            self.folder,
+            visible.len(),
+            self.entries.len(),
+            picked,
+        )));
+
+        target.move_to(console, 0, 1);
+        target.write_str(console, &clip(format!("Filter: {}", self.filter)));
+
+        if visible.is_empty() {
+            target.move_to(console, 0, 3);
+            target.write_str(console, "No entries match, filter by methods and parts of host names");
+        }
+
+        self.selected = self.selected.min(visible.len().saturating_sub(1));
+
+        if self.selected < self.scroll {
+            self.scroll = self.selected;
+        } else if self.selected >= self.scroll + MAX_VISIBLE {
+            self.scroll = self.selected + 1 - MAX_VISIBLE;
+        }
+
+        for (row, &i) in visible.iter().enumerate().skip(self.scroll).take(MAX_VISIBLE) {
+            let entry = &self.entries[i];
+            target.move_to(console, 0, (row - self.scroll) as u16 + 3);
+
+            let check = if self.checked[i] { "[x]" } else { "[ ]" };
+            let status = if entry.status == 0 { String::from("-") } else { entry.status.to_string() };
+            let line = clip(format!("{} {:>3} {:<7} {}", check, status, entry.request.method, entry.request.url));
+
+            if row == self.selected {
+                console.set_colors(Color::Black, Color::White);
+            } else if !self.checked[i] {
+                console.set_fg_color(Color::DarkGrey);
+            }
+
+            target.write_str(console, &line);
+            console.reset_color();
+        }
+
+        target.move_to(console, 0, target.content_height().saturating_sub(1));
+        console.set_fg_color(Color::DarkGrey);
+        target.write_str(console, &clip(String::from(
+            "Type to filter by method or host [Space] Check [Tab] All [Enter] Import checked [Esc] Cancel",
+        )));
+        console.reset_color();
+
+        console.hide_cursor();
+        self.to_re_render = false;
+    }
+
+    fn on_event(&mut self, event: &Event) -> std::io::Result<()> {
+        let Event::Key(KeyEvent { code, kind: KeyEventKind::Press, .. }) = event else {
+            return Ok(());
+        };
+
+        let visible = self.visible();
+
+        match code {
+            KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down => self.selected = std::cmp::min(self.selected + 1, visible.len().saturating_sub(1)),
+            KeyCode::Char(' ') => {
+                if let Some(&i) = visible.get(self.selected) {
+                    self.checked[i] = !self.checked[i];
+                }
+            },
+            KeyCode::Tab => {
+                let check = !visible.iter().all(|&i| self.checked[i]);
+                visible.iter().for_each(|&i| self.checked[i] = check);
+            },
+            KeyCode::Char(c) => {
+                self.filter.push(*c);
+                self.selected = 0;
+            },
+            KeyCode::Backspace => {
+                self.filter.pop();
+                self.selected = 0;
+            },
+            KeyCode::Enter if visible.iter().any(|&i| self.checked[i]) => self.state = DialogState::Submitted,
+            KeyCode::Esc => self.state = DialogState::Cancelled,
+            _ => {},
+        }
+
+        self.to_re_render = true;
+        Ok(())
+    }
+
+    fn needs_re_render(&self) -> bool {
+        self.to_re_render
+    }
+
+    fn invalidate(&mut self) {
+        self.to_re_render = true;
+    }
+}
@@ -0,0 +1,141 @@
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind};
+use crossterm::style::Color;
+
+use crate::console::Console;
+
+use super::block::Block;
+use super::element::{DialogState, Element};
+
+/// Most entries shown at once, the rest is scrolled to.
+const MAX_VISIBLE: usize = 15;
+
+#[derive(Clone, Copy)]
+pub enum HistoryAction {
+    /// Show the old response
+    Open,
+    /// Diff the response with the marked or the latest other one
+    Compare,
+    /// Load the request version into the editors
+    Restore,
+}
+
+/// Dialog listing past executions of a request, newest first.
+pub struct HistoryBrowser {
+    entries: Vec<String>,
+    pub selected: usize,
+    /// Entry the selected one is compared with
+    pub marked: Option<usize>,
+    scroll: usize,
+    pub action: Option<HistoryAction>,
+    pub state: DialogState,
+    to_re_render: bool,
+}
+
+impl HistoryBrowser {
+    pub fn new(entries: Vec<String>) -> Self {
+        Self {
+            entries,
+            selected: 0,
+            marked: None,
+            scroll: 0,
+            action: None,
+            state: DialogState::Open,
+            to_re_render: true,
+        }
+    }
+
+    /// Height of the block needed to show the visible entries and the hint.
+    pub fn height(&self) -> u16 {
+        self.entries.len().clamp(1, MAX_VISIBLE) as u16 + 4
+    }
+}
+
+impl Element for HistoryBrowser {
+    fn output(&mut self, console: &mut Console, target: &mut Block) {
+        console.reset_color();
+        target.empty(console);
+        target.render(console);
+        target.reset();
+
+        let width = target.width.saturating_sub(1) as usize;
+
+        console.set_fg_color(Color::DarkGrey);
+        target.write_str(console, "[Enter] Open [Space] Mark [c] Compare with marked or latest other [r] Restore");
+        console.reset_color();
+
+        if self.entries.is_empty() {
+            target.move_to(console, 0, 2);
+            target.write_str(console, "Not executed yet");
+        }
+
+        if self.selected < self.scroll {
+            self.scroll = self.selected;
+        } else if self.selected >= self.scroll + MAX_VISIBLE {
+            self.scroll = self.selected + 1 - MAX_VISIBLE;
+        }
+
+        for (i, entry) in self.entries.iter().enumerate().skip(self.scroll).take(MAX_VISIBLE) {
+            target.move_to(console, 0, (i - self.scroll) as u16 + 2);
+
+            if i == self.selected {
+                console.set_colors(Color::Black, Color::White);
+            }
+
+            let mark = if self.marked == Some(i) { "* " } else { "  " };
+            let line: String = format!("{}{}", mark, entry).chars().take(width).collect();
+            target.write_str(console, &line);
+            console.reset_color();
+        }
+
+        console.hide_cursor();
+        self.to_re_render = false;
+    }
+
+    fn on_event(&mut self, event: &Event) -> std::io::Result<()> {
+        if let Event::Key(KeyEvent {
+            code,
+            kind: KeyEventKind::Press,
+            ..
+        }) = event {
+            let action = match code {
+                KeyCode::Up => {
+                    self.selected = self.selected.saturating_sub(1);
+                    None
+                },
+                KeyCode::Down => {
+                    self.selected = std::cmp::min(self.selected + 1, self.entries.len().saturating_sub(1));
+                    None
+                },
+                KeyCode::Char(' ') => {
+                    self.marked = if self.marked == Some(self.selected) { None } else { Some(self.selected) };
+                    None
+                },
+                KeyCode::Enter => Some(HistoryAction::Open),
+                KeyCode::Char('c') => Some(HistoryAction::Compare),
+                KeyCode::Char('r') => Some(HistoryAction::Restore),
+                KeyCode::Esc => {
+                    self.state = DialogState::Cancelled;
+                    None
+                },
+                _ => None,
+            };
+
+            if action.is_some() && !self.entries.is_empty() {
+                self.action = action;
+                self.state = DialogState::Submitted;
+            }
+
+            self.to_re_render = true;
+        }
+
+        Ok(())
+    }
+
+    fn needs_re_render(&self) -> bool {
+        self.to_re_render
+    }
+
+    fn invalidate(&mut self) {
+        self.to_re_render = true;
+    }
+}
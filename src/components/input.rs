@@ -1,10 +1,14 @@
 use crossterm::event::{KeyEvent, KeyCode, KeyEventKind};
 
+use crate::secrets;
+
 use super::element::Element;
 
 
 pub struct Input {
     value: String,
+    /// Secret values hidden in the display
+    masked: Vec<String>,
     to_empty: bool,
     to_re_render: bool,
 }
@@ -13,6 +17,7 @@ impl Input {
     pub fn new() -> Self {
         Self { 
             value: String::new(),
+            masked: vec![],
             to_empty: false,
             to_re_render: true,
         }
@@ -22,8 +27,15 @@ impl Input {
         String::from(self.value.trim())
     }
 
+    pub fn set_masked(&mut self, values: Vec<String>) {
+        self.masked = values;
+        self.to_empty = true;
+        self.to_re_render = true;
+    }
+
     pub fn set_val(&mut self, val: &str) {
         self.value = String::from(val);
+        self.to_empty = true;
         self.to_re_render = true;
     }
 }
@@ -38,7 +50,7 @@ impl Element for Input {
                 target.empty(console);
             }
 
-            target.write(console, self.value.as_bytes());
+            target.write(console, secrets::mask(&self.value, &self.masked).as_bytes());
             console.show_cursor();
         }
 
@@ -48,18 +60,14 @@ impl Element for Input {
     fn on_event(&mut self, event: &crossterm::event::Event) -> std::io::Result<()> { 
         if let crossterm::event::Event::Key(KeyEvent { code, kind, .. }) = event {
             match code {
-                KeyCode::Char(c) => {
-                    if *kind == KeyEventKind::Press {
-                        self.value.push(*c);
-                        self.to_re_render = true;
-                    }
+                KeyCode::Char(c) if *kind == KeyEventKind::Press => {
+                    self.value.push(*c);
+                    self.to_re_render = true;
                 },
-                KeyCode::Backspace | KeyCode::Delete => {
-                    if *kind == KeyEventKind::Press {
-                        self.value.pop();
-                        self.to_empty = true;
-                        self.to_re_render = true;
-                    }
+                KeyCode::Backspace | KeyCode::Delete if *kind == KeyEventKind::Press => {
+                    self.value.pop();
+                    self.to_empty = true;
+                    self.to_re_render = true;
                 },
                 KeyCode::Enter => {},
                 _ => {},
@@ -72,4 +80,9 @@ impl Element for Input {
     fn needs_re_render(&self) -> bool { 
         self.to_re_render
     }
+
+    fn invalidate(&mut self) {
+        self.to_empty = true;
+        self.to_re_render = true;
+    }
 }
@@ -1,22 +1,99 @@
-use crossterm::event::{Event, KeyCode, KeyEventKind, KeyEvent};
+use crossterm::event::{Event, KeyCode, KeyEventKind, KeyEvent, KeyModifiers};
 use crossterm::style::Color;
 
 use crate::console::Console;
 use crate::model::Request;
+use crate::search::{Filter, FilterMatch};
 
 use std::sync::{Arc, RwLock};
 use super::block::Block;
 use super::element::Element;
 
 
+pub enum ListAction {
+    New,
+    Duplicate,
+    Rename,
+    ChangeMethod,
+    MoveToFolder,
+    Delete,
+    MoveUp,
+    MoveDown,
+}
+
 pub struct List {
     values: Arc<RwLock<Vec<Arc<Request>>>>,
     pub selected: usize,
     pub option_selected: bool,
+    pub action: Option<ListAction>,
+    filter_query: String,
+    filter_editing: bool,
+    scroll: usize,
     to_re_render: bool,
     width: usize,
 }
 
+pub fn method_color(method: &str) -> Color {
+    match method {
+        "GET" => Color::Green,
+        "POST" => Color::Yellow,
+        "PUT" => Color::Blue,
+        "PATCH" => Color::Magenta,
+        "DELETE" => Color::Red,
+        "HEAD" | "OPTIONS" | "TRACE" => Color::Cyan,
+        _ => Color::White,
+    }
+}
+
+/// Cuts the text to `max` characters, marking the cut with an ellipsis.
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+
+    let mut truncated: String = text.chars().take(max.saturating_sub(1)).collect();
+
+    if max > 0 {
+        truncated.push('…');
+    }
+
+    truncated
+}
+
+/// Part of the URL after the host, along with its character offset in the URL.
+fn url_path(url: &str) -> (&str, usize) {
+    let Some(scheme_end) = url.find("://") else {
+        return (url, 0);
+    };
+
+    match url[scheme_end + 3..].find('/') {
+        Some(path_start) => {
+            let start = scheme_end + 3 + path_start;
+            (&url[start..], url[..start].chars().count())
+        },
+        None => (url, 0),
+    }
+}
+
+fn write_highlighted(
+    console: &mut Console,
+    target: &mut Block,
+    text: &str,
+    positions: &[usize],
+    color: Color,
+    highlight_color: Color,
+) {
+    for (i, c) in text.chars().enumerate() {
+        if positions.contains(&i) {
+            console.set_fg_color(highlight_color);
+        } else {
+            console.set_fg_color(color);
+        }
+
+        target.write(console, c.to_string().as_bytes());
+    }
+}
+
 impl List {
     pub fn new(options: Arc<RwLock<Vec<Arc<Request>>>>, width: usize) -> Self {
         Self {
@@ -24,29 +101,100 @@ impl List {
             selected: 0,
             values: options,
             option_selected: false,
+            action: None,
+            filter_query: String::new(),
+            filter_editing: false,
+            scroll: 0,
             to_re_render: true,
         }
     }
 
+    pub fn select(&mut self, i: usize) {
+        let len = self.values.read().unwrap().len();
+        self.selected = std::cmp::min(i, len.saturating_sub(1));
+        self.ensure_visible_selection();
+        self.to_re_render = true;
+    }
+
+    /// Selected request index, `None` if the list is empty or everything is filtered out.
+    pub fn get_selected(&self) -> Option<usize> {
+        self.visible()
+            .iter()
+            .any(|(i, _)| *i == self.selected)
+            .then_some(self.selected)
+    }
+
+    pub fn is_filtering(&self) -> bool {
+        self.filter_editing
+    }
+
+    /// Requests passing the current filter along with their matched characters.
+    fn visible(&self) -> Vec<(usize, FilterMatch)> {
+        let filter = Filter::parse(&self.filter_query);
+
+        self.values
+            .read().unwrap()
+            .iter()
+            .enumerate()
+            .filter_map(|(i, r)| filter.matches(r).map(|m| (i, m)))
+            .collect()
+    }
+
+    fn ensure_visible_selection(&mut self) {
+        let visible = self.visible();
+
+        if !visible.iter().any(|(i, _)| *i == self.selected) {
+            if let Some((first, _)) = visible.first() {
+                self.selected = *first;
+            }
+        }
+    }
+
+    fn on_filter_event(&mut self, code: &KeyCode) {
+        match code {
+            KeyCode::Char(c) => self.filter_query.push(*c),
+            KeyCode::Backspace | KeyCode::Delete => {
+                self.filter_query.pop();
+            },
+            KeyCode::Enter => self.filter_editing = false,
+            KeyCode::Esc => {
+                self.filter_query.clear();
+                self.filter_editing = false;
+            },
+            KeyCode::Up => self.select_prev(),
+            KeyCode::Down => self.select_next(),
+            _ => {},
+        }
+
+        self.ensure_visible_selection();
+        self.to_re_render = true;
+    }
+
     fn get_max_method_length(&self) -> usize {
         self.values
             .read().unwrap()
             .iter()
-            .map(|v| (*v).method.len())
+            .map(|v| v.method.len())
             .max().unwrap_or(0)
     }
 
     fn select_next(&mut self) {
-        if self.selected < self.values.read().unwrap().len() - 1 {
-            self.selected += 1;
+        let visible = self.visible();
+        let pos = visible.iter().position(|(i, _)| *i == self.selected);
+
+        if let Some((next, _)) = visible.get(pos.map_or(0, |p| p + 1)) {
+            self.selected = *next;
         }
 
         self.to_re_render = true
     }
 
     fn select_prev(&mut self) {
-        if self.selected > 0 {
-            self.selected -= 1;
+        let visible = self.visible();
+        let pos = visible.iter().position(|(i, _)| *i == self.selected);
+
+        if let Some((prev, _)) = pos.and_then(|p| p.checked_sub(1)).and_then(|p| visible.get(p)) {
+            self.selected = *prev;
         }
 
         self.to_re_render = true
@@ -59,59 +207,167 @@ impl Element for List {
         console: &mut Console,
         target: &mut Block,
     ) { 
+        if self.filter_editing || !self.filter_query.is_empty() {
+            target.set_title(Some(&format!("/{}", self.filter_query)));
+        } else {
+            target.set_title(None);
+        }
+
+        target.render(console);
         target.reset();
+
         let max_method_len = self.get_max_method_length();
+        let visible = self.visible();
+        let values = self.values.read().unwrap();
+
+        // Every request takes two lines, the title and the URL subtitle
+        let per_page = std::cmp::max(1, target.content_height() as usize / 2);
+        let selected_pos = visible
+            .iter()
+            .position(|(i, _)| *i == self.selected)
+            .unwrap_or(0);
+
+        if selected_pos < self.scroll {
+            self.scroll = selected_pos;
+        } else if selected_pos >= self.scroll + per_page {
+            self.scroll = selected_pos + 1 - per_page;
+        }
+
+        self.scroll = std::cmp::min(self.scroll, visible.len().saturating_sub(per_page));
+
+        let row_len = self.width.saturating_sub(1);
+        let method_col_len = max_method_len + 2;
+        let title_len = row_len.saturating_sub(method_col_len + 1);
+
+        for (i, matched) in visible.iter().skip(self.scroll).take(per_page) {
+            let i = *i;
+            let option = &values[i];
+            let method = option.method.to_uppercase();
+            let method_color = method_color(&method);
 
-        for (i, option) in self.values.read().unwrap().iter().enumerate() {
-            let (fg_color, bg_color) = if i == self.selected {
-                (Color::Black, Color::White)
+            let (fg_color, bg_color, subtitle_color) = if i == self.selected {
+                (Color::Black, Color::White, Color::DarkGrey)
             } else {
-                (Color::Reset, Color::Reset)
+                (Color::Reset, Color::Reset, Color::DarkGrey)
             };
-            
+
             let (method_fg_color, method_bg_color) = if i == self.selected {
-                (Color::Black, Color::Green)
+                (Color::Black, method_color)
+            } else {
+                (method_color, Color::Reset)
+            };
+
+            let highlight_color = if i == self.selected {
+                Color::DarkRed
             } else {
-                (Color::White, Color::Black)
+                Color::Yellow
             };
 
-            let pad = max_method_len - option.method.len() + 2;
-            let pad_left = std::cmp::max(1, pad / 2); 
-            let pad_right = pad - pad_left;
+            let pad = method_col_len.saturating_sub(method.chars().count());
+            let pad_left = std::cmp::max(1, pad / 2);
+            let pad_right = pad.saturating_sub(pad_left);
 
             target.to_line_start(console);
-            console.set_colors(method_bg_color, method_fg_color);
+            console.set_colors(method_fg_color, method_bg_color);
 
             target.write(console, " ".repeat(pad_left).as_bytes());
-            target.write(console, option.method.to_uppercase().as_bytes());
+            write_highlighted(
+                console,
+                target,
+                &method,
+                &matched.method,
+                method_fg_color,
+                highlight_color,
+            );
             target.write(console, " ".repeat(pad_right).as_bytes());
 
-            let pad_title_right = std::cmp::max(
-                self.width - option.title.len() - pad_left - pad_right - option.method.len() - 2,
-                0);
+            let title = truncate(&option.title, title_len);
 
             console.set_colors(fg_color, bg_color);
             target.write(console, " ".as_bytes());
-            target.write(console, option.title.as_bytes());
-            target.write(console, " ".repeat(pad_title_right).as_bytes());
+            write_highlighted(
+                console,
+                target,
+                &title,
+                &matched.title,
+                fg_color,
+                highlight_color,
+            );
+            target.write(
+                console,
+                " ".repeat(title_len.saturating_sub(title.chars().count())).as_bytes(),
+            );
+
+            // Subtitle with the URL path, aligned with the title
+            let (path, offset) = url_path(&option.url);
+            let url_positions: Vec<usize> = matched.url
+                .iter()
+                .filter_map(|p| p.checked_sub(offset))
+                .collect();
+            let subtitle = truncate(path, title_len);
+
+            target.next_line(console);
+            target.write(console, " ".repeat(method_col_len + 1).as_bytes());
+            write_highlighted(
+                console,
+                target,
+                &subtitle,
+                &url_positions,
+                subtitle_color,
+                highlight_color,
+            );
+            target.write(
+                console,
+                " ".repeat(title_len.saturating_sub(subtitle.chars().count())).as_bytes(),
+            );
             console.reset_color();
 
             target.next_line(console);
         }
 
+        let shown = std::cmp::min(per_page, visible.len().saturating_sub(self.scroll));
+        target.empty_after(console, (shown * 2) as u16);
         self.to_re_render = false;
     }
 
     fn on_event(&mut self, event: &Event) -> std::io::Result<()> { 
         if let Event::Key(KeyEvent {
             code,
+            modifiers,
             kind: KeyEventKind::Press,
             ..
         }) = event {
+            if self.filter_editing {
+                self.on_filter_event(code);
+                return Ok(());
+            }
+
+            let shift = modifiers.contains(KeyModifiers::SHIFT);
+
             match code {
+                KeyCode::Up if shift => self.action = Some(ListAction::MoveUp),
+                KeyCode::Down if shift => self.action = Some(ListAction::MoveDown),
                 KeyCode::Up => self.select_prev(),
                 KeyCode::Down => self.select_next(),
                 KeyCode::Enter => self.option_selected = true,
+                KeyCode::Delete => self.action = Some(ListAction::Delete),
+                KeyCode::Char('/') => {
+                    self.filter_editing = true;
+                    self.to_re_render = true;
+                },
+                KeyCode::Char(c) => {
+                    self.action = match c {
+                        'n' => Some(ListAction::New),
+                        'd' => Some(ListAction::Duplicate),
+                        'r' => Some(ListAction::Rename),
+                        'm' => Some(ListAction::ChangeMethod),
+                        'f' => Some(ListAction::MoveToFolder),
+                        'x' => Some(ListAction::Delete),
+                        'K' => Some(ListAction::MoveUp),
+                        'J' => Some(ListAction::MoveDown),
+                        _ => None,
+                    }
+                },
                 _ => {}
             }
         }
@@ -122,5 +378,66 @@ impl Element for List {
     fn needs_re_render(&self) -> bool {  
         self.to_re_render
     }
+
+    fn invalidate(&mut self) {
+        self.to_re_render = true;
+    }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(titles: &[&str]) -> List {
+        let requests = titles.iter().map(|t| Arc::new(Request::new("GET", t, "", ""))).collect();
+
+        List::new(Arc::new(RwLock::new(requests)), 30)
+    }
+
+    #[test]
+    fn nothing_is_selected_in_an_empty_list() {
+        let mut list = list(&[]);
+
+        list.select_next();
+        list.select_prev();
+        list.select(3);
+
+        assert_eq!(list.selected, 0);
+        assert_eq!(list.get_selected(), None);
+    }
+
+    #[test]
+    fn selection_moves_over_visible_requests_only() {
+        let mut list = list(&["List pets", "List users", "Add pet", "Delete pet"]);
+        list.filter_query = String::from("pet");
+
+        list.select_next();
+        list.select_next();
+        list.select_next();
+        assert_eq!(list.get_selected(), Some(3));
+
+        list.select_prev();
+        assert_eq!(list.get_selected(), Some(2));
+
+        list.select(1);
+        assert_eq!(list.get_selected(), Some(0));
+    }
+
+    #[test]
+    fn nothing_is_selected_when_everything_is_filtered_out() {
+        let mut list = list(&["List pets", "Add pet"]);
+        list.select(1);
+
+        for c in "zzz".chars() {
+            list.on_filter_event(&KeyCode::Char(c));
+        }
+
+        list.select_next();
+        list.select_prev();
+        assert_eq!(list.get_selected(), None);
+
+        list.on_filter_event(&KeyCode::Esc);
+        assert_eq!(list.get_selected(), Some(1));
+    }
+}
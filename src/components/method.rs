@@ -0,0 +1,62 @@
+use crossterm::event::Event;
+
+use crate::console::Console;
+
+use super::block::Block;
+use super::element::Element;
+use super::list::method_color;
+
+
+/// Method of the opened request shown next to the URL input.
+pub struct MethodView {
+    value: String,
+    to_re_render: bool,
+}
+
+impl MethodView {
+    pub fn new() -> Self {
+        Self {
+            value: String::new(),
+            to_re_render: true,
+        }
+    }
+
+    pub fn get_value(&self) -> &str {
+        &self.value
+    }
+
+    pub fn set_val(&mut self, val: &str) {
+        self.value = val.to_uppercase();
+        self.to_re_render = true;
+    }
+}
+
+impl Element for MethodView {
+    fn output(&mut self, console: &mut Console, target: &mut Block) {
+        target.empty(console);
+        target.reset();
+
+        let width = target.width.saturating_sub(1) as usize;
+        let method: String = self.value.chars().take(width).collect();
+        let pad = width.saturating_sub(method.chars().count()) / 2;
+
+        target.move_to(console, pad as u16, 0);
+        console.set_fg_color(method_color(&method));
+        target.write_str(console, &method);
+        console.reset_color();
+
+        self.to_re_render = false;
+    }
+
+    fn on_event(&mut self, _event: &Event) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    fn needs_re_render(&self) -> bool {
+        self.to_re_render
+    }
+
+    fn invalidate(&mut self) {
+        self.to_re_render = true;
+    }
+}
@@ -1,8 +1,19 @@
 pub mod element;
 pub mod list;
+#[allow(dead_code)]
 pub mod tty;
 pub mod block;
 pub mod input;
 pub mod shortcuts;
 pub mod editor;
-
+pub mod prompt;
+pub mod picker;
+pub mod method;
+pub mod response;
+pub mod table;
+pub mod history;
+pub mod diff;
+pub mod run;
+pub mod sync;
+pub mod har;
+pub mod code;
@@ -0,0 +1,103 @@
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind};
+use crossterm::style::Color;
+
+use crate::console::Console;
+
+use super::block::Block;
+use super::element::{DialogState, Element};
+
+
+/// Dialog for choosing one of a fixed set of options.
+pub struct Picker {
+    label: String,
+    options: Vec<String>,
+    pub selected: usize,
+    pub state: DialogState,
+    to_re_render: bool,
+}
+
+impl Picker {
+    pub fn new(label: &str, options: &[&str]) -> Self {
+        Self {
+            label: String::from(label),
+            options: options.iter().map(|o| o.to_string()).collect(),
+            selected: 0,
+            state: DialogState::Open,
+            to_re_render: true,
+        }
+    }
+
+    pub fn confirm(label: &str) -> Self {
+        Self::new(label, &["No", "Yes"])
+    }
+
+    pub fn select(mut self, option: &str) -> Self {
+        if let Some(i) = self.options.iter().position(|o| o == option) {
+            self.selected = i;
+        }
+
+        self
+    }
+
+    pub fn get_value(&self) -> &str {
+        &self.options[self.selected]
+    }
+
+    /// Height of the block needed to show the label and every option.
+    pub fn height(&self) -> u16 {
+        self.options.len() as u16 + 4
+    }
+}
+
+impl Element for Picker {
+    fn output(&mut self, console: &mut Console, target: &mut Block) {
+        console.reset_color();
+        target.empty(console);
+        target.render(console);
+        target.reset();
+
+        target.write_str(console, &self.label);
+
+        for (i, option) in self.options.iter().enumerate() {
+            target.move_to(console, 0, i as u16 + 2);
+
+            if i == self.selected {
+                console.set_colors(Color::Black, Color::White);
+            }
+
+            target.write_str(console, &format!(" {} ", option));
+            console.reset_color();
+        }
+
+        console.hide_cursor();
+        self.to_re_render = false;
+    }
+
+    fn on_event(&mut self, event: &Event) -> std::io::Result<()> {
+        if let Event::Key(KeyEvent {
+            code,
+            kind: KeyEventKind::Press,
+            ..
+        }) = event {
+            match code {
+                KeyCode::Up if self.selected > 0 => self.selected -= 1,
+                KeyCode::Down if self.selected + 1 < self.options.len() => self.selected += 1,
+                KeyCode::Enter => self.state = DialogState::Submitted,
+                KeyCode::Esc => self.state = DialogState::Cancelled,
+                _ => {},
+            }
+
+            self.to_re_render = true;
+        }
+
+        Ok(())
+    }
+
+    fn needs_re_render(&self) -> bool {
+        self.to_re_render
+    }
+
+    fn invalidate(&mut self) {
+        self.to_re_render = true;
+    }
+}
@@ -0,0 +1,89 @@
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind};
+
+use crate::console::Console;
+
+use super::block::Block;
+use super::element::{DialogState, Element};
+
+
+/// Single line text dialog, e.g. for entering a request title.
+pub struct Prompt {
+    label: String,
+    value: String,
+    /// Value is shown as bullets, e.g. for passphrases
+    masked: bool,
+    pub state: DialogState,
+    to_re_render: bool,
+}
+
+impl Prompt {
+    pub fn new(label: &str, value: &str) -> Self {
+        Self {
+            label: String::from(label),
+            value: String::from(value),
+            masked: false,
+            state: DialogState::Open,
+            to_re_render: true,
+        }
+    }
+
+    pub fn masked(mut self) -> Self {
+        self.masked = true;
+        self
+    }
+
+    pub fn get_value(&self) -> String {
+        String::from(self.value.trim())
+    }
+}
+
+impl Element for Prompt {
+    fn output(&mut self, console: &mut Console, target: &mut Block) {
+        console.reset_color();
+        target.empty(console);
+        target.render(console);
+        target.reset();
+
+        target.write_str(console, &self.label);
+        target.move_to(console, 0, 2);
+        if self.masked {
+            target.write_str(console, &"•".repeat(self.value.chars().count()));
+        } else {
+            target.write_str(console, &self.value);
+        }
+
+        console.show_cursor();
+
+        self.to_re_render = false;
+    }
+
+    fn on_event(&mut self, event: &Event) -> std::io::Result<()> {
+        if let Event::Key(KeyEvent {
+            code,
+            kind: KeyEventKind::Press,
+            ..
+        }) = event {
+            match code {
+                KeyCode::Char(c) => self.value.push(*c),
+                KeyCode::Backspace | KeyCode::Delete => {
+                    self.value.pop();
+                },
+                KeyCode::Enter => self.state = DialogState::Submitted,
+                KeyCode::Esc => self.state = DialogState::Cancelled,
+                _ => {},
+            }
+
+            self.to_re_render = true;
+        }
+
+        Ok(())
+    }
+
+    fn needs_re_render(&self) -> bool {
+        self.to_re_render
+    }
+
+    fn invalidate(&mut self) {
+        self.to_re_render = true;
+    }
+}
@@ -0,0 +1,250 @@
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind};
+use crossterm::style::Color;
+
+use crate::console::Console;
+use crate::assertions::AssertionResult;
+use crate::capture::CaptureResult;
+use crate::contract::{SpecOperation, Violation};
+use crate::http::HttpResponse;
+use crate::secrets;
+
+use super::block::Block;
+use super::element::Element;
+
+
+/// Read-only view of the last response: status, headers and body.
+pub struct ResponseView {
+    title: Option<String>,
+    lines: Vec<(Color, String)>,
+    /// Secret values hidden in the display
+    masked: Vec<String>,
+    scroll: usize,
+    to_re_render: bool,
+}
+
+fn status_color(status: u16) -> Color {
+    match status {
+        200..=299 => Color::Green,
+        300..=399 => Color::Cyan,
+        400..=499 => Color::Yellow,
+        _ => Color::Red,
+    }
+}
+
+/// Pretty prints JSON bodies, other bodies are returned as is.
+pub fn format_body(body: &str) -> String {
+    serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|v| serde_json::to_string_pretty(&v).ok())
+        .unwrap_or_else(|| body.to_string())
+}
+
+impl ResponseView {
+    pub fn new() -> Self {
+        Self {
+            title: None,
+            lines: vec![],
+            masked: vec![],
+            scroll: 0,
+            to_re_render: true,
+        }
+    }
+
+    pub fn set_masked(&mut self, values: Vec<String>) {
+        self.masked = values;
+        self.to_re_render = true;
+    }
+
+    pub fn set_message(&mut self, message: &str) {
+        self.title = None;
+        self.lines = message
+            .lines()
+            .map(|l| (Color::Reset, l.to_string()))
+            .collect();
+        self.scroll = 0;
+        self.to_re_render = true;
+    }
+
+    pub fn set_error(&mut self, error: &str) {
+        self.title = Some(String::from("Error"));
+        self.lines = error
+            .lines()
+            .map(|l| (Color::Red, l.to_string()))
+            .collect();
+        self.scroll = 0;
+        self.to_re_render = true;
+    }
+
+    pub fn set_response(&mut self, response: &HttpResponse) {
+        let color = status_color(response.status);
+
+        self.title = Some(format!(
+            "{} {} · {} ms",
+            response.status,
+            response.reason,
+            response.timings.total.as_millis(),
+        ));
+
+        self.lines = vec![
+            (color, format!("HTTP {} {}", response.status, response.reason)),
+            (Color::DarkGrey, format!(
+                "connect {} ms · first byte {} ms · total {} ms",
+                response.timings.connect.as_millis(),
+                response.timings.first_byte.as_millis(),
+                response.timings.total.as_millis(),
+            )),
+        ];
+
+        for (name, value) in response.headers.iter() {
+            self.lines.push((Color::Cyan, format!("{}: {}", name, value)));
+        }
+
+        self.lines.push((Color::Reset, String::new()));
+
+        let body = if response.header("Content-Type").is_some_and(|t| t.contains("json")) {
+            format_body(&response.body)
+        } else {
+            response.body.clone()
+        };
+
+        for line in body.lines() {
+            self.lines.push((Color::Reset, line.to_string()));
+        }
+
+        self.scroll = 0;
+        self.to_re_render = true;
+    }
+
+    /// Adds assertion results under the status line, the title counts the passed ones.
+    pub fn set_assertions(&mut self, results: &[AssertionResult]) {
+        if results.is_empty() {
+            return;
+        }
+
+        let passed = results.iter().filter(|r| r.passed).count();
+
+        self.title = self.title.take().map(|t| format!("{} · {}/{} passed", t, passed, results.len()));
+
+        let lines = results.iter().map(|r| if r.passed {
+            (Color::Green, format!("✓ {}", r.description))
+        } else {
+            (Color::Red, format!("✗ {}: {}", r.description, r.message))
+        });
+
+        // After the status and timings lines
+        let at = std::cmp::min(2, self.lines.len());
+        self.lines.splice(at..at, lines.chain(std::iter::once((Color::Reset, String::new()))));
+        self.to_re_render = true;
+    }
+
+    /// Adds what scripts printed under the status line.
+    pub fn set_log(&mut self, log: &[String]) {
+        if log.is_empty() {
+            return;
+        }
+
+        let lines = log.iter().map(|line| (Color::DarkGrey, format!("» {}", line)));
+
+        let at = std::cmp::min(2, self.lines.len());
+        self.lines.splice(at..at, lines.chain(std::iter::once((Color::Reset, String::new()))));
+        self.to_re_render = true;
+    }
+
+    /// Adds the captured variables under the status line.
+    pub fn set_captures(&mut self, results: &[CaptureResult]) {
+        if results.is_empty() {
+            return;
+        }
+
+        let lines = results.iter().map(|r| match &r.value {
+            Ok(value) => (Color::Cyan, format!("→ {{{{{}}}}} = {}", r.variable, value)),
+            Err(e) => (Color::Yellow, format!("→ {{{{{}}}}} not captured: {}", r.variable, e)),
+        });
+
+        let at = std::cmp::min(2, self.lines.len());
+        self.lines.splice(at..at, lines.chain(std::iter::once((Color::Reset, String::new()))));
+        self.to_re_render = true;
+    }
+
+    /// Adds how the response fits the spec the request was imported from.
+    pub fn set_contract(&mut self, operation: &SpecOperation, violations: &[Violation]) {
+        let lines: Vec<(Color, String)> = if violations.is_empty() {
+            vec![(Color::Green, format!("✓ matches {} in the spec", operation.label()))]
+        } else {
+            std::iter::once((Color::Red, format!("✗ {} violations of {} in the spec", violations.len(), operation.label())))
+                .chain(violations.iter().map(|v| (Color::Red, format!("  {}", v))))
+                .collect()
+        };
+
+        let verdict = if violations.is_empty() { String::from("contract ok") } else { String::from("contract broken") };
+        self.title = self.title.take().map(|t| format!("{} · {}", t, verdict));
+
+        let at = std::cmp::min(2, self.lines.len());
+        self.lines.splice(at..at, lines.into_iter().chain(std::iter::once((Color::Reset, String::new()))));
+        self.to_re_render = true;
+    }
+
+    /// Shows a response from the history, the note tells where it's from.
+    pub fn set_past_response(&mut self, response: &HttpResponse, note: &str) {
+        self.set_response(response);
+        self.title = self.title.take().map(|t| format!("{} · {}", t, note));
+    }
+}
+
+impl Element for ResponseView {
+    fn output(&mut self, console: &mut Console, target: &mut Block) {
+        target.set_title(self.title.as_deref());
+        target.render(console);
+        target.empty(console);
+        target.reset();
+
+        let width = target.width.saturating_sub(1) as usize;
+        let height = target.content_height() as usize;
+
+        for (i, (color, line)) in self.lines.iter().skip(self.scroll).take(height).enumerate() {
+            let line: String = secrets::mask(line, &self.masked)
+                .replace('\t', "    ")
+                .chars()
+                .filter(|c| !c.is_control())
+                .take(width)
+                .collect();
+
+            target.move_to(console, 0, i as u16);
+            console.set_fg_color(*color);
+            target.write_str(console, &line);
+            console.reset_color();
+        }
+
+        self.to_re_render = false;
+    }
+
+    fn on_event(&mut self, event: &Event) -> std::io::Result<()> {
+        if let Event::Key(KeyEvent {
+            code,
+            kind: KeyEventKind::Press,
+            ..
+        }) = event {
+            let max_scroll = self.lines.len().saturating_sub(1);
+
+            match code {
+                KeyCode::Up => self.scroll = self.scroll.saturating_sub(1),
+                KeyCode::Down => self.scroll = std::cmp::min(self.scroll + 1, max_scroll),
+                KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(10),
+                KeyCode::PageDown => self.scroll = std::cmp::min(self.scroll + 10, max_scroll),
+                _ => {},
+            }
+
+            self.to_re_render = true;
+        }
+
+        Ok(())
+    }
+
+    fn needs_re_render(&self) -> bool {
+        self.to_re_render
+    }
+
+    fn invalidate(&mut self) {
+        self.to_re_render = true;
+    }
+}
@@ -0,0 +1,318 @@
+use std::time::Duration;
+
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind};
+use crossterm::style::Color;
+
+use crate::console::Console;
+use crate::model::Model;
+use crate::runner::{CollectionRun, RunSettings, StepResult};
+
+use super::block::Block;
+use super::element::{DialogState, Element};
+use super::table::{Column, Table, TableRow};
+
+const PROGRESS_WIDTH: usize = 40;
+
+const FOLDER: &str = "Folder, empty for all";
+const ITERATIONS: &str = "Iterations, empty for one per row";
+const DATA: &str = "Data file, CSV or JSONL";
+const DELAY: &str = "Delay between requests, ms";
+const STOP: &str = "Stop on failure, yes/no";
+
+/// Dialog setting up a collection run, then showing its progress and summary.
+pub struct RunView {
+    setup: Table,
+    pub run: Option<CollectionRun>,
+    /// Why the run couldn't start
+    error: Option<String>,
+    /// Set when the run is asked to start, cleared by whoever starts it
+    pub start: bool,
+    scroll: usize,
+    height: u16,
+    pub state: DialogState,
+    to_re_render: bool,
+}
+
+fn setting(field: &str, value: &str) -> TableRow {
+    TableRow {
+        cells: vec![field.to_string(), value.to_string()],
+        enabled: true,
+        masked: false,
+    }
+}
+
+fn progress_bar(done: usize, total: usize) -> String {
+    let filled = (done * PROGRESS_WIDTH).checked_div(total).unwrap_or_default();
+
+    format!(
+        "[{}{}] {}%",
+        "█".repeat(filled),
+        "░".repeat(PROGRESS_WIDTH - filled),
+        (done * 100).checked_div(total).unwrap_or_default(),
+    )
+}
+
+fn result_line(result: &StepResult, iterations: usize) -> (Color, String) {
+    let iteration = if iterations > 1 { format!("#{} ", result.iteration + 1) } else { String::new() };
+    let name = format!("{}{} {}", iteration, result.method, result.title);
+
+    match &result.response {
+        Ok((status, reason, ms)) if result.passed() => {
+            (Color::Green, format!("✓ {} · {} {} · {} ms", name, status, reason, ms))
+        },
+        Ok((status, reason, _)) => {
+            let failed = result.checks.iter().filter(|c| !c.passed).count();
+            let first = result.checks.iter().find(|c| !c.passed).map(|c| c.description.as_str()).unwrap_or_default();
+
+            (Color::Red, format!("✗ {} · {} {} · {} failed: {}", name, status, reason, failed, first))
+        },
+        Err(e) => (Color::Red, format!("✗ {} · {}", name, e)),
+    }
+}
+
+impl RunView {
+    /// `requests` is the size of the collection, the dialog is tall enough for its summary.
+    pub fn new(folder: Option<&str>, requests: usize) -> Self {
+        let mut setup = Table::new(vec![
+            Column { title: "Setting", editable: false },
+            Column { title: "Value", editable: true },
+        ]);
+
+        setup.set_rows(vec![
+            setting(FOLDER, folder.unwrap_or_default()),
+            setting(ITERATIONS, ""),
+            setting(DATA, ""),
+            setting(DELAY, "0"),
+            setting(STOP, "no"),
+        ]);
+
+        Self {
+            setup,
+            run: None,
+            error: None,
+            start: false,
+            scroll: 0,
+            height: (requests as u16 + 9).clamp(12, 30),
+            state: DialogState::Open,
+            to_re_render: true,
+        }
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    fn value(&self, field: &str) -> String {
+        self.setup
+            .get_rows()
+            .iter()
+            .find(|r| r.cells[0] == field)
+            .map(|r| r.cells[1].trim().to_string())
+            .unwrap_or_default()
+    }
+
+    pub fn settings(&self) -> Result<RunSettings, String> {
+        let iterations = self.value(ITERATIONS);
+        let delay = self.value(DELAY);
+        let folder = self.value(FOLDER);
+        let data = self.value(DATA);
+
+        Ok(RunSettings {
+            folder: Some(folder).filter(|f| !f.is_empty()),
+            titles: Vec::new(),
+            iterations: match iterations.as_str() {
+                "" => None,
+                n => Some(n.parse().map_err(|_| format!("Invalid iteration count {}", n))?),
+            },
+            data: Some(data).filter(|d| !d.is_empty()),
+            delay: match delay.as_str() {
+                "" => Duration::ZERO,
+                ms => Duration::from_millis(ms.parse().map_err(|_| format!("Invalid delay {}", ms))?),
+            },
+            stop_on_failure: matches!(self.value(STOP).to_lowercase().as_str(), "yes" | "y" | "true" | "1"),
+        })
+    }
+
+    pub fn set_error(&mut self, error: &str) {
+        self.error = Some(error.to_string());
+        self.to_re_render = true;
+    }
+
+    pub fn begin(&mut self, run: CollectionRun) {
+        self.run = Some(run);
+        self.error = None;
+        self.to_re_render = true;
+    }
+
+    /// Sends the next request once the delay has passed, and takes the
+    /// response of the one on its way without waiting for it.
+    pub fn tick(&mut self, model: &mut Model) {
+        let Some(run) = self.run.as_mut() else {
+            return;
+        };
+
+        if run.is_sending() {
+            if run.finish(model, false).is_some() {
+                self.to_re_render = true;
+            }
+        } else if run.is_finished() {
+            // Stopped between requests
+            run.end(model);
+        } else if run.wait_time().is_zero() {
+            run.start(model);
+            self.to_re_render = true;
+        }
+    }
+
+    fn output_setup(&mut self, console: &mut Console, target: &mut Block) {
+        self.setup.output(console, target);
+
+        let bottom = target.content_height().saturating_sub(1);
+
+        if let Some(error) = &self.error {
+            target.move_to(console, 0, bottom.saturating_sub(1));
+            console.set_fg_color(Color::Red);
+            target.write_str(console, error);
+        }
+
+        target.move_to(console, 0, bottom);
+        console.set_fg_color(Color::DarkGrey);
+        target.write_str(console, "[Enter] Edit [r] Run [Esc] Cancel");
+        console.reset_color();
+    }
+
+    fn output_run(&self, run: &CollectionRun, console: &mut Console, target: &mut Block) {
+        let width = target.width.saturating_sub(1) as usize;
+        let height = target.content_height() as usize;
+        let done = run.results.len();
+
+        let status = match run.upcoming() {
+            Some((iteration, request)) => format!(
+                "Iteration {}/{} · sending {} {} · ",
+                iteration + 1,
+                run.iterations,
+                request.method,
+                request.title,
+            ),
+            None if run.is_stopped() => String::from("Stopped · "),
+            None => String::from("Finished · "),
+        };
+
+        let mut lines = vec![
+            (Color::Reset, format!(
+                "{}{}/{} requests · {} passed, {} failed",
+                status,
+                done,
+                run.total(),
+                run.passed(),
+                run.failed(),
+            )),
+            (Color::Reset, progress_bar(done, run.total())),
+            (Color::Reset, String::new()),
+        ];
+
+        let room = height.saturating_sub(lines.len() + 1);
+
+        if run.is_finished() {
+            let summary = run.summary();
+            let name_width = width.saturating_sub(32);
+
+            lines.push((Color::DarkGrey, format!(
+                "{:<name_width$}{:>6}{:>8}{:>8}{:>8}",
+                "Request", "Runs", "Passed", "Failed", "Avg ms",
+            )));
+
+            let mut rows: Vec<(Color, String)> = summary.iter().map(|s| {
+                let name: String = format!("{} {}", s.method, s.title).chars().take(name_width.saturating_sub(1)).collect();
+                let average = s.average_ms.map(|ms| ms.to_string()).unwrap_or(String::from("-"));
+                let color = if s.failed > 0 { Color::Red } else { Color::Green };
+
+                (color, format!("{:<name_width$}{:>6}{:>8}{:>8}{:>8}", name, s.runs, s.passed, s.failed, average))
+            }).collect();
+
+            // Failures follow the table, scrolled to when they don't fit
+            if run.failed() > 0 {
+                rows.push((Color::Reset, String::new()));
+                rows.extend(run.results.iter().filter(|r| !r.passed()).map(|r| result_line(r, run.iterations)));
+            }
+
+            lines.extend(rows.into_iter().skip(self.scroll).take(room.saturating_sub(1)));
+        } else {
+            // The latest results, newest last
+            let skip = done.saturating_sub(room);
+            lines.extend(run.results.iter().skip(skip).map(|r| result_line(r, run.iterations)));
+        }
+
+        for (i, (color, line)) in lines.iter().enumerate().take(height.saturating_sub(1)) {
+            target.move_to(console, 0, i as u16);
+            console.set_fg_color(*color);
+            target.write_str(console, &line.chars().take(width).collect::<String>());
+        }
+
+        target.move_to(console, 0, height.saturating_sub(1) as u16);
+        console.set_fg_color(Color::DarkGrey);
+        target.write_str(console, if run.is_finished() { "[Up/Down] Scroll [Esc] Close" } else { "[Esc] Stop" });
+        console.reset_color();
+    }
+}
+
+impl Element for RunView {
+    fn output(&mut self, console: &mut Console, target: &mut Block) {
+        console.reset_color();
+
+        match self.run.take() {
+            Some(run) => {
+                target.render(console);
+                target.empty(console);
+                target.reset();
+                self.output_run(&run, console, target);
+                self.run = Some(run);
+            },
+            None => self.output_setup(console, target),
+        }
+
+        console.hide_cursor();
+        self.to_re_render = false;
+    }
+
+    fn on_event(&mut self, event: &Event) -> std::io::Result<()> {
+        let Event::Key(KeyEvent { code, kind: KeyEventKind::Press, .. }) = event else {
+            return Ok(());
+        };
+
+        match self.run.as_mut() {
+            Some(run) if !run.is_finished() => {
+                if *code == KeyCode::Esc {
+                    run.stop();
+                }
+            },
+            Some(run) => match code {
+                KeyCode::Up => self.scroll = self.scroll.saturating_sub(1),
+                KeyCode::Down => {
+                    let rows = run.requests().len() + run.failed() + 1;
+                    self.scroll = std::cmp::min(self.scroll + 1, rows.saturating_sub(1));
+                },
+                KeyCode::Esc | KeyCode::Enter => self.state = DialogState::Cancelled,
+                _ => {},
+            },
+            None if self.setup.is_editing() => self.setup.on_event(event)?,
+            None => match code {
+                KeyCode::Esc => self.state = DialogState::Cancelled,
+                KeyCode::Char('r') => self.start = true,
+                _ => self.setup.on_event(event)?,
+            },
+        }
+
+        self.to_re_render = true;
+        Ok(())
+    }
+
+    fn needs_re_render(&self) -> bool {
+        self.to_re_render || (self.run.is_none() && self.setup.needs_re_render())
+    }
+
+    fn invalidate(&mut self) {
+        self.to_re_render = true;
+        self.setup.invalidate();
+    }
+}
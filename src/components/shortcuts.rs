@@ -1,36 +1,185 @@
+use crossterm::style::Color;
+
 use super::element::Element;
 
+pub const DEFAULT_HINTS: &[(&str, &str)] = &[
+    ("s", "Send"),
+    ("e", "Execute"),
+    ("v", "Secrets"),
+    ("h", "History"),
+    ("c", "Run collection"),
+    ("o", "Sync with spec"),
+    ("i", "Import"),
+    ("g", "Code"),
+    ("n", "Environment"),
+    ("d", "Diff environments"),
+    ("k", "TLS verification"),
+];
+
+pub const REQUEST_HINTS: &[(&str, &str)] = &[
+    ("s", "Send"),
+    ("e", "Execute"),
+    ("h", "History"),
+    ("g", "Code"),
+    ("d", "Diff environments"),
+    ("Tab", "Body/Query/Headers/Path/Auth/Tests/Capture/Pre/Post"),
+];
+
+pub const QUERY_HINTS: &[(&str, &str)] = &[
+    ("Enter", "Edit"),
+    ("Space", "Enable/Disable"),
+    ("a", "Add"),
+    ("x", "Delete"),
+];
+
+pub const PATH_HINTS: &[(&str, &str)] = &[
+    ("Enter", "Edit"),
+    ("*", "Required"),
+    ("integer/number/boolean/uuid/string", "Types"),
+];
+
+pub const AUTH_HINTS: &[(&str, &str)] = &[
+    ("Enter", "Edit"),
+    ("t", "Type"),
+    ("f", "Request/Folder auth"),
+];
+
+pub const TESTS_HINTS: &[(&str, &str)] = &[
+    ("Enter", "Edit"),
+    ("Space", "Enable/Disable"),
+    ("a", "Add"),
+    ("x", "Delete"),
+    ("status/header/jsonpath/time/schema", "Checks"),
+];
+
+pub const CAPTURE_HINTS: &[(&str, &str)] = &[
+    ("Enter", "Edit"),
+    ("Space", "Enable/Disable"),
+    ("a", "Add"),
+    ("x", "Delete"),
+    ("jsonpath/regex/header", "Sources"),
+];
+
+pub const SCRIPT_HINTS: &[(&str, &str)] = &[
+    ("request/response/vars", "Values"),
+    ("check(name, passed)", "Post checks"),
+    ("secret hmac_sha256 sha256 nonce timestamp", "Functions"),
+];
+
+pub const LIST_HINTS: &[(&str, &str)] = &[
+    ("/", "Filter"),
+    ("n", "New"),
+    ("d", "Duplicate"),
+    ("r", "Rename"),
+    ("m", "Method"),
+    ("f", "Folder"),
+    ("x", "Delete"),
+    ("J/K", "Move"),
+];
+
+pub const DIFF_HINTS: &[(&str, &str)] = &[
+    ("Up/Down", "Scroll"),
+    ("n", "Next change"),
+    ("i", "Ignored fields"),
+    ("Esc", "Close"),
+];
+
+pub const CODE_HINTS: &[(&str, &str)] = &[
+    ("Tab", "Next client"),
+    ("w", "Save to file"),
+    ("Esc", "Close"),
+];
+
+pub const FILTER_HINTS: &[(&str, &str)] = &[
+    ("Enter", "Apply"),
+    ("Esc", "Clear"),
+    ("method:POST", "By method"),
+    ("url:pets", "By URL"),
+];
+
 pub struct ShortcutsView {
+    hints: &'static [(&'static str, &'static str)],
+    /// Shown before the hints in red, whatever the hints are
+    warning: Option<&'static str>,
+    /// Name of the active environment, shown first
+    environment: Option<String>,
     to_re_render: bool,
 }
 
 impl ShortcutsView {
     pub fn new() -> Self {
-        return Self {
+        Self {
+            hints: DEFAULT_HINTS,
+            warning: None,
+            environment: None,
             to_re_render: true,
         }
     }
+
+    pub fn set_hints(&mut self, hints: &'static [(&'static str, &'static str)]) {
+        if !std::ptr::eq(self.hints, hints) {
+            self.hints = hints;
+            self.to_re_render = true;
+        }
+    }
+
+    pub fn set_warning(&mut self, warning: Option<&'static str>) {
+        if self.warning != warning {
+            self.warning = warning;
+            self.to_re_render = true;
+        }
+    }
+
+    pub fn set_environment(&mut self, environment: Option<&str>) {
+        if self.environment.as_deref() != environment {
+            self.environment = environment.map(String::from);
+            self.to_re_render = true;
+        }
+    }
 }
 
-impl Element for ShortcutsView { 
-    fn output(&mut self, console: &mut crate::console::Console, target: &mut super::block::Block) { 
+impl Element for ShortcutsView {
+    fn output(&mut self, console: &mut crate::console::Console, target: &mut super::block::Block) {
         console.reset_color();
+        target.empty(console);
+        target.render(console);
         target.reset();
 
-        target.write(console, "[s] Send".as_bytes());
-        target.write(console, " [e] Execute".as_bytes());
+        if let Some(environment) = &self.environment {
+            console.set_colors(Color::Black, Color::Cyan);
+            target.write(console, format!(" {} ", environment).as_bytes());
+            console.reset_color();
+            target.write(console, b" ");
+        }
+
+        if let Some(warning) = self.warning {
+            console.set_colors(Color::White, Color::Red);
+            target.write(console, format!(" {} ", warning).as_bytes());
+            console.reset_color();
+            target.write(console, b" ");
+        }
+
+        let line = self.hints
+            .iter()
+            .map(|(key, action)| format!("[{}] {}", key, action))
+            .collect::<Vec<String>>()
+            .join(" ");
+
+        target.write(console, line.as_bytes());
 
         console.flush();
         self.to_re_render = false;
     }
 
-    fn on_event(&mut self, event: &crossterm::event::Event) -> std::io::Result<()> {
-       Ok(()) 
+    fn on_event(&mut self, _event: &crossterm::event::Event) -> std::io::Result<()> {
+       Ok(())
     }
 
-    fn needs_re_render(&self) -> bool { 
+    fn needs_re_render(&self) -> bool {
         self.to_re_render
     }
-}
-
 
+    fn invalidate(&mut self) {
+        self.to_re_render = true;
+    }
+}
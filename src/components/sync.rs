@@ -0,0 +1,158 @@
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind};
+use crossterm::style::Color;
+
+use crate::console::Console;
+use crate::model::Folder;
+use crate::resync::{SyncChange, SyncKind};
+
+use super::block::Block;
+use super::element::{DialogState, Element};
+
+/// Most changes shown at once, the rest is scrolled to.
+const MAX_VISIBLE: usize = 12;
+/// Lines left for the details of the selected change.
+const DETAIL_LINES: usize = 6;
+
+/// Dialog listing what re-syncing with the spec changes, each accepted or rejected.
+pub struct SyncView {
+    pub changes: Vec<SyncChange>,
+    /// Folder of the spec, new requests go there
+    pub folder: Folder,
+    spec: String,
+    selected: usize,
+    scroll: usize,
+    pub state: DialogState,
+    to_re_render: bool,
+}
+
+fn kind_color(kind: SyncKind) -> Color {
+    match kind {
+        SyncKind::Added => Color::Green,
+        SyncKind::Removed => Color::Red,
+        SyncKind::Changed => Color::Yellow,
+    }
+}
+
+impl SyncView {
+    pub fn new(spec: &str, folder: Folder, changes: Vec<SyncChange>) -> Self {
+        Self {
+            changes,
+            folder,
+            spec: spec.to_string(),
+            selected: 0,
+            scroll: 0,
+            state: DialogState::Open,
+            to_re_render: true,
+        }
+    }
+
+    /// Height of the block needed for the visible changes, the details and the hints.
+    pub fn height(&self) -> u16 {
+        (self.changes.len().clamp(1, MAX_VISIBLE) + DETAIL_LINES + 6) as u16
+    }
+
+    fn count(&self, kind: SyncKind) -> usize {
+        self.changes.iter().filter(|c| c.kind == kind).count()
+    }
+}
+
+impl Element for SyncView {
+    fn output(&mut self, console: &mut Console, target: &mut Block) {
+        console.reset_color();
+        target.empty(console);
+        target.render(console);
+        target.reset();
+
+        let width = target.width.saturating_sub(1) as usize;
+        let clip = |text: String| -> String { text.chars().take(width).collect() };
+
+        target.write_str(console, &clip(format!(
+            "{} · {} added, {} changed, {} removed",
+            self.spec,
+            self.count(SyncKind::Added),
+            self.count(SyncKind::Changed),
+            self.count(SyncKind::Removed),
+        )));
+
+        if self.changes.is_empty() {
+            target.move_to(console, 0, 2);
+            target.write_str(console, "The collection is in sync with the spec");
+        }
+
+        if self.selected < self.scroll {
+            self.scroll = self.selected;
+        } else if self.selected >= self.scroll + MAX_VISIBLE {
+            self.scroll = self.selected + 1 - MAX_VISIBLE;
+        }
+
+        for (i, change) in self.changes.iter().enumerate().skip(self.scroll).take(MAX_VISIBLE) {
+            target.move_to(console, 0, (i - self.scroll) as u16 + 2);
+
+            let check = if change.accepted { "[x]" } else { "[ ]" };
+            let line = clip(format!("{} {} {}", check, change.kind.symbol(), change.label));
+
+            if i == self.selected {
+                console.set_colors(Color::Black, Color::White);
+            } else {
+                console.set_fg_color(if change.accepted { kind_color(change.kind) } else { Color::DarkGrey });
+            }
+
+            target.write_str(console, &line);
+            console.reset_color();
+        }
+
+        let details_top = self.changes.len().clamp(1, MAX_VISIBLE) as u16 + 3;
+
+        if let Some(change) = self.changes.get(self.selected) {
+            console.set_fg_color(Color::DarkGrey);
+
+            for (i, detail) in change.details.iter().take(DETAIL_LINES).enumerate() {
+                target.move_to(console, 0, details_top + i as u16);
+                target.write_str(console, &clip(format!("  {}", detail)));
+            }
+        }
+
+        target.move_to(console, 0, target.content_height().saturating_sub(1));
+        console.set_fg_color(Color::DarkGrey);
+        target.write_str(console, "[Space] Accept/Reject [a] All [Enter] Apply accepted [Esc] Cancel");
+        console.reset_color();
+
+        console.hide_cursor();
+        self.to_re_render = false;
+    }
+
+    fn on_event(&mut self, event: &Event) -> std::io::Result<()> {
+        let Event::Key(KeyEvent { code, kind: KeyEventKind::Press, .. }) = event else {
+            return Ok(());
+        };
+
+        match code {
+            KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down => self.selected = std::cmp::min(self.selected + 1, self.changes.len().saturating_sub(1)),
+            KeyCode::Char(' ') => {
+                if let Some(change) = self.changes.get_mut(self.selected) {
+                    change.accepted = !change.accepted;
+                }
+            },
+            KeyCode::Char('a') => {
+                let accept = !self.changes.iter().all(|c| c.accepted);
+                self.changes.iter_mut().for_each(|c| c.accepted = accept);
+            },
+            KeyCode::Enter if self.changes.is_empty() => self.state = DialogState::Cancelled,
+            KeyCode::Enter => self.state = DialogState::Submitted,
+            KeyCode::Esc => self.state = DialogState::Cancelled,
+            _ => {},
+        }
+
+        self.to_re_render = true;
+        Ok(())
+    }
+
+    fn needs_re_render(&self) -> bool {
+        self.to_re_render
+    }
+
+    fn invalidate(&mut self) {
+        self.to_re_render = true;
+    }
+}
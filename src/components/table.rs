@@ -0,0 +1,324 @@
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind};
+use crossterm::style::Color;
+
+use crate::console::Console;
+
+use super::block::Block;
+use super::element::{DialogState, Element};
+
+
+pub struct Column {
+    pub title: &'static str,
+    pub editable: bool,
+}
+
+#[derive(Clone)]
+pub struct TableRow {
+    pub cells: Vec<String>,
+    pub enabled: bool,
+    /// Editable cells are shown as bullets unless edited
+    pub masked: bool,
+}
+
+/// Editable grid of text cells, e.g. key/value pairs of query params.
+pub struct Table {
+    columns: Vec<Column>,
+    rows: Vec<TableRow>,
+    /// Rows can be switched on and off with Space
+    toggleable: bool,
+    /// Rows can be added and removed
+    growable: bool,
+    selected_row: usize,
+    selected_col: usize,
+    editing: bool,
+    pub changed: bool,
+    to_re_render: bool,
+}
+
+fn fit(text: &str, width: usize) -> String {
+    let count = text.chars().count();
+
+    if count > width {
+        let mut cut: String = text.chars().take(width.saturating_sub(1)).collect();
+        cut.push('…');
+        cut
+    } else {
+        format!("{}{}", text, " ".repeat(width - count))
+    }
+}
+
+impl Table {
+    pub fn new(columns: Vec<Column>) -> Self {
+        let selected_col = columns.iter().position(|c| c.editable).unwrap_or(0);
+
+        Self {
+            columns,
+            rows: vec![],
+            toggleable: false,
+            growable: false,
+            selected_row: 0,
+            selected_col,
+            editing: false,
+            changed: false,
+            to_re_render: true,
+        }
+    }
+
+    pub fn toggleable(mut self) -> Self {
+        self.toggleable = true;
+        self
+    }
+
+    pub fn growable(mut self) -> Self {
+        self.growable = true;
+        self
+    }
+
+    pub fn get_rows(&self) -> &[TableRow] {
+        &self.rows
+    }
+
+    pub fn set_rows(&mut self, rows: Vec<TableRow>) {
+        self.rows = rows;
+        self.selected_row = std::cmp::min(self.selected_row, self.rows.len().saturating_sub(1));
+        self.editing = false;
+        self.to_re_render = true;
+    }
+
+    pub fn is_editing(&self) -> bool {
+        self.editing
+    }
+
+    fn move_col(&mut self, right: bool) {
+        let editable: Vec<usize> = self.columns
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.editable)
+            .map(|(i, _)| i)
+            .collect();
+
+        let pos = editable.iter().position(|&i| i == self.selected_col).unwrap_or(0);
+        let next = if right {
+            std::cmp::min(pos + 1, editable.len().saturating_sub(1))
+        } else {
+            pos.saturating_sub(1)
+        };
+
+        if let Some(&col) = editable.get(next) {
+            self.selected_col = col;
+        }
+    }
+
+    fn on_edit_event(&mut self, code: &KeyCode) {
+        let Some(cell) = self.rows
+            .get_mut(self.selected_row)
+            .and_then(|r| r.cells.get_mut(self.selected_col)) else {
+            self.editing = false;
+            return;
+        };
+
+        match code {
+            KeyCode::Char(c) => {
+                cell.push(*c);
+                self.changed = true;
+            },
+            KeyCode::Backspace | KeyCode::Delete => {
+                cell.pop();
+                self.changed = true;
+            },
+            KeyCode::Enter | KeyCode::Esc | KeyCode::Tab => self.editing = false,
+            _ => {},
+        }
+    }
+}
+
+impl Element for Table {
+    fn output(&mut self, console: &mut Console, target: &mut Block) {
+        target.render(console);
+        target.empty(console);
+        target.reset();
+
+        let check_width = if self.toggleable { 4 } else { 0 };
+        let width = (target.width.saturating_sub(1) as usize).saturating_sub(check_width);
+        let col_width = width / std::cmp::max(1, self.columns.len());
+
+        let header: String = self.columns
+            .iter()
+            .map(|c| fit(c.title, col_width))
+            .collect();
+
+        target.move_to(console, 0, 0);
+        console.set_fg_color(Color::DarkGrey);
+        target.write_str(console, &format!("{}{}", " ".repeat(check_width), header));
+        console.reset_color();
+
+        if self.rows.is_empty() {
+            target.move_to(console, 0, 1);
+            console.set_fg_color(Color::DarkGrey);
+            target.write_str(console, if self.growable { "No rows, press [a] to add one" } else { "No rows" });
+            console.reset_color();
+        }
+
+        let height = target.content_height().saturating_sub(1) as usize;
+        let scroll = (self.selected_row + 1).saturating_sub(height);
+
+        for (i, row) in self.rows.iter().enumerate().skip(scroll).take(height) {
+            target.move_to(console, 0, (i - scroll + 1) as u16);
+
+            let selected = i == self.selected_row;
+            let fg = if row.enabled || !self.toggleable { Color::Reset } else { Color::DarkGrey };
+
+            if self.toggleable {
+                console.set_fg_color(fg);
+                target.write_str(console, if row.enabled { "[x] " } else { "[ ] " });
+            }
+
+            for (col, cell) in row.cells.iter().enumerate() {
+                let is_cursor = selected && col == self.selected_col;
+
+                if is_cursor && self.editing {
+                    console.set_colors(Color::Black, Color::Yellow);
+                } else if is_cursor {
+                    console.set_colors(Color::Black, Color::White);
+                } else {
+                    console.set_colors(fg, Color::Reset);
+                }
+
+                let editable = self.columns.get(col).is_some_and(|c| c.editable);
+
+                let text = if is_cursor && self.editing {
+                    format!("{}_", cell)
+                } else if row.masked && editable {
+                    "•".repeat(cell.chars().count())
+                } else {
+                    cell.clone()
+                };
+
+                target.write_str(console, &fit(&text, col_width.saturating_sub(1)));
+                console.reset_color();
+                target.write_str(console, " ");
+            }
+        }
+
+        console.hide_cursor();
+        self.to_re_render = false;
+    }
+
+    fn on_event(&mut self, event: &Event) -> std::io::Result<()> {
+        if let Event::Key(KeyEvent {
+            code,
+            kind: KeyEventKind::Press,
+            ..
+        }) = event {
+            self.to_re_render = true;
+
+            if self.editing {
+                self.on_edit_event(code);
+                return Ok(());
+            }
+
+            match code {
+                KeyCode::Up => self.selected_row = self.selected_row.saturating_sub(1),
+                KeyCode::Down => {
+                    self.selected_row = std::cmp::min(
+                        self.selected_row + 1,
+                        self.rows.len().saturating_sub(1),
+                    );
+                },
+                KeyCode::Left => self.move_col(false),
+                KeyCode::Right => self.move_col(true),
+                KeyCode::Enter if !self.rows.is_empty() => {
+                    self.editing = self.columns
+                        .get(self.selected_col)
+                        .is_some_and(|c| c.editable);
+                },
+                KeyCode::Char(' ') if self.toggleable => {
+                    if let Some(row) = self.rows.get_mut(self.selected_row) {
+                        row.enabled = !row.enabled;
+                        self.changed = true;
+                    }
+                },
+                KeyCode::Char('a') if self.growable => {
+                    self.rows.push(TableRow {
+                        cells: vec![String::new(); self.columns.len()],
+                        enabled: true,
+                        masked: false,
+                    });
+                    self.selected_row = self.rows.len() - 1;
+                    self.selected_col = self.columns.iter().position(|c| c.editable).unwrap_or(0);
+                    self.editing = true;
+                    self.changed = true;
+                },
+                KeyCode::Char('x') | KeyCode::Delete if self.growable && !self.rows.is_empty() => {
+                    self.rows.remove(self.selected_row);
+                    self.selected_row = std::cmp::min(
+                        self.selected_row,
+                        self.rows.len().saturating_sub(1),
+                    );
+                    self.changed = true;
+                },
+                _ => {},
+            }
+        }
+
+        Ok(())
+    }
+
+    fn needs_re_render(&self) -> bool {
+        self.to_re_render
+    }
+
+    fn invalidate(&mut self) {
+        self.to_re_render = true;
+    }
+}
+
+/// Table shown in a dialog, Esc closes it once no cell is edited.
+pub struct TableDialog {
+    pub table: Table,
+    pub state: DialogState,
+}
+
+impl TableDialog {
+    pub fn new(table: Table) -> Self {
+        Self {
+            table,
+            state: DialogState::Open,
+        }
+    }
+
+    /// Height of the block needed to show the header, every row and a spare one.
+    pub fn height(&self) -> u16 {
+        self.table.rows.len() as u16 + 4
+    }
+}
+
+impl Element for TableDialog {
+    fn output(&mut self, console: &mut Console, target: &mut Block) {
+        console.reset_color();
+        self.table.output(console, target);
+    }
+
+    fn on_event(&mut self, event: &Event) -> std::io::Result<()> {
+        if let Event::Key(KeyEvent {
+            code: KeyCode::Esc,
+            kind: KeyEventKind::Press,
+            ..
+        }) = event {
+            if !self.table.is_editing() {
+                self.state = DialogState::Submitted;
+                return Ok(());
+            }
+        }
+
+        self.table.on_event(event)
+    }
+
+    fn needs_re_render(&self) -> bool {
+        self.table.needs_re_render()
+    }
+
+    fn invalidate(&mut self) {
+        self.table.invalidate();
+    }
+}
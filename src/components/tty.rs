@@ -64,7 +64,7 @@ impl PtyView {
         // Launch neovim in pty
         let pty_system = native_pty_system();
 
-        let mut pair = pty_system.openpty(
+        let pair = pty_system.openpty(
             PtySize {
                 rows,
                 cols,
@@ -73,9 +73,9 @@ impl PtyView {
             }
         ).expect("Couldn't open pty");
 
-        let mut cmd = CommandBuilder::new("nano");
+        let cmd = CommandBuilder::new("nano");
 
-        let mut child = pair.slave
+        let _child = pair.slave
             .spawn_command(cmd)
             .expect("Couldn't spawn nvim command");
 
@@ -83,9 +83,9 @@ impl PtyView {
             .try_clone_reader()
             .expect("Couldn't clone reader");
 
-        let mut pty_buffer = [0u8; 2048];
+        let pty_buffer = [0u8; 2048];
         
-        let mut pty_writer = pair.master
+        let pty_writer = pair.master
             .take_writer()
             .expect("Can't take PTY writer");
 
@@ -124,6 +124,8 @@ impl Element for PtyView {
         true
     }
 
+    fn invalidate(&mut self) {}
+
     fn output(
         &mut self,
         console: &mut Console,
@@ -147,7 +149,7 @@ impl Element for PtyView {
 
                     if key_event.modifiers.contains(KeyModifiers::SHIFT) {
                         bytes.extend_from_slice(
-                            &c
+                            c
                             .to_uppercase()
                             .next()
                             .unwrap()
@@ -155,7 +157,7 @@ impl Element for PtyView {
                             .as_bytes()
                         );
                     } else {
-                        bytes.extend_from_slice(&c.to_string().as_bytes());
+                        bytes.extend_from_slice(c.to_string().as_bytes());
                     }
 
                     bytes
@@ -164,7 +166,7 @@ impl Element for PtyView {
             };
 
             self.writer
-                .write(&input_bytes)
+                .write_all(&input_bytes)
                 .expect("Couldn't write");
         }
 
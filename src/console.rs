@@ -4,7 +4,7 @@ use crossterm::{
     QueueableCommand,
     cursor::{MoveTo, Hide, Show},
     style::{Print, Color, SetForegroundColor, SetBackgroundColor, ResetColor},
-    Command, ExecutableCommand, terminal::{EnterAlternateScreen, self, LeaveAlternateScreen, Clear}
+    Command, ExecutableCommand, terminal::{EnterAlternateScreen, self, LeaveAlternateScreen, Clear, ClearType}
 };
 
 
@@ -59,7 +59,11 @@ impl Console {
     }
 
     pub fn write_raw(&mut self, v: &[u8]) {
-        self.stdout.write(v).unwrap();
+        self.stdout.write_all(v).unwrap();
+    }
+
+    pub fn clear(&mut self) {
+        self.queue_safe(Clear(ClearType::All));
     }
 
     pub fn hide_cursor(&mut self) {
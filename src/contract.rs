@@ -0,0 +1,278 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::assertions::AssertionResult;
+use crate::http::HttpResponse;
+use crate::openapi::{self, is_json, media_matches};
+use crate::paths::Paths;
+use crate::schema;
+
+/// Operation of an OpenAPI spec a request was imported from.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SpecOperation {
+    /// Spec file, see `spec_name`
+    pub spec: String,
+    /// Lowercase, as the spec keys it
+    pub method: String,
+    /// Path template, e.g. `/pets/{petId}`
+    pub path: String,
+    #[serde(default)]
+    pub operation_id: Option<String>,
+}
+
+/// How requests refer to a spec file, relative to the project root when it's inside it.
+pub fn spec_name(spec: &Path) -> String {
+    spec.strip_prefix(Paths::project_root())
+        .unwrap_or(spec)
+        .display()
+        .to_string()
+}
+
+impl SpecOperation {
+    pub fn new(spec: &Path, method: &str, path: &str, operation_id: Option<String>) -> Self {
+        Self {
+            spec: spec_name(spec),
+            method: method.to_lowercase(),
+            path: path.to_string(),
+            operation_id,
+        }
+    }
+
+    pub fn label(&self) -> String {
+        format!("{} {}", self.method.to_uppercase(), self.path)
+    }
+}
+
+/// Loaded specs by path, loaded again once their file changes.
+pub struct SpecCache {
+    specs: HashMap<PathBuf, (Option<SystemTime>, Value)>,
+}
+
+impl SpecCache {
+    pub fn new() -> Self {
+        Self { specs: HashMap::new() }
+    }
+
+    fn get(&mut self, path: &Path) -> Result<&Value, String> {
+        let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+
+        if self.specs.get(path).is_none_or(|(loaded, _)| modified.is_none() || *loaded != modified) {
+            let root = openapi::load(path)?;
+            self.specs.insert(path.to_path_buf(), (modified, root));
+        }
+
+        Ok(&self.specs[path].1)
+    }
+}
+
+/// How a response breaks the spec. Body violations point into the body.
+pub struct Violation {
+    pub pointer: Option<String>,
+    pub message: String,
+}
+
+impl Violation {
+    fn new(message: String) -> Self {
+        Self { pointer: None, message }
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.pointer {
+            Some(pointer) => write!(f, "#{}: {}", pointer, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// Checks the response against the operation: the status must be declared,
+/// the content type listed for it and the body valid for its schema.
+pub fn check(specs: &mut SpecCache, operation: &SpecOperation, response: &HttpResponse) -> Vec<Violation> {
+    let root = match specs.get(&Paths::project_root().join(&operation.spec)) {
+        Ok(root) => root,
+        Err(e) => return vec![Violation::new(e)],
+    };
+
+    let item = root
+        .get("paths")
+        .and_then(|p| p.get(&operation.path))
+        .map(|item| openapi::resolve(root, item));
+
+    let Some(spec_operation) = item.and_then(|item| item.get(&operation.method)) else {
+        return vec![Violation::new(format!("{} is not in {} anymore", operation.label(), operation.spec))];
+    };
+
+    let Some(responses) = spec_operation.get("responses").and_then(|r| r.as_object()) else {
+        return vec![Violation::new(String::from("the operation declares no responses"))];
+    };
+
+    let status = response.status.to_string();
+    let range = format!("{}XX", response.status / 100);
+
+    let declared = responses.get(&status)
+        .or_else(|| responses.iter().find(|(code, _)| code.eq_ignore_ascii_case(&range)).map(|(_, r)| r))
+        .or_else(|| responses.get("default"));
+
+    let Some(declared) = declared.map(|r| openapi::resolve(root, r)) else {
+        let codes: Vec<&str> = responses.keys().map(String::as_str).collect();
+        return vec![Violation::new(format!("status {} is not declared, expected {}", status, codes.join(", ")))];
+    };
+
+    let content = declared.get("content").and_then(|c| c.as_object()).filter(|c| !c.is_empty());
+
+    let Some(content) = content else {
+        if response.body.is_empty() {
+            return Vec::new();
+        }
+
+        return vec![Violation::new(format!("status {} declares no body, got {} bytes", status, response.body.len()))];
+    };
+
+    if response.body.is_empty() {
+        return Vec::new();
+    }
+
+    let listed: Vec<&str> = content.keys().map(String::as_str).collect();
+
+    let Some(content_type) = response.header("Content-Type") else {
+        return vec![Violation::new(format!("Content-Type is missing, expected {}", listed.join(", ")))];
+    };
+
+    let Some((media_type, media)) = content.iter().find(|(listed, _)| media_matches(listed, content_type)) else {
+        return vec![Violation::new(format!(
+            "Content-Type {} is not declared, expected {}",
+            content_type,
+            listed.join(", "),
+        ))];
+    };
+
+    let Some(body_schema) = media.get("schema") else {
+        return Vec::new();
+    };
+
+    if !is_json(media_type) {
+        return Vec::new();
+    }
+
+    match serde_json::from_str::<Value>(&response.body) {
+        Ok(body) => schema::violations(root, body_schema, &body)
+            .into_iter()
+            .map(|v| Violation { pointer: Some(v.pointer), message: v.message })
+            .collect(),
+        Err(e) => vec![Violation::new(format!("body is not JSON: {}", e))],
+    }
+}
+
+/// The violations as one check, for runs and reports.
+pub fn as_check(operation: &SpecOperation, violations: &[Violation]) -> AssertionResult {
+    AssertionResult {
+        description: format!("response matches {} in the spec", operation.label()),
+        passed: violations.is_empty(),
+        message: violations.iter().map(|v| v.to_string()).collect::<Vec<String>>().join("; "),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use crate::http::Timings;
+
+    const SPEC: &str = r##"{
+        "openapi": "3.0.3",
+        "paths": { "/pets/{id}": { "get": { "responses": {
+            "200": { "content": { "application/json": { "schema": { "$ref": "#/components/schemas/Pet" } } } },
+            "4XX": { "description": "Client error" }
+        } } } },
+        "components": { "schemas": { "Pet": {
+            "type": "object",
+            "required": ["name"],
+            "properties": { "name": { "type": "string" }, "age": { "type": "integer" } }
+        } } }
+    }"##;
+
+    fn response(status: u16, content_type: Option<&str>, body: &str) -> HttpResponse {
+        HttpResponse {
+            status,
+            reason: String::new(),
+            headers: content_type.map(|c| (String::from("Content-Type"), c.to_string())).into_iter().collect(),
+            body: body.to_string(),
+            timings: Timings { connect: Duration::ZERO, first_byte: Duration::ZERO, total: Duration::ZERO },
+        }
+    }
+
+    fn messages(violations: Vec<Violation>) -> Vec<String> {
+        violations.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn responses_are_checked_against_the_operation() {
+        let path = std::env::temp_dir().join(format!("umbrella-contract-{}.json", std::process::id()));
+        fs::write(&path, SPEC).unwrap();
+
+        let mut specs = SpecCache::new();
+        let operation = SpecOperation::new(&path, "GET", "/pets/{id}", None);
+        let mut check = |status, content_type, body| {
+            messages(check(&mut specs, &operation, &response(status, content_type, body)))
+        };
+
+        let valid = check(200, Some("application/json; charset=utf-8"), r#"{ "name": "Tom" }"#);
+        let range = check(404, None, "");
+        let undeclared = check(500, None, "");
+        let unexpected_body = check(404, Some("text/plain"), "Not found");
+        let missing_type = check(200, None, "{}");
+        let wrong_type = check(200, Some("text/html"), "<p>");
+        let invalid = check(200, Some("application/json"), r#"{ "age": "old" }"#);
+        let not_json = check(200, Some("application/json"), "{");
+
+        fs::remove_file(&path).unwrap();
+
+        assert!(valid.is_empty());
+        assert!(range.is_empty());
+        assert_eq!(undeclared, ["status 500 is not declared, expected 200, 4XX"]);
+        assert_eq!(unexpected_body, ["status 404 declares no body, got 9 bytes"]);
+        assert_eq!(missing_type, ["Content-Type is missing, expected application/json"]);
+        assert_eq!(wrong_type, ["Content-Type text/html is not declared, expected application/json"]);
+        assert_eq!(invalid.len(), 2);
+        assert!(invalid.iter().any(|v| v.starts_with("#/age: ")));
+        assert!(not_json[0].starts_with("body is not JSON"));
+    }
+
+    #[test]
+    fn specs_are_loaded_again_once_changed() {
+        let path = std::env::temp_dir().join(format!("umbrella-contract-cache-{}.json", std::process::id()));
+        let write = |text: &str, modified: u64| {
+            fs::write(&path, text).unwrap();
+            let file = fs::File::options().write(true).open(&path).unwrap();
+            file.set_modified(UNIX_EPOCH + Duration::from_secs(modified)).unwrap();
+        };
+
+        write(SPEC, 1_000);
+        let mut specs = SpecCache::new();
+        let operation = SpecOperation::new(&path, "get", "/pets/{id}", None);
+        let ok = response(404, None, "");
+
+        let first = messages(check(&mut specs, &operation, &ok));
+
+        // Same time, the cached spec is used
+        write(&SPEC.replace("4XX", "2XX"), 1_000);
+        let cached = messages(check(&mut specs, &operation, &ok));
+
+        write(&SPEC.replace("4XX", "2XX"), 2_000);
+        let reloaded = messages(check(&mut specs, &operation, &ok));
+
+        fs::remove_file(&path).unwrap();
+
+        assert!(first.is_empty());
+        assert!(cached.is_empty());
+        assert_eq!(reloaded, ["status 404 is not declared, expected 200, 2XX"]);
+    }
+}
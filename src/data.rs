@@ -0,0 +1,146 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use serde_json::Value;
+
+/// Variables bound by one row of a data file.
+pub type DataRow = BTreeMap<String, String>;
+
+/// Rows of a CSV file with a header line or of a JSONL file with an object per line.
+pub fn load(path: &Path) -> Result<Vec<DataRow>, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("Couldn't read {}: {}", path.display(), e))?;
+
+    let is_jsonl = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("jsonl") || e.eq_ignore_ascii_case("ndjson"));
+
+    let rows = if is_jsonl { parse_jsonl(&text)? } else { parse_csv(&text)? };
+
+    if rows.is_empty() {
+        return Err(format!("{} has no rows", path.display()));
+    }
+
+    Ok(rows)
+}
+
+fn parse_jsonl(text: &str) -> Result<Vec<DataRow>, String> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| match serde_json::from_str::<Value>(line) {
+            Ok(Value::Object(map)) => Ok(map
+                .into_iter()
+                .map(|(name, value)| match value {
+                    Value::String(s) => (name, s),
+                    other => (name, other.to_string()),
+                })
+                .collect()),
+            Ok(_) => Err(format!("Line {} is not a JSON object", i + 1)),
+            Err(e) => Err(format!("Line {} is not JSON: {}", i + 1, e)),
+        })
+        .collect()
+}
+
+/// Splits CSV into records, quoted fields may hold commas, quotes and line breaks.
+fn csv_records(text: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            },
+            ('"', true) => quoted = false,
+            ('"', false) if field.is_empty() => quoted = true,
+            (',', false) => record.push(std::mem::take(&mut field)),
+            ('\r', false) => {},
+            ('\n', false) => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            },
+            (c, _) => field.push(c),
+        }
+    }
+
+    if quoted {
+        return Err(String::from("Unclosed quote in the CSV"));
+    }
+
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+
+    // Blank lines separate nothing
+    records.retain(|r| !(r.len() == 1 && r[0].trim().is_empty()));
+
+    Ok(records)
+}
+
+fn parse_csv(text: &str) -> Result<Vec<DataRow>, String> {
+    let mut records = csv_records(text.trim_start_matches('\u{feff}'))?.into_iter();
+
+    let Some(header) = records.next() else {
+        return Ok(Vec::new());
+    };
+
+    let header: Vec<String> = header.iter().map(|h| h.trim().to_string()).collect();
+
+    records
+        .enumerate()
+        .map(|(i, record)| {
+            if record.len() != header.len() {
+                return Err(format!(
+                    "Row {} has {} fields, the header has {}",
+                    i + 1,
+                    record.len(),
+                    header.len(),
+                ));
+            }
+
+            Ok(header.iter().cloned().zip(record).collect())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(pairs: &[(&str, &str)]) -> DataRow {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn quoted_csv_fields_keep_commas_quotes_and_line_breaks() {
+        let text = "name,note\r\n\"Doe, Jane\",\"says \"\"hi\"\"\"\r\nBob,\"two\nlines\"\r\n";
+
+        assert_eq!(parse_csv(text).unwrap(), [
+            row(&[("name", "Doe, Jane"), ("note", "says \"hi\"")]),
+            row(&[("name", "Bob"), ("note", "two\nlines")]),
+        ]);
+    }
+
+    #[test]
+    fn malformed_csv_is_refused() {
+        assert_eq!(parse_csv("a,b\n1,2,3\n").unwrap_err(), "Row 1 has 3 fields, the header has 2");
+        assert_eq!(parse_csv("a,b\n1\n").unwrap_err(), "Row 1 has 1 fields, the header has 2");
+        assert_eq!(parse_csv("a\n\"open\n").unwrap_err(), "Unclosed quote in the CSV");
+    }
+
+    #[test]
+    fn jsonl_lines_must_be_objects() {
+        let rows = parse_jsonl("{\"id\": 1, \"name\": \"a\"}\n\n{\"id\": 2, \"tags\": [\"x\"]}\n").unwrap();
+
+        assert_eq!(rows, [row(&[("id", "1"), ("name", "a")]), row(&[("id", "2"), ("tags", "[\"x\"]")])]);
+        assert_eq!(parse_jsonl("{}\n[1]\n").unwrap_err(), "Line 2 is not a JSON object");
+        assert!(parse_jsonl("{\"id\": 1\n").unwrap_err().starts_with("Line 1 is not JSON"));
+    }
+}
@@ -0,0 +1,354 @@
+use serde_json::Value;
+
+use crate::history::ReceivedResponse;
+
+/// Bodies with more line pairs than this aren't aligned, every line is shown as changed.
+const MAX_LCS_CELLS: usize = 16_000_000;
+
+/// Changed paths listed in the summary before the rest is counted.
+const MAX_LISTED_CHANGES: usize = 3;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum DiffKind {
+    Same,
+    Removed,
+    Added,
+    /// Removed on the left and added on the right in the same place
+    Changed,
+}
+
+/// Line pair shown side by side, a missing side is left blank.
+pub struct DiffRow {
+    pub kind: DiffKind,
+    pub left: Option<String>,
+    pub right: Option<String>,
+}
+
+/// Side by side line diff based on the longest common subsequence.
+pub fn line_diff(left: &str, right: &str) -> Vec<DiffRow> {
+    let left: Vec<&str> = left.lines().collect();
+    let right: Vec<&str> = right.lines().collect();
+
+    let prefix = left.iter().zip(right.iter()).take_while(|(l, r)| l == r).count();
+    let suffix = left[prefix..]
+        .iter()
+        .rev()
+        .zip(right[prefix..].iter().rev())
+        .take_while(|(l, r)| l == r)
+        .count();
+
+    let same = |line: &&str| DiffRow {
+        kind: DiffKind::Same,
+        left: Some(line.to_string()),
+        right: Some(line.to_string()),
+    };
+
+    let mut rows: Vec<DiffRow> = left[..prefix].iter().map(same).collect();
+    rows.extend(pair_changes(diff_ops(
+        &left[prefix..left.len() - suffix],
+        &right[prefix..right.len() - suffix],
+    )));
+    rows.extend(left[left.len() - suffix..].iter().map(same));
+
+    rows
+}
+
+enum Op<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+fn diff_ops<'a>(left: &[&'a str], right: &[&'a str]) -> Vec<Op<'a>> {
+    let (n, m) = (left.len(), right.len());
+
+    if n * m > MAX_LCS_CELLS {
+        return left.iter().map(|l| Op::Removed(l))
+            .chain(right.iter().map(|r| Op::Added(r)))
+            .collect();
+    }
+
+    // lcs[i][j] is the LCS length of left[i..] and right[j..]
+    let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if left[i] == right[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                std::cmp::max(lcs[i + 1][j], lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut ops = Vec::new();
+    let (mut i, mut j) = (0, 0);
+
+    while i < n && j < m {
+        if left[i] == right[j] {
+            ops.push(Op::Same(left[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            ops.push(Op::Removed(left[i]));
+            i += 1;
+        } else {
+            ops.push(Op::Added(right[j]));
+            j += 1;
+        }
+    }
+
+    ops.extend(left[i..].iter().map(|l| Op::Removed(l)));
+    ops.extend(right[j..].iter().map(|r| Op::Added(r)));
+    ops
+}
+
+/// Puts removed lines next to the lines added in their place.
+fn pair_changes(ops: Vec<Op>) -> Vec<DiffRow> {
+    let mut rows = Vec::new();
+    let mut removed: Vec<&str> = Vec::new();
+    let mut added: Vec<&str> = Vec::new();
+
+    let flush = |rows: &mut Vec<DiffRow>, removed: &mut Vec<&str>, added: &mut Vec<&str>| {
+        for i in 0..std::cmp::max(removed.len(), added.len()) {
+            let (left, right) = (removed.get(i), added.get(i));
+
+            rows.push(DiffRow {
+                kind: match (left, right) {
+                    (Some(_), Some(_)) => DiffKind::Changed,
+                    (Some(_), None) => DiffKind::Removed,
+                    _ => DiffKind::Added,
+                },
+                left: left.map(|l| l.to_string()),
+                right: right.map(|r| r.to_string()),
+            });
+        }
+
+        removed.clear();
+        added.clear();
+    };
+
+    for op in ops {
+        match op {
+            Op::Removed(l) => removed.push(l),
+            Op::Added(r) => added.push(r),
+            Op::Same(line) => {
+                flush(&mut rows, &mut removed, &mut added);
+                rows.push(DiffRow {
+                    kind: DiffKind::Same,
+                    left: Some(line.to_string()),
+                    right: Some(line.to_string()),
+                });
+            },
+        }
+    }
+
+    flush(&mut rows, &mut removed, &mut added);
+    rows
+}
+
+/// Field is ignored by its name anywhere, or by its dotted path from the root.
+///
+/// Array indices aren't part of paths, `items.id` matches the id of every item.
+fn is_ignored(path: &str, key: &str, ignored: &[String]) -> bool {
+    ignored.iter().any(|i| i == key || i == path)
+}
+
+fn join_path(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+/// Drops ignored fields, objects keep their keys sorted, so key order doesn't matter.
+fn strip_ignored(value: Value, path: &str, ignored: &[String]) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .filter(|(k, _)| !is_ignored(&join_path(path, k), k, ignored))
+                .map(|(k, v)| {
+                    let v = strip_ignored(v, &join_path(path, &k), ignored);
+                    (k, v)
+                })
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(
+            items.into_iter().map(|v| strip_ignored(v, path, ignored)).collect(),
+        ),
+        other => other,
+    }
+}
+
+/// Paths of the values that differ, like `$.items[0].id`.
+fn json_changes(left: &Value, right: &Value, path: &str, changes: &mut Vec<String>) {
+    match (left, right) {
+        (Value::Object(l), Value::Object(r)) => {
+            let mut keys: Vec<&String> = l.keys().chain(r.keys()).collect();
+            keys.sort();
+            keys.dedup();
+
+            for key in keys {
+                let path = format!("{}.{}", path, key);
+
+                match (l.get(key), r.get(key)) {
+                    (Some(lv), Some(rv)) => json_changes(lv, rv, &path, changes),
+                    _ => changes.push(path),
+                }
+            }
+        },
+        (Value::Array(l), Value::Array(r)) => {
+            for i in 0..std::cmp::max(l.len(), r.len()) {
+                let path = format!("{}[{}]", path, i);
+
+                match (l.get(i), r.get(i)) {
+                    (Some(lv), Some(rv)) => json_changes(lv, rv, &path, changes),
+                    _ => changes.push(path),
+                }
+            }
+        },
+        (l, r) if l != r => changes.push(path.to_string()),
+        _ => {},
+    }
+}
+
+/// Status line and headers sorted by name, ignored headers left out.
+fn head_text(response: &ReceivedResponse, ignored: &[String]) -> String {
+    let mut headers: Vec<String> = response.headers
+        .iter()
+        .filter(|(name, _)| !ignored.iter().any(|i| i.eq_ignore_ascii_case(name)))
+        .map(|(name, value)| format!("{}: {}", name, value))
+        .collect();
+
+    headers.sort_by_key(|h| h.to_lowercase());
+
+    format!("HTTP {} {}\n{}", response.status, response.reason, headers.join("\n"))
+}
+
+/// Diff of two responses and a short summary of what changed.
+///
+/// JSON bodies are compared structurally, other bodies line by line.
+pub fn response_diff(
+    left: &ReceivedResponse,
+    right: &ReceivedResponse,
+    ignored: &[String],
+) -> (Vec<DiffRow>, String) {
+    let mut rows = line_diff(&head_text(left, ignored), &head_text(right, ignored));
+
+    let json = serde_json::from_str::<Value>(&left.body)
+        .and_then(|l| serde_json::from_str::<Value>(&right.body).map(|r| (l, r)));
+
+    let mut changes = Vec::new();
+
+    if left.status != right.status {
+        changes.push(String::from("status"));
+    }
+
+    // The first row is the status line
+    if rows.iter().skip(1).any(|r| r.kind != DiffKind::Same) {
+        changes.push(String::from("headers"));
+    }
+
+    rows.push(DiffRow { kind: DiffKind::Same, left: Some(String::new()), right: Some(String::new()) });
+
+    match json {
+        Ok((l, r)) => {
+            let l = strip_ignored(l, "", ignored);
+            let r = strip_ignored(r, "", ignored);
+            json_changes(&l, &r, "$", &mut changes);
+
+            let pretty = |v: &Value| serde_json::to_string_pretty(v).unwrap_or_default();
+            rows.extend(line_diff(&pretty(&l), &pretty(&r)));
+        },
+        Err(_) => {
+            let body_rows = line_diff(&left.body, &right.body);
+
+            if body_rows.iter().any(|r| r.kind != DiffKind::Same) {
+                changes.push(String::from("body"));
+            }
+
+            rows.extend(body_rows);
+        },
+    }
+
+    let summary = match changes.len() {
+        0 => String::from("no differences"),
+        n if n <= MAX_LISTED_CHANGES => changes.join(", "),
+        n => format!(
+            "{} and {} more",
+            changes[..MAX_LISTED_CHANGES].join(", "),
+            n - MAX_LISTED_CHANGES,
+        ),
+    };
+
+    (rows, summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(status: u16, headers: &[(&str, &str)], body: &str) -> ReceivedResponse {
+        ReceivedResponse {
+            status,
+            reason: String::from(if status == 200 { "OK" } else { "Not Found" }),
+            headers: headers.iter().map(|(n, v)| (n.to_string(), v.to_string())).collect(),
+            body: body.to_string(),
+            connect_ms: 0,
+            first_byte_ms: 0,
+            total_ms: 0,
+        }
+    }
+
+    fn kinds(rows: &[DiffRow]) -> Vec<DiffKind> {
+        rows.iter().map(|r| r.kind).collect()
+    }
+
+    #[test]
+    fn changed_lines_are_paired_with_their_replacement() {
+        let rows = line_diff("a\nb\nc\nd", "a\nB\nc\nd\ne");
+
+        assert!(kinds(&rows) == [
+            DiffKind::Same,
+            DiffKind::Changed,
+            DiffKind::Same,
+            DiffKind::Same,
+            DiffKind::Added,
+        ]);
+        assert_eq!(rows[1].left.as_deref(), Some("b"));
+        assert_eq!(rows[1].right.as_deref(), Some("B"));
+        assert_eq!(rows[4].left, None);
+    }
+
+    #[test]
+    fn json_bodies_are_compared_by_path_regardless_of_key_order() {
+        let left = response(200, &[], r#"{"id": 1, "items": [{"name": "a"}], "env": "prod"}"#);
+        let right = response(200, &[], r#"{"env": "prod", "items": [{"name": "b"}, {"name": "c"}], "id": 1}"#);
+
+        let (_, summary) = response_diff(&left, &right, &[]);
+        assert_eq!(summary, "$.items[0].name, $.items[1]");
+    }
+
+    #[test]
+    fn ignored_fields_and_headers_are_left_out() {
+        let left = response(200, &[("Date", "Mon"), ("Server", "a")], r#"{"id": 1, "meta": {"at": 1}}"#);
+        let right = response(200, &[("date", "Tue"), ("Server", "a")], r#"{"id": 1, "meta": {"at": 2}}"#);
+
+        let ignored = [String::from("date"), String::from("meta.at")];
+        assert_eq!(response_diff(&left, &right, &ignored).1, "no differences");
+        assert_eq!(response_diff(&left, &right, &[]).1, "headers, $.meta.at");
+    }
+
+    #[test]
+    fn summary_counts_changes_past_the_listed_ones() {
+        let left = response(200, &[], "one\ntwo");
+        let right = response(404, &[("Server", "b")], "one\nthree");
+
+        assert_eq!(response_diff(&left, &right, &[]).1, "status, headers, body");
+
+        let left = response(200, &[], r#"{"a": 1, "b": 1, "c": 1, "d": 1}"#);
+        let right = response(200, &[], r#"{"a": 2, "b": 2, "c": 2, "d": 2}"#);
+        assert_eq!(response_diff(&left, &right, &[]).1, "$.a, $.b, $.c and 1 more");
+    }
+}
@@ -0,0 +1,85 @@
+use std::fs;
+use std::path::Path;
+
+use crate::har;
+use crate::history::History;
+use crate::model::Model;
+use crate::paths::Paths;
+use crate::postman;
+
+const USAGE: &str = "Usage: umbrella export postman|har FILE";
+
+/// Collection name for the file, `api.postman_collection.json` is named `api`.
+fn collection_name(path: &Path) -> String {
+    let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+
+    match stem.strip_suffix(".postman_collection") {
+        Some(name) => name.to_string(),
+        None => stem,
+    }
+}
+
+/// Execution history as HAR 1.2.
+fn export_history(path: &Path) -> i32 {
+    let history = History::load(Paths::history_path());
+
+    if let Some(e) = history.load_error {
+        eprintln!("{}", e);
+        return 1;
+    }
+
+    let json = serde_json::to_string_pretty(&har::export(history.entries())).unwrap_or_default();
+
+    if let Err(e) = fs::write(path, json) {
+        eprintln!("Couldn't write {}: {}", path.display(), e);
+        return 1;
+    }
+
+    println!("Exported {} history entries to {}", history.entries().len(), path.display());
+    0
+}
+
+/// Headless `umbrella export`, from the saved collection or the history.
+pub fn run(args: &[String]) -> i32 {
+    let path = match args {
+        [format, path] if format == "har" => return export_history(Path::new(path)),
+        [format, path] if format == "postman" => Path::new(path),
+        [format, _] => {
+            eprintln!("Unknown format {}\n{}", format, USAGE);
+            return 2;
+        },
+        _ => {
+            eprintln!("{}", USAGE);
+            return 2;
+        },
+    };
+
+    let model = match Model::load_from_disk() {
+        Ok(Some(model)) => model,
+        Ok(None) => {
+            eprintln!("Nothing to export, the collection wasn't saved yet");
+            return 1;
+        },
+        Err(e) => {
+            eprintln!("{}", e);
+            return 1;
+        },
+    };
+
+    let (collection, notes) = postman::export(&model, &collection_name(path));
+    let json = serde_json::to_string_pretty(&collection).unwrap_or_default();
+
+    if let Err(e) = fs::write(path, json) {
+        eprintln!("Couldn't write {}: {}", path.display(), e);
+        return 1;
+    }
+
+    println!("Exported {} requests to {}", model.requests.read().unwrap().len(), path.display());
+
+    if !notes.is_empty() {
+        println!("Left out:");
+        notes.iter().for_each(|note| println!("  {}", note));
+    }
+
+    0
+}
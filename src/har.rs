@@ -0,0 +1,467 @@
+use std::fs;
+use std::path::Path;
+
+use serde_json::{json, Value};
+
+use crate::auth::Auth;
+use crate::history::{self, HistoryEntry};
+use crate::import::{text, Imported};
+use crate::model::{Folder, Header, Request};
+use crate::url;
+
+/// Headers the client sets itself, copying them would send them twice or
+/// ask for compressed bodies it can't read.
+const SET_WHEN_SENDING: [&str; 9] = [
+    "host", "content-length", "connection", "accept-encoding", "keep-alive",
+    "transfer-encoding", "te", "upgrade", "proxy-connection",
+];
+
+/// Host and port of the URL, lowercased.
+fn host(url: &str) -> String {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let end = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+    let authority = &rest[..end];
+
+    authority.rsplit_once('@').map_or(authority, |(_, host)| host).to_lowercase()
+}
+
+/// An entry of a HAR file turned into a request.
+pub struct HarEntry {
+    pub request: Request,
+    pub host: String,
+    /// Status the browser got, 0 when the request never completed
+    pub status: u16,
+    /// What the request can't hold
+    pub skipped: Vec<String>,
+}
+
+/// Which entries are imported, all of them when empty.
+#[derive(Default)]
+pub struct HarFilter {
+    /// Parts of host names
+    pub domains: Vec<String>,
+    pub methods: Vec<String>,
+}
+
+impl HarFilter {
+    /// Filter typed as words, standard methods like `post` and parts of host names.
+    pub fn parse(text: &str) -> Self {
+        let mut filter = Self::default();
+
+        for word in text.split_whitespace() {
+            let upper = word.to_uppercase();
+
+            if crate::http::METHODS.contains(&upper.as_str()) {
+                filter.methods.push(upper);
+            } else {
+                filter.domains.push(word.to_lowercase());
+            }
+        }
+
+        filter
+    }
+
+    pub fn matches(&self, entry: &HarEntry) -> bool {
+        let domain = self.domains.is_empty() || self.domains.iter().any(|d| entry.host.contains(d.as_str()));
+        let method = self.methods.is_empty() || self.methods.iter().any(|m| m.eq_ignore_ascii_case(&entry.request.method));
+
+        domain && method
+    }
+}
+
+fn import_headers(request: &Value) -> Vec<Header> {
+    let mut headers: Vec<Header> = request
+        .get("headers")
+        .and_then(|h| h.as_array())
+        .into_iter()
+        .flatten()
+        .map(|h| Header { name: text(h.get("name")), value: text(h.get("value")), enabled: true })
+        // HTTP/2 pseudo-headers like `:authority` are part of the request line
+        .filter(|h| !h.name.starts_with(':') && !SET_WHEN_SENDING.contains(&h.name.to_lowercase().as_str()))
+        .collect();
+
+    let cookies: Vec<String> = request
+        .get("cookies")
+        .and_then(|c| c.as_array())
+        .into_iter()
+        .flatten()
+        .map(|c| format!("{}={}", text(c.get("name")), text(c.get("value"))))
+        .collect();
+
+    // Some tools only list cookies apart from the headers
+    if !cookies.is_empty() && !headers.iter().any(|h| h.name.eq_ignore_ascii_case("Cookie")) {
+        headers.push(Header { name: String::from("Cookie"), value: cookies.join("; "), enabled: true });
+    }
+
+    headers
+}
+
+/// Body as text, form params without a text are encoded from their fields.
+fn import_body(post_data: &Value, headers: &mut Vec<Header>, owner: &str, skipped: &mut Vec<String>) -> String {
+    let mime_type = text(post_data.get("mimeType"));
+
+    if !mime_type.is_empty() && !headers.iter().any(|h| h.name.eq_ignore_ascii_case("Content-Type")) {
+        headers.push(Header { name: String::from("Content-Type"), value: mime_type.clone(), enabled: true });
+    }
+
+    if let Some(body) = post_data.get("text").and_then(|t| t.as_str()) {
+        return body.to_string();
+    }
+
+    let mut pairs = Vec::new();
+
+    for param in post_data.get("params").and_then(|p| p.as_array()).into_iter().flatten() {
+        let name = text(param.get("name"));
+
+        if param.get("fileName").is_some() {
+            skipped.push(format!("{}: file field {} left out, bodies are text", owner, name));
+        } else {
+            pairs.push(format!("{}={}", url::percent_encode(&name), url::percent_encode(&text(param.get("value")))));
+        }
+    }
+
+    pairs.join("&")
+}
+
+fn import_entry(entry: &Value) -> Option<HarEntry> {
+    let har_request = entry.get("request")?;
+    let url = text(har_request.get("url"));
+
+    // data:, blob: and extension URLs show up in recordings, but can't be sent
+    if !url.starts_with("http://") && !url.starts_with("https://") {
+        return None;
+    }
+
+    let method = text(har_request.get("method")).to_uppercase();
+    let host = host(&url);
+    let path = url.split_once("://").map_or("", |(_, rest)| rest);
+    let path = &path[path.find('/').unwrap_or(path.len())..];
+
+    let mut request = Request::new_default();
+    request.method = method;
+    request.title = url::split_url(path).base.to_string();
+    request.url = url.clone();
+    request.query_params = url::merge_query(&url, &[]);
+    request.headers = import_headers(har_request);
+    // The recorded headers carry the credentials
+    request.auth = Auth::None;
+
+    let mut skipped = Vec::new();
+
+    if let Some(post_data) = har_request.get("postData") {
+        request.body = import_body(post_data, &mut request.headers, &request.title, &mut skipped);
+    }
+
+    let status = entry.get("response").and_then(|r| r.get("status")).and_then(|s| s.as_u64()).unwrap_or(0);
+
+    Some(HarEntry { request, host, status: status as u16, skipped })
+}
+
+/// Requests of the entries of a HAR file, in the order they were recorded.
+pub fn read(path: &Path) -> Result<Vec<HarEntry>, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("Couldn't read {}: {}", path.display(), e))?;
+    let value: Value = serde_json::from_str(&content).map_err(|e| format!("Couldn't parse {}: {}", path.display(), e))?;
+
+    let entries = value
+        .get("log")
+        .and_then(|l| l.get("entries"))
+        .and_then(|e| e.as_array())
+        .ok_or(format!("{} is not a HAR file, it has no log.entries", path.display()))?;
+
+    Ok(entries.iter().filter_map(import_entry).collect())
+}
+
+pub fn is_har(value: &Value) -> bool {
+    value.get("log").and_then(|l| l.get("entries")).is_some_and(|e| e.is_array())
+}
+
+pub fn is_har_file(path: &Path) -> bool {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<Value>(&content).ok())
+        .is_some_and(|value| is_har(&value))
+}
+
+/// Folder the entries of the file are imported into, `bug-42.har` gives `bug-42`.
+pub fn folder_name(path: &Path) -> String {
+    path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or(String::from("HAR"))
+}
+
+/// The entries as requests of one folder.
+pub fn to_imported(entries: Vec<HarEntry>, folder: &str) -> Imported {
+    let mut imported = Imported {
+        folders: vec![Folder { name: folder.to_string(), auth: Auth::None }],
+        ..Imported::default()
+    };
+
+    for mut entry in entries {
+        entry.request.folder = Some(folder.to_string());
+        imported.requests.push(entry.request);
+        imported.skipped.append(&mut entry.skipped);
+    }
+
+    imported
+}
+
+/// Requests of the entries that match the filter.
+pub fn import(path: &Path, filter: &HarFilter) -> Result<Imported, String> {
+    let entries: Vec<HarEntry> = read(path)?.into_iter().filter(|e| filter.matches(e)).collect();
+
+    if entries.is_empty() {
+        return Err(format!("No entries of {} match", path.display()));
+    }
+
+    Ok(to_imported(entries, &folder_name(path)))
+}
+
+fn header_list(headers: &[(String, String)]) -> Vec<Value> {
+    headers.iter().map(|(name, value)| json!({ "name": name, "value": value })).collect()
+}
+
+fn find_header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)).map(|(_, v)| v.as_str())
+}
+
+/// `a=1; b=2` of a Cookie header.
+fn request_cookies(headers: &[(String, String)]) -> Vec<Value> {
+    find_header(headers, "Cookie")
+        .into_iter()
+        .flat_map(|cookies| cookies.split(';'))
+        .filter_map(|cookie| cookie.trim().split_once('='))
+        .map(|(name, value)| json!({ "name": name, "value": value }))
+        .collect()
+}
+
+/// Name and value of each Set-Cookie header, its attributes aren't kept.
+fn response_cookies(headers: &[(String, String)]) -> Vec<Value> {
+    headers
+        .iter()
+        .filter(|(name, _)| name.eq_ignore_ascii_case("Set-Cookie"))
+        .filter_map(|(_, value)| value.split(';').next()?.trim().split_once('='))
+        .map(|(name, value)| json!({ "name": name, "value": value }))
+        .collect()
+}
+
+fn export_entry(entry: &HistoryEntry) -> Value {
+    // Requests that couldn't be built are listed with their placeholders
+    let (method, url, headers, body) = match &entry.sent {
+        Some(sent) => (sent.method.clone(), sent.url.clone(), sent.headers.clone(), sent.body.clone()),
+        None => (entry.request.method.clone(), entry.request.url.clone(), Vec::new(), entry.request.body.clone()),
+    };
+
+    let query: Vec<Value> = url::split_url(&url)
+        .query
+        .map(url::parse_query)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(name, value)| json!({ "name": name, "value": value }))
+        .collect();
+
+    let mut request = json!({
+        "method": method,
+        "url": url,
+        "httpVersion": "HTTP/1.1",
+        "cookies": request_cookies(&headers),
+        "headers": header_list(&headers),
+        "queryString": query,
+        "headersSize": -1,
+        "bodySize": body.len(),
+    });
+
+    if !body.is_empty() {
+        request["postData"] = json!({
+            "mimeType": find_header(&headers, "Content-Type").unwrap_or_default(),
+            "text": body,
+        });
+    }
+
+    let (response, time, timings) = match &entry.response {
+        Some(r) => {
+            let wait = r.first_byte_ms.saturating_sub(r.connect_ms);
+            let receive = r.total_ms.saturating_sub(r.first_byte_ms);
+
+            let response = json!({
+                "status": r.status,
+                "statusText": r.reason,
+                "httpVersion": "HTTP/1.1",
+                "cookies": response_cookies(&r.headers),
+                "headers": header_list(&r.headers),
+                "content": {
+                    "size": r.body.len(),
+                    "mimeType": find_header(&r.headers, "Content-Type").unwrap_or_default(),
+                    "text": r.body,
+                },
+                "redirectURL": find_header(&r.headers, "Location").unwrap_or_default(),
+                "headersSize": -1,
+                "bodySize": r.body.len(),
+            });
+
+            let timings = json!({ "blocked": -1, "dns": -1, "ssl": -1, "connect": r.connect_ms, "send": 0, "wait": wait, "receive": receive });
+
+            (response, r.connect_ms + wait + receive, timings)
+        },
+        // A response is required, status 0 is what browsers record for failed requests
+        None => {
+            let response = json!({
+                "status": 0,
+                "statusText": "",
+                "httpVersion": "",
+                "cookies": [],
+                "headers": [],
+                "content": { "size": 0, "mimeType": "" },
+                "redirectURL": "",
+                "headersSize": -1,
+                "bodySize": -1,
+                "_error": entry.error.clone().unwrap_or_default(),
+            });
+
+            (response, 0, json!({ "send": 0, "wait": 0, "receive": 0 }))
+        },
+    };
+
+    json!({
+        "startedDateTime": format!("{}Z", history::format_time(entry.timestamp).replacen(' ', "T", 1)),
+        "time": time,
+        "request": request,
+        "response": response,
+        "cache": {},
+        "timings": timings,
+        "comment": entry.request.title,
+    })
+}
+
+/// HAR 1.2 log of the history entries, oldest first.
+///
+/// Entries are as recorded, so secrets are redacted and long bodies cut.
+pub fn export(entries: &[HistoryEntry]) -> Value {
+    json!({
+        "log": {
+            "version": "1.2",
+            "creator": { "name": "umbrella", "version": env!("CARGO_PKG_VERSION") },
+            "entries": entries.iter().map(export_entry).collect::<Vec<Value>>(),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::{ReceivedResponse, SentRequest};
+
+    fn recorded(method: &str, url: &str) -> Value {
+        json!({
+            "request": {
+                "method": method,
+                "url": url,
+                "headers": [
+                    { "name": ":authority", "value": "api.test" },
+                    { "name": "Host", "value": "api.test" },
+                    { "name": "Accept-Encoding", "value": "gzip, br" },
+                    { "name": "Accept", "value": "application/json" },
+                ],
+                "cookies": [{ "name": "session", "value": "s1" }, { "name": "theme", "value": "dark" }],
+                "postData": {
+                    "mimeType": "application/x-www-form-urlencoded",
+                    "params": [
+                        { "name": "q", "value": "a b" },
+                        { "name": "avatar", "fileName": "me.png" },
+                    ],
+                },
+            },
+            "response": { "status": 201 },
+        })
+    }
+
+    #[test]
+    fn entries_become_requests_without_client_headers() {
+        let entry = import_entry(&recorded("post", "https://User@API.test:8443/search?x=1#top")).unwrap();
+
+        assert_eq!(entry.host, "api.test:8443");
+        assert_eq!(entry.status, 201);
+        assert_eq!(entry.request.method, "POST");
+        assert_eq!(entry.request.title, "/search");
+        assert_eq!(entry.request.query_params.len(), 1);
+        assert_eq!(entry.request.body, "q=a%20b");
+        assert_eq!(entry.skipped, ["/search: file field avatar left out, bodies are text"]);
+
+        let headers: Vec<(&str, &str)> = entry.request.headers
+            .iter()
+            .map(|h| (h.name.as_str(), h.value.as_str()))
+            .collect();
+        assert_eq!(headers, [
+            ("Accept", "application/json"),
+            ("Cookie", "session=s1; theme=dark"),
+            ("Content-Type", "application/x-www-form-urlencoded"),
+        ]);
+
+        assert!(import_entry(&recorded("GET", "data:text/plain,hi")).is_none());
+    }
+
+    #[test]
+    fn filters_match_methods_and_parts_of_hosts() {
+        let entry = import_entry(&recorded("POST", "https://api.shop.test/")).unwrap();
+
+        assert!(HarFilter::default().matches(&entry));
+        assert!(HarFilter::parse("post shop").matches(&entry));
+        assert!(HarFilter::parse("get post").matches(&entry));
+        assert!(!HarFilter::parse("get").matches(&entry));
+        assert!(!HarFilter::parse("post cdn").matches(&entry));
+    }
+
+    fn entry(response: Option<ReceivedResponse>) -> HistoryEntry {
+        HistoryEntry {
+            request_id: String::from("1"),
+            timestamp: 0,
+            request: Request::new("POST", "Login", "https://{{host}}/login", "{{body}}"),
+            sent: Some(SentRequest {
+                method: String::from("POST"),
+                url: String::from("https://api.test/login?next=%2Fhome"),
+                headers: vec![
+                    (String::from("Content-Type"), String::from("application/json")),
+                    (String::from("Cookie"), String::from("a=1; b=2")),
+                ],
+                body: String::from("{\"user\": \"ann\"}"),
+            }),
+            error: response.is_none().then(|| String::from("Couldn't connect")),
+            response,
+        }
+    }
+
+    #[test]
+    fn history_is_exported_as_sent_and_imports_back() {
+        let response = ReceivedResponse {
+            status: 302,
+            reason: String::from("Found"),
+            headers: vec![
+                (String::from("Location"), String::from("/home")),
+                (String::from("Set-Cookie"), String::from("session=s2; Path=/; HttpOnly")),
+            ],
+            body: String::new(),
+            connect_ms: 10,
+            first_byte_ms: 40,
+            total_ms: 45,
+        };
+
+        let har = export(&[entry(Some(response)), entry(None)]);
+        let entries = har["log"]["entries"].as_array().unwrap();
+
+        let sent = &entries[0];
+        assert_eq!(sent["startedDateTime"], "1970-01-01T00:00:00Z");
+        assert_eq!(sent["time"], 45);
+        assert_eq!(sent["timings"]["wait"], 30);
+        assert_eq!(sent["request"]["queryString"], json!([{ "name": "next", "value": "/home" }]));
+        assert_eq!(sent["request"]["cookies"][1], json!({ "name": "b", "value": "2" }));
+        assert_eq!(sent["response"]["redirectURL"], "/home");
+        assert_eq!(sent["response"]["cookies"], json!([{ "name": "session", "value": "s2" }]));
+
+        let failed = &entries[1]["response"];
+        assert_eq!(failed["status"], 0);
+        assert_eq!(failed["_error"], "Couldn't connect");
+
+        let imported = import_entry(sent).unwrap();
+        assert_eq!(imported.request.url, "https://api.test/login?next=%2Fhome");
+        assert_eq!(imported.request.body, "{\"user\": \"ann\"}");
+        assert_eq!(imported.request.headers.len(), 2);
+        assert_eq!(imported.status, 302);
+    }
+}
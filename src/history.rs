@@ -0,0 +1,335 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::http::{HttpRequest, HttpResponse, Timings};
+use crate::model::Request;
+use crate::secrets::{self, SecretStore};
+use crate::vars;
+
+/// Oldest entries are dropped once there are more than this.
+const MAX_ENTRIES: usize = 500;
+
+/// Longer bodies are cut, so a few large downloads don't bloat the file.
+const MAX_BODY_LEN: usize = 256 * 1024;
+
+/// The file is written on every send, so oldest entries are also dropped
+/// once all bodies together take more than this.
+const MAX_TOTAL_BODY_LEN: usize = 8 * 1024 * 1024;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SentRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ReceivedResponse {
+    pub status: u16,
+    pub reason: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+    pub connect_ms: u64,
+    pub first_byte_ms: u64,
+    pub total_ms: u64,
+}
+
+impl ReceivedResponse {
+    /// Response with secret values replaced by their placeholders.
+    pub fn redacted(response: &HttpResponse, secrets: &SecretStore) -> Self {
+        Self {
+            status: response.status,
+            reason: response.reason.clone(),
+            headers: redact_headers(&response.headers, secrets),
+            body: cut(secrets.redact(&response.body)),
+            connect_ms: response.timings.connect.as_millis() as u64,
+            first_byte_ms: response.timings.first_byte.as_millis() as u64,
+            total_ms: response.timings.total.as_millis() as u64,
+        }
+    }
+}
+
+impl From<&ReceivedResponse> for HttpResponse {
+    fn from(v: &ReceivedResponse) -> Self {
+        HttpResponse {
+            status: v.status,
+            reason: v.reason.clone(),
+            headers: v.headers.clone(),
+            body: v.body.clone(),
+            timings: Timings {
+                connect: Duration::from_millis(v.connect_ms),
+                first_byte: Duration::from_millis(v.first_byte_ms),
+                total: Duration::from_millis(v.total_ms),
+            },
+        }
+    }
+}
+
+/// One execution of a request.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct HistoryEntry {
+    pub request_id: String,
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+    /// Request as edited, with placeholders, it's what gets restored
+    pub request: Request,
+    /// Request as sent, `None` if it couldn't be built
+    pub sent: Option<SentRequest>,
+    pub response: Option<ReceivedResponse>,
+    pub error: Option<String>,
+}
+
+impl HistoryEntry {
+    /// Entry with secret values replaced by their placeholders.
+    pub fn new(
+        request: &Request,
+        sent: Option<&HttpRequest>,
+        result: &Result<HttpResponse, String>,
+        secrets: &SecretStore,
+    ) -> Self {
+        Self {
+            request_id: request.id.clone(),
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            request: Request::from(request),
+            sent: sent.map(|r| SentRequest {
+                method: r.method.clone(),
+                url: secrets.redact(&r.url),
+                headers: redact_headers(&r.headers, secrets),
+                body: cut(secrets.redact(&r.body)),
+            }),
+            response: result.as_ref().ok().map(|r| ReceivedResponse::redacted(r, secrets)),
+            error: result.as_ref().err().map(|e| secrets.redact(e)),
+        }
+    }
+
+    /// One line summary for the history browser.
+    pub fn summary(&self) -> String {
+        let outcome = match (&self.response, &self.error) {
+            (Some(r), _) => format!("{} {} · {} ms · {} B", r.status, r.reason, r.total_ms, r.body.len()),
+            (None, Some(e)) => e.lines().next().unwrap_or_default().to_string(),
+            (None, None) => String::new(),
+        };
+
+        format!("{}  {:<7} {}", format_time(self.timestamp), self.request.method, outcome)
+    }
+
+    fn body_len(&self) -> usize {
+        self.sent.as_ref().map_or(0, |s| s.body.len()) + self.response.as_ref().map_or(0, |r| r.body.len())
+    }
+}
+
+/// Credentials derived from secrets, e.g. Basic auth, can't be redacted by value.
+///
+/// Only the scheme is kept unless the value is a placeholder already.
+fn hide_credentials(name: &str, value: &str) -> String {
+    let is_credential = name.eq_ignore_ascii_case("Authorization")
+        || name.eq_ignore_ascii_case("Proxy-Authorization");
+
+    if !is_credential || !vars::placeholders(value).is_empty() {
+        return value.to_string();
+    }
+
+    match value.split_once(' ') {
+        Some((scheme, _)) => format!("{} ••••", scheme),
+        None => String::from("••••"),
+    }
+}
+
+fn redact_headers(headers: &[(String, String)], secrets: &SecretStore) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(name, value)| (name.clone(), hide_credentials(name, &secrets.redact(value))))
+        .collect()
+}
+
+fn cut(mut body: String) -> String {
+    if body.len() > MAX_BODY_LEN {
+        let mut end = MAX_BODY_LEN;
+
+        while !body.is_char_boundary(end) {
+            end -= 1;
+        }
+
+        body.truncate(end);
+        body.push_str("\n… cut");
+    }
+
+    body
+}
+
+/// `YYYY-MM-DD HH:MM:SS` in UTC.
+pub fn format_time(timestamp: u64) -> String {
+    let days = (timestamp / 86400) as i64;
+    let secs = timestamp % 86400;
+
+    // Civil from days, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        year, month, day, secs / 3600, secs % 3600 / 60, secs % 60,
+    )
+}
+
+/// Executions of all requests, oldest first, kept next to the config.
+pub struct History {
+    path: PathBuf,
+    entries: Vec<HistoryEntry>,
+    /// Why the file couldn't be loaded, it's backed up before the first write.
+    pub load_error: Option<String>,
+    backed_up: bool,
+}
+
+impl History {
+    /// A missing file starts an empty history, so does one that can't be
+    /// read or parsed, but then the error is kept and the file isn't lost.
+    pub fn load(path: PathBuf) -> Self {
+        let loaded = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map_err(|e| format!("Couldn't parse {}: {}", path.display(), e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(format!("Couldn't read {}: {}", path.display(), e)),
+        };
+
+        let backup = secrets::backup_path(&path);
+        let (entries, load_error) = match loaded {
+            Ok(entries) => (entries, None),
+            Err(e) => (Vec::new(), Some(format!("{}, it's kept as {} once new entries are saved", e, backup.display()))),
+        };
+
+        Self { path, entries, load_error, backed_up: false }
+    }
+
+    /// Entries of the request, newest first.
+    pub fn for_request(&self, request_id: &str) -> Vec<&HistoryEntry> {
+        self.entries
+            .iter()
+            .rev()
+            .filter(|e| e.request_id == request_id)
+            .collect()
+    }
+
+    /// All entries, oldest first.
+    pub fn entries(&self) -> &[HistoryEntry] {
+        &self.entries
+    }
+
+    pub fn record(&mut self, entry: HistoryEntry) -> Result<(), String> {
+        self.entries.push(entry);
+
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.drain(..self.entries.len() - MAX_ENTRIES);
+        }
+
+        // Newest first, the entry just sent is always kept
+        let mut total = 0;
+        let kept = self.entries
+            .iter()
+            .rev()
+            .take_while(|e| {
+                total += e.body_len();
+                total <= MAX_TOTAL_BODY_LEN
+            })
+            .count()
+            .max(1);
+
+        self.entries.drain(..self.entries.len() - kept);
+
+        if self.load_error.is_some() && !self.backed_up {
+            fs::copy(&self.path, secrets::backup_path(&self.path))
+                .map_err(|e| format!("Couldn't back up history before writing it: {}", e))?;
+            self.backed_up = true;
+        }
+
+        let content = serde_json::to_string(&self.entries).map_err(|e| e.to_string())?;
+        // Bodies are kept as sent, they may hold credentials
+        secrets::write_private(&self.path, content.as_bytes()).map_err(|e| format!("Couldn't write history: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry() -> HistoryEntry {
+        HistoryEntry {
+            request_id: "r1".to_string(),
+            timestamp: 0,
+            request: Request::new("GET", "Ping", "http://localhost/", ""),
+            sent: None,
+            response: None,
+            error: Some("Connection refused".to_string()),
+        }
+    }
+
+    #[test]
+    fn corrupt_files_are_backed_up_before_writing() {
+        let path = std::env::temp_dir().join(format!("umbrella-history-{}.json", std::process::id()));
+        fs::write(&path, "[{\"broken\"").unwrap();
+
+        let mut history = History::load(path.clone());
+        assert!(history.entries().is_empty());
+        assert!(history.load_error.as_deref().unwrap_or_default().starts_with("Couldn't parse"));
+
+        history.record(entry()).unwrap();
+        let backup = fs::read_to_string(secrets::backup_path(&path)).unwrap();
+        let reloaded = History::load(path.clone());
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(secrets::backup_path(&path)).unwrap();
+
+        assert_eq!(backup, "[{\"broken\"");
+        assert!(reloaded.load_error.is_none());
+        assert_eq!(reloaded.entries().len(), 1);
+    }
+
+    #[test]
+    fn oldest_entries_go_once_bodies_get_too_large() {
+        let path = std::env::temp_dir().join(format!("umbrella-history-large-{}.json", std::process::id()));
+        let mut history = History::load(path.clone());
+
+        for i in 0..10 {
+            history.record(HistoryEntry {
+                timestamp: i,
+                response: Some(ReceivedResponse {
+                    status: 200,
+                    reason: String::from("OK"),
+                    headers: Vec::new(),
+                    body: "x".repeat(MAX_TOTAL_BODY_LEN / 8),
+                    connect_ms: 0,
+                    first_byte_ms: 0,
+                    total_ms: 0,
+                }),
+                ..entry()
+            }).unwrap();
+        }
+
+        let kept: Vec<u64> = history.entries().iter().map(|e| e.timestamp).collect();
+        let size = fs::metadata(&path).unwrap().len() as usize;
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(kept, (2..10).collect::<Vec<u64>>());
+        assert!(size < MAX_TOTAL_BODY_LEN + 64 * 1024);
+    }
+
+    #[test]
+    fn missing_files_start_empty() {
+        let history = History::load(std::env::temp_dir().join("umbrella-history-missing.json"));
+
+        assert!(history.entries().is_empty());
+        assert!(history.load_error.is_none());
+    }
+}
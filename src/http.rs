@@ -0,0 +1,414 @@
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::{Duration, Instant};
+
+use crate::tls::{self, TlsOptions};
+
+pub const METHODS: [&str; 8] = [
+    "GET", "POST", "PUT", "PATCH", "DELETE", "HEAD", "OPTIONS", "TRACE",
+];
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Largest body read, whatever length the peer announces.
+const MAX_BODY: usize = 64 * 1024 * 1024;
+
+pub enum BodyUsage {
+    Allowed,
+    /// Body is sent, but has no defined semantics for the method
+    Discouraged,
+    /// Body is never sent with the method
+    Forbidden,
+}
+
+/// `token` as defined by RFC 9110, what methods and header names are made of.
+fn is_token(text: &str) -> bool {
+    !text.is_empty() && text.chars().all(|c| {
+        c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c)
+    })
+}
+
+/// Method must be a `token` as defined by RFC 9110.
+pub fn is_valid_method(method: &str) -> bool {
+    is_token(method)
+}
+
+/// Refuses what would end the request line or a header early, values come
+/// from captures, scripts and imported files.
+pub fn check_head(request: &HttpRequest) -> Result<(), String> {
+    if !is_valid_method(&request.method) {
+        return Err(format!("Invalid method {:?}", request.method));
+    }
+
+    if request.url.contains(|c: char| c.is_control() || c == ' ') {
+        return Err(format!("URL {:?} has spaces or control characters, encode them", request.url));
+    }
+
+    for (name, value) in request.headers.iter() {
+        if !is_token(name) {
+            return Err(format!("Invalid header name {:?}", name));
+        }
+
+        if value.contains(['\r', '\n', '\0']) {
+            return Err(format!("Header {} has a line break or NUL in its value", name));
+        }
+    }
+
+    Ok(())
+}
+
+pub fn body_usage(method: &str) -> BodyUsage {
+    match method.to_uppercase().as_str() {
+        "HEAD" | "TRACE" => BodyUsage::Forbidden,
+        "GET" | "DELETE" | "OPTIONS" => BodyUsage::Discouraged,
+        _ => BodyUsage::Allowed,
+    }
+}
+
+#[derive(Clone)]
+pub struct HttpRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+pub struct Timings {
+    pub connect: Duration,
+    pub first_byte: Duration,
+    pub total: Duration,
+}
+
+pub struct HttpResponse {
+    pub status: u16,
+    pub reason: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+    pub timings: Timings,
+}
+
+impl HttpResponse {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+struct Target {
+    tls: bool,
+    host: String,
+    port: u16,
+    path: String,
+}
+
+/// Plain or TLS connection.
+trait Connection: Read + Write {}
+
+impl<T: Read + Write> Connection for T {}
+
+fn parse_target(url: &str) -> Result<Target, String> {
+    let (scheme, rest) = url
+        .split_once("://")
+        .ok_or_else(|| format!("URL has no scheme: {}", url))?;
+
+    let (tls, default_port) = match scheme.to_lowercase().as_str() {
+        "http" => (false, 80),
+        "https" => (true, 443),
+        other => return Err(format!("Unsupported scheme: {}", other)),
+    };
+
+    let (authority, path) = match rest.find(['/', '?', '#']) {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, "/"),
+    };
+
+    // Fragments are never sent to the server
+    let path = path.split('#').next().unwrap_or_default();
+    let path = if path.starts_with('/') {
+        path.to_string()
+    } else {
+        format!("/{}", path)
+    };
+
+    let authority = authority.rsplit('@').next().unwrap_or_default();
+
+    let (host, port) = match authority.rsplit_once(':') {
+        Some((host, port)) if !port.contains(']') => (
+            host,
+            port.parse::<u16>().map_err(|_| format!("Invalid port: {}", port))?,
+        ),
+        _ => (authority, default_port),
+    };
+
+    if host.is_empty() {
+        return Err(format!("URL has no host: {}", url));
+    }
+
+    Ok(Target {
+        tls,
+        host: host.trim_start_matches('[').trim_end_matches(']').to_string(),
+        port,
+        path,
+    })
+}
+
+/// Reads the start line and headers of an HTTP message.
+pub fn read_head<R: BufRead>(reader: &mut R) -> Result<(String, Vec<(String, String)>), String> {
+    let mut start_line = String::new();
+    reader.read_line(&mut start_line).map_err(|e| e.to_string())?;
+
+    if start_line.is_empty() {
+        return Err(String::from("Connection closed before the message was received"));
+    }
+
+    let mut headers = Vec::new();
+
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).map_err(|e| e.to_string())?;
+        let line = line.trim_end();
+
+        if line.is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+
+    Ok((start_line.trim_end().to_string(), headers))
+}
+
+/// Appends `len` more bytes to the body, refusing to grow it past `MAX_BODY`.
+fn read_more<R: BufRead>(reader: &mut R, body: &mut Vec<u8>, len: usize) -> Result<(), String> {
+    let total = body.len().checked_add(len).filter(|&t| t <= MAX_BODY)
+        .ok_or_else(|| format!("Body is larger than {} bytes", MAX_BODY))?;
+
+    // Read as it arrives rather than allocated upfront from the announced length
+    reader.take(len as u64).read_to_end(body).map_err(|e| e.to_string())?;
+
+    if body.len() < total {
+        return Err(String::from("Connection closed before the body was received"));
+    }
+
+    Ok(())
+}
+
+/// Reads a message body framed by `Content-Length`, chunked encoding or connection close.
+pub fn read_body<R: BufRead>(
+    reader: &mut R,
+    headers: &[(String, String)],
+    until_close: bool,
+) -> Result<Vec<u8>, String> {
+    let header = |name: &str| headers
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, v)| v.as_str());
+
+    let mut body = Vec::new();
+
+    if header("Transfer-Encoding").is_some_and(|v| v.to_lowercase().contains("chunked")) {
+        loop {
+            let mut size_line = String::new();
+            reader.read_line(&mut size_line).map_err(|e| e.to_string())?;
+
+            let size_hex = size_line.split(';').next().unwrap_or_default().trim();
+            let size = usize::from_str_radix(size_hex, 16)
+                .map_err(|_| format!("Invalid chunk size: {}", size_hex))?;
+
+            if size == 0 {
+                // Skip trailers up to the final empty line
+                read_head(reader).ok();
+                break;
+            }
+
+            read_more(reader, &mut body, size)?;
+
+            let mut crlf = [0; 2];
+            reader.read_exact(&mut crlf).map_err(|e| e.to_string())?;
+        }
+    } else if let Some(len) = header("Content-Length") {
+        let len: usize = len.parse().map_err(|_| format!("Invalid Content-Length: {}", len))?;
+        read_more(reader, &mut body, len)?;
+    } else if until_close {
+        match reader.take(MAX_BODY as u64 + 1).read_to_end(&mut body) {
+            // Plenty of TLS servers close the connection without a close_notify
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => {},
+            result => result.map(|_| ()).map_err(|e| e.to_string())?,
+        }
+
+        if body.len() > MAX_BODY {
+            return Err(format!("Body is larger than {} bytes", MAX_BODY));
+        }
+    }
+
+    Ok(body)
+}
+
+/// Sends the request over a new connection, HTTPS ones are made with the TLS options.
+pub fn send(request: &HttpRequest, tls: &TlsOptions) -> Result<HttpResponse, String> {
+    check_head(request)?;
+
+    let target = parse_target(&request.url)?;
+    let started = Instant::now();
+
+    let addr = (target.host.as_str(), target.port)
+        .to_socket_addrs()
+        .map_err(|e| format!("Couldn't resolve {}: {}", target.host, e))?
+        .next()
+        .ok_or_else(|| format!("Couldn't resolve {}", target.host))?;
+
+    let tcp = TcpStream::connect_timeout(&addr, TIMEOUT)
+        .map_err(|e| format!("Couldn't connect to {}: {}", addr, e))?;
+
+    tcp.set_read_timeout(Some(TIMEOUT)).map_err(|e| e.to_string())?;
+    tcp.set_write_timeout(Some(TIMEOUT)).map_err(|e| e.to_string())?;
+
+    // The handshake counts as connecting
+    let mut stream: Box<dyn Connection> = match target.tls {
+        true => Box::new(tls::connect(tcp, &target.host, tls)?),
+        false => Box::new(tcp),
+    };
+    let connect = started.elapsed();
+
+    let default_port = if target.tls { 443 } else { 80 };
+
+    let host_header = if target.port == default_port {
+        target.host.clone()
+    } else {
+        format!("{}:{}", target.host, target.port)
+    };
+
+    let mut head = format!("{} {} HTTP/1.1\r\n", request.method, target.path);
+    let has_header = |name: &str| request.headers
+        .iter()
+        .any(|(n, _)| n.eq_ignore_ascii_case(name));
+
+    if !has_header("Host") {
+        head.push_str(&format!("Host: {}\r\n", host_header));
+    }
+
+    if !has_header("User-Agent") {
+        head.push_str("User-Agent: umbrella\r\n");
+    }
+
+    for (name, value) in request.headers.iter() {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+
+    if !request.body.is_empty() || matches!(body_usage(&request.method), BodyUsage::Allowed) {
+        head.push_str(&format!("Content-Length: {}\r\n", request.body.len()));
+    }
+
+    head.push_str("Connection: close\r\n\r\n");
+
+    stream.write_all(head.as_bytes()).map_err(|e| e.to_string())?;
+    stream.write_all(request.body.as_bytes()).map_err(|e| e.to_string())?;
+
+    let mut reader = BufReader::new(stream);
+    let (status_line, headers) = read_head(&mut reader)
+        .map_err(|e| if target.tls { tls::explain(e) } else { e })?;
+    let first_byte = started.elapsed();
+
+    let mut parts = status_line.splitn(3, ' ');
+    let _version = parts.next();
+    let status: u16 = parts
+        .next()
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| format!("Malformed status line: {}", status_line))?;
+    let reason = parts.next().unwrap_or_default().to_string();
+
+    let body = if request.method.eq_ignore_ascii_case("HEAD") || status == 204 || status == 304 {
+        Vec::new()
+    } else {
+        read_body(&mut reader, &headers, true)?
+    };
+
+    Ok(HttpResponse {
+        status,
+        reason,
+        headers,
+        body: String::from_utf8_lossy(&body).to_string(),
+        timings: Timings {
+            connect,
+            first_byte,
+            total: started.elapsed(),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(name: &str, value: &str) -> Vec<(String, String)> {
+        vec![(name.to_string(), value.to_string())]
+    }
+
+    #[test]
+    fn reads_chunked_bodies() {
+        let mut reader = "4\r\nWiki\r\n5;ext=1\r\npedia\r\n0\r\n\r\n".as_bytes();
+        let body = read_body(&mut reader, &headers("Transfer-Encoding", "chunked"), false).unwrap();
+
+        assert_eq!(body, b"Wikipedia");
+    }
+
+    #[test]
+    fn reads_content_length_bodies() {
+        let mut reader = "hello, and more".as_bytes();
+        let body = read_body(&mut reader, &headers("Content-Length", "5"), false).unwrap();
+
+        assert_eq!(body, b"hello");
+    }
+
+    #[test]
+    fn huge_lengths_are_refused_before_reading() {
+        let mut reader = "short".as_bytes();
+        let error = read_body(&mut reader, &headers("Content-Length", &usize::MAX.to_string()), false).unwrap_err();
+        assert!(error.contains("larger than"), "{}", error);
+
+        let mut reader = "ffffffffffffffff\r\nshort".as_bytes();
+        let error = read_body(&mut reader, &headers("Transfer-Encoding", "chunked"), false).unwrap_err();
+        assert!(error.contains("larger than"), "{}", error);
+    }
+
+    #[test]
+    fn short_bodies_are_an_error() {
+        let mut reader = "short".as_bytes();
+        let error = read_body(&mut reader, &headers("Content-Length", "1000"), false).unwrap_err();
+
+        assert!(error.contains("closed before"), "{}", error);
+    }
+
+    fn request(method: &str, url: &str, header: (&str, &str)) -> HttpRequest {
+        HttpRequest {
+            method: method.to_string(),
+            url: url.to_string(),
+            headers: headers(header.0, header.1),
+            body: String::new(),
+        }
+    }
+
+    #[test]
+    fn line_breaks_never_make_it_into_the_head() {
+        let refused = [
+            request("GET", "http://localhost/", ("X-Id", "1\r\nX-Injected: yes")),
+            request("GET", "http://localhost/", ("X-Id", "1\nX-Injected: yes")),
+            request("GET", "http://localhost/", ("X-Id", "1\0")),
+            request("GET", "http://localhost/", ("X-Id\r\nX-Injected", "yes")),
+            request("GET", "http://localhost/", ("X Id", "1")),
+            request("GET / HTTP/1.1\r\nX: y", "http://localhost/", ("X-Id", "1")),
+            request("GET", "http://localhost/ HTTP/1.1\r\nX: y", ("X-Id", "1")),
+        ];
+
+        for request in refused {
+            let error = send(&request, &TlsOptions::default()).err().unwrap();
+            assert!(!error.contains("connect"), "{}", error);
+        }
+
+        assert!(check_head(&request("PATCH", "http://localhost/?q=a%20b", ("X-Id", "a: b; c=\"d\""))).is_ok());
+    }
+}
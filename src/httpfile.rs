@@ -0,0 +1,664 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use serde_json::{Map, Value};
+
+use crate::http;
+use crate::import::{self, Imported};
+use crate::model::{new_request_id, Environment, Header, Request};
+use crate::{url, vars};
+
+/// Environments of the `.http` files of a directory, as JetBrains names it:
+/// an object of environment names to objects of variables.
+const ENVIRONMENTS_FILE: &str = "http-client.env.json";
+
+/// Files opened as a collection, as VS Code REST Client and JetBrains name them.
+pub fn is_http_file(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e.eq_ignore_ascii_case("http") || e.eq_ignore_ascii_case("rest"))
+}
+
+fn is_separator(line: &str) -> bool {
+    line.trim_start().starts_with("###")
+}
+
+fn is_comment(line: &str) -> bool {
+    let line = line.trim_start();
+    line.starts_with('#') || line.starts_with("//")
+}
+
+/// `@name = value` declaration.
+fn variable(line: &str) -> Option<(String, String)> {
+    let (name, value) = line.trim().strip_prefix('@')?.split_once('=')?;
+    let name = name.trim();
+
+    (!name.is_empty() && !name.contains(char::is_whitespace)).then(|| (name.to_string(), value.trim().to_string()))
+}
+
+/// `# @key value` comment line, like `# @name login` that names a request in REST Client.
+fn metadata(line: &str, key: &str) -> Option<String> {
+    let line = line.trim_start();
+    let comment = line.strip_prefix('#').or(line.strip_prefix("//"))?;
+    let value = comment.trim().strip_prefix(key)?;
+
+    // `@name` isn't `@names`
+    if !value.is_empty() && !value.starts_with(char::is_whitespace) {
+        return None;
+    }
+
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+fn request_name(line: &str) -> Option<String> {
+    metadata(line, "@name")
+}
+
+/// `# @id 3f2a…` keeps the id of a request, so its history stays with it
+/// when requests are moved, removed or added.
+fn request_id(line: &str) -> Option<String> {
+    metadata(line, "@id")
+}
+
+/// Title of a request nothing names.
+fn default_title(url: &str) -> String {
+    url::split_url(url).base.to_string()
+}
+
+/// A request of the file with the text it was read from.
+#[derive(Clone)]
+struct Block {
+    /// Text as it is in the file, written back as long as the request isn't edited
+    raw: String,
+    /// `###` line, none for a request at the top of the file
+    separator: Option<String>,
+    /// Comments, variables and scripts above the request line
+    preamble: Vec<String>,
+    /// Id of the `# @id` line
+    id: Option<String>,
+    version: Option<String>,
+    /// Response handlers, redirects and blank lines below the body
+    tail: Vec<String>,
+    title: String,
+    method: String,
+    url: String,
+    headers: Vec<Header>,
+    body: String,
+}
+
+impl Block {
+    /// Whether writing the request would give the same request back.
+    fn matches(&self, request: &Request) -> bool {
+        let same_headers = self.headers.len() == request.headers.len()
+            && self.headers.iter().zip(request.headers.iter()).all(|(a, b)| {
+                a.name == b.name && a.value == b.value && a.enabled == b.enabled
+            });
+
+        self.title == request.title
+            && self.method == request.method
+            && self.url == request.url
+            && self.body == request.body
+            && same_headers
+    }
+
+    /// Text of the block with `id` on its `# @id` line, added above the request line.
+    fn with_id(&self, id: &str, newline: &str) -> String {
+        let request_line = usize::from(self.separator.is_some()) + self.preamble.len();
+        let mut text = String::with_capacity(self.raw.len() + id.len() + 8);
+
+        for (i, line) in self.raw.split_inclusive('\n').enumerate() {
+            if i == request_line {
+                text.push_str(&format!("# @id {}{}", id, newline));
+            }
+
+            if i >= request_line || request_id(line).is_none() {
+                text.push_str(line);
+            }
+        }
+
+        text
+    }
+
+    /// Request text with the comments, scripts and handlers of the block kept.
+    fn render(&self, request: &Request, newline: &str, first: bool) -> String {
+        let mut lines = Vec::new();
+        let titled = request.title != default_title(&request.url);
+
+        match &self.separator {
+            Some(separator) if self.title == request.title => lines.push(separator.clone()),
+            _ if titled || !first => lines.push(format!("### {}", request.title)),
+            _ => {},
+        }
+
+        lines.extend(self.preamble.iter().cloned());
+
+        match &self.version {
+            Some(version) => lines.push(format!("{} {} {}", request.method, request.url, version)),
+            None => lines.push(format!("{} {}", request.method, request.url)),
+        }
+
+        for header in request.headers.iter().filter(|h| !h.name.trim().is_empty()) {
+            let prefix = if header.enabled { "" } else { "# " };
+            lines.push(format!("{}{}: {}", prefix, header.name.trim(), header.value));
+        }
+
+        if !request.body.is_empty() {
+            lines.push(String::new());
+            lines.extend(request.body.lines().map(String::from));
+        }
+
+        if self.tail.is_empty() {
+            lines.push(String::new());
+        } else {
+            lines.extend(self.tail.iter().cloned());
+        }
+
+        lines.iter().map(|l| format!("{}{}", l, newline)).collect()
+    }
+}
+
+/// Reads a request out of the text between two separators, none when it only has comments.
+fn parse_block(raw: &str, variables: &mut Vec<(String, String)>) -> Option<Block> {
+    let lines: Vec<&str> = raw.lines().collect();
+    let mut i = 0;
+
+    let separator = lines.first().filter(|l| is_separator(l)).map(|l| l.to_string());
+
+    if separator.is_some() {
+        i += 1;
+    }
+
+    let mut preamble = Vec::new();
+    let mut name = None;
+    let mut id = None;
+    let mut in_script = false;
+
+    // Comments, variables and JetBrains pre-request scripts come before the request line
+    while let Some(line) = lines.get(i) {
+        let trimmed = line.trim();
+
+        if in_script || trimmed.starts_with("< {%") {
+            in_script = !trimmed.ends_with("%}");
+        } else if let Some(declared) = variable(line) {
+            variables.push(declared);
+        } else if let Some(declared) = request_name(line) {
+            name = Some(declared);
+        } else if let Some(declared) = request_id(line) {
+            id = Some(declared);
+        } else if !trimmed.is_empty() && !is_comment(line) {
+            break;
+        }
+
+        preamble.push(line.to_string());
+        i += 1;
+    }
+
+    let request_line = lines.get(i)?.trim();
+    i += 1;
+
+    let mut parts: Vec<&str> = request_line.split_whitespace().collect();
+
+    let version = parts.last().filter(|p| p.starts_with("HTTP/")).map(|p| p.to_string());
+
+    if version.is_some() {
+        parts.pop();
+    }
+
+    let (method, mut url) = match parts.as_slice() {
+        [method, url @ ..] if http::is_valid_method(method) && !url.is_empty() => (method.to_uppercase(), url.join(" ")),
+        // A bare URL is a GET
+        _ => (String::from("GET"), parts.join(" ")),
+    };
+
+    // Long queries continue on indented `?` and `&` lines
+    while let Some(line) = lines.get(i).filter(|l| l.starts_with(char::is_whitespace)) {
+        let trimmed = line.trim();
+
+        if !trimmed.starts_with('?') && !trimmed.starts_with('&') {
+            break;
+        }
+
+        url.push_str(trimmed);
+        i += 1;
+    }
+
+    let mut headers = Vec::new();
+
+    while let Some(line) = lines.get(i).filter(|l| !l.trim().is_empty()) {
+        let (text, enabled) = match line.trim_start().strip_prefix('#') {
+            Some(rest) => (rest.trim_start(), false),
+            None => (line.trim_start(), true),
+        };
+
+        if let Some((name, value)) = text.split_once(':') {
+            headers.push(Header { name: name.trim().to_string(), value: value.trim().to_string(), enabled });
+        }
+
+        i += 1;
+    }
+
+    let rest = &lines[i.min(lines.len())..];
+
+    // Handlers and redirects end the body, so do the blank lines before the next request
+    let handler = rest
+        .iter()
+        .position(|l| l.starts_with("> ") || l.starts_with(">> ") || l.starts_with("<> "))
+        .unwrap_or(rest.len());
+
+    let body_end = rest[..handler].iter().rposition(|l| !l.trim().is_empty()).map_or(0, |p| p + 1);
+    let body_start = rest[..body_end].iter().position(|l| !l.trim().is_empty()).unwrap_or(body_end);
+
+    let title = separator
+        .as_deref()
+        .map(|s| s.trim_start().trim_start_matches('#').trim().to_string())
+        .filter(|t| !t.is_empty())
+        .or(name)
+        .unwrap_or(default_title(&url));
+
+    Some(Block {
+        raw: raw.to_string(),
+        separator,
+        preamble,
+        id,
+        version,
+        tail: rest[body_end..].iter().map(|l| l.to_string()).collect(),
+        title,
+        method,
+        url,
+        headers,
+        body: rest[body_start..body_end].join("\n"),
+    })
+}
+
+/// Text of the file cut at the `###` separators, which start each piece.
+fn split_blocks(content: &str) -> Vec<String> {
+    let mut blocks = vec![String::new()];
+
+    for line in content.split_inclusive('\n') {
+        if is_separator(line) {
+            blocks.push(String::new());
+        }
+
+        if let Some(block) = blocks.last_mut() {
+            block.push_str(line);
+        }
+    }
+
+    blocks
+}
+
+/// A `.http` file opened as the collection, edits are written back into it.
+///
+/// Requests that weren't edited are written back exactly as they were read,
+/// edited ones keep their comments, variables and response handlers. Auth,
+/// tests, captures and scripts have no place in the file and aren't kept.
+pub struct HttpFile {
+    pub path: PathBuf,
+    newline: &'static str,
+    /// Text above the first request, e.g. file variables
+    header: String,
+    /// Blocks as last read or written, by the id of their request
+    blocks: Mutex<Vec<(String, Block)>>,
+    /// `http-client.env.json` next to the file
+    environments_path: PathBuf,
+}
+
+fn read_environments(path: &Path) -> Result<Vec<Environment>, String> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(path).map_err(|e| format!("Couldn't read {}: {}", path.display(), e))?;
+    let value: Value = serde_json::from_str(&content).map_err(|e| format!("Couldn't parse {}: {}", path.display(), e))?;
+
+    let environments = value
+        .as_object()
+        .ok_or(format!("{} should map environment names to their variables", path.display()))?
+        .iter()
+        .map(|(name, variables)| Environment {
+            name: name.clone(),
+            variables: variables
+                .as_object()
+                .into_iter()
+                .flatten()
+                .map(|(name, value)| (name.clone(), import::text(Some(value))))
+                .collect(),
+        })
+        .collect();
+
+    Ok(environments)
+}
+
+impl HttpFile {
+    /// Requests of the file and its variables, references between them resolved.
+    pub fn open(path: &Path) -> Result<(Self, Imported), String> {
+        let content = fs::read_to_string(path).map_err(|e| format!("Couldn't read {}: {}", path.display(), e))?;
+        let file_name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+
+        let mut declared = Vec::new();
+        let mut header = String::new();
+        let mut blocks = Vec::new();
+        let mut requests = Vec::new();
+        let mut used = Vec::new();
+        let written_ids: Vec<String> = content.lines().filter_map(request_id).collect();
+
+        for (i, raw) in split_blocks(&content).into_iter().enumerate() {
+            match parse_block(&raw, &mut declared) {
+                Some(block) => {
+                    // Files written before ids were kept use the position, which saving writes down
+                    let position = format!("{}:{}", file_name, requests.len() + 1);
+
+                    let id = match &block.id {
+                        Some(id) if !used.contains(id) => id.clone(),
+                        _ if !used.contains(&position) && !written_ids.contains(&position) => position,
+                        _ => new_request_id(),
+                    };
+
+                    used.push(id.clone());
+
+                    requests.push(Request {
+                        id: id.clone(),
+                        method: block.method.clone(),
+                        title: block.title.clone(),
+                        url: block.url.clone(),
+                        body: block.body.clone(),
+                        query_params: url::merge_query(&block.url, &[]),
+                        path_params: url::merge_path_params(&block.url, &[]),
+                        headers: block.headers.clone(),
+                        ..Request::new_default()
+                    });
+
+                    blocks.push((id, block));
+                },
+                None if i == 0 => header = raw,
+                // Comments between requests stay with the request above
+                None => match blocks.last_mut() {
+                    Some((_, block)) => {
+                        block.raw.push_str(&raw);
+                        block.tail.extend(raw.lines().map(String::from));
+                    },
+                    None => header.push_str(&raw),
+                },
+            }
+        }
+
+        let mut variables = BTreeMap::new();
+
+        for (name, value) in declared {
+            let lookup = |name: &str| Ok(variables.get(name).cloned().unwrap_or(format!("{{{{{}}}}}", name)));
+            let value = vars::substitute(&value, &lookup).unwrap_or(value);
+            variables.insert(name, value);
+        }
+
+        let environments_path = path.with_file_name(ENVIRONMENTS_FILE);
+        let environments = read_environments(&environments_path)?;
+
+        let file = Self {
+            path: path.to_path_buf(),
+            newline: if content.contains("\r\n") { "\r\n" } else { "\n" },
+            header,
+            blocks: Mutex::new(blocks),
+            environments_path,
+        };
+
+        Ok((file, Imported { requests, variables, environments, ..Imported::default() }))
+    }
+
+    /// Writes the requests in their order, untouched ones as they were.
+    pub fn save(&self, requests: &[Arc<Request>]) -> Result<(), String> {
+        let mut blocks = self.blocks.lock().unwrap();
+        let mut text = self.header.clone();
+        let mut written = Vec::new();
+
+        for (i, request) in requests.iter().enumerate() {
+            let first = i == 0 && self.header.is_empty();
+            let block = blocks.iter().find(|(id, _)| id == &request.id).map(|(_, b)| b);
+
+            let raw = match block {
+                Some(block) if block.matches(request) && (first || block.separator.is_some()) => block.raw.clone(),
+                Some(block) => block.render(request, self.newline, first),
+                None => {
+                    let block = Block {
+                        raw: String::new(),
+                        separator: None,
+                        preamble: Vec::new(),
+                        id: None,
+                        version: None,
+                        tail: Vec::new(),
+                        title: String::new(),
+                        method: String::new(),
+                        url: String::new(),
+                        headers: Vec::new(),
+                        body: String::new(),
+                    };
+
+                    block.render(request, self.newline, first)
+                },
+            };
+
+            // Ids are written down, so history stays with the request wherever it's moved
+            let raw = match parse_block(&raw, &mut Vec::new()) {
+                Some(block) if block.id.as_ref() != Some(&request.id) => block.with_id(&request.id, self.newline),
+                _ => raw,
+            };
+
+            if !text.is_empty() && !text.ends_with('\n') {
+                text.push_str(self.newline);
+            }
+
+            if let Some(block) = parse_block(&raw, &mut Vec::new()) {
+                written.push((request.id.clone(), block));
+            }
+
+            text.push_str(&raw);
+        }
+
+        fs::write(&self.path, text).map_err(|e| format!("Couldn't write {}: {}", self.path.display(), e))?;
+        *blocks = written;
+        Ok(())
+    }
+
+    /// Writes the environments into `http-client.env.json`, which isn't
+    /// created for a collection without any.
+    pub fn save_environments(&self, environments: &[Environment]) -> Result<(), String> {
+        if environments.is_empty() && !self.environments_path.exists() {
+            return Ok(());
+        }
+
+        let value: Map<String, Value> = environments
+            .iter()
+            .map(|e| (e.name.clone(), serde_json::to_value(&e.variables).unwrap_or_default()))
+            .collect();
+
+        let content = serde_json::to_string_pretty(&value).map_err(|e| e.to_string())?;
+
+        if fs::read_to_string(&self.environments_path).is_ok_and(|c| c.trim_end() == content) {
+            return Ok(());
+        }
+
+        fs::write(&self.environments_path, format!("{}\n", content))
+            .map_err(|e| format!("Couldn't write {}: {}", self.environments_path.display(), e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FILE: &str = "\
+@host = api.test
+@base = https://{{host}}/v1
+
+### List pets
+GET {{base}}/pets HTTP/1.1
+    ?limit=10
+    &kind=cat
+Accept: application/json
+# X-Debug: 1
+
+###
+# Creates one
+# @name create
+POST {{base}}/pets
+Content-Type: application/json
+
+{
+  \"name\": \"Tom\"
+}
+
+> {% client.global.set(\"id\", response.body.id); %}
+
+// Trailing comment
+";
+
+    fn temp_file(name: &str, content: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("umbrella-httpfile-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let path = dir.join("api.http");
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn remove(path: &Path) {
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    fn open(path: &Path) -> (HttpFile, Vec<Arc<Request>>) {
+        let (file, imported) = HttpFile::open(path).unwrap();
+        (file, imported.requests.into_iter().map(Arc::new).collect())
+    }
+
+    #[test]
+    fn requests_variables_and_headers_are_read() {
+        let path = temp_file("read", FILE);
+        let (_, imported) = HttpFile::open(&path).unwrap();
+        remove(&path);
+
+        assert_eq!(imported.variables["base"], "https://api.test/v1");
+
+        let [list, create] = imported.requests.as_slice() else {
+            panic!("expected two requests");
+        };
+
+        assert_eq!(list.title, "List pets");
+        assert_eq!(list.url, "{{base}}/pets?limit=10&kind=cat");
+        assert_eq!(list.query_params.len(), 2);
+
+        let headers: Vec<(&str, bool)> = list.headers.iter().map(|h| (h.name.as_str(), h.enabled)).collect();
+        assert_eq!(headers, [("Accept", true), ("X-Debug", false)]);
+
+        assert_eq!(create.title, "create");
+        assert_eq!(create.method, "POST");
+        assert_eq!(create.body, "{\n  \"name\": \"Tom\"\n}");
+    }
+
+    #[test]
+    fn untouched_requests_are_written_back_with_their_ids() {
+        let with_ids = FILE
+            .replace("### List pets\n", "### List pets\n# @id api.http:1\n")
+            .replace("# @name create\n", "# @name create\n# @id api.http:2\n");
+
+        for (name, newline) in [("lf", "\n"), ("crlf", "\r\n")] {
+            let path = temp_file(name, &FILE.replace('\n', newline));
+            let (file, requests) = open(&path);
+
+            file.save(&requests).unwrap();
+            assert_eq!(fs::read_to_string(&path).unwrap(), with_ids.replace('\n', newline));
+
+            // Files with ids are written back as they were
+            let (file, requests) = open(&path);
+            file.save(&requests).unwrap();
+            assert_eq!(fs::read_to_string(&path).unwrap(), with_ids.replace('\n', newline));
+            remove(&path);
+        }
+    }
+
+    #[test]
+    fn ids_stay_with_their_requests() {
+        let path = temp_file("ids", FILE);
+        let (file, requests) = open(&path);
+        let (list, create) = (requests[0].id.clone(), requests[1].id.clone());
+
+        let mut added = Request::new_default();
+        added.id = new_request_id();
+        added.url = String::from("{{base}}/toys");
+
+        // The first one removed, a new one in its place, the others moved
+        file.save(&[Arc::new(added.clone()), requests[1].clone()]).unwrap();
+        let (file, reopened) = open(&path);
+        let ids: Vec<&str> = reopened.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, [added.id.as_str(), create.as_str()]);
+
+        file.save(&[reopened[1].clone(), reopened[0].clone(), requests[0].clone()]).unwrap();
+        let (_, reopened) = open(&path);
+        let ids: Vec<&str> = reopened.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, [create.as_str(), added.id.as_str(), list.as_str()]);
+
+        // A copied block gets an id of its own
+        let copied = fs::read_to_string(&path).unwrap() + "\n### Copy\n# @id api.http:2\nGET /\n";
+        fs::write(&path, copied).unwrap();
+        let (_, reopened) = open(&path);
+        remove(&path);
+        assert_eq!(reopened[0].id, create);
+        assert_eq!(reopened[3].id, "api.http:4");
+    }
+
+    #[test]
+    fn edited_requests_keep_their_comments_and_handlers() {
+        let path = temp_file("edit", FILE);
+        let (file, mut requests) = open(&path);
+
+        let mut create = (*requests[1]).clone();
+        create.body = String::from("{\"name\": \"Jerry\"}");
+        create.headers.push(Header { name: String::from("X-Trace"), value: String::from("on"), enabled: false });
+        requests[1] = Arc::new(create);
+
+        let mut added = Request::new_default();
+        added.title = String::from("Delete pet");
+        added.method = String::from("DELETE");
+        added.url = String::from("{{base}}/pets/1");
+        let added_id = added.id.clone();
+        requests.push(Arc::new(added));
+
+        file.save(&requests).unwrap();
+        let saved = fs::read_to_string(&path).unwrap();
+
+        let list = FILE[..FILE.find("###\n#").unwrap()].replace("### List pets\n", "### List pets\n# @id api.http:1\n");
+        assert!(saved.starts_with(&list), "{}", saved);
+        assert!(saved.contains("# Creates one\n# @name create\n# @id api.http:2\nPOST {{base}}/pets\n"), "{}", saved);
+        assert!(saved.contains("# X-Trace: on\n\n{\"name\": \"Jerry\"}\n"), "{}", saved);
+        assert!(saved.contains("> {% client.global.set"), "{}", saved);
+        assert!(saved.ends_with(&format!("### Delete pet\n# @id {}\nDELETE {{{{base}}}}/pets/1\n\n", added_id)));
+
+        let (_, reopened) = open(&path);
+        remove(&path);
+        let titles: Vec<&str> = reopened.iter().map(|r| r.title.as_str()).collect();
+        assert_eq!(titles, ["List pets", "create", "Delete pet"]);
+        assert_eq!(reopened[1].body, "{\"name\": \"Jerry\"}");
+    }
+
+    #[test]
+    fn environments_file_is_only_written_once_there_are_environments() {
+        let path = temp_file("env", FILE);
+        let environments_path = path.with_file_name(ENVIRONMENTS_FILE);
+        let _ = fs::remove_file(&environments_path);
+        let (file, _) = open(&path);
+
+        file.save_environments(&[]).unwrap();
+        assert!(!environments_path.exists());
+
+        let staging = Environment {
+            name: String::from("staging"),
+            variables: BTreeMap::from([(String::from("host"), String::from("staging.test"))]),
+        };
+        file.save_environments(&[staging]).unwrap();
+
+        let (_, imported) = HttpFile::open(&path).unwrap();
+        remove(&path);
+        assert_eq!(imported.environments.len(), 1);
+        assert_eq!(imported.environments[0].name, "staging");
+        assert_eq!(imported.environments[0].variables["host"], "staging.test");
+    }
+}
@@ -0,0 +1,448 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::auth::{ApiKeyLocation, Auth, OAuth2Grant};
+use crate::contract::{self, SpecOperation};
+use crate::har::{self, HarEntry, HarFilter};
+use crate::insomnia;
+use crate::model::{Environment, Folder, Model, PathParam, QueryParam, Request};
+use crate::openapi;
+use crate::postman;
+
+const USAGE: &str = "Usage: umbrella import openapi|postman|insomnia|har FILE \
+    [--environment NAME] [--domain HOST] [--method METHOD]";
+
+/// What an importer read from a file, added to the collection as a whole.
+#[derive(Default)]
+pub struct Imported {
+    pub requests: Vec<Request>,
+    pub folders: Vec<Folder>,
+    pub variables: BTreeMap<String, String>,
+    pub environments: Vec<Environment>,
+    /// Environment made active, the first one when none is active yet if not set
+    pub environment: Option<String>,
+    /// What the collection can't hold, reported instead of dropped
+    pub skipped: Vec<String>,
+}
+
+impl Imported {
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+
+        match self.folders.as_slice() {
+            [folder] => parts.push(format!("{} requests into folder {}", self.requests.len(), folder.name)),
+            [] if self.requests.is_empty() => {},
+            folders => parts.push(format!("{} requests into {} folders", self.requests.len(), folders.len())),
+        }
+
+        if !self.variables.is_empty() || (parts.is_empty() && self.environments.is_empty()) {
+            parts.push(format!("{} variables", self.variables.len()));
+        }
+
+        match self.environments.as_slice() {
+            [] => {},
+            [environment] => parts.push(format!("environment {}", environment.name)),
+            environments => parts.push(format!("{} environments", environments.len())),
+        }
+
+        let mut summary = format!("Imported {}", parts.join(" and "));
+
+        if !self.skipped.is_empty() {
+            summary.push_str("\nLeft out:");
+
+            for skipped in &self.skipped {
+                summary.push_str(&format!("\n  {}", skipped));
+            }
+        }
+
+        summary
+    }
+}
+
+/// Value of a JSON field as text, exports keep numbers and booleans as strings too.
+pub fn text(value: Option<&Value>) -> String {
+    match value {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Null) | None => String::new(),
+        Some(other) => other.to_string(),
+    }
+}
+
+/// Whether a header, param or variable of an export is switched off.
+pub fn is_disabled(entry: &Value) -> bool {
+    entry.get("disabled").and_then(|d| d.as_bool()).unwrap_or(false)
+}
+
+/// Methods requests are imported for, in the order they're listed.
+const METHODS: [&str; 7] = ["get", "post", "put", "patch", "delete", "head", "options"];
+
+/// Maps a parameter schema onto the types path params are validated against.
+fn schema_type(root: &Value, schema: Option<&Value>) -> String {
+    let Some(schema) = schema.map(|s| openapi::resolve(root, s)) else {
+        return String::from("string");
+    };
+
+    // 3.1 lists nullable types as `["integer", "null"]`
+    let kind = match schema.get("type") {
+        Some(Value::Array(types)) => types.iter().filter_map(|t| t.as_str()).find(|t| *t != "null"),
+        Some(kind) => kind.as_str(),
+        None => None,
+    };
+
+    let format = schema.get("format").and_then(|f| f.as_str());
+
+    let kind = match kind {
+        Some("integer") => "integer",
+        Some("number") if matches!(format, Some("int32" | "int64")) => "integer",
+        Some("number") => "number",
+        Some("boolean") => "boolean",
+        Some("string") if format == Some("uuid") => "uuid",
+        _ => "string",
+    };
+
+    kind.to_string()
+}
+
+/// Maps a security scheme onto auth with empty credentials.
+fn scheme_auth(scheme: &Value) -> Auth {
+    let field = |name: &str| scheme.get(name).and_then(|v| v.as_str()).unwrap_or_default();
+
+    match field("type") {
+        "apiKey" => match field("in") {
+            // Cookies are sent as a header with the cookie name prefilled
+            "cookie" => Auth::ApiKey {
+                name: String::from("Cookie"),
+                value: format!("{}=", field("name")),
+                location: ApiKeyLocation::Header,
+            },
+            location => Auth::ApiKey {
+                name: field("name").to_string(),
+                value: String::new(),
+                location: match location {
+                    "query" => ApiKeyLocation::Query,
+                    _ => ApiKeyLocation::Header,
+                },
+            },
+        },
+        "http" if field("scheme").eq_ignore_ascii_case("basic") => Auth::Basic {
+            username: String::new(),
+            password: String::new(),
+        },
+        "oauth2" => {
+            let flows = scheme.get("flows");
+
+            let (grant, flow) = match (flows.and_then(|f| f.get("clientCredentials")), flows.and_then(|f| f.get("password"))) {
+                (Some(flow), _) => (OAuth2Grant::ClientCredentials, flow),
+                (None, Some(flow)) => (OAuth2Grant::Password, flow),
+                // Browser based flows aren't supported, the token is pasted by hand
+                (None, None) => return Auth::Bearer { token: String::new() },
+            };
+
+            let mut scopes: Vec<&String> = flow
+                .get("scopes")
+                .and_then(|s| s.as_object())
+                .map(|s| s.keys().collect())
+                .unwrap_or_default();
+            scopes.sort();
+
+            Auth::OAuth2 {
+                grant,
+                token_url: flow.get("tokenUrl").and_then(|u| u.as_str()).unwrap_or_default().to_string(),
+                client_id: String::new(),
+                client_secret: String::new(),
+                scope: scopes.into_iter().cloned().collect::<Vec<String>>().join(" "),
+                username: String::new(),
+                password: String::new(),
+            }
+        },
+        _ => Auth::Bearer { token: String::new() },
+    }
+}
+
+/// Auth of the first alternative of the requirements, an empty one makes the auth optional.
+fn requirement_auth(root: &Value, requirements: &Value) -> Option<Auth> {
+    let Some(requirement) = requirements.as_array()?.first() else {
+        return Some(Auth::None);
+    };
+
+    let requirement = requirement.as_object()?;
+
+    let mut names: Vec<&String> = requirement.keys().collect();
+    names.sort();
+
+    let auth = names
+        .first()
+        .and_then(|name| root.get("components")?.get("securitySchemes")?.get(name.as_str()))
+        .map(|scheme| scheme_auth(openapi::resolve(root, scheme)))
+        .unwrap_or(Auth::None);
+
+    Some(auth)
+}
+
+/// URL of the first server, its variables set to their defaults.
+fn base_url(root: &Value) -> String {
+    let Some(server) = root.get("servers").and_then(|s| s.get(0)) else {
+        return String::new();
+    };
+
+    let mut url = server.get("url").and_then(|u| u.as_str()).unwrap_or_default().to_string();
+
+    if let Some(variables) = server.get("variables").and_then(|v| v.as_object()) {
+        for (name, variable) in variables {
+            let default = variable.get("default").and_then(|d| d.as_str()).unwrap_or_default();
+            url = url.replace(&format!("{{{}}}", name), default);
+        }
+    }
+
+    url.trim_end_matches('/').to_string()
+}
+
+/// Requests for the operations of an OpenAPI 3.0/3.1 or Swagger 2.0 spec,
+/// in a folder named after it.
+pub fn openapi(spec_path: &Path) -> Result<(Vec<Request>, Folder), String> {
+    let spec_path = spec_path
+        .canonicalize()
+        .map_err(|e| format!("Couldn't read {}: {}", spec_path.display(), e))?;
+
+    let root = openapi::load(&spec_path)?;
+    let base_url = base_url(&root);
+
+    let schemes = root
+        .get("components")
+        .and_then(|c| c.get("securitySchemes"))
+        .and_then(|s| s.as_object());
+
+    // Without global requirements a single defined scheme is taken as the global one
+    let folder_auth = root
+        .get("security")
+        .and_then(|s| requirement_auth(&root, s))
+        .or_else(|| match schemes {
+            Some(schemes) if schemes.len() == 1 => schemes.values().next().map(|s| scheme_auth(openapi::resolve(&root, s))),
+            _ => None,
+        });
+
+    let title = root.get("info").and_then(|i| i.get("title")).and_then(|t| t.as_str()).unwrap_or_default();
+
+    let folder = Folder {
+        name: match title.is_empty() {
+            true => contract::spec_name(&spec_path),
+            false => title.to_string(),
+        },
+        auth: folder_auth.unwrap_or(Auth::None),
+    };
+
+    let mut requests = Vec::new();
+    let paths = root.get("paths").and_then(|p| p.as_object());
+
+    for (path, item) in paths.into_iter().flatten() {
+        let item = openapi::resolve(&root, item);
+        let shared = item.get("parameters").and_then(|p| p.as_array());
+
+        for method in METHODS {
+            let Some(operation) = item.get(method) else {
+                continue;
+            };
+
+            let operation_id = operation.get("operationId").and_then(|o| o.as_str()).map(String::from);
+            let summary = operation.get("summary").and_then(|s| s.as_str()).filter(|s| !s.is_empty());
+
+            let mut request = Request::new_default();
+            request.method = method.to_uppercase();
+            request.title = summary
+                .map(String::from)
+                .or(operation_id.clone())
+                .unwrap_or(format!("{} {}", request.method, path));
+            request.url = format!("{}{}", base_url, path);
+            request.folder = Some(folder.name.clone());
+            request.operation = Some(SpecOperation::new(&spec_path, method, path, operation_id));
+
+            if let Some(auth) = operation.get("security").and_then(|s| requirement_auth(&root, s)) {
+                request.auth = auth;
+            }
+
+            let own = operation.get("parameters").and_then(|p| p.as_array());
+
+            // The operation's parameters override the path item's ones
+            let mut parameters: Vec<&Value> = Vec::new();
+
+            for parameter in shared.into_iter().flatten().chain(own.into_iter().flatten()) {
+                let parameter = openapi::resolve(&root, parameter);
+                let key = |p: &Value| (p.get("name").cloned(), p.get("in").cloned());
+
+                parameters.retain(|p| key(p) != key(parameter));
+                parameters.push(parameter);
+            }
+
+            for parameter in parameters {
+                let name = parameter.get("name").and_then(|n| n.as_str()).unwrap_or_default().to_string();
+
+                match parameter.get("in").and_then(|i| i.as_str()) {
+                    // Optional params are listed, but not sent until enabled
+                    Some("query") => request.query_params.push(QueryParam {
+                        key: name,
+                        value: String::new(),
+                        enabled: false,
+                    }),
+                    Some("path") => request.path_params.push(PathParam {
+                        name,
+                        param_type: schema_type(&root, parameter.get("schema")),
+                        value: String::new(),
+                        required: parameter.get("required").and_then(|r| r.as_bool()).unwrap_or(true),
+                    }),
+                    _ => {},
+                }
+            }
+
+            requests.push(request);
+        }
+    }
+
+    Ok((requests, folder))
+}
+
+/// Adds what was imported, unless one of its folders is in the collection already.
+fn add(model: &mut Model, imported: Imported) -> Result<String, String> {
+    if let Some(folder) = imported.folders.iter().find(|f| model.get_folder(&f.name).is_some()) {
+        return Err(format!("Folder {} is already in the collection, rename or delete it first", folder.name));
+    }
+
+    let summary = imported.summary();
+    model.add_imported(imported);
+    Ok(summary)
+}
+
+/// Adds the spec's requests to the collection, unless they're there already.
+pub fn import_openapi(model: &mut Model, spec_path: &Path) -> Result<String, String> {
+    let (requests, folder) = openapi(spec_path)?;
+    let spec = requests.first().and_then(|r| r.operation.as_ref()).map(|o| o.spec.clone());
+
+    let imported = model.requests
+        .read().unwrap()
+        .iter()
+        .any(|r| r.operation.as_ref().map(|o| &o.spec) == spec.as_ref());
+
+    if let (true, Some(spec)) = (imported, spec) {
+        return Err(format!("{} is already imported, sync it with [o] in the app", spec));
+    }
+
+    add(model, Imported { requests, folders: vec![folder], ..Imported::default() })
+}
+
+pub fn import_postman(model: &mut Model, path: &Path) -> Result<String, String> {
+    add(model, postman::import(path)?)
+}
+
+/// Adds an Insomnia export, the named sub-environment or else the first one becomes active.
+pub fn import_insomnia(model: &mut Model, path: &Path, environment: Option<&str>) -> Result<String, String> {
+    add(model, insomnia::import(path, environment)?)
+}
+
+/// Adds the entries of a HAR file matching the filter.
+pub fn import_har(model: &mut Model, path: &Path, filter: &HarFilter) -> Result<String, String> {
+    add(model, har::import(path, filter)?)
+}
+
+/// Adds the entries picked out of a HAR file.
+pub fn import_har_entries(model: &mut Model, entries: Vec<HarEntry>, folder: &str) -> Result<String, String> {
+    add(model, har::to_imported(entries, folder))
+}
+
+/// Imports a file of any supported format, told apart by its content.
+pub fn import_file(model: &mut Model, path: &Path) -> Result<String, String> {
+    let value = fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<Value>(&content).ok());
+
+    match value {
+        Some(value) if postman::is_postman(&value) => import_postman(model, path),
+        Some(value) if insomnia::is_insomnia(&value) => import_insomnia(model, path, None),
+        Some(value) if har::is_har(&value) => import_har(model, path, &HarFilter::default()),
+        _ => import_openapi(model, path),
+    }
+}
+
+/// Options of `umbrella import`, each taken by one format only.
+#[derive(Default)]
+struct CliOptions {
+    /// Insomnia sub-environment
+    environment: Option<String>,
+    /// HAR entries to import
+    filter: HarFilter,
+}
+
+fn parse_options(format: &str, args: &[String]) -> Result<CliOptions, String> {
+    let mut options = CliOptions::default();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match (arg.as_str(), format) {
+            ("--environment", "insomnia") => {
+                options.environment = Some(args.next().ok_or("--environment needs a name")?.clone());
+            },
+            ("--domain", "har") => {
+                options.filter.domains.push(args.next().ok_or("--domain needs a host")?.to_lowercase());
+            },
+            ("--method", "har") => {
+                options.filter.methods.push(args.next().ok_or("--method needs a method")?.to_uppercase());
+            },
+            ("--environment", _) => return Err(String::from("--environment only activates Insomnia sub-environments")),
+            ("--domain" | "--method", _) => return Err(String::from("--domain and --method only filter HAR entries")),
+            (other, _) => return Err(format!("Unknown option {}", other)),
+        }
+    }
+
+    Ok(options)
+}
+
+/// Headless `umbrella import`, into the saved collection.
+pub fn run(args: &[String]) -> i32 {
+    let (format, path, options) = match args {
+        [format, path, options @ ..] => (format.as_str(), PathBuf::from(path), options),
+        _ => {
+            eprintln!("{}", USAGE);
+            return 2;
+        },
+    };
+
+    if !matches!(format, "openapi" | "postman" | "insomnia" | "har") {
+        eprintln!("Unknown format {}\n{}", format, USAGE);
+        return 2;
+    }
+
+    let options = match parse_options(format, options) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            return 2;
+        },
+    };
+
+    let mut model = match Model::load_from_disk() {
+        Ok(model) => model.unwrap_or(Model::new(vec![], vec![])),
+        Err(e) => {
+            eprintln!("{}", e);
+            return 1;
+        },
+    };
+
+    let result = match format {
+        "openapi" => import_openapi(&mut model, &path),
+        "postman" => import_postman(&mut model, &path),
+        "insomnia" => import_insomnia(&mut model, &path, options.environment.as_deref()),
+        _ => import_har(&mut model, &path, &options.filter),
+    };
+
+    match result {
+        Ok(summary) => {
+            println!("{}", summary);
+            0
+        },
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        },
+    }
+}
@@ -0,0 +1,570 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde_json::{json, Value};
+
+use crate::auth::{ApiKeyLocation, Auth, OAuth2Grant};
+use crate::import::{is_disabled, text, Imported};
+use crate::model::{Environment, Folder, Header, PathParam, QueryParam, Request};
+use crate::postman::BOUNDARY;
+use crate::url;
+
+/// `{{ _.name }}` or `{{ name }}`, the name is the first group.
+static PLACEHOLDER: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\{\{\s*(?:_\.)?([^{}\s]+)\s*\}\}").expect("Invalid placeholder pattern")
+});
+
+/// `{% name args %}`, the name is the first group.
+static TAG: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\{%\s*(\w+)[^%]*%\}").expect("Invalid tag pattern"));
+
+fn field(resource: &Value, name: &str) -> String {
+    text(resource.get(name))
+}
+
+pub fn is_insomnia(value: &Value) -> bool {
+    value.get("_type").and_then(|t| t.as_str()) == Some("export") && value.get("__export_format").is_some()
+}
+
+/// Insomnia templates with `{{ _.name }}` and `{{ name }}` placeholders as `{{name}}`.
+///
+/// Template tags like `{% uuid %}` are kept as they are and reported.
+fn convert_template(template: &str, owner: &str, skipped: &mut Vec<String>) -> String {
+    for captures in TAG.captures_iter(template) {
+        let note = match &captures[1] {
+            "response" => "use a capture for the value of another response",
+            _ => "it has no equivalent, set the value by hand",
+        };
+
+        skipped.push(format!("{}: template tag {} kept as is, {}", owner, &captures[0], note));
+    }
+
+    PLACEHOLDER.replace_all(template, "{{$1}}").to_string()
+}
+
+/// Environment data as variables, nested objects give `parent.child` names.
+fn flatten_data(prefix: &str, data: &Value, variables: &mut BTreeMap<String, String>, skipped: &mut Vec<String>) {
+    let Some(data) = data.as_object() else {
+        return;
+    };
+
+    for (key, value) in data {
+        let name = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+
+        match value {
+            Value::Object(_) => flatten_data(&name, value, variables, skipped),
+            Value::String(s) => {
+                variables.insert(name, convert_template(s, "environment", skipped));
+            },
+            other => {
+                variables.insert(name, other.to_string());
+            },
+        }
+    }
+}
+
+/// Auth of a request or folder, none when it has none set and inherits it.
+fn import_auth(auth: Option<&Value>, owner: &str, skipped: &mut Vec<String>) -> Option<Auth> {
+    let mut auth = auth.filter(|a| a.as_object().is_some_and(|a| !a.is_empty()))?.clone();
+
+    if is_disabled(&auth) {
+        return Some(Auth::None);
+    }
+
+    for value in auth.as_object_mut().into_iter().flat_map(|a| a.values_mut()) {
+        if let Value::String(template) = value {
+            *template = convert_template(template, owner, skipped);
+        }
+    }
+
+    let auth = &auth;
+    let value = |name: &str| field(auth, name);
+
+    let auth = match field(auth, "type").as_str() {
+        "none" => Auth::None,
+        "basic" => Auth::Basic { username: value("username"), password: value("password") },
+        "bearer" => {
+            let prefix = field(auth, "prefix");
+            let token = value("token");
+
+            // A custom prefix like `Token` can only be sent as a raw header
+            if prefix.is_empty() || prefix.eq_ignore_ascii_case("Bearer") {
+                Auth::Bearer { token }
+            } else {
+                Auth::ApiKey {
+                    name: String::from("Authorization"),
+                    value: format!("{} {}", prefix, token),
+                    location: ApiKeyLocation::Header,
+                }
+            }
+        },
+        "apikey" => match field(auth, "addTo").as_str() {
+            "queryParams" => Auth::ApiKey { name: value("key"), value: value("value"), location: ApiKeyLocation::Query },
+            "cookie" => Auth::ApiKey {
+                name: String::from("Cookie"),
+                value: format!("{}={}", value("key"), value("value")),
+                location: ApiKeyLocation::Header,
+            },
+            _ => Auth::ApiKey { name: value("key"), value: value("value"), location: ApiKeyLocation::Header },
+        },
+        "oauth2" => {
+            let grant = match field(auth, "grantType").as_str() {
+                "client_credentials" => OAuth2Grant::ClientCredentials,
+                "password" => OAuth2Grant::Password,
+                grant => {
+                    skipped.push(format!("{}: OAuth 2.0 {} grant left out, paste a token into the bearer auth", owner, grant));
+                    return Some(Auth::Bearer { token: String::new() });
+                },
+            };
+
+            Auth::OAuth2 {
+                grant,
+                token_url: value("accessTokenUrl"),
+                client_id: value("clientId"),
+                client_secret: value("clientSecret"),
+                scope: value("scope"),
+                username: value("username"),
+                password: value("password"),
+            }
+        },
+        kind => {
+            skipped.push(format!("{}: {} auth left out, it isn't supported", owner, kind));
+            Auth::None
+        },
+    };
+
+    Some(auth)
+}
+
+fn import_headers(headers: Option<&Value>, owner: &str, skipped: &mut Vec<String>) -> Vec<Header> {
+    headers
+        .and_then(|h| h.as_array())
+        .into_iter()
+        .flatten()
+        .filter(|h| !field(h, "name").is_empty())
+        .map(|h| Header {
+            name: field(h, "name"),
+            value: convert_template(&field(h, "value"), owner, skipped),
+            enabled: !is_disabled(h),
+        })
+        .collect()
+}
+
+/// Sets the Content-Type, replacing one without the details the body needs.
+fn replace_content_type(headers: &mut Vec<Header>, content_type: &str) {
+    headers.retain(|h| !h.name.eq_ignore_ascii_case("Content-Type"));
+    headers.push(Header { name: String::from("Content-Type"), value: content_type.to_string(), enabled: true });
+}
+
+fn import_body(body: Option<&Value>, headers: &mut Vec<Header>, owner: &str, skipped: &mut Vec<String>) -> String {
+    let Some(body) = body.filter(|b| b.as_object().is_some_and(|b| !b.is_empty())) else {
+        return String::new();
+    };
+
+    let mime_type = field(body, "mimeType");
+    let params = body.get("params").and_then(|p| p.as_array()).cloned().unwrap_or_default();
+
+    if !mime_type.is_empty() && !headers.iter().any(|h| h.name.eq_ignore_ascii_case("Content-Type")) {
+        headers.push(Header { name: String::from("Content-Type"), value: mime_type.clone(), enabled: true });
+    }
+
+    match mime_type.as_str() {
+        "application/x-www-form-urlencoded" => {
+            let mut pairs = Vec::new();
+
+            for param in params {
+                let name = field(&param, "name");
+
+                if is_disabled(&param) {
+                    skipped.push(format!("{}: disabled form field {} left out", owner, name));
+                } else {
+                    let value = convert_template(&field(&param, "value"), owner, skipped);
+                    pairs.push(format!("{}={}", url::percent_encode(&name), url::percent_encode(&value)));
+                }
+            }
+
+            pairs.join("&")
+        },
+        "multipart/form-data" => {
+            let mut multipart = String::new();
+
+            for param in params {
+                let name = field(&param, "name");
+
+                if field(&param, "type") == "file" {
+                    skipped.push(format!("{}: file field {} left out, bodies are text", owner, name));
+                } else if is_disabled(&param) {
+                    skipped.push(format!("{}: disabled form field {} left out", owner, name));
+                } else {
+                    multipart.push_str(&format!(
+                        "--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n",
+                        BOUNDARY,
+                        name,
+                        convert_template(&field(&param, "value"), owner, skipped),
+                    ));
+                }
+            }
+
+            multipart.push_str(&format!("--{}--\r\n", BOUNDARY));
+            replace_content_type(headers, &format!("multipart/form-data; boundary={}", BOUNDARY));
+            multipart
+        },
+        // GraphQL bodies are kept as the JSON they're sent as
+        "application/graphql" => {
+            let graphql: Value = serde_json::from_str(&field(body, "text")).unwrap_or(json!({}));
+
+            let body = json!({
+                "query": text(graphql.get("query")),
+                "variables": graphql.get("variables").cloned().unwrap_or(json!({})),
+            });
+
+            replace_content_type(headers, "application/json");
+            convert_template(&serde_json::to_string_pretty(&body).unwrap_or_default(), owner, skipped)
+        },
+        _ if body.get("fileName").is_some() => {
+            skipped.push(format!("{}: file body left out, bodies are text", owner));
+            String::new()
+        },
+        _ => convert_template(&field(body, "text"), owner, skipped),
+    }
+}
+
+fn import_request(resource: &Value, folder: &Folder, inherited: &[Header], skipped: &mut Vec<String>) -> Request {
+    let method = field(resource, "method").to_uppercase();
+    let method = if method.is_empty() { String::from("GET") } else { method };
+    let name = field(resource, "name");
+    let title = if name.is_empty() { format!("{} {}", method, field(resource, "url")) } else { name };
+    let owner = format!("{} / {}", folder.name, title);
+
+    let raw = convert_template(&field(resource, "url"), &owner, skipped);
+    let parts = url::split_url(&raw);
+    let base = url::path_variables_to_placeholders(parts.base);
+
+    let mut query_params: Vec<QueryParam> = url::parse_query(parts.query.unwrap_or_default())
+        .into_iter()
+        .map(|(key, value)| QueryParam { key, value, enabled: true })
+        .collect();
+
+    for param in resource.get("parameters").and_then(|p| p.as_array()).into_iter().flatten() {
+        query_params.push(QueryParam {
+            key: field(param, "name"),
+            value: convert_template(&field(param, "value"), &owner, skipped),
+            enabled: !is_disabled(param),
+        });
+    }
+
+    let mut given = Vec::new();
+
+    for param in resource.get("pathParameters").and_then(|p| p.as_array()).into_iter().flatten() {
+        given.push(PathParam {
+            value: convert_template(&field(param, "value"), &owner, skipped),
+            ..PathParam::new(&field(param, "name"))
+        });
+    }
+
+    let mut headers = import_headers(resource.get("headers"), &owner, skipped);
+
+    // Folder headers apply unless the request sets its own
+    for header in inherited {
+        if !headers.iter().any(|h| h.name.eq_ignore_ascii_case(&header.name)) {
+            headers.push(header.clone());
+        }
+    }
+
+    let body = import_body(resource.get("body"), &mut headers, &owner, skipped);
+
+    Request {
+        method,
+        url: url::with_query(&base, &query_params),
+        body,
+        query_params,
+        path_params: url::merge_path_params(&base, &given),
+        headers,
+        folder: Some(folder.name.clone()),
+        auth: import_auth(resource.get("authentication"), &owner, skipped).unwrap_or(Auth::Inherit),
+        title,
+        ..Request::new_default()
+    }
+}
+
+/// Resources of an export by the id of their parent, in the order Insomnia lists them.
+struct Resources<'a> {
+    children: BTreeMap<String, Vec<&'a Value>>,
+}
+
+impl<'a> Resources<'a> {
+    fn new(resources: &'a [Value]) -> Self {
+        let mut children: BTreeMap<String, Vec<&Value>> = BTreeMap::new();
+
+        for resource in resources {
+            children.entry(field(resource, "parentId")).or_default().push(resource);
+        }
+
+        let sort_key = |r: &Value| r.get("metaSortKey").and_then(|k| k.as_f64()).unwrap_or(0.0);
+
+        for list in children.values_mut() {
+            list.sort_by(|a, b| sort_key(a).total_cmp(&sort_key(b)));
+        }
+
+        Self { children }
+    }
+
+    fn of(&self, parent: &Value, kind: &str) -> Vec<&'a Value> {
+        self.children
+            .get(&field(parent, "_id"))
+            .into_iter()
+            .flatten()
+            .filter(|r| field(r, "_type") == kind)
+            .copied()
+            .collect()
+    }
+}
+
+/// Requests of a workspace or request group, groups nest as `Parent / Child`.
+fn import_group(
+    resources: &Resources,
+    group: &Value,
+    folder: &Folder,
+    headers: &[Header],
+    imported: &mut Imported,
+) {
+    for request in resources.of(group, "request") {
+        let request = import_request(request, folder, headers, &mut imported.skipped);
+        imported.requests.push(request);
+    }
+
+    for kind in ["grpc_request", "websocket_request"] {
+        for request in resources.of(group, kind) {
+            let protocol = if kind == "grpc_request" { "gRPC" } else { "WebSocket" };
+            imported.skipped.push(format!("{}: {} request {} left out", folder.name, protocol, field(request, "name")));
+        }
+    }
+
+    for child in resources.of(group, "request_group") {
+        let name = format!("{} / {}", folder.name, field(child, "name"));
+
+        let mut inherited = import_headers(child.get("headers"), &name, &mut imported.skipped);
+
+        for header in headers {
+            if !inherited.iter().any(|h| h.name.eq_ignore_ascii_case(&header.name)) {
+                inherited.push(header.clone());
+            }
+        }
+
+        // Folder variables are shared by the collection, the environment wins
+        let mut variables = BTreeMap::new();
+        flatten_data("", child.get("environment").unwrap_or(&Value::Null), &mut variables, &mut imported.skipped);
+
+        for (key, value) in variables {
+            match imported.variables.get(&key) {
+                Some(existing) if *existing != value => {
+                    imported.skipped.push(format!("{}: folder variable {} left out, the environment sets it", name, key));
+                },
+                _ => {
+                    imported.variables.insert(key, value);
+                },
+            }
+        }
+
+        let child_folder = Folder {
+            auth: import_auth(child.get("authentication"), &name, &mut imported.skipped).unwrap_or(folder.auth.clone()),
+            name,
+        };
+
+        imported.folders.push(child_folder.clone());
+        import_group(resources, child, &child_folder, &inherited, imported);
+    }
+}
+
+/// Variables of the base environment of the workspace, its sub-environments
+/// become environments.
+///
+/// Insomnia doesn't export which sub-environment is active, without a name
+/// the first one is made active.
+fn import_environment(
+    resources: &Resources,
+    workspace: &Value,
+    environment: Option<&str>,
+    imported: &mut Imported,
+) -> Result<(), String> {
+    let Some(base) = resources.of(workspace, "environment").into_iter().next() else {
+        return Ok(());
+    };
+
+    flatten_data("", base.get("data").unwrap_or(&Value::Null), &mut imported.variables, &mut imported.skipped);
+
+    let subs = resources.of(base, "environment");
+    let names: Vec<String> = subs.iter().map(|s| field(s, "name")).collect();
+
+    if let Some(name) = environment {
+        match names.iter().find(|n| n.eq_ignore_ascii_case(name)) {
+            Some(found) => imported.environment = Some(found.clone()),
+            None if names.is_empty() => return Err(format!("{} has no sub-environments", field(workspace, "name"))),
+            None => return Err(format!("No sub-environment {} in {}, it has {}", name, field(workspace, "name"), names.join(", "))),
+        }
+    }
+
+    for (sub, name) in subs.iter().zip(names) {
+        let mut variables = BTreeMap::new();
+        flatten_data("", sub.get("data").unwrap_or(&Value::Null), &mut variables, &mut imported.skipped);
+        imported.environments.push(Environment { name, variables });
+    }
+
+    Ok(())
+}
+
+/// Requests, folders and variables of an Insomnia v4 export, one root
+/// folder per workspace.
+pub fn import(path: &Path, environment: Option<&str>) -> Result<Imported, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("Couldn't read {}: {}", path.display(), e))?;
+    let value: Value = serde_json::from_str(&content).map_err(|e| format!("Couldn't parse {}: {}", path.display(), e))?;
+
+    if !is_insomnia(&value) {
+        return Err(format!("{} is not an Insomnia export", path.display()));
+    }
+
+    let format = value.get("__export_format").and_then(|f| f.as_u64()).unwrap_or_default();
+
+    if format != 4 {
+        return Err(format!("{} is an Insomnia v{} export, export it again as Insomnia v4 JSON", path.display(), format));
+    }
+
+    let all = value.get("resources").and_then(|r| r.as_array()).cloned().unwrap_or_default();
+    let resources = Resources::new(&all);
+    let mut imported = Imported::default();
+
+    let workspaces: Vec<&Value> = all.iter().filter(|r| field(r, "_type") == "workspace").collect();
+
+    if workspaces.is_empty() {
+        return Err(format!("{} has no workspaces", path.display()));
+    }
+
+    for workspace in workspaces {
+        let name = field(workspace, "name");
+
+        let root = Folder {
+            name: if name.is_empty() { String::from("Insomnia workspace") } else { name },
+            auth: Auth::None,
+        };
+
+        import_environment(&resources, workspace, environment, &mut imported)?;
+
+        imported.folders.push(root.clone());
+        import_group(&resources, workspace, &root, &[], &mut imported);
+
+        let suites = resources.of(workspace, "unit_test_suite").len();
+
+        if suites > 0 {
+            imported.skipped.push(format!("{}: {} unit test suites left out", root.name, suites));
+        }
+    }
+
+    // Folders only holding folders stay out of the list
+    let requests = &imported.requests;
+    imported.folders.retain(|f| requests.iter().any(|r| r.folder.as_ref() == Some(&f.name)));
+
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn export() -> Value {
+        json!({
+            "_type": "export",
+            "__export_format": 4,
+            "resources": [
+                { "_id": "wrk_1", "_type": "workspace", "parentId": null, "name": "Shop" },
+                {
+                    "_id": "env_base",
+                    "_type": "environment",
+                    "parentId": "wrk_1",
+                    "name": "Base Environment",
+                    "data": { "version": "v1" },
+                },
+                {
+                    "_id": "env_staging",
+                    "_type": "environment",
+                    "parentId": "env_base",
+                    "name": "Staging",
+                    "metaSortKey": 1,
+                    "data": { "host": "staging.shop.test", "auth": { "user": "tester" } },
+                },
+                {
+                    "_id": "env_production",
+                    "_type": "environment",
+                    "parentId": "env_base",
+                    "name": "Production",
+                    "metaSortKey": 2,
+                    "data": { "host": "shop.test", "retries": 3 },
+                },
+                {
+                    "_id": "fld_1",
+                    "_type": "request_group",
+                    "parentId": "wrk_1",
+                    "name": "Orders",
+                    "headers": [{ "name": "Accept", "value": "application/json" }],
+                },
+                {
+                    "_id": "req_1",
+                    "_type": "request",
+                    "parentId": "fld_1",
+                    "name": "List orders",
+                    "method": "get",
+                    "url": "https://{{ _.host }}/{{ version }}/orders?limit=10",
+                    "headers": [{ "name": "X-User", "value": "{{ _.auth.user }}" }],
+                },
+            ],
+        })
+    }
+
+    fn import_export(environment: Option<&str>) -> Result<Imported, String> {
+        let path = std::env::temp_dir().join(format!("umbrella-insomnia-{}.json", std::process::id()));
+        fs::write(&path, export().to_string()).unwrap();
+
+        let imported = import(&path, environment);
+        fs::remove_file(&path).unwrap();
+        imported
+    }
+
+    #[test]
+    fn every_sub_environment_is_imported() {
+        let imported = import_export(None).unwrap();
+        let environments: Vec<(&str, Vec<(&str, &str)>)> = imported.environments
+            .iter()
+            .map(|e| (e.name.as_str(), e.variables.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect()))
+            .collect();
+
+        assert_eq!(environments, vec![
+            ("Staging", vec![("auth.user", "tester"), ("host", "staging.shop.test")]),
+            ("Production", vec![("host", "shop.test"), ("retries", "3")]),
+        ]);
+        assert_eq!(imported.variables.get("version").map(String::as_str), Some("v1"));
+        assert_eq!(imported.environment, None);
+    }
+
+    #[test]
+    fn the_named_sub_environment_is_made_active() {
+        let imported = import_export(Some("production")).unwrap();
+        assert_eq!(imported.environment.as_deref(), Some("Production"));
+
+        let error = import_export(Some("Local")).err().unwrap();
+        assert_eq!(error, "No sub-environment Local in Shop, it has Staging, Production");
+    }
+
+    #[test]
+    fn requests_use_placeholders_and_folder_headers() {
+        let imported = import_export(None).unwrap();
+        let request = &imported.requests[0];
+        let headers: Vec<(&str, &str)> = request.headers.iter().map(|h| (h.name.as_str(), h.value.as_str())).collect();
+
+        assert_eq!(request.method, "GET");
+        assert_eq!(request.url, "https://{{host}}/{{version}}/orders?limit=10");
+        assert_eq!(request.folder.as_deref(), Some("Shop / Orders"));
+        assert_eq!(headers, vec![("X-User", "{{auth.user}}"), ("Accept", "application/json")]);
+        assert_eq!(imported.folders.iter().map(|f| f.name.as_str()).collect::<Vec<_>>(), vec!["Shop / Orders"]);
+    }
+}
@@ -0,0 +1,187 @@
+use serde_json::Value;
+
+enum Segment {
+    Key(String),
+    /// Negative indices count from the end
+    Index(i64),
+    Wildcard,
+    /// `..key` or `..*`, matches at any depth
+    Descendant(Option<String>),
+}
+
+fn parse_bracket(chars: &[char], i: &mut usize) -> Result<Segment, String> {
+    let close = chars[*i..]
+        .iter()
+        .position(|&c| c == ']')
+        .map(|p| *i + p)
+        .ok_or_else(|| String::from("Unclosed ["))?;
+
+    let inner: String = chars[*i + 1..close].iter().collect();
+    let inner = inner.trim();
+    *i = close + 1;
+
+    if inner == "*" {
+        return Ok(Segment::Wildcard);
+    }
+
+    for quote in ['\'', '"'] {
+        if inner.len() >= 2 && inner.starts_with(quote) && inner.ends_with(quote) {
+            return Ok(Segment::Key(inner[1..inner.len() - 1].to_string()));
+        }
+    }
+
+    inner
+        .parse::<i64>()
+        .map(Segment::Index)
+        .map_err(|_| format!("Invalid index [{}]", inner))
+}
+
+fn parse_name(chars: &[char], i: &mut usize) -> String {
+    let start = *i;
+
+    while *i < chars.len() && chars[*i] != '.' && chars[*i] != '[' {
+        *i += 1;
+    }
+
+    chars[start..*i].iter().collect()
+}
+
+/// Supports `$`, `.key`, `['key']`, `[0]`, `[-1]`, `[*]`, `.*` and `..key`.
+fn parse(path: &str) -> Result<Vec<Segment>, String> {
+    let chars: Vec<char> = path.trim().chars().collect();
+
+    if chars.first() != Some(&'$') {
+        return Err(format!("JSONPath must start with $: {}", path));
+    }
+
+    let mut segments = Vec::new();
+    let mut i = 1;
+
+    while i < chars.len() {
+        match chars[i] {
+            '.' if chars.get(i + 1) == Some(&'.') => {
+                i += 2;
+                let name = parse_name(&chars, &mut i);
+                segments.push(Segment::Descendant(Some(name).filter(|n| n != "*" && !n.is_empty())));
+            },
+            '.' => {
+                i += 1;
+                let name = parse_name(&chars, &mut i);
+
+                segments.push(match name.as_str() {
+                    "" => return Err(format!("Empty key in {}", path)),
+                    "*" => Segment::Wildcard,
+                    _ => Segment::Key(name),
+                });
+            },
+            '[' => segments.push(parse_bracket(&chars, &mut i)?),
+            c => return Err(format!("Unexpected {} in {}", c, path)),
+        }
+    }
+
+    Ok(segments)
+}
+
+fn descendants<'a>(value: &'a Value, found: &mut Vec<&'a Value>) {
+    found.push(value);
+
+    match value {
+        Value::Object(map) => map.values().for_each(|v| descendants(v, found)),
+        Value::Array(items) => items.iter().for_each(|v| descendants(v, found)),
+        _ => {},
+    }
+}
+
+fn children(value: &Value) -> Vec<&Value> {
+    match value {
+        Value::Object(map) => map.values().collect(),
+        Value::Array(items) => items.iter().collect(),
+        _ => vec![],
+    }
+}
+
+/// Values the path points to, empty if nothing matches.
+pub fn select<'a>(value: &'a Value, path: &str) -> Result<Vec<&'a Value>, String> {
+    let mut current = vec![value];
+
+    for segment in parse(path)? {
+        current = current
+            .into_iter()
+            .flat_map(|v| match &segment {
+                Segment::Key(key) => v.get(key).into_iter().collect::<Vec<&Value>>(),
+                Segment::Index(i) => {
+                    let items = v.as_array().map(|a| a.as_slice()).unwrap_or_default();
+                    let i = if *i < 0 { items.len() as i64 + i } else { *i };
+
+                    usize::try_from(i).ok().and_then(|i| items.get(i)).into_iter().collect()
+                },
+                Segment::Wildcard => children(v),
+                Segment::Descendant(name) => {
+                    let mut all = Vec::new();
+                    descendants(v, &mut all);
+
+                    match name {
+                        Some(name) => all.into_iter().filter_map(|d| d.as_object()?.get(name)).collect(),
+                        None => all.into_iter().skip(1).collect(),
+                    }
+                },
+            })
+            .collect();
+    }
+
+    Ok(current)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn document() -> Value {
+        json!({
+            "id": 0,
+            "a.b": "dotted",
+            "items": [
+                {"id": 1, "name": "first"},
+                {"id": 2, "name": "second", "tags": [{"id": 3}]},
+            ],
+        })
+    }
+
+    fn found(path: &str) -> Vec<Value> {
+        select(&document(), path).unwrap().into_iter().cloned().collect()
+    }
+
+    #[test]
+    fn keys_and_indices_select_one_value() {
+        assert_eq!(found("$.items[0].name"), [json!("first")]);
+        assert_eq!(found("$.items[-1].id"), [json!(2)]);
+        assert_eq!(found("$['a.b']"), [json!("dotted")]);
+        assert_eq!(found("$[\"items\"][1].tags[0].id"), [json!(3)]);
+        assert_eq!(found(" $ "), [document()]);
+    }
+
+    #[test]
+    fn wildcards_and_descendants_select_many() {
+        assert_eq!(found("$.items[*].id"), [json!(1), json!(2)]);
+        assert_eq!(found("$.items.*.name"), [json!("first"), json!("second")]);
+        assert_eq!(found("$..id"), [json!(0), json!(1), json!(2), json!(3)]);
+        assert_eq!(found("$.items[1]..*").len(), 5);
+    }
+
+    #[test]
+    fn missing_values_select_nothing() {
+        assert!(found("$.nope").is_empty());
+        assert!(found("$.items[2]").is_empty());
+        assert!(found("$.items[-3]").is_empty());
+        assert!(found("$.id.deeper").is_empty());
+    }
+
+    #[test]
+    fn malformed_paths_are_errors() {
+        for path in ["items", "$.", "$.items[0", "$.items[x]", "$items"] {
+            assert!(select(&document(), path).is_err(), "{}", path);
+        }
+    }
+}
@@ -2,11 +2,54 @@ use crossterm::{terminal, event::{KeyEvent, Event, KeyEventKind, KeyCode}};
 use crate::{components::block::{Block, BlockState}, console::Console};
 
 
+#[derive(Clone, Copy, PartialEq)]
+pub enum Pane {
+    List,
+    Method,
+    Input,
+    Request,
+    Response,
+}
+
+const PANES: [Pane; 5] = [
+    Pane::List,
+    Pane::Method,
+    Pane::Input,
+    Pane::Request,
+    Pane::Response,
+];
+
+impl Pane {
+    /// Pane reached from this one with an arrow key in select mode.
+    fn neighbour(&self, code: &KeyCode) -> Option<Pane> {
+        match (self, code) {
+            (Pane::List, KeyCode::Right) => Some(Pane::Input),
+            (Pane::Method, KeyCode::Left) => Some(Pane::List),
+            (Pane::Method, KeyCode::Right) => Some(Pane::Input),
+            (Pane::Method, KeyCode::Down) => Some(Pane::Request),
+            (Pane::Input, KeyCode::Left) => Some(Pane::Method),
+            (Pane::Input, KeyCode::Down) => Some(Pane::Request),
+            (Pane::Request, KeyCode::Up) => Some(Pane::Input),
+            (Pane::Request, KeyCode::Down) => Some(Pane::Response),
+            (Pane::Request, KeyCode::Left) => Some(Pane::List),
+            (Pane::Response, KeyCode::Up) => Some(Pane::Request),
+            (Pane::Response, KeyCode::Left) => Some(Pane::List),
+            _ => None,
+        }
+    }
+}
+
+const HINT_HEIGHT: u16 = 3;
+
 pub struct Layout {
     pub select_mode: bool,
+    width: u16,
+    height: u16,
     pub list_cont: Block,
+    pub method_cont: Block,
     pub input_cont: Block,
     pub req_cont: Block,
+    pub resp_cont: Block,
     pub hint_cont: Block,
 }
 
@@ -16,49 +59,107 @@ impl Layout {
         let (width, height) = terminal::size().expect("Couldn't get terminal size");
 
         let left_col_width: u16 = 40;
-        let right_col_width: u16 = width - left_col_width - 2;
+        let right_col_width: u16 = width.saturating_sub(left_col_width + 2);
+        let method_width: u16 = 10;
 
         let input_height: u16 = 3;
-        let hint_height: u16 = 3;
-        let list_height: u16 = height - hint_height;
-        let req_height: u16 = height - hint_height - input_height;
+        let hint_height: u16 = HINT_HEIGHT;
+        let list_height: u16 = height.saturating_sub(hint_height);
+        let req_height: u16 = height.saturating_sub(hint_height + input_height) / 2;
+        let resp_height: u16 = height.saturating_sub(hint_height + input_height + req_height);
 
         let mut list_cont = Block::new(0, 0, left_col_width, list_height, true);
         list_cont.set_state(BlockState::Selected);
 
-        let input_cont = Block::new(left_col_width + 1, 0, right_col_width, input_height, true);
+        let method_cont = Block::new(left_col_width + 1, 0, method_width, input_height, true);
+        let input_cont = Block::new(
+            left_col_width + method_width + 2,
+            0,
+            right_col_width.saturating_sub(method_width + 1),
+            input_height,
+            true,
+        );
         let req_cont = Block::new(left_col_width + 1, input_height, right_col_width, req_height, true);
-        let hint_cont = Block::new(0, list_height, width - 1, hint_height, true);
+        let resp_cont = Block::new(
+            left_col_width + 1,
+            input_height + req_height,
+            right_col_width,
+            resp_height,
+            true,
+        );
+        let hint_cont = Block::new(0, list_height, width.saturating_sub(1), hint_height, true);
 
         Self {
             select_mode: true,
+            width,
+            height,
             list_cont,
+            method_cont,
             input_cont,
             req_cont,
+            resp_cont,
             hint_cont,
         }
     }
 
+    pub fn block_mut(&mut self, pane: Pane) -> &mut Block {
+        match pane {
+            Pane::List => &mut self.list_cont,
+            Pane::Method => &mut self.method_cont,
+            Pane::Input => &mut self.input_cont,
+            Pane::Request => &mut self.req_cont,
+            Pane::Response => &mut self.resp_cont,
+        }
+    }
+
+    pub fn selected_pane(&mut self) -> Option<Pane> {
+        PANES.into_iter().find(|&p| self.block_mut(p).is_selected())
+    }
+
+    pub fn active_pane(&mut self) -> Option<Pane> {
+        PANES.into_iter().find(|&p| self.block_mut(p).is_active())
+    }
+
     pub fn enter_select_mode(&mut self) {
         self.select_mode = true;
 
-        if self.input_cont.is_active() {
-            self.input_cont.set_state(BlockState::Selected);
+        if let Some(pane) = self.active_pane() {
+            self.block_mut(pane).set_state(BlockState::Selected);
         }
+    }
 
-        if self.req_cont.is_active() {
-            self.req_cont.set_state(BlockState::Selected);
-        }
+    /// Block centered on the screen for drawing a dialog on top of the layout.
+    pub fn dialog(&self, title: &str, width: u16, height: u16) -> Block {
+        let width = std::cmp::min(width, self.width.saturating_sub(2));
+        let height = std::cmp::min(height, self.height);
+
+        let mut block = Block::new(
+            (self.width - width) / 2,
+            (self.height - height) / 2,
+            width,
+            height,
+            true,
+        );
+
+        block.set_state(BlockState::Active);
+        block.set_title(Some(title));
+        block
+    }
 
-        if self.list_cont.is_active() {
-            self.list_cont.set_state(BlockState::Selected);
-        }
+    /// Block over everything but the hints, for views that need the whole screen.
+    pub fn overlay(&self, title: &str) -> Block {
+        let mut block = Block::new(0, 0, self.width.saturating_sub(1), self.height.saturating_sub(HINT_HEIGHT), true);
+
+        block.set_state(BlockState::Active);
+        block.set_title(Some(title));
+        block
     }
-    
+
     pub fn render(&mut self, console: &mut Console) {
-        self.list_cont.render(console);
-        self.input_cont.render(console);
-        self.req_cont.render(console);
+        for pane in PANES {
+            self.block_mut(pane).render(console);
+        }
+
         self.hint_cont.render(console);
     }
 
@@ -70,70 +171,33 @@ impl Layout {
                 ..
             }
         ) = e {
-            if self.select_mode {
-                match code {
-                    KeyCode::Up => {
-                        if self.req_cont.is_selected() {
-                            self.req_cont.set_state(BlockState::Inactive);
-                            self.input_cont.set_state(BlockState::Selected);
-                            self.req_cont.render(console);
-                            self.input_cont.render(console);
-                        }
-                    },
-                    KeyCode::Down => {
-                        if self.input_cont.is_selected() {
-                            self.input_cont.set_state(BlockState::Inactive);
-                            self.req_cont.set_state(BlockState::Selected);
-                            self.input_cont.render(console);
-                            self.req_cont.render(console);
-                        }
-                    },
-                    KeyCode::Left => {
-                        if self.req_cont.is_selected() {
-                            self.req_cont.set_state(BlockState::Inactive);
-                            self.list_cont.set_state(BlockState::Selected);
-
-                            self.req_cont.render(console);
-                            self.list_cont.render(console);
-                        } else if self.input_cont.is_selected() {
-                            self.input_cont.set_state(BlockState::Inactive);
-                            self.list_cont.set_state(BlockState::Selected);
-
-                            self.input_cont.render(console);
-                            self.list_cont.render(console);
-                        }
-                    },
-                    KeyCode::Right => {
-                        if self.list_cont.is_selected() {
-                            self.list_cont.set_state(BlockState::Inactive);
-                            self.input_cont.set_state(BlockState::Selected);
-
-                            self.list_cont.render(console);
-                            self.input_cont.render(console);
-                        }
-                    },
-                    KeyCode::Enter => {
-                        if self.list_cont.is_selected() {
-                            self.list_cont.set_state(BlockState::Active);
-                            self.list_cont.render(console);
-                        } 
-
-                        if self.req_cont.is_selected() {
-                            self.req_cont.set_state(BlockState::Active);
-                            self.req_cont.render(console);
-                        }
-
-                        if self.input_cont.is_selected() {
-                            self.input_cont.set_state(BlockState::Active);
-                            self.input_cont.render(console);
-                        }
-
-                        self.select_mode = false;
-                    },
-                    _ => {}
-                }
+            if !self.select_mode {
+                return;
+            }
+
+            let Some(current) = self.selected_pane() else {
+                return;
+            };
+
+            match code {
+                KeyCode::Enter => {
+                    let block = self.block_mut(current);
+                    block.set_state(BlockState::Active);
+                    block.render(console);
+                    self.select_mode = false;
+                },
+                _ => {
+                    if let Some(next) = current.neighbour(code) {
+                        let block = self.block_mut(current);
+                        block.set_state(BlockState::Inactive);
+                        block.render(console);
+
+                        let block = self.block_mut(next);
+                        block.set_state(BlockState::Selected);
+                        block.render(console);
+                    }
+                },
             }
         }
     }
 }
-
@@ -1,14 +1,74 @@
 mod paths;
 mod app;
+mod assertions;
+mod auth;
+mod capture;
+mod codegen;
 mod components;
 mod console;
+mod contract;
+mod data;
+mod diff;
+mod export;
+mod har;
 mod layout;
+mod history;
+mod http;
+mod httpfile;
+mod import;
+mod insomnia;
+mod jsonpath;
+mod mock;
 mod model;
+mod openapi;
+mod postman;
+mod report;
+mod resync;
+mod runner;
+mod schema;
+mod script;
+mod search;
+mod secrets;
+mod tls;
+mod url;
+mod vars;
+
+use std::path::Path;
 
 use app::Application;
+use model::Model;
 
 fn main() -> std::io::Result<()> {
-    let mut app = Application::new();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    if args.first().is_some_and(|a| a == "run") {
+        std::process::exit(runner::run(&args[1..]));
+    }
+
+    if args.first().is_some_and(|a| a == "import") {
+        std::process::exit(import::run(&args[1..]));
+    }
+
+    if args.first().is_some_and(|a| a == "export") {
+        std::process::exit(export::run(&args[1..]));
+    }
+
+    if args.first().is_some_and(|a| a == "mock") {
+        std::process::exit(mock::run(&args[1..]));
+    }
+
+    // `umbrella api.http` opens the file as the collection
+    let opened = match args.first().map(Path::new).filter(|p| httpfile::is_http_file(p)) {
+        Some(path) => Model::open_http(path),
+        None => Model::load_from_disk_or_default(),
+    };
+
+    let model = opened.unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
+
+    let mut app = Application::new(model);
     app.run()
 }
 
@@ -0,0 +1,979 @@
+use std::io::{BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use regex::Regex;
+use serde_json::{json, Map, Value};
+
+use crate::http;
+use crate::openapi::{self, is_json, media_matches};
+use crate::schema;
+use crate::url;
+
+const USAGE: &str = "Usage: umbrella mock SPEC [--port N] [--host ADDRESS]";
+
+const DEFAULT_HOST: &str = "127.0.0.1";
+const DEFAULT_PORT: u16 = 4010;
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Deeper schemas are taken for cycles, synthesized data stops there.
+const MAX_DEPTH: usize = 16;
+
+/// Path parameters of a request by name.
+type PathValues = Vec<(String, String)>;
+
+const OPERATIONS: [&str; 8] = ["get", "put", "post", "delete", "options", "head", "patch", "trace"];
+
+/// An operation of the spec and how its path matches request paths.
+struct Route {
+    method: String,
+    template: String,
+    pattern: Regex,
+    /// Names of the path parameters, in the order of the pattern's groups
+    names: Vec<String>,
+    operation: Value,
+    /// Parameters of the path item and the operation, the operation's win
+    parameters: Vec<Value>,
+}
+
+/// A request received by the mock server.
+pub struct MockRequest {
+    pub method: String,
+    pub target: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl MockRequest {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+pub struct MockResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+    /// Operation that answered, for the log
+    pub operation: Option<String>,
+    /// Why the request doesn't match the spec
+    pub errors: Vec<String>,
+}
+
+impl MockResponse {
+    fn error(status: u16, message: &str, errors: Vec<String>) -> Self {
+        let body = json!({ "error": message, "details": errors });
+
+        Self {
+            status,
+            headers: vec![(String::from("Content-Type"), String::from("application/json"))],
+            body: serde_json::to_string_pretty(&body).unwrap_or_default(),
+            operation: None,
+            errors,
+        }
+    }
+}
+
+/// Routes of an OpenAPI document answering with its examples.
+///
+/// Responses use the example of the media type, the first of its `examples`,
+/// the schema's example, or data synthesized from the schema, in that order.
+/// `Prefer: code=404` and `Prefer: example=name` pick another response.
+pub struct MockSpec {
+    root: Value,
+    pub title: String,
+    /// Path of the first server, requests are matched with and without it
+    pub base_path: String,
+    routes: Vec<Route>,
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        202 => "Accepted",
+        204 => "No Content",
+        301 => "Moved Permanently",
+        302 => "Found",
+        304 => "Not Modified",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        415 => "Unsupported Media Type",
+        422 => "Unprocessable Entity",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        501 => "Not Implemented",
+        503 => "Service Unavailable",
+        _ => "",
+    }
+}
+
+/// Path part of a server URL, without the trailing slash.
+fn server_path(url: &str) -> String {
+    let path = match url.split_once("://") {
+        Some((_, rest)) => rest.find('/').map(|i| &rest[i..]).unwrap_or_default(),
+        None => url,
+    };
+
+    path.trim_end_matches('/').to_string()
+}
+
+/// `/pets/{petId}` as a pattern capturing the parameter.
+fn path_pattern(template: &str) -> (Regex, Vec<String>) {
+    let placeholder = Regex::new(r"\{([^}/]+)\}").expect("Invalid placeholder pattern");
+    let mut pattern = String::from("^");
+    let mut names = Vec::new();
+    let mut last = 0;
+
+    for captures in placeholder.captures_iter(template) {
+        let whole = captures.get(0).expect("Match has a group 0");
+        pattern.push_str(&regex::escape(&template[last..whole.start()]));
+        pattern.push_str("([^/]+)");
+        names.push(captures[1].to_string());
+        last = whole.end();
+    }
+
+    pattern.push_str(&regex::escape(&template[last..]));
+    pattern.push('$');
+
+    (Regex::new(&pattern).expect("Escaped path is a valid pattern"), names)
+}
+
+/// Raw parameter values typed after their schema, what isn't valid stays a string.
+fn typed_value(schema: &Value, raw: &str) -> Value {
+    match schema.get("type").and_then(|t| t.as_str()) {
+        Some("integer") => raw.parse::<i64>().map(Value::from).unwrap_or(Value::from(raw)),
+        Some("number") => raw.parse::<f64>().map(Value::from).unwrap_or(Value::from(raw)),
+        Some("boolean") => match raw {
+            "true" => Value::Bool(true),
+            "false" => Value::Bool(false),
+            _ => Value::from(raw),
+        },
+        _ => Value::from(raw),
+    }
+}
+
+/// Validation errors start at `$`, the name of what was validated reads better.
+fn relabel(errors: Vec<String>, label: &str) -> Vec<String> {
+    errors.into_iter().map(|e| e.replacen('$', label, 1)).collect()
+}
+
+impl MockSpec {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        Self::new(openapi::load(path)?)
+    }
+
+    pub fn new(root: Value) -> Result<Self, String> {
+        let paths = root
+            .get("paths")
+            .and_then(|p| p.as_object())
+            .ok_or("The spec has no paths")?;
+
+        let mut routes = Vec::new();
+
+        for (template, item) in paths {
+            let shared: Vec<Value> = item
+                .get("parameters")
+                .and_then(|p| p.as_array())
+                .cloned()
+                .unwrap_or_default();
+
+            for method in OPERATIONS {
+                let Some(operation) = item.get(method) else {
+                    continue;
+                };
+
+                let mut parameters: Vec<Value> = Vec::new();
+                let own: Vec<Value> = operation
+                    .get("parameters")
+                    .and_then(|p| p.as_array())
+                    .cloned()
+                    .unwrap_or_default();
+
+                for parameter in shared.iter().chain(own.iter()) {
+                    let parameter = openapi::resolve(&root, parameter).clone();
+                    let key = |p: &Value| (p.get("name").cloned(), p.get("in").cloned());
+
+                    parameters.retain(|p| key(p) != key(&parameter));
+                    parameters.push(parameter);
+                }
+
+                let (pattern, names) = path_pattern(template);
+
+                routes.push(Route {
+                    method: method.to_uppercase(),
+                    template: template.clone(),
+                    pattern,
+                    names,
+                    operation: operation.clone(),
+                    parameters,
+                });
+            }
+        }
+
+        // Literal paths win over templated ones, /pets/mine before /pets/{id}
+        routes.sort_by_key(|r| r.names.len());
+
+        let base_path = root
+            .pointer("/servers/0/url")
+            .and_then(|u| u.as_str())
+            .map(server_path)
+            .unwrap_or_default();
+
+        let title = root
+            .pointer("/info/title")
+            .and_then(|t| t.as_str())
+            .unwrap_or_default()
+            .to_string();
+
+        Ok(Self { root, title, base_path, routes })
+    }
+
+    /// Method and path of every route, as requests reach them.
+    pub fn endpoints(&self) -> Vec<(String, String)> {
+        let mut endpoints: Vec<(String, String)> = self.routes
+            .iter()
+            .map(|r| (r.method.clone(), format!("{}{}", self.base_path, r.template)))
+            .collect();
+
+        endpoints.sort_by(|a, b| a.1.cmp(&b.1));
+        endpoints
+    }
+
+    fn resolve<'a>(&'a self, value: &'a Value) -> &'a Value {
+        openapi::resolve(&self.root, value)
+    }
+
+    /// The route of the request and its path parameters, or the status telling why there's none.
+    fn find(&self, method: &str, path: &str) -> Result<(&Route, PathValues), u16> {
+        let path = match path.strip_prefix(self.base_path.as_str()) {
+            Some(rest) if !self.base_path.is_empty() && (rest.is_empty() || rest.starts_with('/')) => rest,
+            _ => path,
+        };
+
+        let path = if path.is_empty() { "/" } else { path };
+        let matching: Vec<&Route> = self.routes.iter().filter(|r| r.pattern.is_match(path)).collect();
+
+        if matching.is_empty() {
+            return Err(404);
+        }
+
+        // A HEAD is answered like a GET, without the body
+        let route = matching
+            .iter()
+            .find(|r| r.method == method)
+            .or_else(|| matching.iter().find(|r| method == "HEAD" && r.method == "GET"))
+            .ok_or(405u16)?;
+
+        let captures = route.pattern.captures(path).ok_or(404u16)?;
+        let values = route.names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (name.clone(), url::path_decode(&captures[i + 1])))
+            .collect();
+
+        Ok((route, values))
+    }
+
+    fn parameter_errors(&self, route: &Route, path_values: &PathValues, request: &MockRequest) -> Vec<String> {
+        let query = url::parse_query(url::split_url(&request.target).query.unwrap_or_default());
+        let cookies: Vec<(String, String)> = request
+            .header("Cookie")
+            .unwrap_or_default()
+            .split(';')
+            .filter_map(|c| c.split_once('='))
+            .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+            .collect();
+
+        let mut errors = Vec::new();
+
+        for parameter in route.parameters.iter() {
+            let name = parameter.get("name").and_then(|n| n.as_str()).unwrap_or_default();
+            let location = parameter.get("in").and_then(|n| n.as_str()).unwrap_or_default();
+            let required = location == "path" || parameter.get("required").and_then(|r| r.as_bool()) == Some(true);
+
+            let values: Vec<String> = match location {
+                "path" => path_values.iter().filter(|(n, _)| n == name).map(|(_, v)| v.clone()).collect(),
+                "query" => query.iter().filter(|(n, _)| n == name).map(|(_, v)| v.clone()).collect(),
+                "header" => request.headers
+                    .iter()
+                    .filter(|(n, _)| n.eq_ignore_ascii_case(name))
+                    .map(|(_, v)| v.clone())
+                    .collect(),
+                "cookie" => cookies.iter().filter(|(n, _)| n == name).map(|(_, v)| v.clone()).collect(),
+                _ => continue,
+            };
+
+            let label = format!("{} parameter {}", location, name);
+
+            if values.is_empty() {
+                if required {
+                    errors.push(format!("{} is required", label));
+                }
+
+                continue;
+            }
+
+            let Some(param_schema) = parameter.get("schema").map(|s| self.resolve(s)) else {
+                continue;
+            };
+
+            let value = if param_schema.get("type").and_then(|t| t.as_str()) == Some("array") {
+                let items = param_schema.get("items").map(|i| self.resolve(i)).unwrap_or(&Value::Null);
+
+                // Repeated keys or a comma separated list
+                let raw: Vec<&str> = if values.len() > 1 {
+                    values.iter().map(String::as_str).collect()
+                } else {
+                    values[0].split(',').collect()
+                };
+
+                Value::Array(raw.into_iter().map(|v| typed_value(items, v)).collect())
+            } else {
+                typed_value(param_schema, &values[0])
+            };
+
+            errors.extend(relabel(schema::validate_in(&self.root, param_schema, &value), &label));
+        }
+
+        errors
+    }
+
+    fn body_errors(&self, route: &Route, request: &MockRequest) -> Vec<String> {
+        let Some(request_body) = route.operation.get("requestBody").map(|b| self.resolve(b)) else {
+            return Vec::new();
+        };
+
+        if request.body.is_empty() {
+            return match request_body.get("required").and_then(|r| r.as_bool()) {
+                Some(true) => vec![String::from("request body is required")],
+                _ => Vec::new(),
+            };
+        }
+
+        let Some(content) = request_body.get("content").and_then(|c| c.as_object()) else {
+            return Vec::new();
+        };
+
+        let content_type = request.header("Content-Type");
+
+        let media = match content_type {
+            Some(actual) => content.iter().find(|(listed, _)| media_matches(listed, actual)),
+            None => content.iter().find(|(listed, _)| is_json(listed)).or(content.iter().next()),
+        };
+
+        let Some((media_type, media)) = media else {
+            return vec![format!(
+                "content type {} is not one of {}",
+                content_type.unwrap_or_default(),
+                content.keys().cloned().collect::<Vec<String>>().join(", "),
+            )];
+        };
+
+        let Some(body_schema) = media.get("schema") else {
+            return Vec::new();
+        };
+
+        if !is_json(media_type) {
+            return Vec::new();
+        }
+
+        match serde_json::from_str::<Value>(&request.body) {
+            Ok(body) => relabel(schema::validate_in(&self.root, body_schema, &body), "body"),
+            Err(e) => vec![format!("body is not JSON: {}", e)],
+        }
+    }
+
+    /// Status of the response to send: the one asked for with `Prefer: code=`,
+    /// else the first success, else the default.
+    fn pick_response<'a>(&'a self, route: &'a Route, preferred: Option<&str>) -> Option<(u16, &'a Value)> {
+        let responses = route.operation.get("responses").and_then(|r| r.as_object())?;
+        let status = |code: &str| code.replace(['X', 'x'], "0").parse::<u16>().ok();
+
+        let mut codes: Vec<&String> = responses.keys().filter(|c| status(c).is_some()).collect();
+        codes.sort();
+
+        // A listed status, its range like 4XX, else the default answers with the status asked for
+        if let Some(wanted) = preferred.and_then(|p| p.parse::<u16>().ok()) {
+            let range = format!("{}XX", wanted / 100);
+
+            let code = codes
+                .iter()
+                .find(|c| status(c) == Some(wanted))
+                .or_else(|| codes.iter().find(|c| c.eq_ignore_ascii_case(&range)))
+                .map(|c| c.as_str())
+                .or(responses.contains_key("default").then_some("default"));
+
+            if let Some(code) = code {
+                return Some((wanted, self.resolve(&responses[code])));
+            }
+        }
+
+        let code = codes
+            .iter()
+            .find(|c| c.starts_with('2'))
+            .map(|c| c.as_str())
+            .or(responses.contains_key("default").then_some("default"))
+            .or(codes.first().map(|c| c.as_str()))?;
+
+        // 2XX ranges answer with the first status of the range
+        let status = status(code).unwrap_or(200);
+
+        Some((status, self.resolve(&responses[code])))
+    }
+
+    /// The example of a media type or of its schema, else data following the schema.
+    fn example(&self, media: &Value, preferred: Option<&str>) -> Option<Value> {
+        if let Some(example) = media.get("example") {
+            return Some(example.clone());
+        }
+
+        if let Some(examples) = media.get("examples").and_then(|e| e.as_object()) {
+            let example = preferred
+                .and_then(|name| examples.get(name))
+                .or(examples.values().next())
+                .map(|e| self.resolve(e));
+
+            if let Some(value) = example.and_then(|e| e.get("value")) {
+                return Some(value.clone());
+            }
+        }
+
+        media.get("schema").map(|s| self.synthesize(s, 0))
+    }
+
+    /// A value valid for the schema, built from its examples, defaults, enums and formats.
+    pub fn synthesize(&self, schema: &Value, depth: usize) -> Value {
+        if depth > MAX_DEPTH {
+            return Value::Null;
+        }
+
+        let schema = self.resolve(schema);
+
+        let Some(object) = schema.as_object() else {
+            return Value::Null;
+        };
+
+        for key in ["example", "default", "const"] {
+            if let Some(value) = object.get(key) {
+                return value.clone();
+            }
+        }
+
+        if let Some(first) = object.get("enum").and_then(|e| e.as_array()).and_then(|e| e.first()) {
+            return first.clone();
+        }
+
+        if let Some(all) = object.get("allOf").and_then(|a| a.as_array()) {
+            let mut merged = Map::new();
+
+            for part in all {
+                match self.synthesize(part, depth + 1) {
+                    Value::Object(map) => merged.extend(map),
+                    other if merged.is_empty() => return other,
+                    _ => {},
+                }
+            }
+
+            return Value::Object(merged);
+        }
+
+        for key in ["oneOf", "anyOf"] {
+            if let Some(first) = object.get(key).and_then(|a| a.as_array()).and_then(|a| a.first()) {
+                return self.synthesize(first, depth + 1);
+            }
+        }
+
+        let kind = match object.get("type") {
+            Some(Value::String(t)) => t.as_str(),
+            Some(Value::Array(ts)) => ts.iter().filter_map(|t| t.as_str()).find(|t| *t != "null").unwrap_or("null"),
+            _ if object.contains_key("properties") => "object",
+            _ if object.contains_key("items") => "array",
+            _ => "",
+        };
+
+        match kind {
+            "object" => {
+                let mut map = Map::new();
+
+                if let Some(properties) = object.get("properties").and_then(|p| p.as_object()) {
+                    for (name, property) in properties {
+                        map.insert(name.clone(), self.synthesize(property, depth + 1));
+                    }
+                } else if let Some(additional) = object.get("additionalProperties").filter(|a| a.is_object()) {
+                    map.insert(String::from("key"), self.synthesize(additional, depth + 1));
+                }
+
+                Value::Object(map)
+            },
+            "array" => {
+                let min = object.get("minItems").and_then(|m| m.as_u64()).unwrap_or(1).max(1);
+                let count = object.get("maxItems").and_then(|m| m.as_u64()).map_or(min, |max| min.min(max));
+                let item = object.get("items").map(|i| self.synthesize(i, depth + 1)).unwrap_or(Value::Null);
+
+                Value::Array(vec![item; count as usize])
+            },
+            "string" => Value::from(synthesize_string(object)),
+            "integer" => Value::from(synthesize_number(object).ceil() as i64),
+            "number" => Value::from(synthesize_number(object)),
+            "boolean" => Value::Bool(true),
+            _ => Value::Null,
+        }
+    }
+
+    /// Answers the request: an error when it doesn't fit the spec, else the operation's response.
+    pub fn handle(&self, request: &MockRequest) -> MockResponse {
+        let path = url::split_url(&request.target).base.to_string();
+        let preferences: Vec<(&str, &str)> = request
+            .header("Prefer")
+            .unwrap_or_default()
+            .split([',', ';'])
+            .filter_map(|p| p.split_once('='))
+            .map(|(k, v)| (k.trim(), v.trim().trim_matches('"')))
+            .collect();
+
+        let preferred = |name: &str| preferences.iter().find(|(k, _)| *k == name).map(|(_, v)| *v);
+
+        let (route, path_values) = match self.find(&request.method, &path) {
+            Ok(found) => found,
+            // Browsers ask before cross-origin requests, the mock allows them all
+            Err(405) if request.method == "OPTIONS" => {
+                return MockResponse {
+                    status: 204,
+                    headers: vec![
+                        (String::from("Access-Control-Allow-Methods"), OPERATIONS.join(", ").to_uppercase()),
+                        (String::from("Access-Control-Allow-Headers"), String::from("*")),
+                    ],
+                    body: String::new(),
+                    operation: None,
+                    errors: Vec::new(),
+                };
+            },
+            Err(405) => return MockResponse::error(405, "The method is not defined for this path", Vec::new()),
+            Err(status) => return MockResponse::error(status, "No path of the spec matches", Vec::new()),
+        };
+
+        let operation = route.operation.get("operationId").and_then(|o| o.as_str()).map(String::from);
+
+        let mut errors = self.parameter_errors(route, &path_values, request);
+        errors.extend(self.body_errors(route, request));
+
+        if !errors.is_empty() {
+            return MockResponse {
+                operation,
+                ..MockResponse::error(400, "The request doesn't match the spec", errors)
+            };
+        }
+
+        let Some((status, response)) = self.pick_response(route, preferred("code")) else {
+            return MockResponse {
+                operation,
+                ..MockResponse::error(501, "The operation has no responses", Vec::new())
+            };
+        };
+
+        let mut headers = Vec::new();
+
+        if let Some(defined) = response.get("headers").and_then(|h| h.as_object()) {
+            for (name, header) in defined {
+                let header = self.resolve(header);
+                let value = match header.get("example").cloned() {
+                    Some(example) => example,
+                    None => header.get("schema").map(|s| self.synthesize(s, 0)).unwrap_or_default(),
+                };
+
+                let value = match value {
+                    Value::String(s) => s,
+                    other => other.to_string(),
+                };
+
+                if !name.eq_ignore_ascii_case("Content-Type") && !name.eq_ignore_ascii_case("Content-Length") {
+                    headers.push((name.clone(), value));
+                }
+            }
+        }
+
+        let content = response.get("content").and_then(|c| c.as_object());
+        let accept = request.header("Accept").unwrap_or("*/*");
+
+        let media = content.and_then(|content| {
+            content
+                .iter()
+                .find(|(listed, _)| accept.split(',').any(|a| media_matches(a, listed)) && is_json(listed))
+                .or_else(|| content.iter().find(|(listed, _)| accept.split(',').any(|a| media_matches(a, listed))))
+                .or(content.iter().next())
+        });
+
+        let body = match media {
+            Some((media_type, media)) => {
+                headers.push((String::from("Content-Type"), media_type.clone()));
+
+                match self.example(media, preferred("example")) {
+                    Some(Value::String(text)) if !is_json(media_type) => text,
+                    Some(value) => serde_json::to_string_pretty(&value).unwrap_or_default(),
+                    None => String::new(),
+                }
+            },
+            None => String::new(),
+        };
+
+        MockResponse { status, headers, body, operation, errors }
+    }
+}
+
+fn synthesize_string(schema: &Map<String, Value>) -> String {
+    let format = schema.get("format").and_then(|f| f.as_str()).unwrap_or_default();
+
+    let mut text = String::from(match format {
+        "date-time" => "2024-01-01T12:00:00Z",
+        "date" => "2024-01-01",
+        "time" => "12:00:00",
+        "email" => "user@example.com",
+        "uuid" => "3fa85f64-5717-4562-b3fc-2c963f66afa6",
+        "uri" | "url" => "https://example.com",
+        "hostname" => "example.com",
+        "ipv4" => "192.0.2.1",
+        "ipv6" => "2001:db8::1",
+        "byte" => "c3RyaW5n",
+        _ => "string",
+    });
+
+    let min = schema.get("minLength").and_then(|m| m.as_u64()).unwrap_or_default() as usize;
+    let max = schema.get("maxLength").and_then(|m| m.as_u64()).map(|m| m as usize);
+
+    while text.chars().count() < min {
+        text.push('x');
+    }
+
+    if let Some(max) = max {
+        text = text.chars().take(max).collect();
+    }
+
+    text
+}
+
+fn synthesize_number(schema: &Map<String, Value>) -> f64 {
+    let bound = |key: &str| schema.get(key).and_then(|b| b.as_f64());
+    let exclusive = |key: &str| schema.get(key).and_then(|b| b.as_bool()) == Some(true);
+
+    match (bound("minimum"), bound("exclusiveMinimum"), bound("maximum")) {
+        (Some(min), _, _) if exclusive("exclusiveMinimum") => min + 1.0,
+        (Some(min), _, _) => min,
+        (None, Some(min), _) => min + 1.0,
+        (None, None, Some(max)) if max < 0.0 => max,
+        _ => 0.0,
+    }
+}
+
+fn read_request(stream: &TcpStream) -> Result<MockRequest, String> {
+    let mut reader = BufReader::new(stream);
+    let (start_line, headers) = http::read_head(&mut reader)?;
+
+    let mut parts = start_line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Err(format!("Invalid request line: {}", start_line));
+    };
+
+    let body = http::read_body(&mut reader, &headers, false)?;
+
+    Ok(MockRequest {
+        method: method.to_uppercase(),
+        target: target.to_string(),
+        headers,
+        body: String::from_utf8_lossy(&body).to_string(),
+    })
+}
+
+fn write_response(mut stream: &TcpStream, method: &str, response: &MockResponse) -> std::io::Result<()> {
+    let mut head = format!("HTTP/1.1 {} {}\r\n", response.status, reason(response.status));
+
+    for (name, value) in response.headers.iter() {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+
+    head.push_str("Access-Control-Allow-Origin: *\r\n");
+    head.push_str(&format!("Content-Length: {}\r\nConnection: close\r\n\r\n", response.body.len()));
+
+    stream.write_all(head.as_bytes())?;
+
+    if method != "HEAD" {
+        stream.write_all(response.body.as_bytes())?;
+    }
+
+    stream.flush()
+}
+
+fn serve(spec: &MockSpec, stream: TcpStream) {
+    let started = Instant::now();
+    stream.set_read_timeout(Some(READ_TIMEOUT)).ok();
+
+    let request = match read_request(&stream) {
+        Ok(request) => request,
+        Err(e) => {
+            eprintln!("Bad request: {}", e);
+            return;
+        },
+    };
+
+    let response = spec.handle(&request);
+
+    if let Err(e) = write_response(&stream, &request.method, &response) {
+        eprintln!("Couldn't answer {} {}: {}", request.method, request.target, e);
+    }
+
+    // Lines of one hit are printed together, other threads log too
+    let mut log = format!(
+        "{} {} → {} {}{} · {} ms",
+        request.method,
+        request.target,
+        response.status,
+        reason(response.status),
+        response.operation.map(|o| format!(" · {}", o)).unwrap_or_default(),
+        started.elapsed().as_millis(),
+    );
+
+    for error in response.errors.iter() {
+        log.push_str(&format!("\n  ✗ {}", error));
+    }
+
+    println!("{}", log);
+}
+
+/// Serves the spec until interrupted.
+///
+/// Returns the exit code: 2 when the arguments are wrong, 1 when the spec
+/// can't be read or the port can't be bound.
+pub fn run(args: &[String]) -> i32 {
+    let mut spec_path = None;
+    let mut host = String::from(DEFAULT_HOST);
+    let mut port = DEFAULT_PORT;
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--port" => match args.next().map(|p| p.parse::<u16>()) {
+                Some(Ok(p)) => port = p,
+                _ => {
+                    eprintln!("--port needs a port number\n{}", USAGE);
+                    return 2;
+                },
+            },
+            "--host" => match args.next() {
+                Some(h) => host = h.clone(),
+                None => {
+                    eprintln!("--host needs an address\n{}", USAGE);
+                    return 2;
+                },
+            },
+            flag if flag.starts_with("--") => {
+                eprintln!("Unknown option {}\n{}", flag, USAGE);
+                return 2;
+            },
+            path if spec_path.is_none() => spec_path = Some(PathBuf::from(path)),
+            extra => {
+                eprintln!("Unexpected argument {}\n{}", extra, USAGE);
+                return 2;
+            },
+        }
+    }
+
+    let Some(spec_path) = spec_path else {
+        eprintln!("{}", USAGE);
+        return 2;
+    };
+
+    let spec = match MockSpec::load(&spec_path) {
+        Ok(spec) => Arc::new(spec),
+        Err(e) => {
+            eprintln!("{}", e);
+            return 1;
+        },
+    };
+
+    let listener = match TcpListener::bind((host.as_str(), port)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("Couldn't listen on {}:{}: {}", host, port, e);
+            return 1;
+        },
+    };
+
+    println!("Mocking {} on http://{}:{}{}", spec.title, host, port, spec.base_path);
+
+    for (method, path) in spec.endpoints() {
+        println!("  {:<7} {}", method, path);
+    }
+
+    println!();
+
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                let spec = spec.clone();
+                thread::spawn(move || serve(&spec, stream));
+            },
+            Err(e) => eprintln!("Couldn't accept a connection: {}", e),
+        }
+    }
+
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec() -> MockSpec {
+        MockSpec::new(json!({
+            "info": { "title": "Pets" },
+            "servers": [{ "url": "https://pets.example.com/v1" }],
+            "paths": {
+                "/pets/{id}": {
+                    "parameters": [{ "name": "id", "in": "path", "schema": { "type": "integer" } }],
+                    "get": {
+                        "operationId": "getPet",
+                        "responses": {
+                            "200": { "content": { "application/json": {
+                                "examples": {
+                                    "cat": { "value": { "name": "Tom" } },
+                                    "dog": { "value": { "name": "Rex" } },
+                                },
+                            } } },
+                            "404": { "content": { "application/json": { "example": { "error": "gone" } } } },
+                            "5XX": { "description": "Down" },
+                        },
+                    },
+                },
+                "/pets/mine": {
+                    "get": { "operationId": "myPets", "responses": { "200": { "description": "Mine" } } },
+                },
+                "/pets": {
+                    "post": {
+                        "operationId": "addPet",
+                        "requestBody": {
+                            "required": true,
+                            "content": { "application/json": { "schema": { "$ref": "#/components/schemas/Pet" } } },
+                        },
+                        "responses": { "201": { "content": { "application/json": {
+                            "schema": { "$ref": "#/components/schemas/Pet" },
+                        } } } },
+                    },
+                },
+            },
+            "components": { "schemas": { "Pet": {
+                "type": "object",
+                "required": ["name"],
+                "properties": {
+                    "name": { "type": "string", "minLength": 8 },
+                    "kind": { "type": "string", "enum": ["cat", "dog"] },
+                    "tags": { "type": "array", "items": { "type": "string", "format": "uuid" }, "minItems": 2 },
+                    "born": { "type": "string", "format": "date" },
+                    "age": { "type": "integer", "minimum": 1 },
+                },
+            } } },
+        })).unwrap()
+    }
+
+    fn request(method: &str, target: &str, headers: &[(&str, &str)], body: &str) -> MockRequest {
+        MockRequest {
+            method: method.to_string(),
+            target: target.to_string(),
+            headers: headers.iter().map(|(n, v)| (n.to_string(), v.to_string())).collect(),
+            body: body.to_string(),
+        }
+    }
+
+    fn body(response: &MockResponse) -> Value {
+        serde_json::from_str(&response.body).unwrap()
+    }
+
+    #[test]
+    fn requests_are_routed_with_and_without_the_base_path() {
+        let spec = spec();
+
+        let mine = spec.handle(&request("GET", "/v1/pets/mine", &[], ""));
+        let pet = spec.handle(&request("GET", "/pets/7?x=1", &[], ""));
+        let head = spec.handle(&request("HEAD", "/v1/pets/7", &[], ""));
+
+        assert_eq!(mine.operation.as_deref(), Some("myPets"));
+        assert_eq!((pet.status, pet.operation.as_deref()), (200, Some("getPet")));
+        assert_eq!(head.operation.as_deref(), Some("getPet"));
+        assert_eq!(spec.handle(&request("GET", "/owners", &[], "")).status, 404);
+        assert_eq!(spec.handle(&request("DELETE", "/pets/7", &[], "")).status, 405);
+        assert_eq!(spec.handle(&request("OPTIONS", "/pets/7", &[], "")).status, 204);
+
+        let invalid = spec.handle(&request("GET", "/pets/seven", &[], ""));
+        assert_eq!(invalid.status, 400);
+        assert!(invalid.errors[0].starts_with("path parameter id"));
+    }
+
+    #[test]
+    fn prefer_picks_the_status_and_the_example() {
+        let spec = spec();
+        let prefer = |value: &str| spec.handle(&request("GET", "/pets/7", &[("Prefer", value)], ""));
+
+        assert_eq!(body(&prefer("")), json!({ "name": "Tom" }));
+        assert_eq!(body(&prefer("example=dog")), json!({ "name": "Rex" }));
+
+        let missing = prefer("code=404");
+        assert_eq!((missing.status, body(&missing)), (404, json!({ "error": "gone" })));
+
+        // Answered by the 5XX range, with the status asked for
+        let down = prefer("code=503, example=dog");
+        assert_eq!((down.status, down.body.as_str()), (503, ""));
+    }
+
+    #[test]
+    fn bodies_not_matching_the_schema_are_refused() {
+        let spec = spec();
+        let post = |headers: &[(&str, &str)], body: &str| spec.handle(&request("POST", "/pets", headers, body));
+
+        assert_eq!(post(&[], "").errors, ["request body is required"]);
+        assert!(post(&[], "{").errors[0].starts_with("body is not JSON"));
+        assert_eq!(post(&[("Content-Type", "text/plain")], "Tom").errors, [
+            "content type text/plain is not one of application/json",
+        ]);
+
+        let short = post(&[("Content-Type", "application/json")], r#"{ "name": "Tom" }"#);
+        assert_eq!(short.status, 400);
+        assert_eq!(short.errors.len(), 1);
+        assert!(short.errors[0].starts_with("body.name"));
+        assert_eq!(body(&short)["details"], json!(short.errors));
+
+        assert_eq!(post(&[], r#"{ "name": "Tom the cat" }"#).status, 201);
+    }
+
+    #[test]
+    fn synthesized_data_follows_the_schema() {
+        let spec = spec();
+        let pet = spec.synthesize(&json!({ "$ref": "#/components/schemas/Pet" }), 0);
+
+        assert_eq!(pet, json!({
+            "name": "stringxx",
+            "kind": "cat",
+            "tags": ["3fa85f64-5717-4562-b3fc-2c963f66afa6", "3fa85f64-5717-4562-b3fc-2c963f66afa6"],
+            "born": "2024-01-01",
+            "age": 1,
+        }));
+        assert!(schema::validate_in(&spec.root, &json!({ "$ref": "#/components/schemas/Pet" }), &pet).is_empty());
+
+        // The created pet comes from the schema, there's no example
+        let created = spec.handle(&request("POST", "/pets", &[], r#"{ "name": "Tom the cat" }"#));
+        assert_eq!(body(&created), pet);
+
+        let cycle = MockSpec::new(json!({ "paths": {}, "components": { "schemas": { "Node": {
+            "type": "object", "properties": { "next": { "$ref": "#/components/schemas/Node" } },
+        } } } })).unwrap();
+        assert!(cycle.synthesize(&json!({ "$ref": "#/components/schemas/Node" }), 0).is_object());
+    }
+}
@@ -1,47 +1,207 @@
+use std::collections::BTreeMap;
 use std::fs::{File, self};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
 use std::sync::{Arc, RwLock};
-use std::path::PathBuf;
+use std::thread;
+use std::path::{Path, PathBuf};
 use serde::{Deserialize, Serialize};
-use oapi::{OApi, OApiOperation};
-use sppparse::{SparsePointer, SparseRoot};
 
+use crate::assertions::{self, Assertion, AssertionResult};
+use crate::auth::{self, Auth, TokenCache};
+use crate::capture::{self, Capture, CaptureResult};
+use crate::contract::{SpecCache, SpecOperation};
+use crate::resync::SyncChange;
+use crate::history::{History, HistoryEntry};
+use crate::http::{self, BodyUsage, HttpRequest, HttpResponse};
+use crate::httpfile::HttpFile;
+use crate::import::{self, Imported};
 use crate::paths::Paths;
+use crate::script::{self, ScriptContext};
+use crate::secrets::SecretStore;
+use crate::tls::{self, TlsOptions};
+use crate::{url, vars};
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct PathParam {
     pub name: String,
     pub param_type: String,
+    #[serde(default)]
+    pub value: String,
+    #[serde(default = "enabled_by_default")]
+    pub required: bool,
+}
+
+impl PathParam {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            param_type: String::from("string"),
+            value: String::new(),
+            required: true,
+        }
+    }
+
+    /// Checks the value against `param_type`, unknown types accept any value.
+    pub fn validate(&self) -> Result<(), String> {
+        let value = self.value.trim();
+
+        if value.is_empty() {
+            return Ok(());
+        }
+
+        let valid = match self.param_type.to_lowercase().as_str() {
+            "integer" => value.parse::<i64>().is_ok(),
+            "number" => value.parse::<f64>().is_ok(),
+            "boolean" => value == "true" || value == "false",
+            "uuid" => is_uuid(value),
+            _ => true,
+        };
+
+        if valid {
+            Ok(())
+        } else {
+            Err(format!("{} must be {}, got \"{}\"", self.name, self.param_type, value))
+        }
+    }
+}
+
+/// Canonical 8-4-4-4-12 hex form.
+fn is_uuid(value: &str) -> bool {
+    let groups: Vec<&str> = value.split('-').collect();
+
+    groups.len() == 5
+        && groups
+            .iter()
+            .zip([8, 4, 4, 4, 12])
+            .all(|(g, len)| g.len() == len && g.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(from = "QueryParamRepr")]
+pub struct QueryParam {
+    pub key: String,
+    pub value: String,
+    pub enabled: bool,
+}
+
+/// Query params used to be stored as bare names, both forms are accepted.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum QueryParamRepr {
+    Name(String),
+    Full {
+        key: String,
+        #[serde(default)]
+        value: String,
+        #[serde(default = "enabled_by_default")]
+        enabled: bool,
+    },
+}
+
+/// Serde default of `enabled` fields, rows are on unless saved as off.
+pub fn enabled_by_default() -> bool {
+    true
+}
+
+impl From<QueryParamRepr> for QueryParam {
+    fn from(repr: QueryParamRepr) -> Self {
+        match repr {
+            QueryParamRepr::Name(key) => QueryParam {
+                key,
+                value: String::new(),
+                enabled: false,
+            },
+            QueryParamRepr::Full { key, value, enabled } => QueryParam { key, value, enabled },
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Header {
+    pub name: String,
+    pub value: String,
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
+}
+
+/// Random id that stays with the request through renames and moves.
+pub fn new_request_id() -> String {
+    let mut bytes = [0; 8];
+    getrandom::getrandom(&mut bytes).expect("Couldn't generate a request id");
+
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
 }
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Request {
+    /// Empty in configs saved before ids existed, filled in on load
+    #[serde(default)]
+    pub id: String,
     pub method: String,
     pub title: String,
     pub url: String,
     pub body: String,
-    pub query_params: Vec<String>,
+    pub query_params: Vec<QueryParam>,
     pub path_params: Vec<PathParam>,
+    /// Sent as they are, a Content-Type one replaces the JSON one
+    #[serde(default)]
+    pub headers: Vec<Header>,
+    #[serde(default)]
+    pub folder: Option<String>,
+    #[serde(default)]
+    pub auth: Auth,
+    #[serde(default)]
+    pub assertions: Vec<Assertion>,
+    #[serde(default)]
+    pub captures: Vec<Capture>,
+    /// Rhai script run on the outgoing request
+    #[serde(default)]
+    pub pre_script: String,
+    /// Rhai script run on the response
+    #[serde(default)]
+    pub post_script: String,
+    /// Where the request was imported from, responses are checked against it
+    #[serde(default)]
+    pub operation: Option<SpecOperation>,
 }
 
 impl Request {
     pub fn new_default() -> Request {
         Request {
+            id: new_request_id(),
             method: String::new(),
             title: String::new(),
             url: String::new(),
             body: String::new(),
             query_params: Vec::new(),
             path_params: Vec::new(),
+            headers: Vec::new(),
+            folder: None,
+            auth: Auth::Inherit,
+            assertions: Vec::new(),
+            captures: Vec::new(),
+            pre_script: String::new(),
+            post_script: String::new(),
+            operation: None,
         }
     }
     pub fn new(method: &str, title: &str, url: &str, body: &str) -> Request {
         Request {
+            id: new_request_id(),
             method: method.to_string(),
             title: title.to_string(),
             url: url.to_string(),
             body: body.to_string(),
             query_params: Vec::new(),
             path_params: Vec::new(),
+            headers: Vec::new(),
+            folder: None,
+            auth: Auth::Inherit,
+            assertions: Vec::new(),
+            captures: Vec::new(),
+            pre_script: String::new(),
+            post_script: String::new(),
+            operation: None,
         }
     }
 }
@@ -49,37 +209,153 @@ impl Request {
 impl From<&Request> for Request {
     fn from(v: &Self) -> Self {
         Request {
+            id: String::from(&v.id),
             method: String::from(&v.method),
             title: String::from(&v.title),
             url: String::from(&v.url),
             body: String::from(&v.body),
-            query_params: vec![],
-            path_params: vec![],
+            query_params: v.query_params.clone(),
+            path_params: v.path_params.clone(),
+            headers: v.headers.clone(),
+            folder: v.folder.clone(),
+            auth: v.auth.clone(),
+            assertions: v.assertions.clone(),
+            captures: v.captures.clone(),
+            pre_script: v.pre_script.clone(),
+            post_script: v.post_script.clone(),
+            operation: v.operation.clone(),
         }
     }
 }
 
+/// Group of requests sharing auth.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Folder {
+    pub name: String,
+    #[serde(default = "no_auth")]
+    pub auth: Auth,
+}
+
+fn no_auth() -> Auth {
+    Auth::None
+}
+
+/// Named set of variables, e.g. for staging and production, the active one
+/// is looked up before the variables of the collection.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Environment {
+    pub name: String,
+    #[serde(default)]
+    pub variables: BTreeMap<String, String>,
+}
+
+/// Headers that differ on every response aren't worth diffing.
+fn default_diff_ignore() -> Vec<String> {
+    vec![String::from("Date")]
+}
+
+#[derive(Serialize, Deserialize)]
+struct Config {
+    #[serde(default)]
+    folders: Vec<Folder>,
+    requests: Vec<Request>,
+    /// Headers and JSON fields left out of response diffs
+    #[serde(default = "default_diff_ignore")]
+    diff_ignore: Vec<String>,
+    #[serde(default)]
+    variables: BTreeMap<String, String>,
+    #[serde(default)]
+    environments: Vec<Environment>,
+    /// Name of the active environment
+    #[serde(default)]
+    environment: Option<String>,
+}
+
+/// Config used to be a bare list of requests, both forms are accepted.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ConfigRepr {
+    Requests(Vec<Request>),
+    Full(Config),
+}
+
+/// Request on its way on a worker thread, see `Model::send_request`.
+pub struct Sending {
+    pub request: Request,
+    /// What's sent, the error when it couldn't be built
+    sent: Result<HttpRequest, String>,
+    receiver: Receiver<Result<HttpResponse, String>>,
+}
 
 pub struct Model {
     pub requests: Arc<RwLock<Vec<Arc<Request>>>>,
+    pub folders: Vec<Folder>,
+    pub secrets: SecretStore,
+    pub history: History,
+    pub diff_ignore: Vec<String>,
+    /// Values captured from responses, kept for the session only
+    pub variables: BTreeMap<String, String>,
+    /// Variables of imported collections, saved with the requests
+    pub collection_variables: BTreeMap<String, String>,
+    pub environments: Vec<Environment>,
+    /// Name of the active environment, see `active_environment`
+    pub environment: Option<String>,
+    /// Lines printed by scripts, taken by whoever shows them
+    pub script_log: Vec<String>,
+    /// Opened `.http` file, saved into instead of the config
+    pub http_file: Option<HttpFile>,
+    /// Why the last save failed, taken by whoever shows it
+    pub save_error: Option<String>,
+    /// Specs requests are checked against, see `contract::check`
+    pub specs: SpecCache,
+    tokens: Arc<TokenCache>,
 }
 
 impl Model {
-    pub fn new(requests: Vec<Request>) -> Self {
+    pub fn new(requests: Vec<Request>, folders: Vec<Folder>) -> Self {
+        let missing_ids = requests.iter().any(|r| r.id.is_empty());
+
         let arc_r: Vec<Arc<Request>> = requests
             .iter()
-            .map(|r| Arc::new(Request::from(r)))
+            .map(|r| Arc::new(Request {
+                id: if r.id.is_empty() { new_request_id() } else { r.id.clone() },
+                ..Request::from(r)
+            }))
             .collect();
 
-        Self { requests: Arc::new(RwLock::new(arc_r)) }
+        let mut model = Self {
+            requests: Arc::new(RwLock::new(arc_r)),
+            folders,
+            secrets: SecretStore::load(Paths::secrets_path()),
+            history: History::load(Paths::history_path()),
+            diff_ignore: default_diff_ignore(),
+            variables: BTreeMap::new(),
+            collection_variables: BTreeMap::new(),
+            environments: Vec::new(),
+            environment: None,
+            script_log: Vec::new(),
+            http_file: None,
+            save_error: None,
+            specs: SpecCache::new(),
+            tokens: Arc::new(TokenCache::new()),
+        };
+
+        // History refers to requests by id, so the new ids must stick
+        if missing_ids {
+            model.save_on_disk();
+        }
+
+        model
     }
 
-    pub fn add_request(&mut self, r: Request) {
+    pub fn add_request(&mut self, r: Request) -> usize {
         let lock_clone = self.requests.clone();
         let mut write = lock_clone.write().unwrap();
         write.push(Arc::new(r));
+        let index = write.len() - 1;
         drop(write);
         self.save_on_disk();
+        index
     }
 
     pub fn update_request(&mut self, i: usize, r: &Request) {
@@ -90,79 +366,553 @@ impl Model {
         self.save_on_disk();
     }
 
-    pub fn save_on_disk(&self) {
+    pub fn get_request(&self, i: usize) -> Option<Request> {
+        self.requests
+            .read().unwrap()
+            .get(i)
+            .map(|r| Request::from(r.as_ref()))
+    }
+
+    /// Inserts a copy of the request right after the original, returns the copy's index.
+    pub fn duplicate_request(&mut self, i: usize) -> Option<usize> {
+        let mut copy = self.get_request(i)?;
+        copy.id = new_request_id();
+        copy.title = format!("{} (copy)", copy.title);
+
+        let lock_clone = self.requests.clone();
+        let mut write = lock_clone.write().unwrap();
+        write.insert(i + 1, Arc::new(copy));
+        drop(write);
+        self.save_on_disk();
+
+        Some(i + 1)
+    }
+
+    pub fn rename_request(&mut self, i: usize, title: &str) {
+        if let Some(mut r) = self.get_request(i) {
+            r.title = title.to_string();
+            self.update_request(i, &r);
+        }
+    }
+
+    pub fn set_request_method(&mut self, i: usize, method: &str) {
+        if let Some(mut r) = self.get_request(i) {
+            // Methods are case-sensitive, only the standard ones are normalized
+            let upper = method.to_uppercase();
+            r.method = if http::METHODS.contains(&upper.as_str()) {
+                upper
+            } else {
+                method.to_string()
+            };
+            self.update_request(i, &r);
+        }
+    }
+
+    /// Moves the request into the folder, creating the folder if needed.
+    pub fn set_request_folder(&mut self, i: usize, folder: Option<&str>) {
+        if let Some(name) = folder {
+            if !self.folders.iter().any(|f| f.name == name) {
+                self.folders.push(Folder { name: name.to_string(), auth: Auth::None });
+            }
+        }
+
+        if let Some(mut r) = self.get_request(i) {
+            r.folder = folder.map(|f| f.to_string());
+            self.update_request(i, &r);
+        }
+    }
+
+    pub fn get_folder(&self, name: &str) -> Option<&Folder> {
+        self.folders.iter().find(|f| f.name == name)
+    }
+
+    pub fn set_folder_auth(&mut self, name: &str, auth: &Auth) {
+        if let Some(folder) = self.folders.iter_mut().find(|f| f.name == name) {
+            folder.auth = auth.clone();
+            self.save_on_disk();
+        }
+    }
+
+    /// Auth the request is sent with, `Inherit` is resolved through the folder.
+    pub fn effective_auth(&self, request: &Request) -> Auth {
+        match &request.auth {
+            Auth::Inherit => request.folder
+                .as_deref()
+                .and_then(|f| self.get_folder(f))
+                .map(|f| f.auth.clone())
+                .unwrap_or(Auth::None),
+            auth => auth.clone(),
+        }
+    }
+
+    pub fn set_diff_ignore(&mut self, fields: Vec<String>) {
+        self.diff_ignore = fields;
+        self.save_on_disk();
+    }
+
+    pub fn active_environment(&self) -> Option<&Environment> {
+        let name = self.environment.as_ref()?;
+        self.environments.iter().find(|e| &e.name == name)
+    }
+
+    /// Makes the environment the active one, `None` leaves the collection variables only.
+    pub fn set_environment(&mut self, name: Option<&str>) {
+        self.environment = name.map(String::from);
+        self.save_on_disk();
+    }
+
+    /// Adds imported requests, folders, variables and environments, returns
+    /// how many requests were added.
+    ///
+    /// Environments of the same name are merged, the one the importer picked
+    /// becomes active, else the first one when none is active yet.
+    pub fn add_imported(&mut self, imported: Imported) -> usize {
+        let count = imported.requests.len();
+        let first = imported.environments.first().map(|e| e.name.clone());
+
+        for environment in imported.environments {
+            match self.environments.iter_mut().find(|e| e.name == environment.name) {
+                Some(existing) => existing.variables.extend(environment.variables),
+                None => self.environments.push(environment),
+            }
+        }
+
+        if imported.environment.is_some() {
+            self.environment = imported.environment;
+        } else if self.active_environment().is_none() {
+            self.environment = first;
+        }
+
+        self.requests.write().unwrap().extend(imported.requests.into_iter().map(Arc::new));
+
+        for folder in imported.folders {
+            if !self.folders.iter().any(|f| f.name == folder.name) {
+                self.folders.push(folder);
+            }
+        }
+
+        self.collection_variables.extend(imported.variables);
+        self.save_on_disk();
+        count
+    }
+
+    /// Applies the accepted changes of a re-sync, new requests go to the spec's folder.
+    pub fn apply_sync(&mut self, changes: Vec<SyncChange>, folder: Folder) {
+        let mut requests = self.requests.write().unwrap();
+
+        for change in changes.into_iter().filter(|c| c.accepted) {
+            let index = change.request_id
+                .as_ref()
+                .and_then(|id| requests.iter().position(|r| &r.id == id));
+
+            match (change.updated, index) {
+                (Some(updated), Some(i)) => requests[i] = Arc::new(updated),
+                (Some(added), None) => requests.push(Arc::new(added)),
+                (None, Some(i)) => {
+                    requests.remove(i);
+                },
+                (None, None) => {},
+            }
+        }
+
+        drop(requests);
+
+        if !self.folders.iter().any(|f| f.name == folder.name) {
+            self.folders.push(folder);
+        }
+
+        self.save_on_disk();
+    }
+
+    pub fn remove_request(&mut self, i: usize) {
+        let lock_clone = self.requests.clone();
+        let mut write = lock_clone.write().unwrap();
+
+        if i < write.len() {
+            write.remove(i);
+        }
+
+        drop(write);
+        self.save_on_disk();
+    }
+
+    /// Swaps the request with its neighbour, returns the new index of the moved request.
+    pub fn move_request(&mut self, i: usize, up: bool) -> usize {
+        let lock_clone = self.requests.clone();
+        let mut write = lock_clone.write().unwrap();
+
+        let target = if up {
+            i.saturating_sub(1)
+        } else {
+            std::cmp::min(i + 1, write.len().saturating_sub(1))
+        };
+
+        if target != i {
+            write.swap(i, target);
+        }
+
+        drop(write);
+        self.save_on_disk();
+
+        target
+    }
+
+    /// Writes the requests back to the opened `.http` file or the config,
+    /// a failure is kept in `save_error` rather than stopping the app.
+    pub fn save_on_disk(&mut self) {
+        if let Err(e) = self.try_save() {
+            self.save_error = Some(e);
+        }
+    }
+
+    fn try_save(&self) -> Result<(), String> {
         let lock_clone = self.requests.clone();
         let read = lock_clone.read().unwrap();
+
+        if let Some(http_file) = &self.http_file {
+            http_file.save(&read)?;
+            return http_file.save_environments(&self.environments);
+        }
+
         let save_path = Paths::config_path();
 
-        let file = File::create(save_path).expect("Couldn't create config file");
+        let file = File::create(&save_path)
+            .map_err(|e| format!("Couldn't create {}: {}", save_path.display(), e))?;
 
         // TODO: Avoid copying perhaps?
-        let serializable: Vec<Request> = read.iter()
-            .map(|r| Request::from(r.as_ref()))
-            .collect();
+        let serializable = Config {
+            folders: self.folders.clone(),
+            requests: read.iter()
+                .map(|r| Request::from(r.as_ref()))
+                .collect(),
+            diff_ignore: self.diff_ignore.clone(),
+            variables: self.collection_variables.clone(),
+            environments: self.environments.clone(),
+            environment: self.environment.clone(),
+        };
 
         serde_json::to_writer_pretty(file, &serializable)
-            .expect("Couldn't write file");
+            .map_err(|e| format!("Couldn't write {}: {}", save_path.display(), e))
     }
 
-    pub fn make_request(&self, request: &Request) {}
+    /// Value of a `{{name}}` placeholder, captured values take precedence over
+    /// the active environment, it over collection variables and those over secrets.
+    fn lookup(&self, name: &str) -> Result<String, String> {
+        let environment = self.active_environment().and_then(|e| e.variables.get(name));
 
-    pub fn import_from_openapi(spec_path: PathBuf) -> Self {
-        let sparse_root = SparseRoot::new_from_file(spec_path).expect("Failed to parse the openapi");
+        if let Some(value) = self.variables.get(name).or(environment).or(self.collection_variables.get(name)) {
+            return Ok(value.clone());
+        }
 
-        let doc: OApi = OApi::new(sparse_root);
-        let root = doc.root_get().unwrap();
-        let mut requests = Vec::new();
+        self.secrets
+            .resolve(name)
+            .unwrap_or_else(|| Err(format!("Unknown variable {{{{{}}}}}", name)))
+    }
 
-        let mut base_url = &"".to_string();
+    /// TLS options of the active environment, a locked secret counts as not set.
+    pub fn tls_options(&self) -> TlsOptions {
+        let value = |name: &str| self.lookup(name).ok().filter(|v| !v.trim().is_empty());
 
-        match root.servers() {
-            None => {}
-            Some(servers) => {
-                for s in servers.iter() {
-                    base_url = s.url();
-                }
-            }
+        TlsOptions {
+            ca: value(tls::CA),
+            cert: value(tls::CERT),
+            key: value(tls::KEY),
+            password: value(tls::PASSWORD),
+            sni: value(tls::SNI),
+            insecure: self.tls_insecure(),
         }
+    }
 
-        for (key, val) in root.paths().iter() {
-            for (method, operation) in [
-                ("GET", val.get()),
-                ("POST", val.post()),
-                ("PUT", val.put()),
-            ].iter().filter_map(|&(m, v)| v.clone().map(|op| (m, op))) {
-                let mut request = Request::new_default();
-                request.method = method.to_string();
-                request.title = operation.summary().clone().unwrap_or_default();
-
-                for par in operation.clone().parameters().iter() {
-                    match par.get() {
-                        Ok(result) => {
-                            request.query_params.push(result.name().clone());
-                        }
-                        Err(_) => {}
-                    }
-                }
+    /// Whether server certificates go unchecked, never read from the secrets
+    /// so it can be shown at all times.
+    pub fn tls_insecure(&self) -> bool {
+        let environment = self.active_environment().and_then(|e| e.variables.get(tls::INSECURE));
+
+        self.variables
+            .get(tls::INSECURE)
+            .or(environment)
+            .or(self.collection_variables.get(tls::INSECURE))
+            .is_some_and(|v| v.trim().eq_ignore_ascii_case("true"))
+    }
+
+    /// Saved with the active environment, so staging can skip verification
+    /// while production doesn't.
+    pub fn set_tls_insecure(&mut self, insecure: bool) -> Result<(), String> {
+        let Some(name) = self.environment.clone() else {
+            return Err(String::from("TLS options are kept per environment, pick one with n first"));
+        };
 
+        let Some(environment) = self.environments.iter_mut().find(|e| e.name == name) else {
+            return Err(format!("No environment {}, pick another with n", name));
+        };
 
+        // The environment's value wins over the collection's, `false` is kept to override it
+        let collection_insecure = self.collection_variables.contains_key(tls::INSECURE);
 
-                requests.push(request);
+        match insecure || collection_insecure {
+            true => environment.variables.insert(tls::INSECURE.to_string(), insecure.to_string()),
+            false => environment.variables.remove(tls::INSECURE),
+        };
+
+        self.variables.remove(tls::INSECURE);
+        self.save_on_disk();
+        Ok(())
+    }
+
+    /// Stores the values the request's capture rules extract from a 2xx response.
+    ///
+    /// Nothing is captured from other responses, so a failed login doesn't
+    /// overwrite the token of an earlier one.
+    pub fn capture(&mut self, request: &Request, response: &HttpResponse) -> Vec<CaptureResult> {
+        if !(200..300).contains(&response.status) {
+            return Vec::new();
+        }
+
+        let results = capture::extract(&request.captures, response);
+
+        for result in results.iter() {
+            if let Ok(value) = &result.value {
+                self.variables.insert(result.variable.clone(), value.clone());
             }
         }
 
-        return Self::new(requests);
+        results
+    }
+
+    /// Resolves the request and sends it on a worker thread, so the app keeps
+    /// drawing while it's on the way. `receive_response` takes the result.
+    pub fn send_request(&mut self, request: &Request) -> Sending {
+        let resolved = self.resolve(request).and_then(|(mut sent, auth)| {
+            self.run_pre_script(request, &mut sent)?;
+            Ok((sent, auth))
+        });
+
+        let (sender, receiver) = mpsc::channel();
+
+        match &resolved {
+            Ok((sent, auth)) => {
+                let (mut sent, auth) = (sent.clone(), auth.clone());
+                let (tls, tokens) = (self.tls_options(), self.tokens.clone());
+
+                // OAuth2 tokens are fetched here too, the token endpoint may be slow as well
+                thread::spawn(move || {
+                    let result = auth::authorize(&auth, &mut sent, &tokens, &tls)
+                        .and_then(|_| http::send(&sent, &tls));
+
+                    sender.send(result).ok();
+                });
+            },
+            Err(e) => {
+                sender.send(Err(e.clone())).ok();
+            },
+        }
+
+        Sending { request: request.clone(), sent: resolved.map(|(sent, _)| sent), receiver }
+    }
+
+    /// Like `send_request` with the variables of another environment, the active one stays.
+    pub fn send_request_in(&mut self, request: &Request, environment: Option<&str>) -> Sending {
+        let active = std::mem::replace(&mut self.environment, environment.map(String::from));
+        let sending = self.send_request(request);
+        self.environment = active;
+        sending
     }
 
-    pub fn load_from_disk_or_default() -> Self {
+    /// Result of a request from `send_request` once it's there, recorded in the history.
+    /// `wait` blocks until then, else `None` is returned while it's on the way.
+    pub fn receive_response(&mut self, sending: &Sending, wait: bool) -> Option<Result<HttpResponse, String>> {
+        let received = match wait {
+            true => sending.receiver.recv().map_err(|_| TryRecvError::Disconnected),
+            false => sending.receiver.try_recv(),
+        };
+
+        let result = match received {
+            Ok(result) => result,
+            Err(TryRecvError::Empty) => return None,
+            Err(TryRecvError::Disconnected) => Err(String::from("Sending the request stopped without a result")),
+        };
+
+        let entry = HistoryEntry::new(&sending.request, sending.sent.as_ref().ok(), &result, &self.secrets);
+
+        // The response is still shown when the history can't be written
+        self.history.record(entry).ok();
+
+        Some(result)
+    }
+
+    /// Checks the request's assertions, placeholders in their targets and
+    /// expected values are substituted first.
+    pub fn check_assertions(&self, request: &Request, response: &HttpResponse) -> Vec<AssertionResult> {
+        let lookup = |name: &str| self.lookup(name);
+
+        // An unknown variable leaves the text as it is, the check then tells it didn't match
+        let substitute = |text: &String| vars::substitute(text, &lookup).unwrap_or(text.clone());
+
+        let resolved: Vec<Assertion> = request.assertions
+            .iter()
+            .map(|a| Assertion {
+                target: substitute(&a.target),
+                expected: substitute(&a.expected),
+                ..a.clone()
+            })
+            .collect();
+
+        assertions::check(&resolved, response)
+    }
+
+    /// Values of the secrets the script reads.
+    fn script_secrets(&self, script: &str) -> Result<BTreeMap<String, String>, String> {
+        script::secret_names(script)
+            .into_iter()
+            .map(|name| self.lookup(&name).map(|value| (name, value)))
+            .collect()
+    }
+
+    fn run_pre_script(&mut self, request: &Request, sent: &mut HttpRequest) -> Result<(), String> {
+        if request.pre_script.trim().is_empty() {
+            return Ok(());
+        }
+
+        let context = ScriptContext {
+            secrets: self.script_secrets(&request.pre_script)?,
+            variables: &mut self.variables,
+            log: &mut self.script_log,
+        };
+
+        script::run_pre_request(&request.pre_script, sent, context)
+            .map_err(|e| format!("Pre-request script failed: {}", e))
+    }
+
+    /// Runs the request's post-response script, a failing script is a failed check.
+    pub fn run_post_script(&mut self, request: &Request, response: &HttpResponse) -> Vec<AssertionResult> {
+        if request.post_script.trim().is_empty() {
+            return Vec::new();
+        }
+
+        let result = self.script_secrets(&request.post_script).and_then(|secrets| {
+            let context = ScriptContext {
+                secrets,
+                variables: &mut self.variables,
+                log: &mut self.script_log,
+            };
+
+            script::run_post_response(&request.post_script, response, context)
+        });
+
+        result.unwrap_or_else(|e| vec![AssertionResult {
+            description: String::from("post-response script"),
+            passed: false,
+            message: e,
+        }])
+    }
+
+    /// HTTP request with placeholders substituted and auth applied, an OAuth2
+    /// token that isn't cached yet is left as `auth::PENDING_TOKEN`.
+    pub fn resolve_request(&self, request: &Request) -> Result<HttpRequest, String> {
+        self.resolve(request).map(|(resolved, _)| resolved)
+    }
+
+    /// Like `resolve_request`, with the auth it was built with.
+    fn resolve(&self, request: &Request) -> Result<(HttpRequest, Auth), String> {
+        let method = request.method.trim();
+
+        if !http::is_valid_method(method) {
+            return Err(format!("Invalid method: \"{}\"", method));
+        }
+
+        let lookup = |name: &str| self.lookup(name);
+
+        let body = match http::body_usage(method) {
+            BodyUsage::Forbidden => String::new(),
+            _ => vars::substitute(&request.body, &lookup)?,
+        };
+
+        let mut headers = request.headers
+            .iter()
+            .filter(|h| h.enabled && !h.name.trim().is_empty())
+            .map(|h| Ok((vars::substitute(h.name.trim(), &lookup)?, vars::substitute(&h.value, &lookup)?)))
+            .collect::<Result<Vec<(String, String)>, String>>()?;
+
+        let has_content_type = headers.iter().any(|(name, _)| name.eq_ignore_ascii_case("Content-Type"));
+
+        if !has_content_type && serde_json::from_str::<serde_json::Value>(&body).is_ok() {
+            headers.push((String::from("Content-Type"), String::from("application/json")));
+        }
+
+        let path_params = request.path_params
+            .iter()
+            .map(|p| vars::substitute(&p.value, &lookup).map(|value| PathParam { value, ..p.clone() }))
+            .collect::<Result<Vec<PathParam>, String>>()?;
+
+        let mut http_request = HttpRequest {
+            method: method.to_string(),
+            url: url::fill_path(&vars::substitute_url(&request.url, &lookup)?, &path_params)?,
+            headers,
+            body,
+        };
+
+        let auth = serde_json::to_value(self.effective_auth(request)).map_err(|e| e.to_string())?;
+        let auth: Auth = serde_json::from_value(vars::substitute_json(auth, &lookup)?)
+            .map_err(|e| e.to_string())?;
+
+        auth::apply(&auth, &mut http_request, &self.tokens)?;
+        Ok((http_request, auth))
+    }
+
+    /// The collection of the spec.
+    pub fn import_from_openapi(spec_path: PathBuf) -> Result<Self, String> {
+        let (requests, folder) = import::openapi(&spec_path)?;
+        Ok(Self::new(requests, vec![folder]))
+    }
+
+    /// The saved collection, none when nothing was saved yet.
+    pub fn load_from_disk() -> Result<Option<Self>, String> {
         let config_path = Paths::config_path();
-        let openapi_spec_path = Paths::openapi_path();
 
-        if config_path.exists() {
-            let content = fs::read_to_string(config_path)
-                .expect("Couldn't read file");
+        if !config_path.exists() {
+            return Ok(None);
+        }
+
+        let content = fs::read_to_string(&config_path)
+            .map_err(|e| format!("Couldn't read {}: {}", config_path.display(), e))?;
+
+        let config = serde_json::from_str(&content)
+            .map_err(|e| format!("Couldn't parse {}: {}", config_path.display(), e))?;
+
+        let model = match config {
+            ConfigRepr::Requests(requests) => Self::new(requests, vec![]),
+            ConfigRepr::Full(config) => {
+                let mut model = Self::new(config.requests, config.folders);
+                model.diff_ignore = config.diff_ignore;
+                model.collection_variables = config.variables;
+                model.environments = config.environments;
+                model.environment = config.environment;
+                model
+            },
+        };
 
-            Self::new(serde_json::from_str(&content).expect("Couldn't parse"))
+        Ok(Some(model))
+    }
+
+    /// Collection of the requests of a `.http` file, edits are saved into it.
+    /// The file doesn't say which environment is active, the first one is.
+    pub fn open_http(path: &Path) -> Result<Self, String> {
+        let (http_file, opened) = HttpFile::open(path)?;
+
+        let mut model = Self::new(opened.requests, opened.folders);
+        model.collection_variables = opened.variables;
+        model.environment = opened.environments.first().map(|e| e.name.clone());
+        model.environments = opened.environments;
+        model.http_file = Some(http_file);
+        Ok(model)
+    }
+
+    /// The saved collection, else the one of the OpenAPI spec, else a sample request.
+    pub fn load_from_disk_or_default() -> Result<Self, String> {
+        let openapi_spec_path = Paths::openapi_path();
+
+        if let Some(model) = Self::load_from_disk()? {
+            Ok(model)
         } else if openapi_spec_path.exists() {
             Self::import_from_openapi(openapi_spec_path)
         } else {
@@ -171,22 +921,134 @@ impl Model {
                 title: "Create request".into(),
                 url: "http://google.com".into(),
                 body: "hellooo".into(),
-                query_params: vec![],
-                path_params: vec![],
+                ..Request::new_default()
             };
 
-            let ret = Self::new(vec![
+            let mut ret = Self::new(vec![
                 Request {
                     method: "POST".into(),
                     title: "Create request".into(),
                     url: "http://google.com".into(),
-                    body: serde_json::to_string_pretty(&req).unwrap().into(),
-                    query_params: vec![],
-                    path_params: vec![],
+                    body: serde_json::to_string_pretty(&req).unwrap(),
+                    ..Request::new_default()
                 }
-            ]);
+            ], vec![]);
             ret.save_on_disk();
-            ret
+            Ok(ret)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn environment(name: &str, variables: &[(&str, &str)]) -> Environment {
+        Environment {
+            name: name.to_string(),
+            variables: variables.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
         }
     }
+
+    #[test]
+    fn the_active_environment_comes_before_collection_variables() {
+        let mut model = Model::new(vec![], vec![]);
+        model.collection_variables.insert(String::from("host"), String::from("localhost"));
+        model.collection_variables.insert(String::from("version"), String::from("v1"));
+        model.environments = vec![
+            environment("Staging", &[("host", "staging.test")]),
+            environment("Production", &[("host", "shop.test")]),
+        ];
+
+        assert_eq!(model.lookup("host").unwrap(), "localhost");
+
+        model.environment = Some(String::from("Production"));
+        assert_eq!(model.lookup("host").unwrap(), "shop.test");
+        assert_eq!(model.lookup("version").unwrap(), "v1");
+
+        model.variables.insert(String::from("host"), String::from("captured.test"));
+        assert_eq!(model.lookup("host").unwrap(), "captured.test");
+    }
+
+    #[test]
+    fn tls_verification_is_toggled_per_environment_of_http_files() {
+        let dir = std::env::temp_dir().join(format!("umbrella-tls-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("api.http"), "GET https://{{host}}/\n").unwrap();
+        fs::write(dir.join("http-client.env.json"), r#"{ "local": { "host": "localhost" }, "prod": {} }"#).unwrap();
+
+        let mut model = Model::open_http(&dir.join("api.http")).unwrap();
+        assert_eq!(model.environment.as_deref(), Some("local"));
+        model.set_tls_insecure(true).unwrap();
+
+        let mut reopened = Model::open_http(&dir.join("api.http")).unwrap();
+        let insecure_in_local = reopened.tls_insecure();
+        reopened.set_environment(Some("prod"));
+        let insecure_in_prod = reopened.tls_insecure();
+
+        reopened.set_environment(None);
+        let error = reopened.set_tls_insecure(true).err();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(insecure_in_local);
+        assert!(!insecure_in_prod);
+        assert_eq!(error.as_deref(), Some("TLS options are kept per environment, pick one with n first"));
+    }
+
+    #[test]
+    fn failed_saves_are_kept_for_the_app_to_show() {
+        let dir = std::env::temp_dir().join(format!("umbrella-save-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("api.http"), "GET https://example.com/\n").unwrap();
+
+        let mut model = Model::open_http(&dir.join("api.http")).unwrap();
+
+        // A directory in place of the file can't be written on any platform
+        fs::remove_file(dir.join("api.http")).unwrap();
+        fs::create_dir(dir.join("api.http")).unwrap();
+        model.rename_request(0, "Moved away");
+
+        let error = model.save_error.take();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(error.is_some_and(|e| e.starts_with("Couldn't write")));
+    }
+
+    #[test]
+    fn only_successful_responses_are_captured() {
+        let mut model = Model::new(vec![], vec![]);
+        model.variables.insert(String::from("token"), String::from("old"));
+
+        let request = Request {
+            captures: vec![Capture {
+                variable: String::from("token"),
+                source: capture::CaptureSource::JsonPath,
+                expression: String::from("$.token"),
+                enabled: true,
+            }],
+            ..Request::new_default()
+        };
+        let response = |status, body: &str| HttpResponse {
+            status,
+            reason: String::new(),
+            headers: Vec::new(),
+            body: body.to_string(),
+            timings: http::Timings {
+                connect: Default::default(),
+                first_byte: Default::default(),
+                total: Default::default(),
+            },
+        };
+
+        let failed = model.capture(&request, &response(401, r#"{ "token": "denied" }"#));
+        assert!(failed.is_empty());
+        assert_eq!(model.variables["token"], "old");
+
+        let missing = model.capture(&request, &response(200, "{}"));
+        assert_eq!(missing[0].value, Err(String::from("$.token not found")));
+        assert_eq!(model.variables["token"], "old");
+
+        model.capture(&request, &response(200, r#"{ "token": "new" }"#));
+        assert_eq!(model.variables["token"], "new");
+    }
 }
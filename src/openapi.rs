@@ -0,0 +1,607 @@
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Map, Value};
+use sppparse::SparseRoot;
+
+/// Longer `$ref` chains are taken for cycles.
+const MAX_REFS: usize = 16;
+
+/// The spec as a plain JSON value, YAML included.
+///
+/// OpenAPI 3.0 and 3.1 documents are taken as they are, Swagger 2.0 ones
+/// are upgraded to 3.0 so the rest of the app sees a single shape.
+pub fn load(path: &Path) -> Result<Value, String> {
+    // The parser resolves the file relative to its own idea of the root
+    let path = path.canonicalize().map_err(|e| format!("Couldn't read {}: {}", path.display(), e))?;
+
+    let sparse_root = SparseRoot::<Value>::new_from_file(PathBuf::from(&path))
+        .map_err(|e| format!("Couldn't parse {}: {}", path.display(), e))?;
+
+    let root = sparse_root
+        .root_get()
+        .map_err(|e| format!("Couldn't parse {}: {}", path.display(), e))?;
+
+    let root = Value::clone(&root);
+
+    if let Some(version) = root.get("openapi").and_then(|v| v.as_str()) {
+        return match version.starts_with("3.0") || version.starts_with("3.1") {
+            true => Ok(root),
+            false => Err(format!(
+                "{}: OpenAPI {} isn't supported, only 2.0, 3.0 and 3.1 are",
+                path.display(),
+                version,
+            )),
+        };
+    }
+
+    match root.get("swagger").and_then(|v| v.as_str()) {
+        Some("2.0") => Ok(upgrade_swagger(root)),
+        Some(version) => Err(format!(
+            "{}: Swagger {} isn't supported, only 2.0 is",
+            path.display(),
+            version,
+        )),
+        None => Err(format!(
+            "{} is not an OpenAPI document, it has no openapi or swagger version",
+            path.display(),
+        )),
+    }
+}
+
+/// Follows `$ref`s to local definitions, what can't be resolved is left as it is.
+pub fn resolve<'a>(root: &'a Value, mut value: &'a Value) -> &'a Value {
+    for _ in 0..MAX_REFS {
+        let target = value
+            .get("$ref")
+            .and_then(|r| r.as_str())
+            .and_then(|r| r.strip_prefix('#'))
+            .and_then(|pointer| root.pointer(pointer));
+
+        match target {
+            Some(target) => value = target,
+            None => break,
+        }
+    }
+
+    value
+}
+
+/// Whether a media type is one the spec lists, wildcards included.
+pub fn media_matches(listed: &str, actual: &str) -> bool {
+    let listed = listed.split(';').next().unwrap_or_default().trim().to_lowercase();
+    let actual = actual.split(';').next().unwrap_or_default().trim().to_lowercase();
+
+    listed == actual
+        || listed == "*/*"
+        || listed.strip_suffix("/*").is_some_and(|kind| actual.split('/').next() == Some(kind))
+}
+
+pub fn is_json(media: &str) -> bool {
+    let media = media.to_lowercase();
+    media.contains("/json") || media.contains("+json")
+}
+
+/// Points `$ref`s of a Swagger document to where OpenAPI 3 keeps the definitions.
+fn move_refs(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                match value {
+                    Value::String(reference) if key == "$ref" => {
+                        for (old, new) in [
+                            ("#/definitions/", "#/components/schemas/"),
+                            ("#/responses/", "#/components/responses/"),
+                            ("#/parameters/", "#/components/parameters/"),
+                        ] {
+                            if let Some(name) = reference.strip_prefix(old) {
+                                *reference = format!("{}{}", new, name);
+                            }
+                        }
+                    },
+                    value => move_refs(value),
+                }
+            }
+        },
+        Value::Array(items) => items.iter_mut().for_each(move_refs),
+        _ => {},
+    }
+}
+
+/// Keywords of a Swagger parameter or header that belong in its schema in OpenAPI 3.
+const SCHEMA_KEYWORDS: [&str; 15] = [
+    "type", "format", "items", "enum", "default", "minimum", "maximum", "exclusiveMinimum",
+    "exclusiveMaximum", "minLength", "maxLength", "pattern", "minItems", "maxItems", "uniqueItems",
+];
+
+/// Moves the type keywords of a Swagger parameter or header into a `schema`.
+fn with_schema(mut item: Map<String, Value>) -> Map<String, Value> {
+    let mut schema = Map::new();
+
+    for keyword in SCHEMA_KEYWORDS {
+        if let Some(value) = item.remove(keyword) {
+            schema.insert(keyword.to_string(), value);
+        }
+    }
+
+    item.remove("collectionFormat");
+    item.remove("allowEmptyValue");
+
+    if !schema.is_empty() {
+        item.insert(String::from("schema"), Value::Object(schema));
+    }
+
+    item
+}
+
+fn media_types(value: Option<&Value>) -> Option<Vec<String>> {
+    let types = value?.as_array()?;
+    Some(types.iter().filter_map(|t| t.as_str().map(String::from)).collect())
+}
+
+fn upgrade_response(response: &Value, produces: &[String]) -> Value {
+    let Some(response) = response.as_object() else {
+        return response.clone();
+    };
+
+    if response.contains_key("$ref") {
+        return Value::Object(response.clone());
+    }
+
+    let mut upgraded = Map::new();
+    let description = response.get("description").cloned().unwrap_or(json!(""));
+    upgraded.insert(String::from("description"), description);
+
+    if let Some(schema) = response.get("schema") {
+        let examples = response.get("examples").and_then(|e| e.as_object());
+
+        let content: Map<String, Value> = produces
+            .iter()
+            .map(|media| {
+                let mut media_type = json!({ "schema": schema });
+
+                if let Some(example) = examples.and_then(|e| e.get(media)) {
+                    media_type["example"] = example.clone();
+                }
+
+                (media.clone(), media_type)
+            })
+            .collect();
+
+        upgraded.insert(String::from("content"), Value::Object(content));
+    }
+
+    if let Some(headers) = response.get("headers").and_then(|h| h.as_object()) {
+        let headers: Map<String, Value> = headers
+            .iter()
+            .map(|(name, header)| {
+                let header = with_schema(header.as_object().cloned().unwrap_or_default());
+                (name.clone(), Value::Object(header))
+            })
+            .collect();
+
+        upgraded.insert(String::from("headers"), Value::Object(headers));
+    }
+
+    Value::Object(upgraded)
+}
+
+/// Parameters of an operation and its request body, body and form parameters become the body.
+fn upgrade_parameters(
+    parameters: &[Value],
+    global: &Map<String, Value>,
+    consumes: &[String],
+) -> (Vec<Value>, Option<Value>) {
+    let mut upgraded = Vec::new();
+    let mut body = None;
+    let mut form = Map::new();
+    let mut form_required = Vec::new();
+    let mut has_file = false;
+
+    for parameter in parameters {
+        // Body parameters can't stay references, they turn into the request body,
+        // the references were already moved to the components
+        let name = parameter
+            .get("$ref")
+            .and_then(|r| r.as_str())
+            .and_then(|r| r.strip_prefix("#/components/parameters/"));
+
+        let global = name.and_then(|name| global.get(name));
+        let location = global.and_then(|p| p.get("in")).and_then(|i| i.as_str());
+
+        let parameter = match global {
+            Some(global) if matches!(location, Some("body" | "formData")) => global.clone(),
+            _ => parameter.clone(),
+        };
+
+        let Some(map) = parameter.as_object().cloned() else {
+            continue;
+        };
+
+        match map.get("in").and_then(|i| i.as_str()) {
+            Some("body") => {
+                let content: Map<String, Value> = consumes
+                    .iter()
+                    .map(|media| {
+                        let schema = map.get("schema").cloned().unwrap_or(json!({}));
+                        (media.clone(), json!({ "schema": schema }))
+                    })
+                    .collect();
+
+                let mut request_body = json!({ "content": content });
+
+                if let Some(required) = map.get("required") {
+                    request_body["required"] = required.clone();
+                }
+
+                body = Some(request_body);
+            },
+            Some("formData") => {
+                let name = map.get("name").and_then(|n| n.as_str()).unwrap_or_default().to_string();
+                has_file |= map.get("type") == Some(&json!("file"));
+
+                if map.get("required") == Some(&json!(true)) {
+                    form_required.push(json!(name));
+                }
+
+                let mut schema = with_schema(map).remove("schema").unwrap_or(json!({}));
+
+                if schema.get("type") == Some(&json!("file")) {
+                    schema = json!({ "type": "string", "format": "binary" });
+                }
+
+                form.insert(name, schema);
+            },
+            Some(_) => upgraded.push(Value::Object(with_schema(map))),
+            None => upgraded.push(Value::Object(map)),
+        }
+    }
+
+    if !form.is_empty() {
+        let media = match has_file {
+            true => "multipart/form-data",
+            false => "application/x-www-form-urlencoded",
+        };
+        let schema = json!({ "type": "object", "properties": form, "required": form_required });
+
+        body = Some(json!({ "content": { media: { "schema": schema } } }));
+    }
+
+    (upgraded, body)
+}
+
+/// Swagger 2.0 as OpenAPI 3.0: servers from the host, components from the
+/// definitions and request bodies from body and form parameters.
+fn upgrade_swagger(mut swagger: Value) -> Value {
+    move_refs(&mut swagger);
+
+    let field = |name: &str| swagger.get(name).cloned();
+    let global_parameters = swagger
+        .get("parameters")
+        .and_then(|p| p.as_object())
+        .cloned()
+        .unwrap_or_default();
+    let json = vec![String::from("application/json")];
+    let consumes = media_types(swagger.get("consumes")).unwrap_or(json.clone());
+    let produces = media_types(swagger.get("produces")).unwrap_or(json);
+
+    let base_path = swagger
+        .get("basePath")
+        .and_then(|b| b.as_str())
+        .unwrap_or_default()
+        .trim_end_matches('/');
+    let scheme = swagger
+        .get("schemes")
+        .and_then(|s| s.as_array())
+        .and_then(|s| {
+            let schemes: Vec<&str> = s.iter().filter_map(|s| s.as_str()).collect();
+            schemes.iter().find(|s| **s == "https").or(schemes.first()).copied()
+        })
+        .unwrap_or("https");
+
+    let server = match swagger.get("host").and_then(|h| h.as_str()) {
+        Some(host) => format!("{}://{}{}", scheme, host, base_path),
+        None => base_path.to_string(),
+    };
+
+    let mut upgraded = json!({
+        "openapi": "3.0.3",
+        "info": field("info").unwrap_or(json!({ "title": "", "version": "" })),
+        "paths": {},
+        "components": {},
+    });
+
+    if !server.is_empty() {
+        upgraded["servers"] = json!([{ "url": server }]);
+    }
+
+    for name in ["security", "tags", "externalDocs"] {
+        if let Some(value) = field(name) {
+            upgraded[name] = value;
+        }
+    }
+
+    if let Some(definitions) = field("definitions") {
+        upgraded["components"]["schemas"] = definitions;
+    }
+
+    let plain_parameters: Map<String, Value> = global_parameters
+        .iter()
+        .filter(|(_, p)| !matches!(p.get("in").and_then(|i| i.as_str()), Some("body" | "formData")))
+        .map(|(name, p)| {
+            let parameter = with_schema(p.as_object().cloned().unwrap_or_default());
+            (name.clone(), Value::Object(parameter))
+        })
+        .collect();
+
+    if !plain_parameters.is_empty() {
+        upgraded["components"]["parameters"] = Value::Object(plain_parameters);
+    }
+
+    if let Some(responses) = swagger.get("responses").and_then(|r| r.as_object()) {
+        let responses: Map<String, Value> = responses
+            .iter()
+            .map(|(name, r)| (name.clone(), upgrade_response(r, &produces)))
+            .collect();
+
+        upgraded["components"]["responses"] = Value::Object(responses);
+    }
+
+    if let Some(definitions) = swagger.get("securityDefinitions").and_then(|d| d.as_object()) {
+        let schemes: Map<String, Value> = definitions
+            .iter()
+            .map(|(name, definition)| (name.clone(), upgrade_security(definition)))
+            .collect();
+
+        upgraded["components"]["securitySchemes"] = Value::Object(schemes);
+    }
+
+    let paths = swagger.get("paths").and_then(|p| p.as_object()).cloned().unwrap_or_default();
+
+    for (path, item) in paths {
+        let Some(item) = item.as_object() else {
+            continue;
+        };
+
+        let shared: Vec<Value> = item
+            .get("parameters")
+            .and_then(|p| p.as_array())
+            .cloned()
+            .unwrap_or_default();
+        let mut upgraded_item = Map::new();
+
+        for (key, operation) in item {
+            if key == "parameters" {
+                continue;
+            }
+
+            let Some(operation) = operation.as_object() else {
+                upgraded_item.insert(key.clone(), operation.clone());
+                continue;
+            };
+
+            let mut operation = operation.clone();
+            let consumes = media_types(operation.remove("consumes").as_ref())
+                .unwrap_or(consumes.clone());
+            let produces = media_types(operation.remove("produces").as_ref())
+                .unwrap_or(produces.clone());
+            operation.remove("schemes");
+
+            // Body and form parameters of the path item end up in each operation's body
+            let own: Vec<Value> = operation
+                .remove("parameters")
+                .and_then(|p| p.as_array().cloned())
+                .unwrap_or_default();
+            let all: Vec<Value> = shared.iter().chain(own.iter()).cloned().collect();
+            let (parameters, body) = upgrade_parameters(&all, &global_parameters, &consumes);
+
+            if !parameters.is_empty() {
+                operation.insert(String::from("parameters"), Value::Array(parameters));
+            }
+
+            if let Some(body) = body {
+                operation.insert(String::from("requestBody"), body);
+            }
+
+            if let Some(responses) = operation.get("responses").and_then(|r| r.as_object()) {
+                let responses: Map<String, Value> = responses
+                    .iter()
+                    .map(|(code, r)| (code.clone(), upgrade_response(r, &produces)))
+                    .collect();
+
+                operation.insert(String::from("responses"), Value::Object(responses));
+            }
+
+            upgraded_item.insert(key.clone(), Value::Object(operation));
+        }
+
+        upgraded["paths"][path] = Value::Object(upgraded_item);
+    }
+
+    upgraded
+}
+
+fn upgrade_security(definition: &Value) -> Value {
+    let field = |name: &str| definition.get(name).cloned().unwrap_or(json!(""));
+    let scopes = definition.get("scopes").cloned().unwrap_or(json!({}));
+
+    match definition.get("type").and_then(|t| t.as_str()) {
+        Some("basic") => json!({ "type": "http", "scheme": "basic" }),
+        Some("oauth2") => {
+            let flow = match definition.get("flow").and_then(|f| f.as_str()) {
+                Some("application") => json!({ "clientCredentials": {
+                    "tokenUrl": field("tokenUrl"),
+                    "scopes": scopes,
+                } }),
+                Some("password") => json!({ "password": {
+                    "tokenUrl": field("tokenUrl"),
+                    "scopes": scopes,
+                } }),
+                Some("accessCode") => json!({ "authorizationCode": {
+                    "authorizationUrl": field("authorizationUrl"),
+                    "tokenUrl": field("tokenUrl"),
+                    "scopes": scopes,
+                } }),
+                _ => json!({ "implicit": {
+                    "authorizationUrl": field("authorizationUrl"),
+                    "scopes": scopes,
+                } }),
+            };
+
+            json!({ "type": "oauth2", "flows": flow })
+        },
+        _ => definition.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn petstore() -> Value {
+        json!({
+            "swagger": "2.0",
+            "info": { "title": "Petstore", "version": "1.0" },
+            "host": "petstore.test",
+            "basePath": "/v2/",
+            "schemes": ["http", "https"],
+            "consumes": ["application/json"],
+            "produces": ["application/json"],
+            "parameters": {
+                "petId": {
+                    "name": "petId",
+                    "in": "path",
+                    "required": true,
+                    "type": "integer",
+                    "format": "int64",
+                },
+                "pet": {
+                    "name": "pet",
+                    "in": "body",
+                    "required": true,
+                    "schema": { "$ref": "#/definitions/Pet" },
+                },
+            },
+            "definitions": {
+                "Pet": { "type": "object", "properties": { "name": { "type": "string" } } },
+            },
+            "securityDefinitions": {
+                "oauth": {
+                    "type": "oauth2",
+                    "flow": "application",
+                    "tokenUrl": "https://auth.test/token",
+                    "scopes": {},
+                },
+            },
+            "paths": {
+                "/pet": {
+                    "post": {
+                        "parameters": [{ "$ref": "#/parameters/pet" }],
+                        "responses": {
+                            "200": {
+                                "description": "Created",
+                                "schema": { "$ref": "#/definitions/Pet" },
+                            },
+                        },
+                    },
+                },
+                "/pet/{petId}": {
+                    "parameters": [{ "$ref": "#/parameters/petId" }],
+                    "post": {
+                        "consumes": ["multipart/form-data"],
+                        "parameters": [
+                            {
+                                "name": "name",
+                                "in": "formData",
+                                "required": true,
+                                "type": "string",
+                            },
+                            { "name": "photo", "in": "formData", "type": "file" },
+                        ],
+                        "responses": { "204": { "description": "Updated" } },
+                    },
+                },
+            },
+        })
+    }
+
+    #[test]
+    fn swagger_servers_come_from_host_and_base_path() {
+        let upgraded = upgrade_swagger(petstore());
+
+        assert_eq!(upgraded["openapi"], json!("3.0.3"));
+        assert_eq!(upgraded["servers"], json!([{ "url": "https://petstore.test/v2" }]));
+        assert_eq!(upgraded["info"]["title"], json!("Petstore"));
+    }
+
+    #[test]
+    fn swagger_definitions_and_refs_move_to_components() {
+        let upgraded = upgrade_swagger(petstore());
+        let created = &upgraded["paths"]["/pet"]["post"]["responses"]["200"];
+
+        assert_eq!(upgraded["components"]["schemas"]["Pet"]["type"], json!("object"));
+        let schema = &created["content"]["application/json"]["schema"];
+        assert_eq!(*schema, json!({ "$ref": "#/components/schemas/Pet" }));
+        assert_eq!(
+            upgraded["components"]["parameters"]["petId"],
+            json!({
+                "name": "petId",
+                "in": "path",
+                "required": true,
+                "schema": { "type": "integer", "format": "int64" },
+            }),
+        );
+        assert!(upgraded["components"]["parameters"].get("pet").is_none());
+    }
+
+    #[test]
+    fn swagger_body_parameters_become_request_bodies() {
+        let upgraded = upgrade_swagger(petstore());
+        let operation = &upgraded["paths"]["/pet"]["post"];
+
+        assert!(operation.get("parameters").is_none());
+        assert_eq!(
+            operation["requestBody"],
+            json!({
+                "content": {
+                    "application/json": { "schema": { "$ref": "#/components/schemas/Pet" } },
+                },
+                "required": true,
+            }),
+        );
+    }
+
+    #[test]
+    fn swagger_form_parameters_become_a_multipart_body() {
+        let upgraded = upgrade_swagger(petstore());
+        let operation = &upgraded["paths"]["/pet/{petId}"]["post"];
+
+        assert_eq!(operation["parameters"], json!([{ "$ref": "#/components/parameters/petId" }]));
+        assert_eq!(
+            operation["requestBody"]["content"]["multipart/form-data"]["schema"],
+            json!({
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string" },
+                    "photo": { "type": "string", "format": "binary" },
+                },
+                "required": ["name"],
+            }),
+        );
+        assert_eq!(operation["responses"]["204"], json!({ "description": "Updated" }));
+    }
+
+    #[test]
+    fn swagger_oauth2_flows_are_renamed() {
+        let upgraded = upgrade_swagger(petstore());
+
+        assert_eq!(
+            upgraded["components"]["securitySchemes"]["oauth"],
+            json!({
+                "type": "oauth2",
+                "flows": {
+                    "clientCredentials": { "tokenUrl": "https://auth.test/token", "scopes": {} },
+                },
+            }),
+        );
+    }
+}
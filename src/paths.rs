@@ -14,4 +14,12 @@ impl Paths {
     pub fn config_path() -> PathBuf {
         Paths::project_root().join("config.json")
     }
+
+    pub fn secrets_path() -> PathBuf {
+        Paths::project_root().join("secrets.json")
+    }
+
+    pub fn history_path() -> PathBuf {
+        Paths::project_root().join("history.json")
+    }
 }
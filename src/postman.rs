@@ -0,0 +1,909 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use serde_json::{json, Value};
+
+use crate::auth::{ApiKeyLocation, Auth, OAuth2Grant};
+use crate::http::{self, BodyUsage};
+use crate::import::{is_disabled, text, Imported};
+use crate::model::{Environment, Folder, Header, Model, PathParam, QueryParam, Request};
+use crate::{url, vars};
+
+const SCHEMA: &str = "https://schema.getpostman.com/json/collection/v2.1.0/collection.json";
+
+/// Boundary of multipart bodies built from form-data.
+pub const BOUNDARY: &str = "umbrella-form-boundary";
+
+pub fn is_collection(value: &Value) -> bool {
+    let schema = value.get("info").and_then(|i| i.get("schema")).and_then(|s| s.as_str());
+
+    schema.is_some_and(|s| s.contains("getpostman.com/json/collection"))
+        || value.get("info").is_some() && value.get("item").is_some_and(|i| i.is_array())
+}
+
+pub fn is_environment(value: &Value) -> bool {
+    value.get("_postman_variable_scope").and_then(|s| s.as_str()) == Some("environment")
+        || value.get("name").is_some() && value.get("values").is_some_and(|v| v.is_array())
+}
+
+/// Collections exported by Postman before v2 list requests next to an `order`.
+fn is_v1_collection(value: &Value) -> bool {
+    value.get("requests").is_some_and(|r| r.is_array()) && value.get("order").is_some()
+}
+
+/// Whether the JSON is something `import` takes.
+pub fn is_postman(value: &Value) -> bool {
+    is_collection(value) || is_environment(value) || is_v1_collection(value)
+}
+
+/// Auth params by key, v2.1 lists them, v2.0 keeps them in an object.
+fn auth_params(auth: &Value, kind: &str) -> BTreeMap<String, String> {
+    match auth.get(kind) {
+        Some(Value::Array(params)) => params
+            .iter()
+            .map(|p| (text(p.get("key")), text(p.get("value"))))
+            .collect(),
+        Some(Value::Object(params)) => params
+            .iter()
+            .map(|(key, value)| (key.clone(), text(Some(value))))
+            .collect(),
+        _ => BTreeMap::new(),
+    }
+}
+
+/// Auth set on an item, none when it inherits its parent's.
+fn import_auth(auth: Option<&Value>, owner: &str, skipped: &mut Vec<String>) -> Option<Auth> {
+    let auth = auth.filter(|a| !a.is_null())?;
+    let kind = auth.get("type").and_then(|t| t.as_str()).unwrap_or("noauth");
+    let params = auth_params(auth, kind);
+    let param = |key: &str| params.get(key).cloned().unwrap_or_default();
+
+    let auth = match kind {
+        "inherit" => return None,
+        "noauth" => Auth::None,
+        "basic" => Auth::Basic { username: param("username"), password: param("password") },
+        "bearer" => Auth::Bearer { token: param("token") },
+        "apikey" => Auth::ApiKey {
+            name: param("key"),
+            value: param("value"),
+            location: match param("in").as_str() {
+                "query" => ApiKeyLocation::Query,
+                _ => ApiKeyLocation::Header,
+            },
+        },
+        "oauth2" => {
+            let grant = match param("grant_type").as_str() {
+                "client_credentials" => OAuth2Grant::ClientCredentials,
+                "password_credentials" => OAuth2Grant::Password,
+                other => {
+                    skipped.push(format!(
+                        "{}: OAuth2 {} grant isn't supported, its access token is used as a bearer token",
+                        owner,
+                        if other.is_empty() { "authorization code" } else { other },
+                    ));
+                    return Some(Auth::Bearer { token: param("accessToken") });
+                },
+            };
+
+            Auth::OAuth2 {
+                grant,
+                token_url: param("accessTokenUrl"),
+                client_id: param("clientId"),
+                client_secret: param("clientSecret"),
+                scope: param("scope"),
+                username: param("username"),
+                password: param("password"),
+            }
+        },
+        other => {
+            skipped.push(format!("{}: {} auth isn't supported, no auth is set", owner, other));
+            Auth::None
+        },
+    };
+
+    Some(auth)
+}
+
+/// Scripts of an item or a folder, Postman runs JavaScript so they are only reported.
+fn report_scripts(item: &Value, owner: &str, skipped: &mut Vec<String>) {
+    for event in item.get("event").and_then(|e| e.as_array()).into_iter().flatten() {
+        let exec = event.get("script").and_then(|s| s.get("exec"));
+
+        let empty = match exec {
+            Some(Value::Array(lines)) => lines.iter().all(|l| text(Some(l)).trim().is_empty()),
+            Some(Value::String(source)) => source.trim().is_empty(),
+            _ => true,
+        };
+
+        if !empty {
+            let kind = match event.get("listen").and_then(|l| l.as_str()) {
+                Some("prerequest") => "pre-request",
+                _ => "test",
+            };
+
+            skipped.push(format!("{}: {} script left out, Postman scripts are JavaScript", owner, kind));
+        }
+    }
+}
+
+fn import_url(url: Option<&Value>) -> (String, Vec<QueryParam>, Vec<PathParam>) {
+    let raw = match url {
+        Some(Value::String(raw)) => raw.clone(),
+        Some(url) => match url.get("raw").and_then(|r| r.as_str()) {
+            Some(raw) => raw.to_string(),
+            None => {
+                let join = |key: &str, separator: &str| match url.get(key) {
+                    Some(Value::Array(parts)) => parts.iter().map(|p| text(Some(p))).collect::<Vec<String>>().join(separator),
+                    other => text(other),
+                };
+
+                let protocol = text(url.get("protocol"));
+                let host = join("host", ".");
+                let path = join("path", "/");
+
+                format!(
+                    "{}{}{}{}",
+                    if protocol.is_empty() { String::new() } else { format!("{}://", protocol) },
+                    host,
+                    if path.is_empty() { "" } else { "/" },
+                    path,
+                )
+            },
+        },
+        None => String::new(),
+    };
+
+    let parts = url::split_url(&raw);
+    let base = url::path_variables_to_placeholders(parts.base);
+
+    let query_params: Vec<QueryParam> = match url.and_then(|u| u.get("query")).and_then(|q| q.as_array()) {
+        Some(query) => query
+            .iter()
+            .map(|q| QueryParam {
+                key: text(q.get("key")),
+                value: text(q.get("value")),
+                enabled: !is_disabled(q),
+            })
+            .collect(),
+        None => url::parse_query(parts.query.unwrap_or_default())
+            .into_iter()
+            .map(|(key, value)| QueryParam { key, value, enabled: true })
+            .collect(),
+    };
+
+    let given: Vec<PathParam> = url
+        .and_then(|u| u.get("variable"))
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .map(|v| PathParam {
+            value: text(v.get("value")),
+            ..PathParam::new(&text(v.get("key")))
+        })
+        .collect();
+
+    let path_params = url::merge_path_params(&base, &given);
+
+    (url::with_query(&base, &query_params), query_params, path_params)
+}
+
+fn import_headers(headers: Option<&Value>) -> Vec<Header> {
+    match headers {
+        Some(Value::Array(headers)) => headers
+            .iter()
+            .map(|h| Header {
+                name: text(h.get("key")),
+                value: text(h.get("value")),
+                enabled: !is_disabled(h),
+            })
+            .collect(),
+        // v2.0 also takes headers as a raw block
+        Some(Value::String(block)) => block
+            .lines()
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| Header {
+                name: name.trim().to_string(),
+                value: value.trim().to_string(),
+                enabled: true,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn set_content_type(headers: &mut Vec<Header>, content_type: &str) {
+    if !headers.iter().any(|h| h.name.eq_ignore_ascii_case("Content-Type")) {
+        headers.push(Header {
+            name: String::from("Content-Type"),
+            value: content_type.to_string(),
+            enabled: true,
+        });
+    }
+}
+
+/// Body of the request as text, form-data is turned into a multipart body.
+fn import_body(body: Option<&Value>, headers: &mut Vec<Header>, owner: &str, skipped: &mut Vec<String>) -> String {
+    let Some(body) = body.filter(|b| !is_disabled(b)) else {
+        return String::new();
+    };
+
+    let fields = |mode: &str| body.get(mode).and_then(|f| f.as_array()).cloned().unwrap_or_default();
+
+    match body.get("mode").and_then(|m| m.as_str()) {
+        Some("raw") => {
+            let language = body
+                .get("options")
+                .and_then(|o| o.get("raw"))
+                .and_then(|r| r.get("language"))
+                .and_then(|l| l.as_str());
+
+            let content_type = match language {
+                Some("json") => Some("application/json"),
+                Some("xml") => Some("application/xml"),
+                Some("html") => Some("text/html"),
+                Some("javascript") => Some("application/javascript"),
+                _ => None,
+            };
+
+            if let Some(content_type) = content_type {
+                set_content_type(headers, content_type);
+            }
+
+            text(body.get("raw"))
+        },
+        Some("urlencoded") => {
+            let mut pairs = Vec::new();
+
+            for field in fields("urlencoded") {
+                let key = text(field.get("key"));
+
+                if is_disabled(&field) {
+                    skipped.push(format!("{}: disabled form field {} left out", owner, key));
+                } else {
+                    pairs.push(format!("{}={}", url::percent_encode(&key), url::percent_encode(&text(field.get("value")))));
+                }
+            }
+
+            set_content_type(headers, "application/x-www-form-urlencoded");
+            pairs.join("&")
+        },
+        Some("formdata") => {
+            let mut multipart = String::new();
+
+            for field in fields("formdata") {
+                let key = text(field.get("key"));
+
+                if field.get("type").and_then(|t| t.as_str()) == Some("file") {
+                    skipped.push(format!("{}: file field {} left out, bodies are text", owner, key));
+                } else if is_disabled(&field) {
+                    skipped.push(format!("{}: disabled form field {} left out", owner, key));
+                } else {
+                    multipart.push_str(&format!(
+                        "--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n",
+                        BOUNDARY,
+                        key,
+                        text(field.get("value")),
+                    ));
+                }
+            }
+
+            multipart.push_str(&format!("--{}--\r\n", BOUNDARY));
+            set_content_type(headers, &format!("multipart/form-data; boundary={}", BOUNDARY));
+            multipart
+        },
+        Some("graphql") => {
+            let graphql = body.get("graphql");
+            let variables = text(graphql.and_then(|g| g.get("variables")));
+
+            let body = json!({
+                "query": text(graphql.and_then(|g| g.get("query"))),
+                "variables": serde_json::from_str::<Value>(&variables).unwrap_or(json!({})),
+            });
+
+            set_content_type(headers, "application/json");
+            serde_json::to_string_pretty(&body).unwrap_or_default()
+        },
+        Some(mode) => {
+            skipped.push(format!("{}: {} body left out, bodies are text", owner, mode));
+            String::new()
+        },
+        None => String::new(),
+    }
+}
+
+fn import_request(item: &Value, folder: &str, skipped: &mut Vec<String>) -> Request {
+    let title = text(item.get("name"));
+    let owner = format!("{} / {}", folder, title);
+
+    // v2.0 allows a bare URL in place of the request
+    let spec = match item.get("request") {
+        Some(Value::String(url)) => json!({ "url": url }),
+        Some(request) => request.clone(),
+        None => json!({}),
+    };
+
+    let (url, query_params, path_params) = import_url(spec.get("url"));
+    let mut headers = import_headers(spec.get("header"));
+    let body = import_body(spec.get("body"), &mut headers, &owner, skipped);
+    let method = text(spec.get("method")).to_uppercase();
+
+    report_scripts(item, &owner, skipped);
+
+    let request = Request {
+        method: if method.is_empty() { String::from("GET") } else { method },
+        title,
+        url,
+        body,
+        query_params,
+        path_params,
+        headers,
+        folder: Some(folder.to_string()),
+        auth: import_auth(spec.get("auth"), &owner, skipped).unwrap_or(Auth::Inherit),
+        ..Request::new_default()
+    };
+
+    let texts = [request.url.as_str(), request.body.as_str()]
+        .into_iter()
+        .chain(request.headers.iter().map(|h| h.value.as_str()));
+
+    let mut dynamic: Vec<&str> = texts
+        .flat_map(vars::placeholders)
+        .map(|(_, name)| name)
+        .filter(|name| name.starts_with('$'))
+        .collect();
+    dynamic.dedup();
+
+    for name in dynamic {
+        skipped.push(format!("{}: dynamic variable {{{{{}}}}} has no equivalent, set it by hand", owner, name));
+    }
+
+    request
+}
+
+/// Requests of the items, folders nest as `Parent / Child` with their auth resolved.
+fn import_items(items: &[Value], folder: &Folder, imported: &mut Imported, examples: &mut usize) {
+    for item in items {
+        let name = text(item.get("name"));
+
+        match item.get("item").and_then(|i| i.as_array()) {
+            Some(children) => {
+                let name = format!("{} / {}", folder.name, name);
+
+                report_scripts(item, &name, &mut imported.skipped);
+
+                let child = Folder {
+                    auth: import_auth(item.get("auth"), &name, &mut imported.skipped).unwrap_or(folder.auth.clone()),
+                    name,
+                };
+
+                imported.folders.push(child.clone());
+                import_items(children, &child, imported, examples);
+            },
+            None => {
+                *examples += item.get("response").and_then(|r| r.as_array()).map_or(0, |r| r.len());
+
+                let request = import_request(item, &folder.name, &mut imported.skipped);
+                imported.requests.push(request);
+            },
+        }
+    }
+}
+
+fn import_collection(collection: &Value) -> Imported {
+    let mut imported = Imported::default();
+    let name = text(collection.get("info").and_then(|i| i.get("name")));
+
+    let root = Folder {
+        name: if name.is_empty() { String::from("Postman collection") } else { name },
+        auth: import_auth(collection.get("auth"), "collection", &mut imported.skipped).unwrap_or(Auth::None),
+    };
+
+    report_scripts(collection, "collection", &mut imported.skipped);
+
+    imported.folders.push(root.clone());
+
+    let mut examples = 0;
+    let items = collection.get("item").and_then(|i| i.as_array()).cloned().unwrap_or_default();
+    import_items(&items, &root, &mut imported, &mut examples);
+
+    if examples > 0 {
+        imported.skipped.push(format!("{} saved example responses left out", examples));
+    }
+
+    for variable in collection.get("variable").and_then(|v| v.as_array()).into_iter().flatten() {
+        let key = text(variable.get("key").or(variable.get("id")));
+
+        if !key.is_empty() && !is_disabled(variable) {
+            imported.variables.insert(key, text(variable.get("value")));
+        }
+    }
+
+    // Folders only holding folders stay out of the list
+    let requests = &imported.requests;
+    imported.folders.retain(|f| requests.iter().any(|r| r.folder.as_ref() == Some(&f.name)));
+
+    imported
+}
+
+/// An environment export, made the active environment.
+fn import_environment(environment: &Value) -> Imported {
+    let mut imported = Imported::default();
+    let mut variables = BTreeMap::new();
+
+    for value in environment.get("values").and_then(|v| v.as_array()).into_iter().flatten() {
+        let key = text(value.get("key"));
+
+        if key.is_empty() {
+            continue;
+        }
+
+        if value.get("enabled").and_then(|e| e.as_bool()) == Some(false) {
+            imported.skipped.push(format!("disabled variable {} left out", key));
+        } else if value.get("type").and_then(|t| t.as_str()) == Some("secret") {
+            imported.skipped.push(format!("secret {} left out, add it with [v] Secrets", key));
+        } else {
+            variables.insert(key, text(value.get("value")));
+        }
+    }
+
+    let name = match text(environment.get("name")) {
+        name if name.trim().is_empty() => String::from("Postman environment"),
+        name => name,
+    };
+
+    imported.environment = Some(name.clone());
+    imported.environments.push(Environment { name, variables });
+    imported
+}
+
+/// Requests, folders and variables of a Postman v2.0/v2.1 collection, or
+/// an environment.
+pub fn import(path: &Path) -> Result<Imported, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("Couldn't read {}: {}", path.display(), e))?;
+    let value: Value = serde_json::from_str(&content).map_err(|e| format!("Couldn't parse {}: {}", path.display(), e))?;
+
+    if is_environment(&value) {
+        Ok(import_environment(&value))
+    } else if is_v1_collection(&value) {
+        Err(format!("{} is a Postman v1 collection, export it again as Collection v2.1", path.display()))
+    } else if is_collection(&value) {
+        Ok(import_collection(&value))
+    } else {
+        Err(format!("{} is not a Postman collection or environment", path.display()))
+    }
+}
+
+fn auth_entries(params: &[(&str, &str)]) -> Value {
+    params
+        .iter()
+        .map(|(key, value)| json!({ "key": key, "value": value, "type": "string" }))
+        .collect()
+}
+
+fn export_auth(auth: &Auth) -> Option<Value> {
+    let auth = match auth {
+        Auth::Inherit => return None,
+        Auth::None => json!({ "type": "noauth" }),
+        Auth::Basic { username, password } => json!({
+            "type": "basic",
+            "basic": auth_entries(&[("username", username), ("password", password)]),
+        }),
+        Auth::Bearer { token } => json!({
+            "type": "bearer",
+            "bearer": auth_entries(&[("token", token)]),
+        }),
+        Auth::ApiKey { name, value, location } => json!({
+            "type": "apikey",
+            "apikey": auth_entries(&[
+                ("key", name),
+                ("value", value),
+                ("in", match location {
+                    ApiKeyLocation::Header => "header",
+                    ApiKeyLocation::Query => "query",
+                }),
+            ]),
+        }),
+        Auth::OAuth2 { grant, token_url, client_id, client_secret, scope, username, password } => json!({
+            "type": "oauth2",
+            "oauth2": auth_entries(&[
+                ("grant_type", match grant {
+                    OAuth2Grant::ClientCredentials => "client_credentials",
+                    OAuth2Grant::Password => "password_credentials",
+                }),
+                ("accessTokenUrl", token_url),
+                ("clientId", client_id),
+                ("clientSecret", client_secret),
+                ("scope", scope),
+                ("username", username),
+                ("password", password),
+            ]),
+        }),
+    };
+
+    Some(auth)
+}
+
+/// URL object with `{name}` placeholders as Postman's `:name` path variables.
+fn export_url(request: &Request) -> Value {
+    let base = url::split_url(&request.url).base;
+    let mut converted = String::new();
+    let mut last = 0;
+
+    for (range, name) in url::placeholders(base) {
+        converted.push_str(&base[last..range.start]);
+        converted.push(':');
+        converted.push_str(name);
+        last = range.end;
+    }
+
+    converted.push_str(&base[last..]);
+
+    let (protocol, rest) = match converted.split_once("://") {
+        Some((protocol, rest)) => (Some(protocol), rest),
+        None => (None, converted.as_str()),
+    };
+
+    let (host, path) = rest.split_once('/').unwrap_or((rest, ""));
+
+    let mut url = json!({
+        "raw": url::with_query(&converted, &request.query_params),
+        "host": host.split('.').collect::<Vec<&str>>(),
+        "path": path.split('/').filter(|s| !s.is_empty()).collect::<Vec<&str>>(),
+    });
+
+    if let Some(protocol) = protocol {
+        url["protocol"] = json!(protocol);
+    }
+
+    if !request.query_params.is_empty() {
+        url["query"] = request.query_params
+            .iter()
+            .map(|p| json!({ "key": p.key, "value": p.value, "disabled": !p.enabled }))
+            .collect();
+    }
+
+    if !request.path_params.is_empty() {
+        url["variable"] = request.path_params
+            .iter()
+            .map(|p| json!({ "key": p.name, "value": p.value }))
+            .collect();
+    }
+
+    url
+}
+
+fn export_body(request: &Request) -> Option<Value> {
+    if request.body.is_empty() || matches!(http::body_usage(&request.method), BodyUsage::Forbidden) {
+        return None;
+    }
+
+    let content_type = request.headers
+        .iter()
+        .find(|h| h.enabled && h.name.eq_ignore_ascii_case("Content-Type"))
+        .map(|h| h.value.to_lowercase());
+
+    if content_type.as_deref().is_some_and(|t| t.starts_with("application/x-www-form-urlencoded")) {
+        let fields: Vec<Value> = url::parse_query(&request.body)
+            .into_iter()
+            .map(|(key, value)| json!({ "key": key, "value": value }))
+            .collect();
+
+        return Some(json!({ "mode": "urlencoded", "urlencoded": fields }));
+    }
+
+    let is_json = content_type.as_deref().map_or(
+        serde_json::from_str::<Value>(&request.body).is_ok(),
+        |t| t.contains("json"),
+    );
+
+    Some(json!({
+        "mode": "raw",
+        "raw": request.body,
+        "options": { "raw": { "language": if is_json { "json" } else { "text" } } },
+    }))
+}
+
+fn export_request(request: &Request, notes: &mut Vec<String>) -> Value {
+    let mut spec = json!({
+        "method": request.method,
+        "header": request.headers
+            .iter()
+            .map(|h| json!({ "key": h.name, "value": h.value, "disabled": !h.enabled }))
+            .collect::<Vec<Value>>(),
+        "url": export_url(request),
+    });
+
+    if let Some(body) = export_body(request) {
+        spec["body"] = body;
+    }
+
+    if let Some(auth) = export_auth(&request.auth) {
+        spec["auth"] = auth;
+    }
+
+    let left_out: Vec<&str> = [
+        (!request.pre_script.trim().is_empty() || !request.post_script.trim().is_empty(), "scripts"),
+        (!request.assertions.is_empty(), "tests"),
+        (!request.captures.is_empty(), "captures"),
+    ]
+    .into_iter()
+    .filter_map(|(present, what)| present.then_some(what))
+    .collect();
+
+    if !left_out.is_empty() {
+        notes.push(format!("{}: {} left out, Postman can't run them", request.title, left_out.join(", ")));
+    }
+
+    json!({ "name": request.title, "request": spec })
+}
+
+/// Postman folder being built, folders nest by the ` / ` in their names.
+#[derive(Default)]
+struct FolderItem {
+    name: String,
+    auth: Option<Value>,
+    folders: Vec<FolderItem>,
+    requests: Vec<Value>,
+}
+
+impl FolderItem {
+    /// Folder at the path below this one, created on the way.
+    fn child(&mut self, path: &[&str]) -> &mut FolderItem {
+        let Some((name, rest)) = path.split_first() else {
+            return self;
+        };
+
+        let position = match self.folders.iter().position(|f| f.name == *name) {
+            Some(position) => position,
+            None => {
+                self.folders.push(FolderItem { name: name.to_string(), ..FolderItem::default() });
+                self.folders.len() - 1
+            },
+        };
+
+        self.folders[position].child(rest)
+    }
+
+    /// Subfolders, then requests.
+    fn items(self) -> Vec<Value> {
+        let folders = self.folders.into_iter().map(|folder| {
+            let auth = folder.auth.clone();
+            let mut item = json!({ "name": folder.name, "item": folder.items() });
+
+            if let Some(auth) = auth {
+                item["auth"] = auth;
+            }
+
+            item
+        });
+
+        folders.chain(self.requests).collect()
+    }
+}
+
+/// First part of the folder names when every request is below it, which is
+/// the collection the requests were imported from, e.g. `Shop` of `Shop / Pets`.
+fn imported_root(folders: &[Folder], requests: &[Arc<Request>]) -> Option<String> {
+    let root = |name: &str| name.split(" / ").next().unwrap_or_default().to_string();
+    let first = root(&folders.first()?.name);
+
+    let shared = folders.iter().all(|f| root(&f.name) == first)
+        && requests.iter().all(|r| r.folder.as_deref().is_some_and(|f| root(f) == first));
+
+    shared.then_some(first)
+}
+
+/// The collection as Postman v2.1, with notes on what Postman can't take.
+///
+/// Folders nest by the ` / ` in their names and requests outside of folders
+/// stay at the top. Requests imported from a collection are exported as that
+/// collection, so importing them again gives the same folders, otherwise the
+/// collection is named `name`. Secrets are never exported.
+pub fn export(model: &Model, name: &str) -> (Value, Vec<String>) {
+    let requests = model.requests.read().unwrap();
+    let mut notes = Vec::new();
+    let mut folders: Vec<Folder> = model.folders.clone();
+
+    // Folders requests were moved to by name only
+    for folder in requests.iter().filter_map(|r| r.folder.as_ref()) {
+        if !folders.iter().any(|f| &f.name == folder) {
+            folders.push(Folder { name: folder.clone(), auth: Auth::Inherit });
+        }
+    }
+
+    let root_name = imported_root(&folders, &requests);
+    let mut root = FolderItem { name: root_name.clone().unwrap_or(name.to_string()), ..FolderItem::default() };
+
+    for folder in folders {
+        let children: Vec<Value> = requests
+            .iter()
+            .filter(|r| r.folder.as_ref() == Some(&folder.name))
+            .map(|r| export_request(r, &mut notes))
+            .collect();
+
+        if children.is_empty() {
+            continue;
+        }
+
+        let path: Vec<&str> = match &root_name {
+            Some(root_name) if folder.name == *root_name => Vec::new(),
+            Some(root_name) => folder.name[root_name.len() + 3..].split(" / ").collect(),
+            None => folder.name.split(" / ").collect(),
+        };
+
+        let item = root.child(&path);
+        item.requests.extend(children);
+        item.auth = export_auth(&folder.auth).or(item.auth.take());
+    }
+
+    root.requests.extend(requests.iter().filter(|r| r.folder.is_none()).map(|r| export_request(r, &mut notes)));
+
+    let variables: Vec<Value> = model.collection_variables
+        .iter()
+        .map(|(key, value)| json!({ "key": key, "value": value }))
+        .collect();
+
+    let mut collection = json!({
+        "info": { "name": root.name, "schema": SCHEMA },
+        "variable": variables,
+    });
+
+    if let Some(auth) = root.auth.take() {
+        collection["auth"] = auth;
+    }
+
+    collection["item"] = json!(root.items());
+
+    (collection, notes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collection() -> Value {
+        json!({
+            "info": { "name": "Shop", "schema": SCHEMA },
+            "auth": { "type": "bearer", "bearer": [{ "key": "token", "value": "{{token}}" }] },
+            "variable": [
+                { "key": "host", "value": "shop.test" },
+                { "key": "old", "value": "1", "disabled": true },
+            ],
+            "item": [{
+                "name": "Pets",
+                // v2.0 keeps auth params in an object
+                "auth": { "type": "basic", "basic": { "username": "ann", "password": "pw" } },
+                "item": [{
+                    "name": "Toys",
+                    "auth": { "type": "inherit" },
+                    "item": [{
+                        "name": "Get toy",
+                        "event": [{ "listen": "test", "script": { "exec": ["pm.test('ok')"] } }],
+                        "request": {
+                            "method": "get",
+                            "url": {
+                                "raw": "https://{{host}}/toys/:id?color=red&size=1",
+                                "query": [
+                                    { "key": "color", "value": "red" },
+                                    { "key": "size", "value": "1", "disabled": true },
+                                ],
+                                "variable": [{ "key": "id", "value": "7" }],
+                            },
+                            "header": [
+                                { "key": "X-Trace", "value": "{{$guid}}" },
+                                { "key": "X-Off", "value": "1", "disabled": true },
+                            ],
+                        },
+                        "response": [{ "name": "Example" }],
+                    }],
+                }],
+            }, {
+                "name": "Login",
+                "request": {
+                    "method": "POST",
+                    "url": "https://{{host}}/login",
+                    "body": {
+                        "mode": "urlencoded",
+                        "urlencoded": [
+                            { "key": "user", "value": "a b" },
+                            { "key": "debug", "value": "1", "disabled": true },
+                        ],
+                    },
+                },
+            }],
+        })
+    }
+
+    #[test]
+    fn items_become_requests_in_nested_folders() {
+        let imported = import_collection(&collection());
+
+        let folders: Vec<&str> = imported.folders.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(folders, ["Shop", "Shop / Pets / Toys"]);
+        assert!(matches!(&imported.folders[0].auth, Auth::Bearer { token } if token == "{{token}}"));
+        assert!(matches!(&imported.folders[1].auth, Auth::Basic { username, .. } if username == "ann"));
+        assert_eq!(imported.variables.keys().collect::<Vec<_>>(), ["host"]);
+
+        let toy = &imported.requests[0];
+        assert_eq!(toy.method, "GET");
+        assert_eq!(toy.url, "https://{{host}}/toys/{id}?color=red");
+        assert_eq!(toy.path_params[0].value, "7");
+        assert!(!toy.query_params[1].enabled);
+        assert!(!toy.headers[1].enabled);
+        assert!(matches!(toy.auth, Auth::Inherit));
+
+        let login = &imported.requests[1];
+        assert_eq!(login.folder.as_deref(), Some("Shop"));
+        assert_eq!(login.body, "user=a%20b");
+        assert_eq!(login.headers[0].value, "application/x-www-form-urlencoded");
+
+        assert_eq!(imported.skipped, [
+            "Shop / Pets / Toys / Get toy: test script left out, Postman scripts are JavaScript",
+            "Shop / Pets / Toys / Get toy: dynamic variable {{$guid}} has no equivalent, set it by hand",
+            "Shop / Login: disabled form field debug left out",
+            "1 saved example responses left out",
+        ]);
+    }
+
+    #[test]
+    fn environments_leave_out_disabled_values_and_secrets() {
+        let imported = import_environment(&json!({
+            "name": " ",
+            "_postman_variable_scope": "environment",
+            "values": [
+                { "key": "host", "value": "shop.test", "enabled": true },
+                { "key": "old", "value": "1", "enabled": false },
+                { "key": "token", "value": "t", "type": "secret" },
+            ],
+        }));
+
+        assert_eq!(imported.environment.as_deref(), Some("Postman environment"));
+        assert_eq!(imported.environments[0].variables.keys().collect::<Vec<_>>(), ["host"]);
+        assert_eq!(imported.skipped.len(), 2);
+    }
+
+    #[test]
+    fn exported_collections_import_back() {
+        let imported = import_collection(&collection());
+        let mut model = Model::new(imported.requests, imported.folders);
+        model.collection_variables = imported.variables;
+
+        let (exported, _) = export(&model, "shop-export");
+        assert!(is_collection(&exported));
+        assert_eq!(exported["info"]["name"], "Shop");
+        assert_eq!(exported["auth"]["type"], "bearer");
+        assert_eq!(exported["item"][0]["item"][0]["name"], "Toys");
+
+        let again = import_collection(&exported);
+        let urls: Vec<&str> = again.requests.iter().map(|r| r.url.as_str()).collect();
+        assert_eq!(urls, ["https://{{host}}/toys/{id}?color=red", "https://{{host}}/login"]);
+
+        let toy = &again.requests[0];
+        assert_eq!(toy.folder.as_deref(), Some("Shop / Pets / Toys"));
+        assert_eq!(toy.path_params[0].value, "7");
+        assert_eq!(toy.query_params.len(), 2);
+        assert_eq!(again.requests[1].body, "user=a%20b");
+        assert!(matches!(&again.folders[1].auth, Auth::Basic { password, .. } if password == "pw"));
+        assert_eq!(again.variables["host"], "shop.test");
+    }
+
+    #[test]
+    fn folders_of_other_collections_nest_below_the_named_one() {
+        let mut pet = Request::new_default();
+        pet.title = String::from("Get pet");
+        pet.folder = Some(String::from("pets / cats"));
+
+        let model = Model::new(vec![pet, Request::new_default()], vec![]);
+
+        let (exported, _) = export(&model, "api");
+        assert_eq!(exported["info"]["name"], "api");
+        assert_eq!(exported["item"][0]["name"], "pets");
+        assert_eq!(exported["item"][0]["item"][0]["item"][0]["name"], "Get pet");
+
+        let again = import_collection(&exported);
+        let folders: Vec<Option<&str>> = again.requests.iter().map(|r| r.folder.as_deref()).collect();
+        assert_eq!(folders, [Some("api / pets / cats"), Some("api")]);
+    }
+}
@@ -0,0 +1,368 @@
+use serde::Serialize;
+
+use crate::history;
+use crate::runner::{CollectionRun, StepResult};
+use crate::secrets::SecretStore;
+
+fn escape_xml(text: &str) -> String {
+    text.chars()
+        // Control characters other than whitespace aren't allowed in XML 1.0
+        .filter(|c| !c.is_control() || matches!(c, '\n' | '\r' | '\t'))
+        .map(|c| match c {
+            '&' => String::from("&amp;"),
+            '<' => String::from("&lt;"),
+            '>' => String::from("&gt;"),
+            '"' => String::from("&quot;"),
+            '\'' => String::from("&apos;"),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+fn seconds(ms: u128) -> String {
+    format!("{:.3}", ms as f64 / 1000.0)
+}
+
+fn iso_time(timestamp: u64) -> String {
+    history::format_time(timestamp).replace(' ', "T")
+}
+
+fn result_ms(result: &StepResult) -> u128 {
+    result.response.as_ref().map(|r| r.2).unwrap_or_default()
+}
+
+/// Testcases of a request: one per check, or one for the response when it has none.
+fn testcases(result: &StepResult, secrets: &SecretStore, xml: &mut String) -> (usize, usize, usize) {
+    let classname = escape_xml(&format!("{} {}", result.method, result.title));
+    let time = seconds(result_ms(result));
+
+    let Ok((status, reason, _)) = &result.response else {
+        let error = secrets.redact(result.response.as_ref().err().map(String::as_str).unwrap_or_default());
+
+        xml.push_str(&format!(
+            "    <testcase classname=\"{}\" name=\"response\" time=\"{}\">\n      <error message=\"{}\"/>\n    </testcase>\n",
+            classname,
+            time,
+            escape_xml(&error),
+        ));
+
+        return (1, 0, 1);
+    };
+
+    if result.checks.is_empty() {
+        xml.push_str(&format!(
+            "    <testcase classname=\"{}\" name=\"{}\" time=\"{}\"/>\n",
+            classname,
+            escape_xml(&format!("response {} {}", status, reason)),
+            time,
+        ));
+
+        return (1, 0, 0);
+    }
+
+    let mut failures = 0;
+
+    for check in result.checks.iter() {
+        let name = escape_xml(&check.description);
+
+        if check.passed {
+            xml.push_str(&format!("    <testcase classname=\"{}\" name=\"{}\" time=\"{}\"/>\n", classname, name, time));
+        } else {
+            failures += 1;
+
+            xml.push_str(&format!(
+                "    <testcase classname=\"{}\" name=\"{}\" time=\"{}\">\n      <failure message=\"{}\"/>\n    </testcase>\n",
+                classname,
+                name,
+                time,
+                escape_xml(&secrets.redact(&check.message)),
+            ));
+        }
+    }
+
+    (result.checks.len(), failures, 0)
+}
+
+/// JUnit XML with a testsuite per iteration and a testcase per check of every request.
+pub fn junit(run: &CollectionRun, secrets: &SecretStore) -> String {
+    let mut suites = String::new();
+    let (mut all_tests, mut all_failures, mut all_errors, mut all_ms) = (0, 0, 0, 0);
+
+    for iteration in 0..run.iterations {
+        let results: Vec<&StepResult> = run.results.iter().filter(|r| r.iteration == iteration).collect();
+
+        if results.is_empty() {
+            continue;
+        }
+
+        let mut cases = String::new();
+        let (mut tests, mut failures, mut errors) = (0, 0, 0);
+
+        for result in results.iter() {
+            let (t, f, e) = testcases(result, secrets, &mut cases);
+            tests += t;
+            failures += f;
+            errors += e;
+        }
+
+        let ms: u128 = results.iter().map(|r| result_ms(r)).sum();
+        let name = if run.iterations > 1 { format!("Iteration {}", iteration + 1) } else { String::from("Collection") };
+
+        suites.push_str(&format!(
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" time=\"{}\" timestamp=\"{}\">\n{}  </testsuite>\n",
+            name,
+            tests,
+            failures,
+            errors,
+            seconds(ms),
+            iso_time(run.started),
+            cases,
+        ));
+
+        all_tests += tests;
+        all_failures += failures;
+        all_errors += errors;
+        all_ms += ms;
+    }
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuites name=\"umbrella\" tests=\"{}\" failures=\"{}\" errors=\"{}\" time=\"{}\">\n{}</testsuites>\n",
+        all_tests,
+        all_failures,
+        all_errors,
+        seconds(all_ms),
+        suites,
+    )
+}
+
+#[derive(Serialize)]
+struct JsonCheck {
+    description: String,
+    passed: bool,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    message: String,
+}
+
+/// Captured values are left out, they're often tokens.
+#[derive(Serialize)]
+struct JsonCapture {
+    variable: String,
+    captured: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct JsonResult {
+    iteration: usize,
+    method: String,
+    title: String,
+    passed: bool,
+    status: Option<u16>,
+    reason: Option<String>,
+    time_ms: Option<u128>,
+    error: Option<String>,
+    checks: Vec<JsonCheck>,
+    captures: Vec<JsonCapture>,
+    log: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct JsonSummary {
+    method: String,
+    title: String,
+    runs: usize,
+    passed: usize,
+    failed: usize,
+    average_ms: Option<u128>,
+}
+
+#[derive(Serialize)]
+struct JsonReport {
+    started: String,
+    iterations: usize,
+    requests: usize,
+    passed: usize,
+    failed: usize,
+    /// Whether the run stopped at a failure before sending everything
+    stopped: bool,
+    results: Vec<JsonResult>,
+    summary: Vec<JsonSummary>,
+}
+
+/// Machine-readable report with every result and the summary per request.
+pub fn json(run: &CollectionRun, secrets: &SecretStore) -> String {
+    let results = run.results
+        .iter()
+        .map(|r| JsonResult {
+            iteration: r.iteration + 1,
+            method: r.method.clone(),
+            title: r.title.clone(),
+            passed: r.passed(),
+            status: r.response.as_ref().ok().map(|s| s.0),
+            reason: r.response.as_ref().ok().map(|s| s.1.clone()),
+            time_ms: r.response.as_ref().ok().map(|s| s.2),
+            error: r.response.as_ref().err().map(|e| secrets.redact(e)),
+            checks: r.checks
+                .iter()
+                .map(|c| JsonCheck {
+                    description: c.description.clone(),
+                    passed: c.passed,
+                    message: secrets.redact(&c.message),
+                })
+                .collect(),
+            captures: r.captured
+                .iter()
+                .map(|c| JsonCapture {
+                    variable: c.variable.clone(),
+                    captured: c.value.is_ok(),
+                    error: c.value.as_ref().err().cloned(),
+                })
+                .collect(),
+            log: r.log.iter().map(|l| secrets.redact(l)).collect(),
+        })
+        .collect();
+
+    let summary = run.summary()
+        .into_iter()
+        .map(|s| JsonSummary {
+            method: s.method,
+            title: s.title,
+            runs: s.runs,
+            passed: s.passed,
+            failed: s.failed,
+            average_ms: s.average_ms,
+        })
+        .collect();
+
+    let report = JsonReport {
+        started: format!("{}Z", iso_time(run.started)),
+        iterations: run.iterations,
+        requests: run.results.len(),
+        passed: run.passed(),
+        failed: run.failed(),
+        stopped: run.is_stopped(),
+        results,
+        summary,
+    };
+
+    serde_json::to_string_pretty(&report).expect("Report is always serializable")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    use serde_json::{json, Value};
+
+    use crate::assertions::AssertionResult;
+    use crate::capture::CaptureResult;
+    use crate::model::{Model, Request};
+    use crate::runner::RunSettings;
+
+    fn check(description: &str, passed: bool, message: &str) -> AssertionResult {
+        AssertionResult { description: description.to_string(), passed, message: message.to_string() }
+    }
+
+    fn result(iteration: usize, request: usize, response: Result<(u16, String, u128), String>) -> StepResult {
+        let (method, title) = [("GET", "List pets"), ("POST", "Add <pet> & \"co\"")][request];
+
+        StepResult {
+            iteration,
+            request,
+            method: method.to_string(),
+            title: title.to_string(),
+            response,
+            captured: Vec::new(),
+            checks: Vec::new(),
+            log: Vec::new(),
+        }
+    }
+
+    /// Two iterations, the first with a failed check and a request without a response.
+    fn run() -> CollectionRun {
+        let requests = ["List pets", "Add <pet> & \"co\""].map(|title| Request {
+            method: String::from("GET"),
+            title: title.to_string(),
+            ..Request::new_default()
+        });
+        let model = Model::new(requests.to_vec(), vec![]);
+        let settings = RunSettings { iterations: Some(2), ..RunSettings::default() };
+        let mut run = CollectionRun::new(&model, &settings).unwrap();
+
+        let mut listed = result(0, 0, Ok((200, String::from("OK"), 1500)));
+        listed.checks = vec![check("status is 200", true, ""), check("body has 'cat'", false, "got <none>\u{1b}[0m")];
+        listed.captured = vec![CaptureResult { variable: String::from("token"), value: Ok(String::from("abc")) }];
+
+        let mut added = result(1, 1, Ok((201, String::from("Created"), 250)));
+        added.checks = vec![check("created", true, "")];
+
+        run.results = vec![
+            listed,
+            result(0, 1, Err(String::from("Connection refused"))),
+            result(1, 0, Ok((200, String::from("OK"), 500))),
+            added,
+        ];
+        run
+    }
+
+    fn no_secrets() -> SecretStore {
+        SecretStore::load(PathBuf::from(format!("/nonexistent/umbrella-report-{}.json", std::process::id())))
+    }
+
+    #[test]
+    fn xml_text_is_escaped() {
+        assert_eq!(escape_xml("a<b>&\"c\" 'd'"), "a&lt;b&gt;&amp;&quot;c&quot; &apos;d&apos;");
+        assert_eq!(escape_xml("bell\u{7}\u{1b}[0m\ttab\r\nline"), "bell[0m\ttab\r\nline");
+    }
+
+    #[test]
+    fn junit_counts_failures_and_errors_per_iteration() {
+        let xml = junit(&run(), &no_secrets());
+        let lines: Vec<&str> = xml.lines().collect();
+
+        let added = r#"classname="POST Add &lt;pet&gt; &amp; &quot;co&quot;""#;
+
+        assert_eq!(lines[1], r#"<testsuites name="umbrella" tests="5" failures="1" errors="1" time="2.250">"#);
+        assert!(lines[2].starts_with(r#"  <testsuite name="Iteration 1" tests="3" failures="1" errors="1""#));
+        assert_eq!(&lines[3..10], [
+            r#"    <testcase classname="GET List pets" name="status is 200" time="1.500"/>"#,
+            r#"    <testcase classname="GET List pets" name="body has &apos;cat&apos;" time="1.500">"#,
+            r#"      <failure message="got &lt;none&gt;[0m"/>"#,
+            "    </testcase>",
+            &format!(r#"    <testcase {} name="response" time="0.000">"#, added),
+            r#"      <error message="Connection refused"/>"#,
+            "    </testcase>",
+        ]);
+        assert_eq!(lines[10], "  </testsuite>");
+        assert!(lines[11].starts_with(r#"  <testsuite name="Iteration 2" tests="2" failures="0" errors="0""#));
+        assert_eq!(lines[12], r#"    <testcase classname="GET List pets" name="response 200 OK" time="0.500"/>"#);
+        assert_eq!(lines[13], format!(r#"    <testcase {} name="created" time="0.250"/>"#, added));
+        assert_eq!(&lines[14..], ["  </testsuite>", "</testsuites>"]);
+    }
+
+    #[test]
+    fn json_reports_results_and_the_summary() {
+        let report: Value = serde_json::from_str(&json(&run(), &no_secrets())).unwrap();
+
+        assert_eq!([&report["requests"], &report["passed"], &report["failed"]], [&json!(4), &json!(2), &json!(2)]);
+        assert_eq!(report["stopped"], json!(false));
+        assert_eq!(report["results"][0]["checks"][1], json!({
+            "description": "body has 'cat'",
+            "passed": false,
+            "message": "got <none>\u{1b}[0m",
+        }));
+        assert_eq!(report["results"][0]["captures"], json!([{ "variable": "token", "captured": true }]));
+        assert_eq!(report["results"][1]["error"], json!("Connection refused"));
+        assert_eq!(report["results"][3]["iteration"], json!(2));
+        assert_eq!(report["summary"][0], json!({
+            "method": "GET",
+            "title": "List pets",
+            "runs": 2,
+            "passed": 1,
+            "failed": 1,
+            "average_ms": 1000,
+        }));
+    }
+}
@@ -0,0 +1,224 @@
+use crate::model::{PathParam, QueryParam, Request};
+use crate::url;
+use crate::vars;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SyncKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+impl SyncKind {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            SyncKind::Added => "+",
+            SyncKind::Removed => "-",
+            SyncKind::Changed => "~",
+        }
+    }
+}
+
+/// A difference between the collection and the spec, applied when accepted.
+pub struct SyncChange {
+    pub kind: SyncKind,
+    /// Method, path and summary of the operation
+    pub label: String,
+    pub details: Vec<String>,
+    /// Id of the request it applies to, none for added operations
+    pub request_id: Option<String>,
+    /// The request after the change, none for removed operations
+    pub updated: Option<Request>,
+    pub accepted: bool,
+}
+
+fn label(request: &Request) -> String {
+    match &request.operation {
+        Some(operation) => format!("{} · {}", operation.label(), request.title),
+        None => format!("{} {} · {}", request.method, url::split_url(&request.url).base, request.title),
+    }
+}
+
+/// Whether the existing request stands for the operation of the incoming one.
+fn same_operation(existing: &Request, incoming: &Request, by_id: bool) -> bool {
+    let Some(new) = &incoming.operation else {
+        return false;
+    };
+
+    match &existing.operation {
+        Some(old) if old.spec != new.spec => false,
+        Some(old) if by_id => old.operation_id.is_some() && old.operation_id == new.operation_id,
+        Some(old) => old.method == new.method && old.path == new.path,
+        // Imported before requests kept their operation
+        None => !by_id && existing.method == incoming.method && url::split_url(&existing.url).base == incoming.url,
+    }
+}
+
+/// Params of the spec, with the values and toggles of the existing ones.
+fn merge_query(existing: &[QueryParam], incoming: &[QueryParam]) -> Vec<QueryParam> {
+    incoming
+        .iter()
+        .map(|param| existing
+            .iter()
+            .find(|p| p.key == param.key)
+            .cloned()
+            .unwrap_or(param.clone()))
+        .collect()
+}
+
+fn merge_path(existing: &[PathParam], incoming: &[PathParam]) -> Vec<PathParam> {
+    incoming
+        .iter()
+        .map(|param| PathParam {
+            value: existing
+                .iter()
+                .find(|p| p.name == param.name)
+                .map(|p| p.value.clone())
+                .unwrap_or_default(),
+            ..param.clone()
+        })
+        .collect()
+}
+
+/// URL of the updated request, a base turned into a variable is kept.
+fn merge_url(existing: &Request, incoming: &Request, query: &[QueryParam]) -> String {
+    let base = url::split_url(&existing.url).base;
+
+    let kept = existing.operation.as_ref().and_then(|old| {
+        let new = incoming.operation.as_ref()?;
+        let prefix = base.strip_suffix(old.path.as_str())?;
+
+        (!vars::placeholders(prefix).is_empty()).then(|| format!("{}{}", prefix, new.path))
+    });
+
+    url::with_query(&kept.unwrap_or(incoming.url.clone()), query)
+}
+
+fn details(existing: &Request, incoming: &Request, updated: &Request) -> Vec<String> {
+    let mut details = Vec::new();
+
+    match (&existing.operation, &incoming.operation) {
+        (Some(old), Some(new)) => {
+            if old.method != new.method || old.path != new.path {
+                details.push(format!("operation {} → {}", old.label(), new.label()));
+            }
+
+            if old.operation_id != new.operation_id {
+                details.push(format!(
+                    "operationId {} → {}",
+                    old.operation_id.as_deref().unwrap_or("none"),
+                    new.operation_id.as_deref().unwrap_or("none"),
+                ));
+            }
+        },
+        (None, Some(_)) => details.push(String::from("linked to the spec, responses get checked against it")),
+        _ => {},
+    }
+
+    let (old_url, new_url) = (url::split_url(&existing.url).base, url::split_url(&updated.url).base);
+
+    if old_url != new_url {
+        details.push(format!("URL {} → {}", old_url, new_url));
+    }
+
+    for param in incoming.query_params.iter().filter(|p| !existing.query_params.iter().any(|e| e.key == p.key)) {
+        details.push(format!("query param {} added", param.key));
+    }
+
+    for param in existing.query_params.iter().filter(|p| !incoming.query_params.iter().any(|i| i.key == p.key)) {
+        details.push(format!("query param {} removed", param.key));
+    }
+
+    for param in incoming.path_params.iter() {
+        match existing.path_params.iter().find(|e| e.name == param.name) {
+            None => details.push(format!("path param {} added", param.name)),
+            Some(old) if old.param_type != param.param_type => {
+                details.push(format!("path param {} is {} instead of {}", param.name, param.param_type, old.param_type));
+            },
+            Some(old) if old.required != param.required => {
+                let now = if param.required { "required" } else { "optional" };
+                details.push(format!("path param {} is {} now", param.name, now));
+            },
+            Some(_) => {},
+        }
+    }
+
+    for param in existing.path_params.iter().filter(|p| !incoming.path_params.iter().any(|i| i.name == p.name)) {
+        details.push(format!("path param {} removed", param.name));
+    }
+
+    details
+}
+
+/// Changes bringing the collection in line with the spec's requests.
+///
+/// Operations are matched by operationId, then by method and path. Updates
+/// take the URL, the params and the link to the spec, titles, bodies, auth,
+/// tests and scripts stay as they were edited. Only requests imported from
+/// `spec` are taken as removed when the spec doesn't have them anymore.
+pub fn plan(existing: &[Request], incoming: Vec<Request>, spec: &str) -> Vec<SyncChange> {
+    let mut matched = vec![false; existing.len()];
+    let mut changes = Vec::new();
+
+    for request in incoming {
+        let found = [true, false].into_iter().find_map(|by_id| {
+            existing
+                .iter()
+                .enumerate()
+                .find(|(i, e)| !matched[*i] && same_operation(e, &request, by_id))
+        });
+
+        let Some((i, old)) = found else {
+            changes.push(SyncChange {
+                kind: SyncKind::Added,
+                label: label(&request),
+                details: vec![format!("{} {}", request.method, request.url)],
+                request_id: None,
+                updated: Some(request),
+                accepted: true,
+            });
+            continue;
+        };
+
+        matched[i] = true;
+
+        let query_params = merge_query(&old.query_params, &request.query_params);
+
+        let updated = Request {
+            method: request.method.clone(),
+            url: merge_url(old, &request, &query_params),
+            query_params,
+            path_params: merge_path(&old.path_params, &request.path_params),
+            operation: request.operation.clone(),
+            ..Request::from(old)
+        };
+
+        let details = details(old, &request, &updated);
+
+        if !details.is_empty() {
+            changes.push(SyncChange {
+                kind: SyncKind::Changed,
+                label: label(&updated),
+                details,
+                request_id: Some(old.id.clone()),
+                updated: Some(updated),
+                accepted: true,
+            });
+        }
+    }
+
+    for (old, _) in existing.iter().zip(matched).filter(|(_, matched)| !matched) {
+        if old.operation.as_ref().is_some_and(|o| o.spec == spec) {
+            changes.push(SyncChange {
+                kind: SyncKind::Removed,
+                label: label(old),
+                details: vec![String::from("the spec doesn't have the operation anymore, the request is deleted")],
+                request_id: Some(old.id.clone()),
+                updated: None,
+                accepted: true,
+            });
+        }
+    }
+
+    changes
+}
@@ -0,0 +1,534 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::assertions::AssertionResult;
+use crate::capture::CaptureResult;
+use crate::contract;
+use crate::data::{self, DataRow};
+use crate::model::{Model, Request, Sending};
+use crate::paths::Paths;
+use crate::report;
+use crate::secrets::{SecretStore, PASSPHRASE_VAR};
+
+const USAGE: &str = "Usage: umbrella run [--file FILE.http] [--environment NAME] [--folder NAME] [--iterations N] \
+    [--data FILE.csv|FILE.jsonl] [--delay MS] [--stop-on-failure] [--junit FILE] [--json FILE] [TITLE...]";
+
+/// Which requests run and how often.
+#[derive(Default)]
+pub struct RunSettings {
+    /// All folders when not set
+    pub folder: Option<String>,
+    /// All requests of the folder when empty
+    pub titles: Vec<String>,
+    /// Once per data row when not set, or once without data
+    pub iterations: Option<usize>,
+    /// CSV or JSONL file relative to the project root, every row binds variables for an iteration
+    pub data: Option<String>,
+    /// Pause between two requests
+    pub delay: Duration,
+    pub stop_on_failure: bool,
+}
+
+/// Outcome of one request of one iteration.
+pub struct StepResult {
+    pub iteration: usize,
+    /// Index of the request in the run
+    pub request: usize,
+    pub method: String,
+    pub title: String,
+    /// Status, reason and total time, or why there's no response
+    pub response: Result<(u16, String, u128), String>,
+    pub captured: Vec<CaptureResult>,
+    pub checks: Vec<AssertionResult>,
+    /// What scripts printed
+    pub log: Vec<String>,
+}
+
+impl StepResult {
+    pub fn passed(&self) -> bool {
+        self.response.is_ok() && self.checks.iter().all(|c| c.passed)
+    }
+}
+
+/// Results of a request over all iterations.
+pub struct RequestSummary {
+    pub method: String,
+    pub title: String,
+    pub runs: usize,
+    pub passed: usize,
+    pub failed: usize,
+    /// Average time of the requests that got a response
+    pub average_ms: Option<u128>,
+}
+
+/// Requests of the collection run in order, iteration after iteration.
+///
+/// Each `step` sends one request, so the TUI can draw the progress between
+/// them, it uses `start` and `finish` to draw while a request is on its way.
+/// Values captured by a request are available to the ones after it.
+pub struct CollectionRun {
+    requests: Vec<Request>,
+    rows: Vec<DataRow>,
+    /// Session values hidden by the bound data row, put back by `end`
+    shadowed: BTreeMap<String, Option<String>>,
+    pub iterations: usize,
+    delay: Duration,
+    stop_on_failure: bool,
+    next: usize,
+    last_sent: Option<Instant>,
+    stopped: bool,
+    /// Boxed, the run is a variant of the app's dialogs
+    sending: Option<Box<Sending>>,
+    /// Unix time the run was set up at
+    pub started: u64,
+    pub results: Vec<StepResult>,
+}
+
+impl CollectionRun {
+    pub fn new(model: &Model, settings: &RunSettings) -> Result<Self, String> {
+        let requests: Vec<Request> = model.requests
+            .read().unwrap()
+            .iter()
+            .map(|r| Request::from(r.as_ref()))
+            .filter(|r| settings.folder.is_none() || r.folder == settings.folder)
+            .filter(|r| settings.titles.is_empty() || settings.titles.contains(&r.title))
+            .collect();
+
+        if requests.is_empty() {
+            return Err(String::from("No requests to run"));
+        }
+
+        let rows = match settings.data.as_deref().map(str::trim).filter(|d| !d.is_empty()) {
+            Some(file) => data::load(&Paths::project_root().join(file))?,
+            None => Vec::new(),
+        };
+
+        let iterations = settings.iterations.unwrap_or(std::cmp::max(rows.len(), 1));
+
+        if iterations == 0 {
+            return Err(String::from("Iterations must be at least 1"));
+        }
+
+        Ok(Self {
+            requests,
+            rows,
+            shadowed: BTreeMap::new(),
+            iterations,
+            delay: settings.delay,
+            stop_on_failure: settings.stop_on_failure,
+            next: 0,
+            last_sent: None,
+            stopped: false,
+            sending: None,
+            started: SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs(),
+            results: Vec::new(),
+        })
+    }
+
+    pub fn requests(&self) -> &[Request] {
+        &self.requests
+    }
+
+    /// Requests to send over all iterations.
+    pub fn total(&self) -> usize {
+        self.iterations * self.requests.len()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.stopped || self.next >= self.total()
+    }
+
+    /// Whether the run ended before all requests were sent.
+    pub fn is_stopped(&self) -> bool {
+        self.stopped && self.next < self.total()
+    }
+
+    pub fn stop(&mut self) {
+        self.stopped = true;
+    }
+
+    /// Iteration and request sent by the next step.
+    pub fn upcoming(&self) -> Option<(usize, &Request)> {
+        if self.is_finished() {
+            return None;
+        }
+
+        Some((self.next / self.requests.len(), &self.requests[self.next % self.requests.len()]))
+    }
+
+    /// Time to wait before the next request.
+    pub fn wait_time(&self) -> Duration {
+        self.last_sent
+            .map(|sent| self.delay.saturating_sub(sent.elapsed()))
+            .unwrap_or_default()
+    }
+
+    pub fn passed(&self) -> usize {
+        self.results.iter().filter(|r| r.passed()).count()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.results.len() - self.passed()
+    }
+
+    /// Sends the next request and waits for its response.
+    pub fn step(&mut self, model: &mut Model) -> Option<&StepResult> {
+        self.start(model);
+        self.finish(model, true)
+    }
+
+    /// Whether a request is on its way, `finish` takes its response.
+    pub fn is_sending(&self) -> bool {
+        self.sending.is_some()
+    }
+
+    /// Starts sending the next request, the iteration's data row is bound before its first request.
+    pub fn start(&mut self, model: &mut Model) {
+        if self.sending.is_some() {
+            return;
+        }
+
+        let Some((iteration, _)) = self.upcoming() else {
+            return;
+        };
+
+        let index = self.next % self.requests.len();
+
+        if index == 0 && !self.rows.is_empty() {
+            self.end(model);
+
+            for (name, value) in &self.rows[iteration % self.rows.len()] {
+                let previous = model.variables.insert(name.clone(), value.clone());
+                self.shadowed.insert(name.clone(), previous);
+            }
+        }
+
+        self.sending = Some(Box::new(model.send_request(&self.requests[index])));
+    }
+
+    /// Checks the response of the request on its way, `wait` blocks until it's there.
+    pub fn finish(&mut self, model: &mut Model, wait: bool) -> Option<&StepResult> {
+        let response = model.receive_response(self.sending.as_ref()?, wait)?;
+        self.sending = None;
+
+        let index = self.next % self.requests.len();
+        let request = &self.requests[index];
+        let mut result = StepResult {
+            iteration: self.next / self.requests.len(),
+            request: index,
+            method: request.method.clone(),
+            title: request.title.clone(),
+            response: Err(String::new()),
+            captured: Vec::new(),
+            checks: Vec::new(),
+            log: Vec::new(),
+        };
+
+        match response {
+            Ok(response) => {
+                result.captured = model.capture(request, &response);
+                result.checks = model.check_assertions(request, &response);
+                result.checks.extend(model.run_post_script(request, &response));
+
+                if let Some(operation) = &request.operation {
+                    let violations = contract::check(&mut model.specs, operation, &response);
+                    result.checks.push(contract::as_check(operation, &violations));
+                }
+                result.response = Ok((response.status, response.reason, response.timings.total.as_millis()));
+            },
+            Err(e) => result.response = Err(e),
+        }
+
+        result.log = std::mem::take(&mut model.script_log);
+
+        self.next += 1;
+        self.last_sent = Some(Instant::now());
+
+        if self.stop_on_failure && !result.passed() {
+            self.stopped = true;
+        }
+
+        if self.is_finished() {
+            self.end(model);
+        }
+
+        self.results.push(result);
+        self.results.last()
+    }
+
+    /// Unbinds the data row, the session gets back the values it had before.
+    pub fn end(&mut self, model: &mut Model) {
+        for (name, previous) in std::mem::take(&mut self.shadowed) {
+            match previous {
+                Some(value) => model.variables.insert(name, value),
+                None => model.variables.remove(&name),
+            };
+        }
+    }
+
+    /// Results per request, in collection order.
+    pub fn summary(&self) -> Vec<RequestSummary> {
+        self.requests
+            .iter()
+            .enumerate()
+            .map(|(i, request)| {
+                let results: Vec<&StepResult> = self.results.iter().filter(|r| r.request == i).collect();
+                let times: Vec<u128> = results.iter().filter_map(|r| r.response.as_ref().ok().map(|t| t.2)).collect();
+                let passed = results.iter().filter(|r| r.passed()).count();
+
+                RequestSummary {
+                    method: request.method.clone(),
+                    title: request.title.clone(),
+                    runs: results.len(),
+                    passed,
+                    failed: results.len() - passed,
+                    average_ms: (!times.is_empty()).then(|| times.iter().sum::<u128>() / times.len() as u128),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Run settings and where the reports go.
+#[derive(Default)]
+struct CliOptions {
+    settings: RunSettings,
+    /// `.http` file run instead of the saved collection
+    file: Option<PathBuf>,
+    /// Environment used instead of the active one
+    environment: Option<String>,
+    junit: Option<PathBuf>,
+    json: Option<PathBuf>,
+}
+
+fn parse_args(args: &[String]) -> Result<CliOptions, String> {
+    let mut options = CliOptions::default();
+    let settings = &mut options.settings;
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--file" => {
+                options.file = Some(args.next().ok_or("--file needs a .http file")?.into());
+            },
+            "--environment" => {
+                options.environment = Some(args.next().ok_or("--environment needs a name")?.clone());
+            },
+            "--folder" => {
+                settings.folder = Some(args.next().ok_or("--folder needs a name")?.clone());
+            },
+            "--iterations" => {
+                let count = args.next().ok_or("--iterations needs a count")?;
+                settings.iterations = Some(count.parse().map_err(|_| format!("Invalid iteration count {}", count))?);
+            },
+            "--data" => {
+                settings.data = Some(args.next().ok_or("--data needs a file")?.clone());
+            },
+            "--delay" => {
+                let ms = args.next().ok_or("--delay needs milliseconds")?;
+                settings.delay = Duration::from_millis(ms.parse().map_err(|_| format!("Invalid delay {}", ms))?);
+            },
+            "--stop-on-failure" => settings.stop_on_failure = true,
+            "--junit" => {
+                options.junit = Some(args.next().ok_or("--junit needs a file")?.into());
+            },
+            "--json" => {
+                options.json = Some(args.next().ok_or("--json needs a file")?.into());
+            },
+            flag if flag.starts_with("--") => return Err(format!("Unknown option {}", flag)),
+            title => settings.titles.push(title.to_string()),
+        }
+    }
+
+    Ok(options)
+}
+
+fn print_result(result: &StepResult) {
+    let verdict = if result.passed() { "PASS" } else { "FAIL" };
+
+    match &result.response {
+        Ok((status, reason, ms)) => {
+            println!("{} {} {} · {} {} · {} ms", verdict, result.method, result.title, status, reason, ms);
+        },
+        Err(e) => println!("{} {} {} · {}", verdict, result.method, result.title, e),
+    }
+
+    for line in result.log.iter() {
+        println!("  » {}", line);
+    }
+
+    for captured in result.captured.iter() {
+        match &captured.value {
+            Ok(value) => println!("  → {{{{{}}}}} = {}", captured.variable, value),
+            Err(e) => println!("  → {{{{{}}}}} not captured: {}", captured.variable, e),
+        }
+    }
+
+    for check in result.checks.iter() {
+        if check.passed {
+            println!("  ✓ {}", check.description);
+        } else {
+            println!("  ✗ {}: {}", check.description, check.message);
+        }
+    }
+}
+
+fn print_summary(run: &CollectionRun) {
+    let summary = run.summary();
+    let width = summary
+        .iter()
+        .map(|s| s.method.chars().count() + s.title.chars().count() + 1)
+        .max()
+        .unwrap_or_default()
+        .max(7);
+
+    println!("\n{:<width$}  {:>5}  {:>6}  {:>6}  {:>6}", "Request", "Runs", "Passed", "Failed", "Avg ms");
+
+    for s in summary.iter() {
+        let average = s.average_ms.map(|ms| ms.to_string()).unwrap_or(String::from("-"));
+        let name = format!("{} {}", s.method, s.title);
+
+        println!("{:<width$}  {:>5}  {:>6}  {:>6}  {:>6}", name, s.runs, s.passed, s.failed, average);
+    }
+}
+
+/// Runs the requests of the collection without the TUI and prints the results.
+///
+/// Returns the exit code: 0 when every request succeeded and passed its
+/// assertions, 1 when any didn't, 2 when the arguments are wrong or a
+/// report couldn't be written.
+pub fn run(args: &[String]) -> i32 {
+    let options = match parse_args(args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            return 2;
+        },
+    };
+
+    let opened = match &options.file {
+        Some(path) => Model::open_http(path),
+        None => Model::load_from_disk_or_default(),
+    };
+
+    let mut model = match opened {
+        Ok(model) => model,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 1;
+        },
+    };
+
+    if let Some(name) = &options.environment {
+        if !model.environments.iter().any(|e| &e.name == name) {
+            let names: Vec<&str> = model.environments.iter().map(|e| e.name.as_str()).collect();
+            eprintln!("No environment {}, there are: {}", name, names.join(", "));
+            return 1;
+        }
+
+        model.environment = Some(name.clone());
+    }
+
+    if model.secrets.is_locked() {
+        eprintln!("Secrets are locked, set {} to use them", PASSPHRASE_VAR);
+    }
+
+    for e in [&model.history.load_error, &model.secrets.load_error].into_iter().flatten() {
+        eprintln!("{}", e);
+    }
+
+    if model.tls_insecure() {
+        eprintln!("TLS verification is off, any server certificate is accepted");
+    }
+
+    let mut run = match CollectionRun::new(&model, &options.settings) {
+        Ok(run) => run,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 1;
+        },
+    };
+
+    while let Some((iteration, _)) = run.upcoming() {
+        if run.iterations > 1 && run.results.last().is_none_or(|r| r.iteration != iteration) {
+            println!("── Iteration {}/{}", iteration + 1, run.iterations);
+        }
+
+        thread::sleep(run.wait_time());
+
+        if let Some(result) = run.step(&mut model) {
+            print_result(result);
+        }
+    }
+
+    if run.iterations > 1 {
+        print_summary(&run);
+    }
+
+    if run.is_stopped() {
+        println!("\nStopped after the first failure");
+    }
+
+    println!(
+        "\n{} requests, {} passed, {} failed",
+        run.results.len(),
+        run.passed(),
+        run.failed(),
+    );
+
+    let reports = [
+        (options.junit, report::junit as fn(&CollectionRun, &SecretStore) -> String),
+        (options.json, report::json),
+    ];
+
+    for (path, write) in reports {
+        let Some(path) = path else {
+            continue;
+        };
+
+        if let Err(e) = fs::write(&path, write(&run, &model.secrets)) {
+            eprintln!("Couldn't write {}: {}", path.display(), e);
+            return 2;
+        }
+    }
+
+    if run.failed() > 0 { 1 } else { 0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn data_rows_are_unbound_when_the_run_ends() {
+        let path = std::env::temp_dir().join(format!("umbrella-rows-{}.csv", std::process::id()));
+        fs::write(&path, "user,extra\na,1\nb,2\n").unwrap();
+
+        // Nothing listens on port 1, every request fails right away
+        let mut model = Model::new(vec![Request {
+            method: String::from("GET"),
+            url: String::from("http://127.0.0.1:1/{{user}}"),
+            ..Request::new_default()
+        }], vec![]);
+        model.variables.insert(String::from("user"), String::from("session"));
+
+        let settings = RunSettings { data: Some(path.display().to_string()), ..RunSettings::default() };
+        let mut run = CollectionRun::new(&model, &settings).unwrap();
+        let mut bound = Vec::new();
+
+        while run.upcoming().is_some() {
+            run.start(&mut model);
+            bound.push(model.variables.get("user").cloned());
+            run.finish(&mut model, true);
+        }
+
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(bound, [Some(String::from("a")), Some(String::from("b"))]);
+        assert_eq!(model.variables.get("user").map(String::as_str), Some("session"));
+        assert!(!model.variables.contains_key("extra"));
+    }
+}
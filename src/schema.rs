@@ -0,0 +1,435 @@
+use regex::Regex;
+use serde_json::Value;
+
+/// Deeper `$ref` chains are taken for cycles.
+const MAX_DEPTH: usize = 64;
+
+/// Checks values against a JSON Schema.
+///
+/// Covers the keywords used to describe API payloads: types, properties,
+/// items, enums, bounds, patterns, combinators, local `$ref`s and the
+/// OpenAPI 3.0 `nullable`. Unknown keywords are ignored.
+struct Validator<'a> {
+    root: &'a Value,
+    errors: Vec<Violation>,
+}
+
+/// Why a value doesn't match, at a JSON pointer into the value.
+pub struct Violation {
+    pub pointer: String,
+    pub message: String,
+}
+
+/// Errors of the value, empty if it's valid.
+pub fn validate(schema: &Value, value: &Value) -> Vec<String> {
+    validate_in(schema, schema, value)
+}
+
+/// Like `validate`, with `$ref`s resolved against `root`, e.g. a whole OpenAPI document.
+pub fn validate_in(root: &Value, schema: &Value, value: &Value) -> Vec<String> {
+    violations(root, schema, value)
+        .into_iter()
+        .map(|v| format!("{}: {}", display_path(&v.pointer), v.message))
+        .collect()
+}
+
+/// Violations of the value, `$ref`s are resolved against `root`.
+pub fn violations(root: &Value, schema: &Value, value: &Value) -> Vec<Violation> {
+    let mut validator = Validator { root, errors: Vec::new() };
+    validator.check(schema, value, "", 0);
+    validator.errors
+}
+
+/// Escapes a key for a JSON pointer, RFC 6901.
+fn pointer_key(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// `/items/0/id` as `$.items[0].id`.
+fn display_path(pointer: &str) -> String {
+    let mut path = String::from("$");
+
+    for key in pointer.split('/').skip(1) {
+        let key = key.replace("~1", "/").replace("~0", "~");
+
+        if !key.is_empty() && key.chars().all(|c| c.is_ascii_digit()) {
+            path.push_str(&format!("[{}]", key));
+        } else {
+            path.push_str(&format!(".{}", key));
+        }
+    }
+
+    path
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn is_type(value: &Value, expected: &str) -> bool {
+    let actual = type_name(value);
+
+    actual == expected
+        || (expected == "number" && actual == "integer")
+        // 1.0 is an integer as far as schemas go
+        || (expected == "integer" && value.as_f64().is_some_and(|f| f.fract() == 0.0))
+}
+
+impl<'a> Validator<'a> {
+    fn error(&mut self, path: &str, message: String) {
+        self.errors.push(Violation { pointer: path.to_string(), message });
+    }
+
+    /// Validates without recording errors, for combinators.
+    fn passes(&self, schema: &Value, value: &Value, depth: usize) -> bool {
+        let mut nested = Validator { root: self.root, errors: Vec::new() };
+        nested.check(schema, value, "", depth);
+        nested.errors.is_empty()
+    }
+
+    fn resolve(&mut self, reference: &str, path: &str) -> Option<&'a Value> {
+        let resolved = reference
+            .strip_prefix('#')
+            .and_then(|pointer| self.root.pointer(pointer));
+
+        if resolved.is_none() {
+            self.error(path, format!("can't resolve $ref {}", reference));
+        }
+
+        resolved
+    }
+
+    fn check(&mut self, schema: &Value, value: &Value, path: &str, depth: usize) {
+        if depth > MAX_DEPTH {
+            self.error(path, String::from("schema is nested too deep"));
+            return;
+        }
+
+        let schema = match schema {
+            Value::Bool(true) => return,
+            Value::Bool(false) => {
+                self.error(path, String::from("no value is allowed here"));
+                return;
+            },
+            Value::Object(schema) => schema,
+            _ => return,
+        };
+
+        if let Some(reference) = schema.get("$ref").and_then(|r| r.as_str()) {
+            if let Some(resolved) = self.resolve(reference, path) {
+                self.check(resolved, value, path, depth + 1);
+            }
+
+            return;
+        }
+
+        if value.is_null() && schema.get("nullable").and_then(|n| n.as_bool()) == Some(true) {
+            return;
+        }
+
+        if let Some(expected) = schema.get("type") {
+            let types: Vec<&str> = match expected {
+                Value::String(t) => vec![t.as_str()],
+                Value::Array(ts) => ts.iter().filter_map(|t| t.as_str()).collect(),
+                _ => vec![],
+            };
+
+            if !types.is_empty() && !types.iter().any(|t| is_type(value, t)) {
+                self.error(path, format!("expected {}, got {}", types.join(" or "), type_name(value)));
+                return;
+            }
+        }
+
+        if let Some(options) = schema.get("enum").and_then(|e| e.as_array()) {
+            if !options.contains(value) {
+                self.error(path, format!("{} is not one of {}", value, Value::Array(options.clone())));
+            }
+        }
+
+        if let Some(expected) = schema.get("const") {
+            if expected != value {
+                self.error(path, format!("expected {}, got {}", expected, value));
+            }
+        }
+
+        self.check_combinators(schema, value, path, depth);
+
+        match value {
+            Value::Number(n) => self.check_number(schema, n.as_f64().unwrap_or_default(), path),
+            Value::String(s) => self.check_string(schema, s, path),
+            Value::Array(items) => self.check_array(schema, items, path, depth),
+            Value::Object(map) => self.check_object(schema, map, path, depth),
+            _ => {},
+        }
+    }
+
+    fn check_combinators(
+        &mut self,
+        schema: &serde_json::Map<String, Value>,
+        value: &Value,
+        path: &str,
+        depth: usize,
+    ) {
+        if let Some(all) = schema.get("allOf").and_then(|a| a.as_array()) {
+            for sub in all {
+                self.check(sub, value, path, depth + 1);
+            }
+        }
+
+        if let Some(any) = schema.get("anyOf").and_then(|a| a.as_array()) {
+            if !any.iter().any(|sub| self.passes(sub, value, depth + 1)) {
+                self.error(path, String::from("matches none of anyOf"));
+            }
+        }
+
+        if let Some(one) = schema.get("oneOf").and_then(|a| a.as_array()) {
+            let matched = one.iter().filter(|sub| self.passes(sub, value, depth + 1)).count();
+
+            if matched != 1 {
+                self.error(path, format!("matches {} of oneOf, expected exactly 1", matched));
+            }
+        }
+
+        if let Some(not) = schema.get("not") {
+            if self.passes(not, value, depth + 1) {
+                self.error(path, String::from("matches the schema in not"));
+            }
+        }
+    }
+
+    fn check_number(&mut self, schema: &serde_json::Map<String, Value>, n: f64, path: &str) {
+        let bound = |key: &str| schema.get(key).and_then(|b| b.as_f64());
+
+        // Draft 4 and OpenAPI 3.0 use boolean exclusive flags
+        let exclusive = |key: &str| schema.get(key).and_then(|b| b.as_bool()) == Some(true);
+
+        if let Some(min) = bound("minimum") {
+            if n < min || (exclusive("exclusiveMinimum") && n == min) {
+                self.error(path, format!("{} is less than the minimum {}", n, min));
+            }
+        }
+
+        if let Some(max) = bound("maximum") {
+            if n > max || (exclusive("exclusiveMaximum") && n == max) {
+                self.error(path, format!("{} is more than the maximum {}", n, max));
+            }
+        }
+
+        if let Some(min) = bound("exclusiveMinimum") {
+            if n <= min {
+                self.error(path, format!("{} is not more than {}", n, min));
+            }
+        }
+
+        if let Some(max) = bound("exclusiveMaximum") {
+            if n >= max {
+                self.error(path, format!("{} is not less than {}", n, max));
+            }
+        }
+
+        if let Some(step) = bound("multipleOf").filter(|s| *s > 0.0) {
+            // 19.99 / 0.01 is 1998.9999999999998 in floating point
+            let quotient = n / step;
+
+            if (quotient - quotient.round()).abs() > 1e-9 * quotient.abs().max(1.0) {
+                self.error(path, format!("{} is not a multiple of {}", n, step));
+            }
+        }
+    }
+
+    fn check_string(&mut self, schema: &serde_json::Map<String, Value>, s: &str, path: &str) {
+        let len = s.chars().count() as u64;
+
+        if let Some(min) = schema.get("minLength").and_then(|m| m.as_u64()) {
+            if len < min {
+                self.error(path, format!("shorter than {} characters", min));
+            }
+        }
+
+        if let Some(max) = schema.get("maxLength").and_then(|m| m.as_u64()) {
+            if len > max {
+                self.error(path, format!("longer than {} characters", max));
+            }
+        }
+
+        if let Some(pattern) = schema.get("pattern").and_then(|p| p.as_str()) {
+            match Regex::new(pattern) {
+                Ok(re) if !re.is_match(s) => {
+                    self.error(path, format!("\"{}\" doesn't match {}", s, pattern));
+                },
+                Ok(_) => {},
+                Err(_) => self.error(path, format!("invalid pattern {}", pattern)),
+            }
+        }
+    }
+
+    fn check_array(
+        &mut self,
+        schema: &serde_json::Map<String, Value>,
+        items: &[Value],
+        path: &str,
+        depth: usize,
+    ) {
+        if let Some(min) = schema.get("minItems").and_then(|m| m.as_u64()) {
+            if (items.len() as u64) < min {
+                self.error(path, format!("fewer than {} items", min));
+            }
+        }
+
+        if let Some(max) = schema.get("maxItems").and_then(|m| m.as_u64()) {
+            if items.len() as u64 > max {
+                self.error(path, format!("more than {} items", max));
+            }
+        }
+
+        if schema.get("uniqueItems").and_then(|u| u.as_bool()) == Some(true) {
+            let duplicate = items.iter().enumerate().any(|(i, item)| items[..i].contains(item));
+
+            if duplicate {
+                self.error(path, String::from("items are not unique"));
+            }
+        }
+
+        if let Some(item_schema) = schema.get("items").filter(|s| s.is_object() || s.is_boolean()) {
+            for (i, item) in items.iter().enumerate() {
+                self.check(item_schema, item, &format!("{}/{}", path, i), depth + 1);
+            }
+        }
+    }
+
+    fn check_object(
+        &mut self,
+        schema: &serde_json::Map<String, Value>,
+        map: &serde_json::Map<String, Value>,
+        path: &str,
+        depth: usize,
+    ) {
+        if let Some(required) = schema.get("required").and_then(|r| r.as_array()) {
+            for name in required.iter().filter_map(|n| n.as_str()) {
+                if !map.contains_key(name) {
+                    self.error(path, format!("missing required property {}", name));
+                }
+            }
+        }
+
+        let properties = schema.get("properties").and_then(|p| p.as_object());
+
+        for (name, value) in map {
+            let property_path = format!("{}/{}", path, pointer_key(name));
+
+            match properties.and_then(|p| p.get(name)) {
+                Some(property) => self.check(property, value, &property_path, depth + 1),
+                None => match schema.get("additionalProperties") {
+                    Some(Value::Bool(false)) => {
+                        self.error(path, format!("property {} is not allowed", name));
+                    },
+                    Some(additional) => self.check(additional, value, &property_path, depth + 1),
+                    None => {},
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn errors_name_the_path_of_the_value() {
+        let schema = json!({
+            "type": "object",
+            "required": ["id", "name"],
+            "properties": {
+                "id": {"type": "integer"},
+                "tags": {"type": "array", "items": {"type": "string"}},
+                "a/b": {"type": "string"},
+            },
+        });
+
+        let errors = validate(&schema, &json!({"id": "1", "tags": ["a", 2], "a/b": 3}));
+        assert_eq!(errors, [
+            "$: missing required property name",
+            "$.a/b: expected string, got integer",
+            "$.id: expected integer, got string",
+            "$.tags[1]: expected string, got integer",
+        ]);
+
+        assert!(validate(&schema, &json!({"id": 1.0, "name": "x"})).is_empty());
+    }
+
+    #[test]
+    fn refs_are_resolved_against_the_root() {
+        let document = json!({
+            "components": {"schemas": {
+                "Pet": {
+                    "type": "object",
+                    "properties": {"owner": {"$ref": "#/components/schemas/Owner"}},
+                    "additionalProperties": false,
+                },
+                "Owner": {"type": "string", "nullable": true},
+            }},
+        });
+        let pet = json!({"$ref": "#/components/schemas/Pet"});
+
+        assert!(validate_in(&document, &pet, &json!({"owner": null})).is_empty());
+        assert_eq!(validate_in(&document, &pet, &json!({"owner": 1, "age": 2})), [
+            "$: property age is not allowed",
+            "$.owner: expected string, got integer",
+        ]);
+        assert_eq!(validate(&json!({"$ref": "#/nope"}), &json!(1)), ["$: can't resolve $ref #/nope"]);
+    }
+
+    #[test]
+    fn ref_cycles_stop_at_the_depth_limit() {
+        let errors = validate(&json!({"$ref": "#"}), &json!(1));
+        assert_eq!(errors, ["$: schema is nested too deep"]);
+    }
+
+    #[test]
+    fn bounds_patterns_and_enums_are_checked() {
+        let check = |schema: Value, value: Value| validate(&schema, &value);
+
+        assert_eq!(check(json!({"minimum": 1, "exclusiveMinimum": true}), json!(1)), [
+            "$: 1 is less than the minimum 1",
+        ]);
+        assert_eq!(check(json!({"exclusiveMaximum": 10}), json!(10)), ["$: 10 is not less than 10"]);
+        assert_eq!(check(json!({"multipleOf": 0.5}), json!(1.25)), ["$: 1.25 is not a multiple of 0.5"]);
+        assert!(check(json!({"multipleOf": 0.01}), json!(19.99)).is_empty());
+        assert!(check(json!({"multipleOf": 0.1}), json!(0.3)).is_empty());
+        assert_eq!(check(json!({"multipleOf": 0.01}), json!(19.995)), ["$: 19.995 is not a multiple of 0.01"]);
+        assert_eq!(check(json!({"pattern": "^[a-z]+$", "maxLength": 2}), json!("abc")), [
+            "$: longer than 2 characters",
+        ]);
+        assert_eq!(check(json!({"pattern": "^[a-z]+$"}), json!("A")), ["$: \"A\" doesn't match ^[a-z]+$"]);
+        assert_eq!(check(json!({"uniqueItems": true, "minItems": 3}), json!([1, 1])), [
+            "$: fewer than 3 items",
+            "$: items are not unique",
+        ]);
+        assert_eq!(check(json!({"enum": ["a", "b"]}), json!("c")), ["$: \"c\" is not one of [\"a\",\"b\"]"]);
+    }
+
+    #[test]
+    fn combinators_count_matching_schemas() {
+        let one_of = json!({"oneOf": [{"type": "integer"}, {"type": "number"}]});
+        assert_eq!(validate(&one_of, &json!(1)), ["$: matches 2 of oneOf, expected exactly 1"]);
+        assert!(validate(&one_of, &json!(1.5)).is_empty());
+
+        let any_of = json!({"anyOf": [{"type": "string"}, {"type": "null"}]});
+        assert_eq!(validate(&any_of, &json!(1)), ["$: matches none of anyOf"]);
+
+        let not = json!({"not": {"const": 0}});
+        assert_eq!(validate(&not, &json!(0)), ["$: matches the schema in not"]);
+        assert_eq!(validate(&json!(false), &json!(0)), ["$: no value is allowed here"]);
+    }
+}
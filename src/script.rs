@@ -0,0 +1,380 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use base64::Engine as _;
+use hmac::{Hmac, Mac};
+use regex::Regex;
+use rhai::module_resolvers::DummyModuleResolver;
+use rhai::{Dynamic, Engine, Map, Scope};
+use sha2::{Digest, Sha256};
+
+use crate::assertions::AssertionResult;
+use crate::http::{self, HttpRequest, HttpResponse};
+
+/// Scripts running longer are stopped, a runaway loop shouldn't hang the app.
+const MAX_RUN_TIME: Duration = Duration::from_secs(5);
+const MAX_OPERATIONS: u64 = 50_000_000;
+const MAX_CALL_LEVELS: usize = 64;
+const MAX_COLLECTION_SIZE: usize = 100_000;
+
+/// What scripts see besides the request and the response.
+pub struct ScriptContext<'a> {
+    /// Captured values, written back after the script
+    pub variables: &'a mut BTreeMap<String, String>,
+    /// Values of the names the script passes to `secret()`
+    pub secrets: BTreeMap<String, String>,
+    /// Lines the script printed
+    pub log: &'a mut Vec<String>,
+}
+
+/// Names passed to `secret("...")`, resolved before the script runs.
+///
+/// Only string literals are found, so the secrets a script reads are known
+/// without running it.
+pub fn secret_names(script: &str) -> Vec<String> {
+    let re = Regex::new(r#"secret\(\s*"([^"]+)"\s*\)"#).expect("Invalid secret pattern");
+
+    re.captures_iter(script)
+        .map(|c| c[1].to_string())
+        .collect()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn hmac_sha256(key: &str, message: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key.as_bytes()).expect("HMAC takes keys of any size");
+    mac.update(message.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn random_hex(len: usize) -> Result<String, Box<rhai::EvalAltResult>> {
+    let mut bytes = vec![0; len.div_ceil(2)];
+    getrandom::getrandom(&mut bytes).map_err(|e| e.to_string())?;
+
+    Ok(hex(&bytes).chars().take(len).collect())
+}
+
+fn unix_time() -> Duration {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default()
+}
+
+/// Engine without file access or `eval`, limited in time and memory.
+fn engine(secrets: BTreeMap<String, String>, log: Rc<RefCell<Vec<String>>>) -> Engine {
+    let mut engine = Engine::new();
+
+    engine.set_module_resolver(DummyModuleResolver::new());
+    engine.disable_symbol("eval");
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.set_max_call_levels(MAX_CALL_LEVELS);
+    engine.set_max_array_size(MAX_COLLECTION_SIZE);
+    engine.set_max_map_size(MAX_COLLECTION_SIZE);
+
+    let started = Instant::now();
+    engine.on_progress(move |_| (started.elapsed() > MAX_RUN_TIME).then(|| Dynamic::from("timed out")));
+
+    let print_log = log.clone();
+    engine.on_print(move |text| print_log.borrow_mut().push(text.to_string()));
+    engine.on_debug(move |text, _, _| log.borrow_mut().push(text.to_string()));
+
+    engine.register_fn("secret", move |name: &str| -> Result<String, Box<rhai::EvalAltResult>> {
+        secrets
+            .get(name)
+            .cloned()
+            .ok_or_else(|| format!("secret {} is not known", name).into())
+    });
+
+    engine.register_fn("sha256", |text: &str| hex(&Sha256::digest(text.as_bytes())));
+    engine.register_fn("hmac_sha256", |key: &str, message: &str| hex(&hmac_sha256(key, message)));
+    engine.register_fn("hmac_sha256_base64", |key: &str, message: &str| {
+        base64::engine::general_purpose::STANDARD.encode(hmac_sha256(key, message))
+    });
+    engine.register_fn("base64_encode", |text: &str| base64::engine::general_purpose::STANDARD.encode(text));
+    engine.register_fn("nonce", || random_hex(32));
+    engine.register_fn("nonce", |len: i64| random_hex(len.clamp(1, 1024) as usize));
+    engine.register_fn("timestamp", || unix_time().as_secs() as i64);
+    engine.register_fn("timestamp_ms", || unix_time().as_millis() as i64);
+
+    engine
+}
+
+/// Headers by name, a repeated header like `Set-Cookie` has an array of its values.
+fn headers_map(headers: &[(String, String)]) -> Map {
+    let mut map = Map::new();
+
+    for (name, value) in headers {
+        let value = Dynamic::from(value.clone());
+
+        match map.get_mut(name.as_str()) {
+            Some(existing) if existing.is_array() => existing.write_lock::<rhai::Array>().unwrap().push(value),
+            Some(existing) => *existing = Dynamic::from(vec![existing.clone(), value]),
+            None => {
+                map.insert(name.as_str().into(), value);
+            },
+        }
+    }
+
+    map
+}
+
+/// Values of a header in the map, an array stands for a repeated header.
+fn header_values(value: &Dynamic) -> Vec<String> {
+    match value.clone().try_cast::<rhai::Array>() {
+        Some(values) => values.iter().map(text_of).collect(),
+        None => vec![text_of(value)],
+    }
+}
+
+/// Strings are taken as they are, other values as their text.
+fn text_of(value: &Dynamic) -> String {
+    value.clone().into_string().unwrap_or_else(|_| value.to_string())
+}
+
+fn variables_map(variables: &BTreeMap<String, String>) -> Map {
+    variables
+        .iter()
+        .map(|(name, value)| (name.as_str().into(), Dynamic::from(value.clone())))
+        .collect()
+}
+
+fn take_variables(scope: &Scope, variables: &mut BTreeMap<String, String>) {
+    if let Some(map) = scope.get_value::<Map>("vars") {
+        *variables = map.iter().map(|(name, value)| (name.to_string(), text_of(value))).collect();
+    }
+}
+
+fn run(engine: &Engine, scope: &mut Scope, script: &str) -> Result<(), String> {
+    engine
+        .run_with_scope(scope, script)
+        .map_err(|e| e.to_string())
+}
+
+/// Runs the pre-request script, which can change `request.method`, `.url`,
+/// `.headers` and `.body` and the `vars` map.
+pub fn run_pre_request(script: &str, request: &mut HttpRequest, context: ScriptContext) -> Result<(), String> {
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let engine = engine(context.secrets, log.clone());
+
+    let mut outgoing = Map::new();
+    outgoing.insert("method".into(), Dynamic::from(request.method.clone()));
+    outgoing.insert("url".into(), Dynamic::from(request.url.clone()));
+    outgoing.insert("headers".into(), Dynamic::from(headers_map(&request.headers)));
+    outgoing.insert("body".into(), Dynamic::from(request.body.clone()));
+
+    let mut scope = Scope::new();
+    scope.push("request", outgoing);
+    scope.push("vars", variables_map(context.variables));
+
+    let result = run(&engine, &mut scope, script);
+    context.log.append(&mut log.borrow_mut());
+    result?;
+
+    take_variables(&scope, context.variables);
+
+    let outgoing = scope.get_value::<Map>("request").ok_or("request is not a map anymore")?;
+    let field = |name: &str| outgoing.get(name).map(text_of).unwrap_or_default();
+
+    request.method = field("method");
+    request.url = field("url");
+    request.body = field("body");
+
+    let headers = outgoing
+        .get("headers")
+        .and_then(|h| h.clone().try_cast::<Map>())
+        .ok_or("request.headers is not a map anymore")?;
+
+    // Headers keep their order, new ones go last
+    let mut names: Vec<&str> = Vec::new();
+
+    for name in request.headers.iter().map(|(n, _)| n.as_str()).chain(headers.keys().map(|n| n.as_str())) {
+        if !names.contains(&name) && headers.contains_key(name) {
+            names.push(name);
+        }
+    }
+
+    request.headers = names
+        .into_iter()
+        .flat_map(|name| header_values(&headers[name]).into_iter().map(move |v| (name.to_string(), v)))
+        .collect();
+
+    // The script may have left what would break the request head
+    http::check_head(request)
+}
+
+/// Runs the post-response script, which sees `response` and `vars` and
+/// reports results with `check(description, passed)`.
+pub fn run_post_response(
+    script: &str,
+    response: &HttpResponse,
+    context: ScriptContext,
+) -> Result<Vec<AssertionResult>, String> {
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let checks = Rc::new(RefCell::new(Vec::new()));
+    let mut engine = engine(context.secrets, log.clone());
+
+    let results = checks.clone();
+    engine.register_fn("check", move |description: &str, passed: bool| {
+        results.borrow_mut().push(AssertionResult {
+            description: description.to_string(),
+            passed,
+            message: String::from("check failed"),
+        });
+    });
+
+    let json = serde_json::from_str::<serde_json::Value>(&response.body)
+        .ok()
+        .and_then(|v| rhai::serde::to_dynamic(v).ok())
+        .unwrap_or(Dynamic::UNIT);
+
+    let mut incoming = Map::new();
+    incoming.insert("status".into(), Dynamic::from(response.status as i64));
+    incoming.insert("reason".into(), Dynamic::from(response.reason.clone()));
+    incoming.insert("headers".into(), Dynamic::from(headers_map(&response.headers)));
+    incoming.insert("body".into(), Dynamic::from(response.body.clone()));
+    incoming.insert("json".into(), json);
+    incoming.insert("time_ms".into(), Dynamic::from(response.timings.total.as_millis() as i64));
+
+    let mut scope = Scope::new();
+    scope.push_constant("response", incoming);
+    scope.push("vars", variables_map(context.variables));
+
+    let result = run(&engine, &mut scope, script);
+    context.log.append(&mut log.borrow_mut());
+    result?;
+
+    take_variables(&scope, context.variables);
+
+    let checks = checks.take();
+    Ok(checks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::Timings;
+
+    fn request() -> HttpRequest {
+        HttpRequest {
+            method: String::from("GET"),
+            url: String::from("https://example.com/pets"),
+            headers: vec![
+                (String::from("Cookie"), String::from("a=1")),
+                (String::from("Accept"), String::from("*/*")),
+                (String::from("Cookie"), String::from("b=2")),
+            ],
+            body: String::new(),
+        }
+    }
+
+    fn pre(script: &str, request: &mut HttpRequest, variables: &mut BTreeMap<String, String>) -> Result<(), String> {
+        let mut log = Vec::new();
+        let secrets = BTreeMap::from([(String::from("key"), String::from("s3cret"))]);
+
+        run_pre_request(script, request, ScriptContext { variables, secrets, log: &mut log })
+    }
+
+    #[test]
+    fn pre_request_scripts_change_the_request_and_vars() {
+        let mut request = request();
+        let mut variables = BTreeMap::from([(String::from("user"), String::from("tom"))]);
+
+        pre(r#"
+            request.method = "POST";
+            request.url += "?user=" + vars.user;
+            request.body = `{"sig":"${hmac_sha256("k", "m")}"}`;
+            request.headers["X-Key"] = secret("key");
+            vars.count = 2;
+            vars.remove("user");
+        "#, &mut request, &mut variables).unwrap();
+
+        assert_eq!((request.method.as_str(), request.url.as_str()), ("POST", "https://example.com/pets?user=tom"));
+        assert_eq!(request.body, format!(r#"{{"sig":"{}"}}"#, hex(&hmac_sha256("k", "m"))));
+        assert_eq!(request.headers.last().unwrap(), &(String::from("X-Key"), String::from("s3cret")));
+        assert_eq!(variables, BTreeMap::from([(String::from("count"), String::from("2"))]));
+    }
+
+    #[test]
+    fn repeated_headers_are_kept_apart() {
+        let mut untouched = request();
+        pre("", &mut untouched, &mut BTreeMap::new()).unwrap();
+
+        let mut changed = request();
+        let script = r#"request.headers.Cookie.push("c=3"); request.headers.Accept = ["a", "b"];"#;
+        pre(script, &mut changed, &mut BTreeMap::new()).unwrap();
+
+        let pairs = |request: &HttpRequest| -> Vec<String> {
+            request.headers.iter().map(|(n, v)| format!("{}: {}", n, v)).collect()
+        };
+
+        assert_eq!(pairs(&untouched), ["Cookie: a=1", "Cookie: b=2", "Accept: */*"]);
+        assert_eq!(pairs(&changed), ["Cookie: a=1", "Cookie: b=2", "Cookie: c=3", "Accept: a", "Accept: b"]);
+    }
+
+    #[test]
+    fn scripts_cant_break_the_request_head() {
+        let mut variables = BTreeMap::new();
+
+        let method = pre(r#"request.method = "GET / HTTP/1.1\r\nX: y";"#, &mut request(), &mut variables);
+        let header = pre(r#"request.headers.Accept = "a\r\nHost: evil";"#, &mut request(), &mut variables);
+        let name = pre(r#"request.headers["Bad Name"] = "x";"#, &mut request(), &mut variables);
+
+        assert!(method.unwrap_err().starts_with("Invalid method"));
+        assert_eq!(header.unwrap_err(), "Header Accept has a line break or NUL in its value");
+        assert_eq!(name.unwrap_err(), "Invalid header name \"Bad Name\"");
+    }
+
+    #[test]
+    fn scripts_are_sandboxed() {
+        let run = |script: &str| pre(script, &mut request(), &mut BTreeMap::new());
+
+        assert!(run(r#"eval("1 + 1")"#).is_err());
+        assert!(run(r#"import "lib" as lib;"#).is_err());
+        assert!(run(r#"secret("other")"#).unwrap_err().contains("secret other is not known"));
+        assert!(run("fn deeper(n) { deeper(n + 1) } deeper(0);").unwrap_err().starts_with("Stack overflow"));
+        assert!(run("let a = []; a.pad(200000, 0);").unwrap_err().starts_with("Size of array"));
+    }
+
+    #[test]
+    fn runaway_scripts_are_stopped() {
+        let started = Instant::now();
+        let result = pre("let n = 0; loop { n += 1; }", &mut request(), &mut BTreeMap::new());
+
+        assert!(result.unwrap_err().starts_with("Script terminated"));
+        assert!(started.elapsed() < MAX_RUN_TIME * 2);
+    }
+
+    #[test]
+    fn post_response_scripts_check_the_response() {
+        let response = HttpResponse {
+            status: 201,
+            reason: String::from("Created"),
+            headers: vec![
+                (String::from("Set-Cookie"), String::from("a=1")),
+                (String::from("Set-Cookie"), String::from("b=2")),
+            ],
+            body: String::from(r#"{ "id": 7, "tags": ["x"] }"#),
+            timings: Timings { connect: Duration::ZERO, first_byte: Duration::ZERO, total: Duration::ZERO },
+        };
+
+        let mut variables = BTreeMap::new();
+        let mut log = Vec::new();
+        let context = ScriptContext { variables: &mut variables, secrets: BTreeMap::new(), log: &mut log };
+
+        let checks = run_post_response(r#"
+            check("created", response.status == 201);
+            check("two cookies", response.headers["Set-Cookie"].len() == 2);
+            check("tagged", response.json.tags.contains("y"));
+            vars.id = response.json.id;
+            print(`id ${response.json.id}`);
+        "#, &response, context).unwrap();
+
+        let passed: Vec<(&str, bool)> = checks.iter().map(|c| (c.description.as_str(), c.passed)).collect();
+
+        assert_eq!(passed, [("created", true), ("two cookies", true), ("tagged", false)]);
+        assert_eq!(variables.get("id").map(String::as_str), Some("7"));
+        assert_eq!(log, ["id 7"]);
+    }
+}
@@ -0,0 +1,189 @@
+use crate::model::Request;
+
+/// Positions of matched characters per request field, used for highlighting.
+#[derive(Default)]
+pub struct FilterMatch {
+    pub method: Vec<usize>,
+    pub title: Vec<usize>,
+    pub url: Vec<usize>,
+}
+
+enum Term {
+    /// Fuzzy term matched against title, method and URL
+    Any(String),
+    Title(String),
+    Url(String),
+    /// Exact method match, `method:GET,POST` accepts any of the listed methods
+    Method(Vec<String>),
+}
+
+/// Parsed filter query, e.g. `method:POST pets url:v1`.
+///
+/// Every term has to match for the request to be shown.
+pub struct Filter {
+    terms: Vec<Term>,
+}
+
+/// Matches `pattern` as a case-insensitive subsequence of `text`.
+///
+/// Returns the matched character positions and a score that favours
+/// consecutive matches and matches at word starts.
+pub fn fuzzy_match(pattern: &str, text: &str) -> Option<(Vec<usize>, i64)> {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+
+    if pattern.is_empty() {
+        return Some((vec![], 0));
+    }
+
+    let mut positions = Vec::with_capacity(pattern.len());
+    let mut score = 0;
+    let mut pi = 0;
+    let mut prev: Option<char> = None;
+
+    for (i, c) in text.chars().enumerate() {
+        if pi < pattern.len() && c.to_lowercase().eq(pattern[pi].to_lowercase()) {
+            score += 1;
+
+            if positions.last().is_some_and(|&last| last + 1 == i) {
+                score += 5;
+            }
+
+            if prev.is_none_or(|p| !p.is_alphanumeric()) {
+                score += 3;
+            }
+
+            positions.push(i);
+            pi += 1;
+        }
+
+        prev = Some(c);
+    }
+
+    if pi == pattern.len() {
+        Some((positions, score))
+    } else {
+        None
+    }
+}
+
+impl Filter {
+    pub fn parse(query: &str) -> Self {
+        let terms = query
+            .split_whitespace()
+            .map(|term| match term.split_once(':') {
+                Some(("method", v)) => Term::Method(
+                    v.split(',')
+                        .filter(|m| !m.is_empty())
+                        .map(|m| m.to_uppercase())
+                        .collect(),
+                ),
+                Some(("title", v)) => Term::Title(v.to_string()),
+                Some(("url", v)) => Term::Url(v.to_string()),
+                _ => Term::Any(term.to_string()),
+            })
+            .collect();
+
+        Self { terms }
+    }
+
+    pub fn matches(&self, request: &Request) -> Option<FilterMatch> {
+        let mut result = FilterMatch::default();
+
+        for term in self.terms.iter() {
+            match term {
+                Term::Method(methods) => {
+                    if !methods.is_empty() && !methods.contains(&request.method.to_uppercase()) {
+                        return None;
+                    }
+                },
+                Term::Title(pattern) => {
+                    let (positions, _) = fuzzy_match(pattern, &request.title)?;
+                    result.title.extend(positions);
+                },
+                Term::Url(pattern) => {
+                    let (positions, _) = fuzzy_match(pattern, &request.url)?;
+                    result.url.extend(positions);
+                },
+                Term::Any(pattern) => {
+                    let best = [
+                        (0, fuzzy_match(pattern, &request.title)),
+                        (1, fuzzy_match(pattern, &request.method)),
+                        (2, fuzzy_match(pattern, &request.url)),
+                    ]
+                        .into_iter()
+                        .filter_map(|(field, m)| m.map(|m| (field, m)))
+                        .max_by_key(|(field, (_, score))| (*score, -field));
+
+                    let (field, (positions, _)) = best?;
+
+                    match field {
+                        0 => result.title.extend(positions),
+                        1 => result.method.extend(positions),
+                        _ => result.url.extend(positions),
+                    }
+                },
+            }
+        }
+
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(method: &str, title: &str, url: &str) -> Request {
+        Request::new(method, title, url, "")
+    }
+
+    #[test]
+    fn fuzzy_matches_favour_word_starts_and_runs() {
+        assert_eq!(fuzzy_match("", "anything"), Some((vec![], 0)));
+        assert_eq!(fuzzy_match("xyz", "List pets"), None);
+        assert_eq!(fuzzy_match("PET", "List pets").map(|m| m.0), Some(vec![5, 6, 7]));
+
+        let start = fuzzy_match("lp", "List pets").unwrap().1;
+        let middle = fuzzy_match("is", "List pets").unwrap().1;
+        let scattered = fuzzy_match("lt", "List pets").unwrap().1;
+
+        assert_eq!((start, middle, scattered), (1 + 3 + 1 + 3, 1 + 1 + 5, 1 + 3 + 1));
+    }
+
+    #[test]
+    fn terms_match_their_field() {
+        let pets = request("POST", "Add pet", "https://api.test/v1/pets");
+        let users = request("GET", "List users", "https://api.test/v2/users");
+        let shown = |query: &str| -> Vec<&str> {
+            let filter = Filter::parse(query);
+            [&pets, &users].into_iter().filter(|r| filter.matches(r).is_some()).map(|r| r.title.as_str()).collect()
+        };
+
+        assert_eq!(shown(""), ["Add pet", "List users"]);
+        assert_eq!(shown("method:get"), ["List users"]);
+        assert_eq!(shown("method:GET,post"), ["Add pet", "List users"]);
+        assert_eq!(shown("method:"), ["Add pet", "List users"]);
+        assert_eq!(shown("title:v1"), Vec::<&str>::new());
+        assert_eq!(shown("url:v1"), ["Add pet"]);
+        assert_eq!(shown("v1"), ["Add pet"]);
+        assert_eq!(shown("method:POST users"), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn any_term_highlights_the_best_field() {
+        let request = request("GET", "Get pet", "https://api.test/get");
+
+        let title = Filter::parse("get").matches(&request).unwrap();
+        assert_eq!((title.title, title.method, title.url), (vec![0, 1, 2], vec![], vec![]));
+
+        // Ties go to the title
+        let tie = Filter::parse("gt").matches(&request).unwrap();
+        assert_eq!((tie.title, tie.method), (vec![0, 2], vec![]));
+
+        let method = Filter::parse("gt").matches(&Request::new("GET", "List pets", "https://api.test/", "")).unwrap();
+        assert_eq!((method.title, method.method, method.url), (vec![], vec![0, 2], vec![]));
+
+        let url = Filter::parse("api").matches(&request).unwrap();
+        assert_eq!((url.title, url.method, url.url), (vec![], vec![], vec![8, 9, 10]));
+    }
+}
@@ -0,0 +1,403 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+
+use argon2::Argon2;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use chacha20poly1305::aead::Aead;
+use chacha20poly1305::{ChaCha20Poly1305, Key, KeyInit, Nonce};
+use serde::{Deserialize, Serialize};
+
+/// Passphrase is taken from this variable when set, otherwise it's asked for.
+pub const PASSPHRASE_VAR: &str = "UMBRELLA_PASSPHRASE";
+
+/// Values shorter than this aren't masked, they'd match all over the place.
+const MIN_MASKED_LEN: usize = 3;
+
+/// Writes a file only its owner can read, a looser file is tightened first.
+pub fn write_private(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+        options.mode(0o600);
+
+        if path.exists() {
+            fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+        }
+    }
+
+    options.open(path)?.write_all(content)
+}
+
+/// Where a file that couldn't be loaded is copied to before it's overwritten.
+pub fn backup_path(path: &Path) -> PathBuf {
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(".bak");
+    PathBuf::from(backup)
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(tag = "source", rename_all = "snake_case")]
+pub enum Secret {
+    /// Value kept in the encrypted file
+    Stored { value: String },
+    /// Value of an environment variable
+    Env { var: String },
+    /// First line printed by a command, e.g. `pass show api/token`
+    Command { command: String },
+}
+
+impl Secret {
+    pub fn source(&self) -> &'static str {
+        match self {
+            Secret::Stored { .. } => "stored",
+            Secret::Env { .. } => "env",
+            Secret::Command { .. } => "cmd",
+        }
+    }
+
+    pub fn from_source(source: &str, value: &str) -> Secret {
+        let value = value.to_string();
+
+        match source.trim() {
+            "env" => Secret::Env { var: value },
+            "cmd" => Secret::Command { command: value },
+            _ => Secret::Stored { value },
+        }
+    }
+
+    /// Stored value, variable name or command, depending on the source.
+    pub fn value(&self) -> &str {
+        match self {
+            Secret::Stored { value } => value,
+            Secret::Env { var } => var,
+            Secret::Command { command } => command,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct EncryptedFile {
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+/// Secret variables kept apart from the config, encrypted with a passphrase derived key.
+pub struct SecretStore {
+    path: PathBuf,
+    secrets: BTreeMap<String, Secret>,
+    /// Contents of the file until it's decrypted
+    encrypted: Option<EncryptedFile>,
+    key: Option<([u8; 16], [u8; 32])>,
+    command_outputs: Mutex<HashMap<String, String>>,
+    /// Why the file couldn't be loaded, it's backed up before the first write.
+    pub load_error: Option<String>,
+    backed_up: bool,
+}
+
+fn random<const N: usize>() -> Result<[u8; N], String> {
+    let mut buf = [0; N];
+    getrandom::getrandom(&mut buf).map_err(|e| format!("Couldn't generate random bytes: {}", e))?;
+    Ok(buf)
+}
+
+fn derive_key(passphrase: &str, salt: &[u8; 16]) -> Result<[u8; 32], String> {
+    let mut key = [0; 32];
+
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| format!("Couldn't derive the key: {}", e))?;
+
+    Ok(key)
+}
+
+fn decode<const N: usize>(value: &str) -> Result<[u8; N], String> {
+    STANDARD
+        .decode(value)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| String::from("Secrets file is damaged"))
+}
+
+/// Replaces every occurrence of the values with bullets of the same length.
+pub fn mask(text: &str, values: &[String]) -> String {
+    let mut masked = text.to_string();
+
+    for value in values.iter().filter(|v| v.chars().count() >= MIN_MASKED_LEN) {
+        masked = masked.replace(value.as_str(), &"•".repeat(value.chars().count()));
+    }
+
+    masked
+}
+
+impl SecretStore {
+    /// A missing file starts an empty store, so does one that can't be read
+    /// or parsed, but then the error is kept and the file isn't lost.
+    pub fn load(path: PathBuf) -> Self {
+        let loaded = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map(Some)
+                .map_err(|e| format!("Couldn't parse {}: {}", path.display(), e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(format!("Couldn't read {}: {}", path.display(), e)),
+        };
+
+        let (encrypted, load_error) = match loaded {
+            Ok(encrypted) => (encrypted, None),
+            Err(e) => (None, Some(format!("{}, it's kept as {} once secrets are saved", e, backup_path(&path).display()))),
+        };
+
+        let mut store = Self {
+            path,
+            secrets: BTreeMap::new(),
+            encrypted,
+            key: None,
+            command_outputs: Mutex::new(HashMap::new()),
+            load_error,
+            backed_up: false,
+        };
+
+        if let Ok(passphrase) = std::env::var(PASSPHRASE_VAR) {
+            store.unlock(&passphrase).ok();
+        }
+
+        store
+    }
+
+    /// Store file exists, but isn't decrypted yet.
+    pub fn is_locked(&self) -> bool {
+        self.encrypted.is_some()
+    }
+
+    pub fn has_passphrase(&self) -> bool {
+        self.key.is_some()
+    }
+
+    /// Decrypts the store, or sets the passphrase of a store that has no file yet.
+    pub fn unlock(&mut self, passphrase: &str) -> Result<(), String> {
+        let Some(file) = &self.encrypted else {
+            let salt = random::<16>()?;
+            self.key = Some((salt, derive_key(passphrase, &salt)?));
+            return Ok(());
+        };
+
+        let salt = decode::<16>(&file.salt)?;
+        let nonce = decode::<12>(&file.nonce)?;
+        let ciphertext = STANDARD
+            .decode(&file.ciphertext)
+            .map_err(|_| String::from("Secrets file is damaged"))?;
+
+        let key = derive_key(passphrase, &salt)?;
+        let plaintext = ChaCha20Poly1305::new(Key::from_slice(&key))
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_ref())
+            .map_err(|_| String::from("Wrong passphrase or damaged secrets file"))?;
+
+        self.secrets = serde_json::from_slice(&plaintext)
+            .map_err(|e| format!("Couldn't read secrets: {}", e))?;
+        self.key = Some((salt, key));
+        self.encrypted = None;
+
+        Ok(())
+    }
+
+    pub fn save(&mut self) -> Result<(), String> {
+        let Some((salt, key)) = &self.key else {
+            return Err(String::from("Secrets have no passphrase yet"));
+        };
+
+        let nonce = random::<12>()?;
+        let plaintext = serde_json::to_vec(&self.secrets).map_err(|e| e.to_string())?;
+        let ciphertext = ChaCha20Poly1305::new(Key::from_slice(key))
+            .encrypt(Nonce::from_slice(&nonce), plaintext.as_ref())
+            .map_err(|_| String::from("Couldn't encrypt secrets"))?;
+
+        let file = EncryptedFile {
+            salt: STANDARD.encode(salt),
+            nonce: STANDARD.encode(nonce),
+            ciphertext: STANDARD.encode(ciphertext),
+        };
+
+        let content = serde_json::to_string_pretty(&file).map_err(|e| e.to_string())?;
+
+        if self.load_error.is_some() && !self.backed_up {
+            fs::copy(&self.path, backup_path(&self.path))
+                .map_err(|e| format!("Couldn't back up secrets before writing them: {}", e))?;
+            self.backed_up = true;
+        }
+
+        write_private(&self.path, content.as_bytes()).map_err(|e| format!("Couldn't write secrets: {}", e))
+    }
+
+    pub fn get_all(&self) -> &BTreeMap<String, Secret> {
+        &self.secrets
+    }
+
+    pub fn set_all(&mut self, secrets: BTreeMap<String, Secret>) {
+        self.secrets = secrets;
+        self.command_outputs.lock().unwrap().clear();
+    }
+
+    /// Value of the secret, `None` if there is no secret with the name.
+    pub fn resolve(&self, name: &str) -> Option<Result<String, String>> {
+        if self.is_locked() {
+            return Some(Err(String::from("Secrets are locked, unlock them with the passphrase")));
+        }
+
+        let secret = self.secrets.get(name)?;
+
+        Some(match secret {
+            Secret::Stored { value } => Ok(value.clone()),
+            Secret::Env { var } => std::env::var(var)
+                .map_err(|_| format!("Environment variable {} of secret {} is not set", var, name)),
+            Secret::Command { command } => self.command_output(name, command),
+        })
+    }
+
+    /// Commands run once per session, their output is kept in memory only.
+    fn command_output(&self, name: &str, command: &str) -> Result<String, String> {
+        let mut outputs = self.command_outputs.lock().unwrap();
+
+        if let Some(output) = outputs.get(command) {
+            return Ok(output.clone());
+        }
+
+        let output = Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .output()
+            .map_err(|e| format!("Couldn't run the command of secret {}: {}", name, e))?;
+
+        if !output.status.success() {
+            return Err(format!(
+                "Command of secret {} failed: {}",
+                name,
+                String::from_utf8_lossy(&output.stderr).trim(),
+            ));
+        }
+
+        let value = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .to_string();
+
+        outputs.insert(command.to_string(), value.clone());
+        Ok(value)
+    }
+
+    /// Names and values known so far, commands that haven't run yet aren't included.
+    fn known(&self) -> Vec<(&str, String)> {
+        let outputs = self.command_outputs.lock().unwrap();
+
+        self.secrets
+            .iter()
+            .filter_map(|(name, secret)| match secret {
+                Secret::Stored { value } => Some(value.clone()),
+                Secret::Env { var } => std::env::var(var).ok(),
+                Secret::Command { command } => outputs.get(command).cloned(),
+            }.map(|value| (name.as_str(), value)))
+            .collect()
+    }
+
+    pub fn known_values(&self) -> Vec<String> {
+        self.known().into_iter().map(|(_, value)| value).collect()
+    }
+
+    /// Replaces secret values with their `{{name}}` placeholders, for text written to disk.
+    pub fn redact(&self, text: &str) -> String {
+        let mut redacted = text.to_string();
+
+        for (name, value) in self.known() {
+            if value.chars().count() >= MIN_MASKED_LEN {
+                redacted = redacted.replace(value.as_str(), &format!("{{{{{}}}}}", name));
+            }
+        }
+
+        redacted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn private_files_are_owner_only() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = std::env::temp_dir().join(format!("umbrella-private-{}.json", std::process::id()));
+        fs::write(&path, "{}").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+
+        write_private(&path, b"secret").unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode() & 0o777;
+        let content = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(mode, 0o600);
+        assert_eq!(content, "secret");
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("umbrella-secrets-{}-{}.json", name, std::process::id()))
+    }
+
+    #[test]
+    fn secrets_are_decrypted_with_the_passphrase_they_were_saved_with() {
+        let path = temp_path("round-trip");
+        let _ = fs::remove_file(&path);
+
+        let mut store = SecretStore::load(path.clone());
+        assert!(!store.is_locked());
+        store.unlock("correct horse").unwrap();
+        store.set_all(BTreeMap::from([(String::from("token"), Secret::from_source("stored", "s3cr3t"))]));
+        store.save().unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(!content.contains("s3cr3t"));
+
+        let mut reopened = SecretStore::load(path.clone());
+        assert!(reopened.is_locked());
+        assert!(reopened.resolve("token").unwrap().is_err());
+
+        let error = reopened.unlock("wrong horse").unwrap_err();
+        assert_eq!(error, "Wrong passphrase or damaged secrets file");
+        assert!(reopened.is_locked());
+
+        reopened.unlock("correct horse").unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(reopened.resolve("token").unwrap().unwrap(), "s3cr3t");
+        assert_eq!(reopened.redact("Bearer s3cr3t"), "Bearer {{token}}");
+    }
+
+    #[test]
+    fn corrupt_files_are_backed_up_before_writing() {
+        let path = temp_path("corrupt");
+        fs::write(&path, "{ not json").unwrap();
+
+        let mut store = SecretStore::load(path.clone());
+        assert!(store.load_error.as_deref().is_some_and(|e| e.contains("Couldn't parse")));
+        assert!(!store.is_locked());
+
+        store.unlock("passphrase").unwrap();
+        store.save().unwrap();
+        store.save().unwrap();
+
+        let backup = fs::read_to_string(backup_path(&path)).unwrap();
+        fs::remove_file(backup_path(&path)).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(backup, "{ not json");
+    }
+}
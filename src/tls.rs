@@ -0,0 +1,324 @@
+use std::fs;
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use p12_keystore::KeyStore;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{self, CryptoProvider};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer, ServerName, UnixTime};
+use rustls::{ClientConfig, ClientConnection, DigitallySignedStruct, RootCertStore, SignatureScheme, StreamOwned};
+
+use crate::paths::Paths;
+
+/// Variables the options are read from, environments set them like any other variable.
+pub const CA: &str = "tls.ca";
+pub const CERT: &str = "tls.cert";
+pub const KEY: &str = "tls.key";
+pub const PASSWORD: &str = "tls.password";
+pub const SNI: &str = "tls.sni";
+pub const INSECURE: &str = "tls.insecure";
+
+/// How HTTPS connections are made, files are relative to the project root.
+#[derive(Default)]
+pub struct TlsOptions {
+    /// PEM bundle trusted on top of the system roots
+    pub ca: Option<String>,
+    /// Client certificate, PEM or PKCS#12 when named `.p12` or `.pfx`
+    pub cert: Option<String>,
+    /// PEM private key of the client certificate, read from the certificate file when not set
+    pub key: Option<String>,
+    /// Password of a PKCS#12 client certificate
+    pub password: Option<String>,
+    /// Name sent in the handshake and checked against the certificate instead of the URL host
+    pub sni: Option<String>,
+    /// Accepts any server certificate
+    pub insecure: bool,
+}
+
+pub type TlsStream = StreamOwned<ClientConnection, TcpStream>;
+
+/// Accepts any certificate, signatures are still checked so the handshake
+/// fails on a broken server rather than later.
+#[derive(Debug)]
+struct NoVerification(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for NoVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        crypto::verify_tls12_signature(message, cert, dss, &self.0.signature_verification_algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        crypto::verify_tls13_signature(message, cert, dss, &self.0.signature_verification_algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+fn resolve(path: &str) -> PathBuf {
+    Paths::project_root().join(path.trim())
+}
+
+fn read_certs(path: &str, what: &str) -> Result<Vec<CertificateDer<'static>>, String> {
+    let certs = CertificateDer::pem_file_iter(resolve(path))
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| format!("Couldn't read {} {}: {}", what, path, e))?;
+
+    if certs.is_empty() {
+        return Err(format!("No certificates in {} {}", what, path));
+    }
+
+    Ok(certs)
+}
+
+/// System roots plus the CA bundle, a system without roots still trusts the bundle.
+fn root_store(ca: Option<&str>) -> Result<RootCertStore, String> {
+    let mut roots = RootCertStore::empty();
+    roots.add_parsable_certificates(rustls_native_certs::load_native_certs().certs);
+
+    if let Some(ca) = ca {
+        let (added, _) = roots.add_parsable_certificates(read_certs(ca, "CA bundle")?);
+
+        if added == 0 {
+            return Err(format!("No usable certificates in CA bundle {}", ca));
+        }
+    }
+
+    Ok(roots)
+}
+
+fn is_pkcs12(path: &str) -> bool {
+    let lower = path.trim().to_lowercase();
+    lower.ends_with(".p12") || lower.ends_with(".pfx")
+}
+
+/// Certificate chain and key presented to servers asking for one.
+fn client_identity(options: &TlsOptions, cert: &str) -> Result<(Vec<CertificateDer<'static>>, PrivateKeyDer<'static>), String> {
+    if is_pkcs12(cert) {
+        let data = fs::read(resolve(cert)).map_err(|e| format!("Couldn't read client certificate {}: {}", cert, e))?;
+        let store = KeyStore::from_pkcs12(&data, options.password.as_deref().unwrap_or_default())
+            .map_err(|e| format!("Couldn't open client certificate {}, is {} right? {}", cert, PASSWORD, e))?;
+        let (_, chain) = store
+            .private_key_chain()
+            .ok_or(format!("Client certificate {} has no private key", cert))?;
+
+        let certs = chain.chain().iter().map(|c| CertificateDer::from(c.as_der().to_vec())).collect();
+        let key = PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(chain.key().to_vec()));
+
+        return Ok((certs, key));
+    }
+
+    let key_path = options.key.as_deref().unwrap_or(cert);
+    let key = PrivateKeyDer::from_pem_file(resolve(key_path))
+        .map_err(|e| format!("Couldn't read the client key from {}: {}", key_path, e))?;
+
+    Ok((read_certs(cert, "client certificate")?, key))
+}
+
+fn client_config(options: &TlsOptions) -> Result<ClientConfig, String> {
+    let provider = Arc::new(crypto::ring::default_provider());
+    let builder = ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .map_err(|e| e.to_string())?;
+
+    let builder = if options.insecure {
+        builder.dangerous().with_custom_certificate_verifier(Arc::new(NoVerification(provider)))
+    } else {
+        builder.with_root_certificates(root_store(options.ca.as_deref())?)
+    };
+
+    match &options.cert {
+        Some(cert) => {
+            let (certs, key) = client_identity(options, cert)?;
+
+            builder
+                .with_client_auth_cert(certs, key)
+                .map_err(|e| format!("Client certificate {} doesn't match its key: {}", cert, e))
+        },
+        None => Ok(builder.with_no_client_auth()),
+    }
+}
+
+/// Adds which option fixes a TLS error, TLS 1.3 servers reject client
+/// certificates after the handshake, so errors reading the response are explained too.
+pub fn explain(error: String) -> String {
+    let hint = if error.contains("UnknownIssuer") {
+        format!(", set {} to the CA bundle of the server", CA)
+    } else if error.contains("not valid for name") {
+        format!(", set {} to a name the certificate is for", SNI)
+    } else if error.contains("CertificateRequired") || error.contains("BadCertificate") || error.contains("UnknownCA") {
+        format!(", set {} to a client certificate the server accepts", CERT)
+    } else {
+        String::new()
+    };
+
+    format!("{}{}", error, hint)
+}
+
+/// Makes the TLS handshake on the connection, so certificate errors show up
+/// before anything is sent.
+pub fn connect(mut stream: TcpStream, host: &str, options: &TlsOptions) -> Result<TlsStream, String> {
+    let name = options.sni.as_deref().map(str::trim).unwrap_or(host);
+    let server_name = ServerName::try_from(name.to_string()).map_err(|_| format!("Invalid TLS server name: {}", name))?;
+
+    let mut connection = ClientConnection::new(Arc::new(client_config(options)?), server_name)
+        .map_err(|e| e.to_string())?;
+
+    while connection.is_handshaking() {
+        if let Err(e) = connection.complete_io(&mut stream) {
+            return Err(explain(format!("TLS handshake with {} failed: {}", name, e)));
+        }
+    }
+
+    Ok(StreamOwned::new(connection, stream))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    use rustls::server::WebPkiClientVerifier;
+    use rustls::{ServerConfig, ServerConnection};
+
+    use super::*;
+    use crate::http::{self, HttpRequest};
+
+    /// Local HTTPS server with the certificate of `tls/server.pem`, answering `ok` to anything.
+    fn serve(client_auth: bool) -> u16 {
+        let provider = Arc::new(crypto::ring::default_provider());
+        let builder = ServerConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()
+            .unwrap();
+
+        let builder = if client_auth {
+            let roots = root_store(Some("tls/ca.pem")).unwrap();
+            builder.with_client_cert_verifier(
+                WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider).build().unwrap(),
+            )
+        } else {
+            builder.with_no_client_auth()
+        };
+
+        let key = PrivateKeyDer::from_pem_file(resolve("tls/server-key.pem")).unwrap();
+        let config = Arc::new(builder.with_single_cert(read_certs("tls/server.pem", "server").unwrap(), key).unwrap());
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        thread::spawn(move || {
+            for tcp in listener.incoming().flatten() {
+                let connection = ServerConnection::new(config.clone()).unwrap();
+                let mut stream = StreamOwned::new(connection, tcp);
+                let mut request = [0; 1024];
+
+                // Failed handshakes send their alert and drop the connection
+                if stream.read(&mut request).is_ok() {
+                    let _ = stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok");
+                    stream.conn.send_close_notify();
+                    let _ = stream.flush();
+                }
+            }
+        });
+
+        port
+    }
+
+    fn get(url: &str, options: TlsOptions) -> Result<String, String> {
+        let request = HttpRequest {
+            method: String::from("GET"),
+            url: url.to_string(),
+            headers: Vec::new(),
+            body: String::new(),
+        };
+
+        http::send(&request, &options).map(|response| response.body)
+    }
+
+    fn with_ca() -> TlsOptions {
+        TlsOptions { ca: Some(String::from("tls/ca.pem")), ..Default::default() }
+    }
+
+    #[test]
+    fn servers_are_verified_against_the_ca_bundle() {
+        let url = format!("https://localhost:{}/", serve(false));
+        assert_eq!(get(&url, with_ca()).unwrap(), "ok");
+
+        let error = get(&url, TlsOptions::default()).unwrap_err();
+        assert!(error.contains("UnknownIssuer") && error.contains("set tls.ca"), "{}", error);
+    }
+
+    #[test]
+    fn insecure_mode_accepts_any_certificate() {
+        let url = format!("https://localhost:{}/", serve(false));
+        let options = TlsOptions { insecure: true, ..Default::default() };
+
+        assert_eq!(get(&url, options).unwrap(), "ok");
+    }
+
+    #[test]
+    fn the_sni_name_is_verified_instead_of_the_host() {
+        let url = format!("https://127.0.0.1:{}/", serve(false));
+        let sni = |name: &str| TlsOptions { sni: Some(name.to_string()), ..with_ca() };
+
+        assert_eq!(get(&url, sni("internal.test")).unwrap(), "ok");
+
+        let error = get(&url, sni("wrong.test")).unwrap_err();
+        assert!(error.contains("not valid for name") && error.contains("set tls.sni"), "{}", error);
+    }
+
+    #[test]
+    fn pem_client_certificates_are_presented() {
+        let url = format!("https://localhost:{}/", serve(true));
+        let options = TlsOptions {
+            cert: Some(String::from("tls/client.pem")),
+            key: Some(String::from("tls/client-key.pem")),
+            ..with_ca()
+        };
+
+        assert_eq!(get(&url, options).unwrap(), "ok");
+
+        let error = get(&url, with_ca()).unwrap_err();
+        assert!(error.contains("set tls.cert"), "{}", error);
+    }
+
+    #[test]
+    fn pkcs12_client_certificates_are_opened_with_the_password() {
+        let url = format!("https://localhost:{}/", serve(true));
+        let p12 = |password: &str| TlsOptions {
+            cert: Some(String::from("tls/client.p12")),
+            password: Some(password.to_string()),
+            ..with_ca()
+        };
+
+        assert_eq!(get(&url, p12("umbrella")).unwrap(), "ok");
+
+        let error = get(&url, p12("wrong")).unwrap_err();
+        assert!(error.contains("is tls.password right?"), "{}", error);
+    }
+}
@@ -0,0 +1,350 @@
+use std::ops::Range;
+
+use crate::model::{PathParam, QueryParam};
+use crate::vars;
+
+/// URL split into the part before the query, the raw query and the fragment.
+pub struct UrlParts<'a> {
+    pub base: &'a str,
+    pub query: Option<&'a str>,
+    pub fragment: Option<&'a str>,
+}
+
+pub fn split_url(url: &str) -> UrlParts<'_> {
+    let (rest, fragment) = match url.split_once('#') {
+        Some((rest, fragment)) => (rest, Some(fragment)),
+        None => (url, None),
+    };
+
+    let (base, query) = match rest.split_once('?') {
+        Some((base, query)) => (base, Some(query)),
+        None => (rest, None),
+    };
+
+    UrlParts { base, query, fragment }
+}
+
+/// Encodes everything except RFC 3986 unreserved characters.
+///
+/// `{{var}}` placeholders are kept as is, they are substituted at send time.
+pub fn percent_encode(s: &str) -> String {
+    let mut encoded = String::with_capacity(s.len());
+    let mut last = 0;
+
+    for (range, _) in vars::placeholders(s) {
+        encode_bytes(&s[last..range.start], &mut encoded);
+        encoded.push_str(&s[range.clone()]);
+        last = range.end;
+    }
+
+    encode_bytes(&s[last..], &mut encoded);
+    encoded
+}
+
+fn encode_bytes(s: &str, encoded: &mut String) {
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(b as char);
+            },
+            _ => encoded.push_str(&format!("%{:02X}", b)),
+        }
+    }
+}
+
+/// Decodes `%XX` sequences and, as query strings do, `+` as a space.
+pub fn percent_decode(s: &str) -> String {
+    decode(s, true)
+}
+
+/// Decodes `%XX` sequences of a path segment, where `+` is just a plus.
+pub fn path_decode(s: &str) -> String {
+    decode(s, false)
+}
+
+fn decode(s: &str, plus_as_space: bool) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or_default();
+
+                match u8::from_str_radix(hex, 16) {
+                    Ok(b) => {
+                        decoded.push(b);
+                        i += 3;
+                        continue;
+                    },
+                    Err(_) => decoded.push(b'%'),
+                }
+            },
+            b'+' if plus_as_space => decoded.push(b' '),
+            b => decoded.push(b),
+        }
+
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+pub fn parse_query(query: &str) -> Vec<(String, String)> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.split_once('=') {
+            Some((k, v)) => (percent_decode(k), percent_decode(v)),
+            None => (percent_decode(pair), String::new()),
+        })
+        .collect()
+}
+
+/// Replaces the query of the URL with the enabled params, params without a key are skipped.
+pub fn with_query(url: &str, params: &[QueryParam]) -> String {
+    let parts = split_url(url);
+
+    let query = params
+        .iter()
+        .filter(|p| p.enabled && !p.key.is_empty())
+        .map(|p| if p.value.is_empty() {
+            percent_encode(&p.key)
+        } else {
+            format!("{}={}", percent_encode(&p.key), percent_encode(&p.value))
+        })
+        .collect::<Vec<String>>()
+        .join("&");
+
+    let mut result = parts.base.to_string();
+
+    if !query.is_empty() {
+        result.push('?');
+        result.push_str(&query);
+    }
+
+    if let Some(fragment) = parts.fragment {
+        result.push('#');
+        result.push_str(fragment);
+    }
+
+    result
+}
+
+/// Adds a param to the end of the URL query.
+pub fn append_query(url: &str, key: &str, value: &str) -> String {
+    let parts = split_url(url);
+    let param = format!("{}={}", percent_encode(key), percent_encode(value));
+
+    let mut result = parts.base.to_string();
+    result.push('?');
+
+    if let Some(query) = parts.query.filter(|q| !q.is_empty()) {
+        result.push_str(query);
+        result.push('&');
+    }
+
+    result.push_str(&param);
+
+    if let Some(fragment) = parts.fragment {
+        result.push('#');
+        result.push_str(fragment);
+    }
+
+    result
+}
+
+/// Updates params from the URL query, keeping disabled params in their places.
+///
+/// Enabled params are replaced one by one with the params of the URL,
+/// URL params left over are appended as enabled. Params without a key
+/// never make it into the URL, so they are kept as well.
+pub fn merge_query(url: &str, params: &[QueryParam]) -> Vec<QueryParam> {
+    let mut from_url = split_url(url)
+        .query
+        .map(parse_query)
+        .unwrap_or_default()
+        .into_iter();
+
+    let mut merged = Vec::new();
+
+    for param in params.iter() {
+        if !param.enabled || param.key.is_empty() {
+            merged.push(param.clone());
+        } else if let Some((key, value)) = from_url.next() {
+            merged.push(QueryParam { key, value, enabled: true });
+        }
+    }
+
+    merged.extend(from_url.map(|(key, value)| QueryParam { key, value, enabled: true }));
+    merged
+}
+
+/// `{name}` placeholders in the path of the URL with their byte ranges.
+///
+/// Double braces are left alone, so `{{var}}` never counts as a path param.
+pub fn placeholders(url: &str) -> Vec<(Range<usize>, &str)> {
+    let path = split_url(url).base;
+    let mut found = Vec::new();
+    let mut rest = 0;
+
+    while let Some(start) = path[rest..].find('{').map(|i| rest + i) {
+        if path[start + 1..].starts_with('{') {
+            rest = path[start..].find("}}").map_or(path.len(), |i| start + i + 2);
+            continue;
+        }
+
+        let Some(end) = path[start..].find('}').map(|i| start + i) else {
+            break;
+        };
+
+        let name = &path[start + 1..end];
+
+        if !name.is_empty() {
+            found.push((start..end + 1, name));
+        }
+
+        rest = end + 1;
+    }
+
+    found
+}
+
+/// `:name` path variables, as Postman and Insomnia write them, as `{name}` placeholders.
+pub fn path_variables_to_placeholders(base: &str) -> String {
+    base.split('/')
+        .map(|segment| match segment.strip_prefix(':') {
+            Some(name) if !name.is_empty() => format!("{{{}}}", name),
+            _ => segment.to_string(),
+        })
+        .collect::<Vec<String>>()
+        .join("/")
+}
+
+/// Path params for the placeholders of the URL, keeping values of the known ones.
+pub fn merge_path_params(url: &str, params: &[PathParam]) -> Vec<PathParam> {
+    let mut merged: Vec<PathParam> = Vec::new();
+
+    for (_, name) in placeholders(url) {
+        if merged.iter().any(|p| p.name == name) {
+            continue;
+        }
+
+        merged.push(
+            params
+                .iter()
+                .find(|p| p.name == name)
+                .cloned()
+                .unwrap_or_else(|| PathParam::new(name)),
+        );
+    }
+
+    merged
+}
+
+/// Substitutes path params into the URL placeholders.
+pub fn fill_path(url: &str, params: &[PathParam]) -> Result<String, String> {
+    let mut filled = String::with_capacity(url.len());
+    let mut last = 0;
+
+    for (range, name) in placeholders(url) {
+        let param = params.iter().find(|p| p.name == name);
+
+        let value = match param {
+            Some(p) if !p.value.trim().is_empty() => {
+                p.validate()?;
+                percent_encode(p.value.trim())
+            },
+            Some(p) if !p.required => String::new(),
+            _ => return Err(format!("Path parameter {} is not filled", name)),
+        };
+
+        filled.push_str(&url[last..range.start]);
+        filled.push_str(&value);
+        last = range.end;
+    }
+
+    filled.push_str(&url[last..]);
+    Ok(filled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plus_is_a_space_in_queries_only() {
+        assert_eq!(percent_decode("a+b%2Bc"), "a b+c");
+        assert_eq!(path_decode("a+b%2Bc"), "a+b+c");
+        assert_eq!(path_decode("caf%C3%A9%20bar"), "café bar");
+    }
+
+    #[test]
+    fn broken_escapes_are_kept() {
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("100%zz%4"), "100%zz%4");
+        assert_eq!(percent_decode("%41%"), "A%");
+    }
+
+    #[test]
+    fn encoding_keeps_placeholders() {
+        assert_eq!(percent_encode("a b/c~{{my var}}"), "a%20b%2Fc~{{my var}}");
+        assert_eq!(percent_encode("é"), "%C3%A9");
+    }
+
+    fn param(key: &str, value: &str, enabled: bool) -> QueryParam {
+        QueryParam { key: key.to_string(), value: value.to_string(), enabled }
+    }
+
+    #[test]
+    fn query_params_round_trip_through_the_url() {
+        let params = [param("q", "a b", true), param("off", "1", false), param("flag", "", true)];
+        let url = with_query("https://api.test/items?old=1#top", &params);
+        assert_eq!(url, "https://api.test/items?q=a%20b&flag#top");
+
+        // Editing the URL keeps the disabled param where it was
+        let merged: Vec<(String, String, bool)> = merge_query("https://api.test/items?q=b&flag&new=x", &params)
+            .into_iter()
+            .map(|p| (p.key, p.value, p.enabled))
+            .collect();
+        assert_eq!(merged, [
+            (String::from("q"), String::from("b"), true),
+            (String::from("off"), String::from("1"), false),
+            (String::from("flag"), String::new(), true),
+            (String::from("new"), String::from("x"), true),
+        ]);
+
+        assert_eq!(append_query("https://api.test/#top", "k", "a&b"), "https://api.test/?k=a%26b#top");
+        assert_eq!(append_query("https://api.test/?a=1", "k", "v"), "https://api.test/?a=1&k=v");
+    }
+
+    #[test]
+    fn path_placeholders_skip_variables() {
+        let url = "https://{{host}}/pets/{petId}/{{x}}/{kind}?q={not}";
+        let names: Vec<&str> = placeholders(url).into_iter().map(|(_, name)| name).collect();
+        assert_eq!(names, ["petId", "kind"]);
+
+        assert_eq!(path_variables_to_placeholders("/pets/:id/:/toys"), "/pets/{id}/:/toys");
+    }
+
+    #[test]
+    fn path_params_are_filled_encoded_and_checked() {
+        let mut id = PathParam::new("id");
+        id.param_type = String::from("integer");
+        let mut kind = PathParam::new("kind");
+        kind.required = false;
+
+        let url = "https://api.test/pets/{id}/{kind}";
+        assert_eq!(fill_path(url, &[id.clone(), kind.clone()]).unwrap_err(), "Path parameter id is not filled");
+
+        id.value = String::from("x");
+        assert_eq!(fill_path(url, &[id.clone(), kind.clone()]).unwrap_err(), "id must be integer, got \"x\"");
+
+        id.value = String::from(" 7 ");
+        assert_eq!(fill_path(url, &[id.clone(), kind.clone()]).unwrap(), "https://api.test/pets/7/");
+
+        kind.value = String::from("cat/dog");
+        assert_eq!(fill_path(url, &[id, kind]).unwrap(), "https://api.test/pets/7/cat%2Fdog");
+    }
+}
@@ -0,0 +1,90 @@
+use std::ops::Range;
+
+use serde_json::Value;
+
+use crate::url;
+
+/// `{{name}}` placeholders in the text with their byte ranges.
+pub fn placeholders(text: &str) -> Vec<(Range<usize>, &str)> {
+    let mut found = Vec::new();
+    let mut rest = 0;
+
+    while let Some(start) = text[rest..].find("{{").map(|i| rest + i) {
+        let Some(end) = text[start + 2..].find("}}").map(|i| start + 2 + i) else {
+            break;
+        };
+
+        let name = text[start + 2..end].trim();
+
+        if !name.is_empty() {
+            found.push((start..end + 2, name));
+        }
+
+        rest = end + 2;
+    }
+
+    found
+}
+
+/// Replaces placeholders with values returned by `lookup`.
+pub fn substitute<F>(text: &str, lookup: &F) -> Result<String, String>
+where
+    F: Fn(&str) -> Result<String, String>,
+{
+    substitute_with(text, lookup, |v| v)
+}
+
+fn substitute_with<F, E>(text: &str, lookup: &F, encode: E) -> Result<String, String>
+where
+    F: Fn(&str) -> Result<String, String>,
+    E: Fn(String) -> String,
+{
+    let mut result = String::with_capacity(text.len());
+    let mut last = 0;
+
+    for (range, name) in placeholders(text) {
+        result.push_str(&text[last..range.start]);
+        result.push_str(&encode(lookup(name)?));
+        last = range.end;
+    }
+
+    result.push_str(&text[last..]);
+    Ok(result)
+}
+
+/// Like `substitute`, but values placed into the query are percent-encoded.
+pub fn substitute_url<F>(text: &str, lookup: &F) -> Result<String, String>
+where
+    F: Fn(&str) -> Result<String, String>,
+{
+    match text.split_once('?') {
+        Some((base, query)) => Ok(format!(
+            "{}?{}",
+            substitute(base, lookup)?,
+            substitute_with(query, lookup, |v| url::percent_encode(&v))?,
+        )),
+        None => substitute(text, lookup),
+    }
+}
+
+/// Substitutes placeholders in every string of the JSON value.
+pub fn substitute_json<F>(value: Value, lookup: &F) -> Result<Value, String>
+where
+    F: Fn(&str) -> Result<String, String>,
+{
+    Ok(match value {
+        Value::String(s) => Value::String(substitute(&s, lookup)?),
+        Value::Array(items) => Value::Array(
+            items
+                .into_iter()
+                .map(|v| substitute_json(v, lookup))
+                .collect::<Result<Vec<Value>, String>>()?,
+        ),
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(k, v)| substitute_json(v, lookup).map(|v| (k, v)))
+                .collect::<Result<serde_json::Map<String, Value>, String>>()?,
+        ),
+        other => other,
+    })
+}
@@ -0,0 +1,30 @@
+#!/bin/sh
+# Recreates the test certificates, see README.md
+set -e
+cd "$(dirname "$0")"
+
+openssl req -x509 -newkey ec -pkeyopt ec_paramgen_curve:P-256 -nodes -days 3650 \
+    -subj "/CN=umbrella test CA" -keyout ca-key.pem -out ca.pem
+
+cat > server.ext <<END
+subjectAltName = DNS:localhost, IP:127.0.0.1, DNS:internal.test
+extendedKeyUsage = serverAuth
+END
+
+openssl req -newkey ec -pkeyopt ec_paramgen_curve:P-256 -nodes \
+    -subj "/CN=localhost" -keyout server-key.pem -out server.csr
+openssl x509 -req -in server.csr -CA ca.pem -CAkey ca-key.pem -CAcreateserial \
+    -days 3650 -extfile server.ext -out server.pem
+
+cat > client.ext <<END
+extendedKeyUsage = clientAuth
+END
+
+openssl req -newkey ec -pkeyopt ec_paramgen_curve:P-256 -nodes \
+    -subj "/CN=umbrella test client" -keyout client-key.pem -out client.csr
+openssl x509 -req -in client.csr -CA ca.pem -CAkey ca-key.pem -CAcreateserial \
+    -days 3650 -extfile client.ext -out client.pem
+
+openssl pkcs12 -export -in client.pem -inkey client-key.pem -passout pass:umbrella -out client.p12
+
+rm -f server.csr client.csr server.ext client.ext ca.srl